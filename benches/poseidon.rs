@@ -0,0 +1,157 @@
+use bellperson::bls::Bls12;
+use criterion::measurement::WallTime;
+use criterion::{
+    criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
+};
+use ff::ScalarEngine;
+use generic_array::typenum::{U11, U2, U4, U8};
+use neptune::poseidon::{batch_hash, PoseidonConstants};
+use neptune::{quintic_s_box, scalar_from_u64, Arity, Poseidon};
+
+// Each group reports throughput in hashes per second, or in layers per second for the steps of a round. Benchmark IDs
+// are named by field, then arity or batch size, so that another field is one more call to `bench_fields`.
+
+fn preimage<E: ScalarEngine>(arity: usize) -> Vec<E::Fr> {
+    (0..arity)
+        .map(|i| scalar_from_u64::<E::Fr>(i as u64))
+        .collect()
+}
+
+fn bench_hash<E, A>(group: &mut BenchmarkGroup<WallTime>, field: &str)
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    let constants = PoseidonConstants::<E, A>::new();
+    let preimage = preimage::<E>(A::to_usize());
+    let mut h = Poseidon::<E, A>::new(&constants);
+
+    group.bench_function(BenchmarkId::new(field, A::to_usize()), |b| {
+        b.iter(|| {
+            h.set_preimage(&preimage);
+            h.hash()
+        })
+    });
+}
+
+// The dense MDS matrix-vector multiply of a full round.
+fn bench_mds<E, A>(group: &mut BenchmarkGroup<WallTime>, field: &str)
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    let constants = PoseidonConstants::<E, A>::new();
+    let mut h = Poseidon::<E, A>::new_with_preimage(&preimage::<E>(A::to_usize()), &constants);
+
+    group.bench_function(BenchmarkId::new(field, A::to_usize()), |b| {
+        b.iter(|| h.product_mds())
+    });
+}
+
+// The S-box layer of a full round, applying `x^5` to each element of the state.
+fn bench_sbox<E, A>(group: &mut BenchmarkGroup<WallTime>, field: &str)
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    let constants = PoseidonConstants::<E, A>::new();
+    let mut h = Poseidon::<E, A>::new_with_preimage(&preimage::<E>(A::to_usize()), &constants);
+
+    group.bench_function(BenchmarkId::new(field, A::to_usize()), |b| {
+        b.iter(|| {
+            h.elements
+                .iter_mut()
+                .for_each(|l| quintic_s_box::<E>(l, None, None))
+        })
+    });
+}
+
+fn bench_batch_hash<E>(group: &mut BenchmarkGroup<WallTime>, field: &str)
+where
+    E: ScalarEngine + Send + Sync,
+{
+    for batch_size in [64, 512, 4096].iter() {
+        let preimages = vec![preimage::<E>(8); *batch_size];
+        group.throughput(Throughput::Elements(*batch_size as u64));
+        group.bench_function(BenchmarkId::new(field, batch_size), |b| {
+            b.iter(|| batch_hash::<E>(&preimages))
+        });
+    }
+}
+
+// Loading constants written by `write_bytes`, against generating them.
+fn bench_constants<E, A>(group: &mut BenchmarkGroup<WallTime>, field: &str)
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    let mut bytes = Vec::new();
+    PoseidonConstants::<E, A>::new()
+        .write_bytes(&mut bytes)
+        .unwrap();
+
+    group.bench_function(
+        BenchmarkId::new(format!("{}/generate", field), A::to_usize()),
+        |b| b.iter(PoseidonConstants::<E, A>::new),
+    );
+    group.bench_function(
+        BenchmarkId::new(format!("{}/read-bytes", field), A::to_usize()),
+        |b| b.iter(|| PoseidonConstants::<E, A>::read_bytes(&mut &bytes[..]).unwrap()),
+    );
+
+    // Cloning used to copy the six MDS matrices, the pre-sparse matrix, the sparse matrices, and the compressed round
+    // constants. They are now shared behind `Arc`s, so a clone only copies the `t * (R_F + R_P)` round constants.
+    let constants = PoseidonConstants::<E, A>::new();
+    group.bench_function(
+        BenchmarkId::new(format!("{}/clone", field), A::to_usize()),
+        |b| b.iter(|| constants.clone()),
+    );
+}
+
+fn bench_fields<E>(c: &mut Criterion, field: &str)
+where
+    E: ScalarEngine + Send + Sync,
+{
+    let mut group = c.benchmark_group("hash");
+    group.throughput(Throughput::Elements(1));
+    bench_hash::<E, U2>(&mut group, field);
+    bench_hash::<E, U4>(&mut group, field);
+    bench_hash::<E, U8>(&mut group, field);
+    bench_hash::<E, U11>(&mut group, field);
+    group.finish();
+
+    let mut group = c.benchmark_group("mds-multiply");
+    group.throughput(Throughput::Elements(1));
+    bench_mds::<E, U2>(&mut group, field);
+    bench_mds::<E, U4>(&mut group, field);
+    bench_mds::<E, U8>(&mut group, field);
+    bench_mds::<E, U11>(&mut group, field);
+    group.finish();
+
+    let mut group = c.benchmark_group("sbox-layer");
+    group.throughput(Throughput::Elements(1));
+    bench_sbox::<E, U2>(&mut group, field);
+    bench_sbox::<E, U4>(&mut group, field);
+    bench_sbox::<E, U8>(&mut group, field);
+    bench_sbox::<E, U11>(&mut group, field);
+    group.finish();
+
+    let mut group = c.benchmark_group("constants");
+    bench_constants::<E, U2>(&mut group, field);
+    bench_constants::<E, U4>(&mut group, field);
+    bench_constants::<E, U8>(&mut group, field);
+    bench_constants::<E, U11>(&mut group, field);
+    group.finish();
+
+    // Batches of arity 8, hashed in parallel with the `parallel` feature.
+    let mut group = c.benchmark_group("batch-hash");
+    bench_batch_hash::<E>(&mut group, field);
+    group.finish();
+}
+
+fn bench_poseidon(c: &mut Criterion) {
+    bench_fields::<Bls12>(c, "bls12-381");
+}
+
+criterion_group!(poseidon, bench_poseidon);
+criterion_main!(poseidon);
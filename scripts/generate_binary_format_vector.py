@@ -0,0 +1,185 @@
+#!/usr/bin/env python3
+"""Generates tests/vectors/poseidon_constants_bls12_381_2.bin, the golden file of the binary format that
+`binary_format_is_stable` compares `PoseidonConstants::<Bls12, U2>::new().write_bytes` with, byte for byte.
+
+The constants are derived independently of neptune, as generate_test_vectors.py derives them, and the optimized
+constants as neptune derives them from those:
+
+- The compressed round constants push the round constants of each round but the first back through the MDS matrix,
+  as `preprocessing::compress_round_constants` does.
+- The pre-sparse and sparse matrices factor the MDS matrix of each partial round into `M' x M''`, as
+  `mds::factor_to_sparse_matrices` does.
+
+Before writing, the optimized permutation is checked against the naive permutation of generate_test_vectors.py.
+
+Usage, from the root of the repository, after changing the binary format or the constants:
+
+    python3 scripts/generate_binary_format_vector.py > tests/vectors/poseidon_constants_bls12_381_2.bin
+"""
+
+import hashlib
+import struct
+import sys
+
+from generate_test_vectors import ALPHA, P, Permutation, round_numbers
+
+ARITY = 2
+FORMAT_VERSION = 3
+# The S-box identifier of x^5, `Sbox::Quintic.id()`.
+SBOX = 1
+SECURITY_LEVEL = 128
+# `DEFAULT_SECURITY_MARGIN`: two extra full rounds, and 7.5% more partial rounds.
+EXTRA_FULL_ROUNDS = 2
+PARTIAL_MULTIPLIER = 1.075
+
+
+def invert(m, p=P):
+    """Returns the inverse of the square matrix `m`, by Gauss-Jordan elimination."""
+    n = len(m)
+    a = [row[:] + [int(i == j) for j in range(n)] for i, row in enumerate(m)]
+    for c in range(n):
+        pivot = next(r for r in range(c, n) if a[r][c])
+        a[c], a[pivot] = a[pivot], a[c]
+        scale = pow(a[c][c], p - 2, p)
+        a[c] = [x * scale % p for x in a[c]]
+        for r in range(n):
+            if r != c and a[r][c]:
+                factor = a[r][c]
+                a[r] = [(x - factor * y) % p for x, y in zip(a[r], a[c])]
+    return [row[n:] for row in a]
+
+
+def minor(m):
+    return [row[1:] for row in m[1:]]
+
+
+def mat_mul(a, b, p=P):
+    return [[sum(x * y for x, y in zip(row, column)) % p for column in zip(*b)] for row in a]
+
+
+def apply_matrix(m, v, p=P):
+    """The vector-matrix product `v * m`, as `matrix::apply_matrix` computes."""
+    return [sum(v[i] * m[i][j] for i in range(len(v))) % p for j in range(len(v))]
+
+
+def left_apply_matrix(m, v, p=P):
+    """The matrix-vector product `m * v`."""
+    return [sum(x * y for x, y in zip(row, v)) % p for row in m]
+
+
+def make_prime(m):
+    t = len(m)
+    return [[int(j == 0) for j in range(t)]] + [[0] + row[1:] for row in m[1:]]
+
+
+def make_double_prime(m, m_hat_inv):
+    t = len(m)
+    w_hat = left_apply_matrix(m_hat_inv, [row[0] for row in m[1:]])
+    return [m[0][:]] + [[w_hat[i - 1]] + [int(j == i) for j in range(1, t)] for i in range(1, t)]
+
+
+def factor_to_sparse_matrices(m, partial_rounds):
+    current, sparse = m, []
+    for _ in range(partial_rounds):
+        sparse.append(make_double_prime(current, invert(minor(current))))
+        current = mat_mul(m, make_prime(current))
+    sparse.reverse()
+    return current, sparse
+
+
+def compress_round_constants(t, full_rounds, partial_rounds, round_constants, m_inv):
+    def round_keys(r):
+        return round_constants[r * t : (r + 1) * t]
+
+    half = full_rounds // 2
+    compressed = list(round_keys(0))
+    for i in range(half - 1):
+        compressed += apply_matrix(m_inv, round_keys(i + 1))
+
+    final_round = half + partial_rounds
+    partial_keys = []
+    acc = round_keys(final_round)
+    for i in range(partial_rounds):
+        inverted = apply_matrix(m_inv, acc)
+        partial_keys.append(inverted[0])
+        inverted[0] = 0
+        acc = [(x + y) % P for x, y in zip(round_keys(final_round - i - 1), inverted)]
+    compressed += apply_matrix(m_inv, acc)
+    compressed += reversed(partial_keys)
+
+    for i in range(1, half):
+        compressed += apply_matrix(m_inv, round_keys(i + final_round))
+    return compressed
+
+
+def permute_optimized(state, m, compressed, pre_sparse, sparse, full_rounds, partial_rounds):
+    """The permutation of `Poseidon::hash_optimized_static`."""
+    t, half = len(state), full_rounds // 2
+    state = [(x + k) % P for x, k in zip(state, compressed)]
+    offset = t
+    for r in range(full_rounds + partial_rounds):
+        full = r < half or r >= half + partial_rounds
+        last = r == full_rounds + partial_rounds - 1
+        if full:
+            keys = [0] * t if last else compressed[offset : offset + t]
+            state = [(pow(x, ALPHA, P) + k) % P for x, k in zip(state, keys)]
+            offset += 0 if last else t
+        else:
+            state[0] = (pow(state[0], ALPHA, P) + compressed[offset]) % P
+            offset += 1
+        if r == half - 1:
+            state = apply_matrix(pre_sparse, state)
+        elif half - 1 < r < half + partial_rounds:
+            state = apply_matrix(sparse[r - half], state)
+        else:
+            state = apply_matrix(m, state)
+    assert offset == len(compressed)
+    return state
+
+
+def u64(x):
+    return struct.pack("<Q", x)
+
+
+def elements(xs):
+    return b"".join(x.to_bytes(32, "little") for x in xs)
+
+
+def main():
+    t = ARITY + 1
+    full_rounds, partial_rounds = round_numbers()[t]
+    permutation = Permutation(t, full_rounds, partial_rounds)
+    m, round_constants = permutation.mds, permutation.round_constants
+    m_inv = invert(m)
+    m_hat = minor(m)
+    m_hat_inv = invert(m_hat)
+    m_prime = make_prime(m)
+    m_double_prime = make_double_prime(m, m_hat_inv)
+    compressed = compress_round_constants(t, full_rounds, partial_rounds, round_constants, m_inv)
+    pre_sparse, sparse = factor_to_sparse_matrices(m, partial_rounds)
+
+    domain_tag = (1 << ARITY) - 1
+    state = [domain_tag] + list(range(ARITY))
+    assert permutation(state) == permute_optimized(
+        state, m, compressed, pre_sparse, sparse, full_rounds, partial_rounds
+    )
+
+    out = b"NPTC" + bytes([FORMAT_VERSION, 32]) + P.to_bytes(32, "little")
+    # The width, the standard strength, the S-box, and the Merkle tree hash type.
+    out += u64(t) + bytes([0, SBOX, 0])
+    out += u64(full_rounds) + u64(partial_rounds) + u64(SECURITY_LEVEL)
+    out += u64(EXTRA_FULL_ROUNDS) + struct.pack("<f", PARTIAL_MULTIPLIER)
+    # The domain tag, then the tag of the absent seed.
+    out += elements([domain_tag]) + bytes([0])
+    out += elements(round_constants) + elements(compressed)
+    for matrix in [m, m_inv, m_hat, m_hat_inv, m_prime, m_double_prime, pre_sparse]:
+        for row in matrix:
+            out += elements(row)
+    for matrix in sparse:
+        out += elements([row[0] for row in matrix]) + elements(matrix[0][1:])
+    out += hashlib.blake2s(out).digest()
+    sys.stdout.buffer.write(out)
+
+
+if __name__ == "__main__":
+    main()
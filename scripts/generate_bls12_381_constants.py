@@ -0,0 +1,87 @@
+#!/usr/bin/env python3
+"""Writes src/constants/bls12_381.rs, the precomputed round constants of BLS12-381 for widths 2 through 16,
+and 17, 25, 33, and 37 for the wide arities of 16, 24, 32, and 36.
+
+The constants are generated by the Grain LFSR of generate_test_vectors.py, as `GrainLfsr` generates them for the
+x^5 S-box and the standard round numbers of parameters/round_numbers.txt. Each is written as the four little-endian
+64-bit limbs of its canonical representation. The test of the written file checks them against `GrainLfsr`.
+
+Usage, from the root of the repository, after changing the round numbers:
+
+    python3 scripts/generate_bls12_381_constants.py
+"""
+
+from generate_test_vectors import grain_round_constants, round_numbers
+
+WIDTHS = list(range(2, 17)) + [17, 25, 33, 37]
+PATH = "src/constants/bls12_381.rs"
+
+HEADER = """\
+//! The round constants of BLS12-381 for widths 2 through 16, 17, 25, 33, and 37, with the x^5 S-box and the round
+//! numbers of `Strength::Standard`, so that the constants of the most used widths need not be generated by the Grain
+//! LFSR.
+//!
+//! Each constant is given by the four little-endian limbs of its canonical representation.
+//!
+//! Generated by `scripts/generate_bls12_381_constants.py`. Do not edit.
+
+/// Returns the round constants of width `t` with `r_f` full and `r_p` partial rounds, if they are precomputed.
+pub(crate) fn round_constants(t: u16, r_f: u16, r_p: u16) -> Option<&'static [[u64; 4]]> {
+    match (t, r_f, r_p) {
+"""
+
+TEST = """
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grain::GrainLfsr;
+    use crate::Sbox;
+    use bellperson::bls::{Bls12, Fr, FrRepr};
+    use ff::PrimeField;
+
+    #[test]
+    fn precomputed_constants_match_grain() {
+        for t in (2..=17).chain([25, 33, 37].iter().cloned()) {
+            let (r_f, r_p) = crate::round_numbers(t - 1, &crate::Strength::Standard);
+            let precomputed = round_constants(t as u16, r_f as u16, r_p as u16).unwrap();
+            let generated = GrainLfsr::<Bls12>::new(255, Sbox::Quintic, t, r_f, r_p)
+                .take(t * (r_f + r_p))
+                .collect::<Vec<_>>();
+            let precomputed = precomputed
+                .iter()
+                .map(|limbs| Fr::from_repr(FrRepr(*limbs)).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(generated, precomputed, "width {}", t);
+        }
+        assert!(round_constants(18, 8, 59).is_none());
+        assert!(round_constants(3, 8, 56).is_none());
+    }
+}
+"""
+
+
+def limbs(x):
+    return "[%s]" % ", ".join("0x%016x" % ((x >> (64 * i)) & (2 ** 64 - 1)) for i in range(4))
+
+
+def main():
+    numbers = round_numbers()
+    tables = []
+    with open(PATH, "w") as f:
+        f.write(HEADER)
+        for t in WIDTHS:
+            full_rounds, partial_rounds = numbers[t]
+            f.write("        (%d, %d, %d) => Some(&T%d),\n" % (t, full_rounds, partial_rounds, t))
+            tables.append((t, full_rounds, partial_rounds, grain_round_constants(t, full_rounds, partial_rounds)))
+        f.write("        _ => None,\n    }\n}\n")
+        for t, full_rounds, partial_rounds, constants in tables:
+            f.write("\n// Width %d, R_F = %d, R_P = %d.\n" % (t, full_rounds, partial_rounds))
+            f.write("#[rustfmt::skip]\nconst T%d: [[u64; 4]; %d] = [\n" % (t, len(constants)))
+            for x in constants:
+                f.write("    %s,\n" % limbs(x))
+            f.write("];\n")
+        f.write(TEST)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,165 @@
+#!/usr/bin/env python3
+"""Generates tests/vectors/poseidon_bn254.json, the test vectors of circomlib's Poseidon over BN254 checked by
+tests/compatibility.rs against `bn254::circom_hash`.
+
+Each vector is a hash of circomlib's `poseidon`: the state is a zero followed by the inputs, and the output is the
+first element of the state after the permutation. This script implements the permutation independently of neptune,
+from the reference script generate_parameters_grain.sage, as circomlib generated its constants:
+
+- The round numbers are 8 full rounds and the partial rounds of circomlib for each width.
+- The round constants are generated by the Grain LFSR of the reference script, seeded with the S-box identifier 0
+  and the 254-bit prime field.
+- The MDS matrix is the Cauchy matrix `M[i][j] = 1 / (x_i + y_j)` of `2t` points sampled from the LFSR after the
+  round constants, resampled while two points coincide or a sum is zero. The reference script also resamples matrices
+  failing its checks against subspace trails, which this script omits, as the first matrix of each of circomlib's
+  widths passes them, as `bn254::circom_constants` checks.
+
+Before writing, the digests of `poseidon([1, 2, ..., n])` for 1, 2, 4, 5, and 6 inputs are checked against those of
+circomlibjs.
+
+Field elements are big-endian hex strings, prefixed by `0x` and zero-padded to 64 digits.
+
+Usage, from the root of the repository:
+
+    python3 scripts/generate_bn254_vectors.py > tests/vectors/poseidon_bn254.json
+"""
+
+import json
+import random
+import sys
+
+# The modulus of the BN254 scalar field.
+P = 0x30644E72E131A029B85045B68181585D2833E84879B9709143E1F593F0000001
+FIELD_SIZE = 254
+# The S-box identifier the reference script seeds the Grain LFSR with for x^5.
+SBOX = 0
+ALPHA = 5
+
+FULL_ROUNDS = 8
+# circomlib's partial rounds for widths 2 through 17, i.e. 1 through 16 inputs, indexed by `t - 2`.
+PARTIAL_ROUNDS = [56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68]
+RANDOM_VECTORS_PER_ARITY = 1
+
+# The digests of circomlibjs's `poseidon` for the preimages `[1, 2, ..., n]`, by n.
+CIRCOMLIBJS_DIGESTS = {
+    1: 0x29176100EAA962BDC1FE6C654D6A3C130E96A4D1168B33848B897DC502820133,
+    2: 0x115CC0F5E7D690413DF64C6B9662E9CF2A3617F2743245519E19607A4417189A,
+    4: 0x299C867DB6C1FDD79DCEFA40E4510B9837E60EBB1CE0663DBAA525DF65250465,
+    5: 0x0DAB9449E4A1398A15224C0B15A49D598B2174D305A316C918125F8FEEB123C0,
+    6: 0x2D1A03850084442813C8EBF094DEA47538490A68B05F2239134A4CCA2F6302E1,
+}
+
+
+def grain(t, full_rounds, partial_rounds):
+    """Returns a function sampling `FIELD_SIZE` bits at a time from the Grain LFSR of the reference script."""
+
+    def bits(n, value):
+        return [(value >> i) & 1 for i in reversed(range(n))]
+
+    state = (
+        bits(2, 1)
+        + bits(4, SBOX)
+        + bits(12, FIELD_SIZE)
+        + bits(12, t)
+        + bits(10, full_rounds)
+        + bits(10, partial_rounds)
+        + [1] * 30
+    )
+
+    def new_bit():
+        bit = state[62] ^ state[51] ^ state[38] ^ state[23] ^ state[13] ^ state[0]
+        state.pop(0)
+        state.append(bit)
+        return bit
+
+    for _ in range(160):
+        new_bit()
+
+    def next_bit():
+        # Self-shrinking: a bit is output only after a 1.
+        while not new_bit():
+            new_bit()
+        return new_bit()
+
+    def sample():
+        value = 0
+        for _ in range(FIELD_SIZE):
+            value = (value << 1) | next_bit()
+        return value
+
+    return sample
+
+
+class Permutation:
+    def __init__(self, t):
+        self.t = t
+        self.partial_rounds = PARTIAL_ROUNDS[t - 2]
+        sample = grain(t, FULL_ROUNDS, self.partial_rounds)
+
+        self.round_constants = []
+        while len(self.round_constants) < t * (FULL_ROUNDS + self.partial_rounds):
+            value = sample()
+            if value < P:
+                self.round_constants.append(value)
+
+        while True:
+            points = [sample() % P for _ in range(2 * t)]
+            xs, ys = points[:t], points[t:]
+            if len(set(points)) == len(points) and all((x + y) % P for x in xs for y in ys):
+                break
+        self.mds = [[pow(x + y, P - 2, P) for y in ys] for x in xs]
+
+    def __call__(self, state):
+        t, half = self.t, FULL_ROUNDS // 2
+        for r in range(FULL_ROUNDS + self.partial_rounds):
+            keys = self.round_constants[r * t : (r + 1) * t]
+            state = [(x + k) % P for x, k in zip(state, keys)]
+            full = r < half or r >= half + self.partial_rounds
+            state = [pow(x, ALPHA, P) if full or i == 0 else x for i, x in enumerate(state)]
+            # The reference script multiplies the state as a column vector.
+            state = [sum(m * x for m, x in zip(row, state)) % P for row in self.mds]
+        return state
+
+
+def to_hex(x):
+    return "0x%064x" % x
+
+
+def main():
+    rng = random.Random(0x63697263756D)
+    vectors = []
+    for arity in range(1, len(PARTIAL_ROUNDS) + 1):
+        permutation = Permutation(arity + 1)
+        inputs = [
+            list(range(1, arity + 1)),
+            [0] * arity,
+            [P - 1] * arity,
+        ]
+        inputs += [[rng.randrange(P) for _ in range(arity)] for _ in range(RANDOM_VECTORS_PER_ARITY)]
+        for preimage in inputs:
+            output = permutation([0] + preimage)[0]
+            if preimage == list(range(1, arity + 1)) and arity in CIRCOMLIBJS_DIGESTS:
+                assert output == CIRCOMLIBJS_DIGESTS[arity], "arity %d does not match circomlibjs" % arity
+            vectors.append(
+                {
+                    "arity": arity,
+                    "inputs": [to_hex(x) for x in preimage],
+                    "expected_output": to_hex(output),
+                }
+            )
+
+    json.dump(
+        {
+            "field": "bn254",
+            "modulus": to_hex(P),
+            "hash_type": "circomlib",
+            "vectors": vectors,
+        },
+        sys.stdout,
+        indent=2,
+    )
+    sys.stdout.write("\n")
+
+
+if __name__ == "__main__":
+    main()
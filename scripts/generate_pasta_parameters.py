@@ -0,0 +1,48 @@
+#!/usr/bin/env python3
+"""Writes the round constants of the Pasta fields to parameters/, in the format of create_rcs_grain.sage, for auditing.
+
+The constants are generated by the Grain LFSR of generate_test_vectors.py, as neptune generates them for
+`neptune::pasta::Pallas` and `neptune::pasta::Vesta`: both moduli are 255-bit primes, so the round numbers are those
+of BLS12-381, read from parameters/round_numbers.txt. `PoseidonConstants::from_reference_params` reads the files back.
+
+Usage, from the root of the repository:
+
+    python3 scripts/generate_pasta_parameters.py
+"""
+
+from generate_test_vectors import FIELD_SIZE, SBOX, grain_round_constants, round_numbers, to_hex
+
+# The moduli of the scalar fields of Pallas and Vesta, each the base field of the other curve.
+PALLAS_SCALAR = 0x40000000000000000000000000000000224698FC0994A8DD8C46EB2100000001
+VESTA_SCALAR = 0x40000000000000000000000000000000224698FC094CF91B992D30ED00000001
+
+WIDTHS = [3, 9]
+
+HEADER = """\
+# Using https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/create_rcs_grain.sage
+# Output after header was generated by scripts/generate_pasta_parameters.py, a port of the script, with the arguments:
+# sage create_rcs_grain.sage {arguments}
+"""
+
+
+def write_parameters(p, t, full_rounds, partial_rounds):
+    arguments = "1 %d %d %d %d %d %X" % (SBOX, FIELD_SIZE, t, full_rounds, partial_rounds, p)
+    constants = grain_round_constants(t, full_rounds, partial_rounds, p)
+    path = "parameters/round_constants-%s.txt" % arguments.replace(" ", "-")
+    with open(path, "w") as f:
+        f.write(HEADER.format(arguments=arguments))
+        f.write("\nR_F: %d\nR_P: %d\n" % (full_rounds, partial_rounds))
+        f.write("# Constants: %d\n" % len(constants))
+        f.write("Round constants for GF(p):\n")
+        f.write("[%s]\n" % ", ".join("'%s'" % to_hex(x) for x in constants))
+
+
+def main():
+    numbers = round_numbers()
+    for p in [PALLAS_SCALAR, VESTA_SCALAR]:
+        for t in WIDTHS:
+            write_parameters(p, t, *numbers[t])
+
+
+if __name__ == "__main__":
+    main()
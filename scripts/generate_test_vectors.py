@@ -0,0 +1,157 @@
+#!/usr/bin/env python3
+"""Generates tests/vectors/poseidon_bls12_381.json, the Poseidon test vectors checked by tests/compatibility.rs.
+
+Each vector is a Merkle tree hash with the standard strength, as `Poseidon::hash` computes with
+`PoseidonConstants::new()`: the state is the domain tag `2^arity - 1` followed by the inputs, and the output is the
+second element of the state after the permutation. This script implements the permutation independently of neptune,
+from the reference scripts:
+
+- The round numbers are read from parameters/round_numbers.txt, written by `cargo run --bin gen_round_numbers`.
+- The round constants are generated by the Grain LFSR of create_rcs_grain.sage, seeded as neptune seeds it.
+- The MDS matrix is the Cauchy matrix `M[i][j] = 1 / (i + j + t)`.
+
+Field elements are big-endian hex strings, prefixed by `0x` and zero-padded to 64 digits.
+
+Usage, from the root of the repository, after changing the parameters:
+
+    python3 scripts/generate_test_vectors.py > tests/vectors/poseidon_bls12_381.json
+"""
+
+import json
+import random
+import sys
+
+# The modulus of the BLS12-381 scalar field.
+P = 0x73EDA753299D7D483339D80809A1D80553BDA402FFFE5BFEFFFFFFFF00000001
+FIELD_SIZE = 255
+# The S-box identifier neptune seeds the Grain LFSR with for x^5.
+SBOX = 1
+ALPHA = 5
+
+# The arities of the first vectors, whose random inputs are drawn from one generator in this order, so that they stay
+# those first published.
+FIRST_ARITIES = [2, 4, 8, 11, 16, 24, 36]
+# The arities added since, each drawing its random inputs from its own generator, seeded by the arity, so that adding
+# an arity leaves the inputs of every other unchanged.
+ADDED_ARITIES = [23, 32]
+RANDOM_VECTORS_PER_ARITY = 6
+SEED = 0x6E657074756E65
+
+
+def round_numbers(path="parameters/round_numbers.txt"):
+    """Returns the full and partial rounds of each width, by width."""
+    numbers = {}
+    with open(path) as f:
+        for line in f:
+            if line.startswith("#") or not line.strip():
+                continue
+            t, full_rounds, partial_rounds = (int(x) for x in line.split()[:3])
+            numbers[t] = (full_rounds, partial_rounds)
+    return numbers
+
+
+def grain_round_constants(t, full_rounds, partial_rounds, p=P):
+    """Returns the `t * (R_F + R_P)` round constants of create_rcs_grain.sage over the 255-bit prime field `p`."""
+
+    def bits(n, value):
+        return [(value >> i) & 1 for i in reversed(range(n))]
+
+    state = (
+        bits(2, 1)
+        + bits(4, SBOX)
+        + bits(12, FIELD_SIZE)
+        + bits(12, t)
+        + bits(10, full_rounds)
+        + bits(10, partial_rounds)
+        + [1] * 30
+    )
+
+    def new_bit():
+        bit = state[62] ^ state[51] ^ state[38] ^ state[23] ^ state[13] ^ state[0]
+        state.pop(0)
+        state.append(bit)
+        return bit
+
+    for _ in range(160):
+        new_bit()
+
+    def next_bit():
+        # Self-shrinking: a bit is output only after a 1.
+        while not new_bit():
+            new_bit()
+        return new_bit()
+
+    constants = []
+    while len(constants) < t * (full_rounds + partial_rounds):
+        value = 0
+        for _ in range(FIELD_SIZE):
+            value = (value << 1) | next_bit()
+        if value < p:
+            constants.append(value)
+    return constants
+
+
+class Permutation:
+    def __init__(self, t, full_rounds, partial_rounds, p=P):
+        self.t = t
+        self.p = p
+        self.full_rounds = full_rounds
+        self.partial_rounds = partial_rounds
+        self.round_constants = grain_round_constants(t, full_rounds, partial_rounds, p)
+        self.mds = [[pow(i + j + t, p - 2, p) for j in range(t)] for i in range(t)]
+
+    def __call__(self, state):
+        t, p = self.t, self.p
+        half = self.full_rounds // 2
+        for r in range(self.full_rounds + self.partial_rounds):
+            keys = self.round_constants[r * t : (r + 1) * t]
+            state = [(x + k) % p for x, k in zip(state, keys)]
+            full = r < half or r >= half + self.partial_rounds
+            state = [pow(x, ALPHA, p) if full or i == 0 else x for i, x in enumerate(state)]
+            state = [sum(state[i] * self.mds[i][j] for i in range(t)) % p for j in range(t)]
+        return state
+
+
+def to_hex(x):
+    return "0x%064x" % x
+
+
+def main():
+    numbers = round_numbers()
+    first_rng = random.Random(SEED)
+    vectors = []
+    for arity in sorted(FIRST_ARITIES + ADDED_ARITIES):
+        rng = first_rng if arity in FIRST_ARITIES else random.Random(SEED + (arity << 64))
+        permutation = Permutation(arity + 1, *numbers[arity + 1])
+        inputs = [
+            list(range(arity)),
+            [0] * arity,
+            [P - 1] * arity,
+        ]
+        inputs += [[rng.randrange(P) for _ in range(arity)] for _ in range(RANDOM_VECTORS_PER_ARITY)]
+        for preimage in inputs:
+            output = permutation([(1 << arity) - 1] + preimage)[1]
+            vectors.append(
+                {
+                    "arity": arity,
+                    "inputs": [to_hex(x) for x in preimage],
+                    "expected_output": to_hex(output),
+                }
+            )
+
+    json.dump(
+        {
+            "field": "bls12-381",
+            "modulus": to_hex(P),
+            "hash_type": "merkle-tree",
+            "strength": "standard",
+            "vectors": vectors,
+        },
+        sys.stdout,
+        indent=2,
+    )
+    sys.stdout.write("\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -1,6 +1,7 @@
+#[cfg(feature = "opencl")]
 use rust_gpu_tools::opencl;
 use std::fmt::{self, Debug};
-use std::marker::PhantomData;
+#[cfg(feature = "gpu")]
 use std::sync::{Arc, Mutex};
 
 use crate::error::Error;
@@ -12,6 +13,7 @@ use crate::triton::cl;
 use crate::{Arity, BatchHasher, Strength, DEFAULT_STRENGTH};
 use bellperson::bls::Fr;
 use generic_array::GenericArray;
+#[cfg(any(feature = "gpu", feature = "opencl"))]
 use rust_gpu_tools::opencl::GPUSelector;
 
 #[cfg(feature = "gpu")]
@@ -69,7 +71,8 @@ impl<A> Batcher<A>
 where
     A: Arity<Fr>,
 {
-    pub(crate) fn t(&self) -> BatcherType {
+    /// Returns the type of the batcher.
+    pub fn t(&self) -> BatcherType {
         match self {
             #[cfg(feature = "gpu")]
             Batcher::GPU(_) => BatcherType::GPU,
@@ -135,19 +138,14 @@ where
         }
     }
 
-    #[cfg(feature = "gpu")]
-    pub(crate) fn futhark_context(&self) -> Option<Arc<Mutex<FutharkContext>>> {
-        match self {
-            Batcher::GPU(b) => Some(b.futhark_context()),
-            _ => None,
-        }
-    }
-
-    #[cfg(feature = "opencl")]
-    pub(crate) fn device(&self) -> Option<opencl::Device> {
+    /// Returns the type of a batcher hashing on the same device as this one, unless this one hashes on the CPU.
+    pub(crate) fn device_type(&self) -> Option<BatcherType> {
         match self {
-            Batcher::OpenCL(b) => Some(b.device()),
-            _ => None,
+            Batcher::CPU(_) => None,
+            #[cfg(feature = "gpu")]
+            Batcher::GPU(b) => Some(BatcherType::FromFutharkContext(b.futhark_context())),
+            #[cfg(feature = "opencl")]
+            Batcher::OpenCL(b) => Some(BatcherType::FromDevice(b.device())),
         }
     }
 }
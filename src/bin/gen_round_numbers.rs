@@ -0,0 +1,34 @@
+//! Writes the round numbers with the security margin for the width of each supported arity to
+//! `parameters/round_numbers.txt`, or to the path given as the only argument.
+//!
+//! Each line holds the five columns `t R_F R_P sbox_cost size_cost`, as read by `RoundNumbers::from_line`.
+
+use neptune::round_numbers::{DEFAULT_PRIME_BITLEN, DEFAULT_SECURITY_LEVEL};
+use neptune::RoundNumbers;
+use std::env;
+use std::fs;
+
+const DEFAULT_PATH: &str = "parameters/round_numbers.txt";
+
+// Widths for typenum arities 1 through 36.
+const WIDTHS: std::ops::RangeInclusive<usize> = 2..=37;
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_PATH.to_string());
+
+    let mut output = format!(
+        "# Generated by `cargo run --bin gen_round_numbers` for n = {}, M = {}, and the x^5 S-box.\n\
+         # t R_F R_P sbox_cost size_cost\n",
+        DEFAULT_PRIME_BITLEN, DEFAULT_SECURITY_LEVEL
+    );
+    for t in WIDTHS {
+        let round_numbers = RoundNumbers::compute(t, true)
+            .unwrap_or_else(|e| panic!("no round numbers for t = {}: {}", t, e));
+        output.push_str(&round_numbers.to_line(t));
+        output.push('\n');
+    }
+
+    fs::write(&path, output).unwrap_or_else(|e| panic!("cannot write {}: {}", path, e));
+}
@@ -0,0 +1,542 @@
+use crate::error::RoundNumberError;
+use crate::hash_type::HashType;
+use crate::matrix::Matrix;
+use crate::mds::{MDSMatrices, SparseMatrix};
+use crate::poseidon::{Arity, PoseidonConstants};
+use crate::round_numbers::{
+    check_round_numbers, SecurityMargin, MAX_SECURITY_LEVEL, MIN_SECURITY_LEVEL,
+};
+use crate::{alpha_is_coprime, Error, Sbox, Strength};
+use ff::{PrimeField, PrimeFieldRepr, ScalarEngine};
+use generic_array::typenum::Unsigned;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+// The first bytes of the binary format.
+const MAGIC: &[u8; 4] = b"NPTC";
+
+// The version of the binary format written by `PoseidonConstants::write_bytes`. Reading any other version fails, so
+// that caches written by other versions are regenerated rather than misread.
+const FORMAT_VERSION: u8 = 3;
+
+// The length of the BLAKE2s checksum ending the format.
+const CHECKSUM_BYTES: usize = 32;
+
+// The binary format, with integers as little-endian `u64`s and field elements as their little-endian canonical
+// representation:
+//
+// - `MAGIC` and `FORMAT_VERSION`
+// - the length in bytes of a field element, as a `u8`, followed by the modulus
+// - the width, the strength (a tag, followed by the round numbers of `Strength::Custom`), the S-box identifier of the
+//   reference script as a `u8`, and the hash type (a tag, followed by the length of `HashType::ConstantLength`)
+// - the full and partial rounds, the security level, the extra full rounds and partial round multiplier (as an
+//   `f32`) of the security margin, and the domain tag
+// - the seed of `PoseidonConstants::new_seeded` (a tag, followed by its length and bytes if there is a seed)
+// - the round constants and compressed round constants
+// - the MDS matrices `m`, `m_inv`, `m_hat`, `m_hat_inv`, `m_prime`, and `m_double_prime`, row by row
+// - the pre-sparse matrix, then `w_hat` and `v_rest` of each sparse matrix
+// - the BLAKE2s-256 hash of the preceding bytes
+//
+// The number of elements of each is determined by the width and round numbers.
+
+impl<E, A> PoseidonConstants<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Writes the constants to `writer` in a versioned binary format, including the optimized constants, so that
+    /// `read_bytes` loads them without deriving anything.
+    ///
+    /// Unlike serialization with serde, which derives the optimized constants again when loading, this trades size for
+    /// load time.
+    pub fn write_bytes<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(FORMAT_VERSION);
+
+        let modulus = E::Fr::char();
+        bytes.push((modulus.as_ref().len() * 8) as u8);
+        modulus
+            .write_le(&mut bytes)
+            .expect("writing to a Vec cannot fail");
+
+        write_u64(&mut bytes, self.width());
+        match self.strength {
+            Strength::Standard => bytes.push(0),
+            Strength::Strengthened => bytes.push(1),
+            Strength::Custom { full, partial } => {
+                bytes.push(2);
+                write_u64(&mut bytes, full);
+                write_u64(&mut bytes, partial);
+            }
+        }
+        bytes.push(self.sbox.id());
+        match self.hash_type {
+            HashType::MerkleTree => bytes.push(0),
+            HashType::ConstantLength(length) => {
+                bytes.push(1);
+                write_u64(&mut bytes, length);
+            }
+            HashType::Encryption => bytes.push(2),
+            ref hash_type => {
+                return Err(Error::Other(format!(
+                    "unsupported hash type: {:?}",
+                    hash_type
+                )))
+            }
+        }
+
+        write_u64(&mut bytes, self.full_rounds);
+        write_u64(&mut bytes, self.partial_rounds);
+        write_u64(&mut bytes, self.security_level);
+        write_u64(&mut bytes, self.security_margin.extra_full_rounds);
+        bytes.extend_from_slice(&self.security_margin.partial_multiplier.to_le_bytes());
+        write_elements::<E>(&mut bytes, &[self.domain_tag]);
+        match self.seed {
+            None => bytes.push(0),
+            Some(ref seed) => {
+                bytes.push(1);
+                write_u64(&mut bytes, seed.len());
+                bytes.extend_from_slice(seed);
+            }
+        }
+        write_elements::<E>(&mut bytes, &self.round_constants);
+        write_elements::<E>(&mut bytes, &self.compressed_round_constants);
+
+        let mds = &self.mds_matrices;
+        for m in [
+            &mds.m,
+            &mds.m_inv,
+            &mds.m_hat,
+            &mds.m_hat_inv,
+            &mds.m_prime,
+            &mds.m_double_prime,
+            &*self.pre_sparse_matrix,
+        ]
+        .iter()
+        {
+            for row in m.iter() {
+                write_elements::<E>(&mut bytes, row);
+            }
+        }
+        for sparse_matrix in self.sparse_matrixes.iter() {
+            write_elements::<E>(&mut bytes, &sparse_matrix.w_hat);
+            write_elements::<E>(&mut bytes, &sparse_matrix.v_rest);
+        }
+
+        let checksum = blake2s_simd::blake2s(&bytes);
+        bytes.extend_from_slice(checksum.as_bytes());
+        writer
+            .write_all(&bytes)
+            .map_err(|e| Error::Other(e.to_string()))
+    }
+
+    /// Reads constants written by `write_bytes` from `reader`, to its end.
+    ///
+    /// Bytes that are not in the binary format, are of another version of the format, fail the checksum, or are for
+    /// another field, arity, or number of constants return `Error::InvalidConstants`, as do round numbers that fail the
+    /// security inequalities at the security level they were written with. A security level out of range returns
+    /// `Error::InvalidSecurityLevel`. Field elements that cannot be decoded return `Error::DecodingError`. The
+    /// optimized constants are read as written, with only their dimensions checked.
+    pub fn read_bytes<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let invalid = |message: String| Err(Error::InvalidConstants(message));
+
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::Other(e.to_string()))?;
+
+        if bytes.len() < MAGIC.len() + 1 + CHECKSUM_BYTES || &bytes[..MAGIC.len()] != MAGIC {
+            return invalid("not in the binary format".to_string());
+        }
+        let version = bytes[MAGIC.len()];
+        if version != FORMAT_VERSION {
+            return invalid(format!(
+                "format version {} is not {}",
+                version, FORMAT_VERSION
+            ));
+        }
+        let (contents, checksum) = bytes.split_at(bytes.len() - CHECKSUM_BYTES);
+        if blake2s_simd::blake2s(contents).as_bytes() != checksum {
+            return invalid("checksum does not match".to_string());
+        }
+
+        let mut input = Input {
+            bytes: &contents[MAGIC.len() + 1..],
+        };
+
+        let modulus = E::Fr::char();
+        let element_bytes = input.u8()? as usize;
+        let mut repr = <E::Fr as PrimeField>::Repr::default();
+        if element_bytes != modulus.as_ref().len() * 8
+            || repr.read_le(input.take(element_bytes)?).is_err()
+            || repr != modulus
+        {
+            return invalid("modulus does not match the field".to_string());
+        }
+
+        let width = A::ConstantsSize::to_usize();
+        let serialized_width = input.usize()?;
+        if serialized_width != width {
+            return invalid(format!(
+                "width {} does not match {}",
+                serialized_width, width
+            ));
+        }
+        let strength = match input.u8()? {
+            0 => Strength::Standard,
+            1 => Strength::Strengthened,
+            2 => Strength::Custom {
+                full: input.usize()?,
+                partial: input.usize()?,
+            },
+            tag => return invalid(format!("unknown strength: {}", tag)),
+        };
+        let sbox = match input.u8()? {
+            0 => Sbox::Cubic,
+            1 => Sbox::Quintic,
+            2 => Sbox::Inverse,
+            tag => return invalid(format!("unknown S-box: {}", tag)),
+        };
+        let hash_type = match input.u8()? {
+            0 => HashType::MerkleTree,
+            1 => match input.usize()? {
+                length if length > 0 && length < width => HashType::ConstantLength(length),
+                length => {
+                    return invalid(format!(
+                        "constant length {} is not between 1 and the arity",
+                        length
+                    ))
+                }
+            },
+            2 => HashType::Encryption,
+            tag => return invalid(format!("unknown hash type: {}", tag)),
+        };
+
+        let full_rounds = input.usize()?;
+        let partial_rounds = input.usize()?;
+        // Each round has more than a byte of constants, which bounds the counts below.
+        if full_rounds.saturating_add(partial_rounds) > input.bytes.len() {
+            return invalid("unexpected end of input".to_string());
+        }
+        if full_rounds % 2 != 0 {
+            return Err(RoundNumberError::OddFullRounds { rf: full_rounds }.into());
+        }
+        if let Strength::Custom { full, partial } = strength {
+            if (full, partial) != (full_rounds, partial_rounds) {
+                return invalid(format!(
+                    "round numbers ({}, {}) do not match custom strength ({}, {})",
+                    full_rounds, partial_rounds, full, partial
+                ));
+            }
+        }
+        let security_level = input.usize()?;
+        if security_level < MIN_SECURITY_LEVEL || security_level > MAX_SECURITY_LEVEL {
+            return Err(Error::InvalidSecurityLevel(security_level));
+        }
+        let security_margin = SecurityMargin {
+            extra_full_rounds: input.usize()?,
+            partial_multiplier: input.f32()?,
+        };
+        security_margin.check()?;
+        let alpha = sbox.alpha();
+        if alpha > 0 && !alpha_is_coprime::<E::Fr>(alpha as u64) {
+            return Err(Error::InvalidAlpha(alpha));
+        }
+        check_round_numbers(
+            width,
+            full_rounds,
+            partial_rounds,
+            &strength,
+            E::Fr::NUM_BITS as usize,
+            security_level,
+            alpha,
+        )?;
+
+        let domain_tag = input.element::<E>()?;
+        let seed = match input.u8()? {
+            0 => None,
+            1 => {
+                let length = input.usize()?;
+                Some(input.take(length)?.to_vec())
+            }
+            tag => return invalid(format!("unknown seed tag: {}", tag)),
+        };
+        let round_constants = input.elements::<E>(width * (full_rounds + partial_rounds))?;
+        let compressed_round_constants =
+            input.elements::<E>(width * full_rounds + partial_rounds)?;
+
+        let m = input.matrix::<E>(width)?;
+        let m_inv = input.matrix::<E>(width)?;
+        let m_hat = input.matrix::<E>(width - 1)?;
+        let m_hat_inv = input.matrix::<E>(width - 1)?;
+        let m_prime = input.matrix::<E>(width)?;
+        let m_double_prime = input.matrix::<E>(width)?;
+        let pre_sparse_matrix = input.matrix::<E>(width)?;
+        let sparse_matrixes = (0..partial_rounds)
+            .map(|_| {
+                Ok(SparseMatrix {
+                    w_hat: input.elements::<E>(width)?,
+                    v_rest: input.elements::<E>(width - 1)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        if !input.bytes.is_empty() {
+            return invalid(format!("{} bytes left over", input.bytes.len()));
+        }
+
+        Ok(PoseidonConstants {
+            mds_matrices: Arc::new(MDSMatrices {
+                m,
+                m_inv,
+                m_hat,
+                m_hat_inv,
+                m_prime,
+                m_double_prime,
+            }),
+            round_constants,
+            compressed_round_constants: Arc::new(compressed_round_constants),
+            pre_sparse_matrix: Arc::new(pre_sparse_matrix),
+            sparse_matrixes: Arc::new(sparse_matrixes),
+            strength,
+            sbox,
+            domain_tag,
+            full_rounds,
+            half_full_rounds: full_rounds / 2,
+            partial_rounds,
+            hash_type,
+            seed,
+            security_level,
+            security_margin,
+            _a: PhantomData,
+        })
+    }
+}
+
+fn write_u64(bytes: &mut Vec<u8>, x: usize) {
+    bytes.extend_from_slice(&(x as u64).to_le_bytes());
+}
+
+fn write_elements<E: ScalarEngine>(bytes: &mut Vec<u8>, elements: &[E::Fr]) {
+    for element in elements {
+        element
+            .into_repr()
+            .write_le(&mut *bytes)
+            .expect("writing to a Vec cannot fail");
+    }
+}
+
+// The bytes remaining to be read.
+struct Input<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Input<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if n > self.bytes.len() {
+            return Err(Error::InvalidConstants(
+                "unexpected end of input".to_string(),
+            ));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn usize(&mut self) -> Result<usize, Error> {
+        let mut le_bytes = [0u8; 8];
+        le_bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(le_bytes) as usize)
+    }
+
+    fn f32(&mut self) -> Result<f32, Error> {
+        let mut le_bytes = [0u8; 4];
+        le_bytes.copy_from_slice(self.take(4)?);
+        Ok(f32::from_le_bytes(le_bytes))
+    }
+
+    fn element<E: ScalarEngine>(&mut self) -> Result<E::Fr, Error> {
+        let mut repr = <E::Fr as PrimeField>::Repr::default();
+        let bytes = self.take(repr.as_ref().len() * 8)?;
+        repr.read_le(bytes).map_err(|_| Error::DecodingError)?;
+        E::Fr::from_repr(repr).map_err(|_| Error::DecodingError)
+    }
+
+    fn elements<E: ScalarEngine>(&mut self, n: usize) -> Result<Vec<E::Fr>, Error> {
+        (0..n).map(|_| self.element::<E>()).collect()
+    }
+
+    fn matrix<E: ScalarEngine>(&mut self, size: usize) -> Result<Matrix<E::Fr>, Error> {
+        (0..size).map(|_| self.elements::<E>(size)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::Poseidon;
+    use crate::round_numbers::NO_SECURITY_MARGIN;
+    use crate::scalar_from_u64;
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U4, U8};
+
+    fn to_bytes<A: Arity<Fr>>(constants: &PoseidonConstants<Bls12, A>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        constants.write_bytes(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn hash<A: Arity<Fr>>(constants: &PoseidonConstants<Bls12, A>) -> Fr {
+        let preimage: Vec<Fr> = (0..A::to_usize())
+            .map(|i| scalar_from_u64(i as u64))
+            .collect();
+        Poseidon::new_with_preimage(&preimage, constants).hash()
+    }
+
+    fn round_trip<A: Arity<Fr>>(constants: PoseidonConstants<Bls12, A>) {
+        let bytes = to_bytes(&constants);
+        let read = PoseidonConstants::<Bls12, A>::read_bytes(&mut &bytes[..]).unwrap();
+
+        assert_eq!(constants, read);
+        assert_eq!(hash(&constants), hash(&read));
+        assert_eq!(bytes, to_bytes(&read));
+    }
+
+    fn read_error<A: Arity<Fr>>(bytes: &[u8]) -> String {
+        match PoseidonConstants::<Bls12, A>::read_bytes(&mut &bytes[..]) {
+            Ok(_) => panic!("read invalid constants"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    // Replaces the checksum of `bytes`, so that tampering is caught by the later checks.
+    fn with_checksum(mut bytes: Vec<u8>) -> Vec<u8> {
+        let contents = bytes.len() - CHECKSUM_BYTES;
+        let checksum = blake2s_simd::blake2s(&bytes[..contents]);
+        bytes[contents..].copy_from_slice(checksum.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        round_trip(PoseidonConstants::<Bls12, U2>::new());
+        round_trip(PoseidonConstants::<Bls12, U8>::new());
+        round_trip(PoseidonConstants::<Bls12, U4>::new_with_strength(
+            Strength::Strengthened,
+        ));
+        round_trip(PoseidonConstants::<Bls12, U8>::new_constant_length(5));
+        round_trip(PoseidonConstants::<Bls12, U4>::new_with_sbox(Sbox::Inverse));
+        round_trip(PoseidonConstants::<Bls12, U2>::new_seeded(
+            b"my-protocol-v1",
+        ));
+        round_trip(PoseidonConstants::<Bls12, U2>::new_with_strength(
+            Strength::Custom {
+                full: 8,
+                partial: 60,
+            },
+        ));
+        round_trip(PoseidonConstants::<Bls12, U2>::new_with_security_level(80));
+        round_trip(PoseidonConstants::<Bls12, U2>::new_with_security_margin(
+            NO_SECURITY_MARGIN,
+        ));
+    }
+
+    #[test]
+    fn binary_format_is_stable() {
+        // The constants of arity 2, written by `scripts/generate_binary_format_vector.py` independently of neptune,
+        // so that any change to the format, or to the constants, is caught. After changing either on purpose,
+        // increment `FORMAT_VERSION` and regenerate the file.
+        let golden: &[u8] = include_bytes!("../tests/vectors/poseidon_constants_bls12_381_2.bin");
+        let bytes = to_bytes(&PoseidonConstants::<Bls12, U2>::new());
+        assert_eq!(golden.len(), bytes.len());
+        for (i, (expected, actual)) in golden.iter().zip(bytes.iter()).enumerate() {
+            assert_eq!(expected, actual, "byte {} differs from the golden file", i);
+        }
+
+        let read = PoseidonConstants::<Bls12, U2>::read_bytes(&mut &golden[..]).unwrap();
+        assert_eq!(PoseidonConstants::<Bls12, U2>::new(), read);
+
+        // The tag of the seed precedes the round constants, compressed round constants, matrices, and sparse matrices,
+        // and a seed follows it, with its length.
+        let seed_tag = bytes.len() - CHECKSUM_BYTES - (189 + 79 + 5 * 9 + 2 * 4 + 55 * 5) * 32 - 1;
+        assert_eq!(0, bytes[seed_tag]);
+        let bytes = to_bytes(&PoseidonConstants::<Bls12, U2>::new_seeded(b"abc"));
+        assert_eq!(
+            &[1, 3, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', b'c'],
+            &bytes[seed_tag..seed_tag + 12]
+        );
+    }
+
+    #[test]
+    fn binary_read_rejects_invalid_bytes() {
+        let bytes = to_bytes(&PoseidonConstants::<Bls12, U2>::new());
+
+        assert_eq!(
+            "Invalid Poseidon constants: not in the binary format",
+            read_error::<U2>(&bytes[1..])
+        );
+
+        let mut version = bytes.clone();
+        version[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert_eq!(
+            "Invalid Poseidon constants: format version 4 is not 3",
+            read_error::<U2>(&version)
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[100] ^= 1;
+        assert_eq!(
+            "Invalid Poseidon constants: checksum does not match",
+            read_error::<U2>(&corrupted)
+        );
+
+        let mut truncated = bytes[..bytes.len() - CHECKSUM_BYTES - 1].to_vec();
+        truncated.extend_from_slice(&[0; CHECKSUM_BYTES]);
+        assert_eq!(
+            "Invalid Poseidon constants: unexpected end of input",
+            read_error::<U2>(&with_checksum(truncated))
+        );
+
+        let mut extended = bytes[..bytes.len() - CHECKSUM_BYTES].to_vec();
+        extended.extend_from_slice(&[0; 1 + CHECKSUM_BYTES]);
+        assert_eq!(
+            "Invalid Poseidon constants: 1 bytes left over",
+            read_error::<U2>(&with_checksum(extended))
+        );
+
+        assert_eq!(
+            "Invalid Poseidon constants: width 3 does not match 5",
+            read_error::<U4>(&bytes)
+        );
+    }
+
+    #[test]
+    fn binary_read_rejects_insecure_round_numbers() {
+        let mut bytes = to_bytes(&PoseidonConstants::<Bls12, U2>::new());
+        // The partial rounds follow the modulus, the width, the tags, and the full rounds.
+        let partial_rounds = MAGIC.len() + 2 + 32 + 8 + 3 + 8;
+        bytes[partial_rounds] = 40;
+
+        assert!(read_error::<U2>(&with_checksum(bytes)).starts_with(
+            "Round number error: Round numbers R_F = 8, R_P = 40 for width 3 fail the"
+        ));
+
+        // The round numbers of 80 bits are checked at the security level written after them.
+        let mut bytes = to_bytes(&PoseidonConstants::<Bls12, U2>::new_with_security_level(80));
+        let security_level = partial_rounds + 8;
+        assert_eq!(80, bytes[security_level]);
+        bytes[security_level] = 128;
+        assert!(read_error::<U2>(&with_checksum(bytes.clone())).starts_with("Round number error:"));
+        bytes[security_level + 1] = 1;
+        assert_eq!(
+            "Security level of 384 bits is outside of the supported range.",
+            read_error::<U2>(&with_checksum(bytes))
+        );
+    }
+}
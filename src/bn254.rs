@@ -0,0 +1,303 @@
+//! The scalar field of BN254 (alt-bn128), over which Ethereum-facing circuits, such as those of Circom and snarkjs,
+//! hash with circomlib's Poseidon.
+//!
+//! `Bn254` is a `ScalarEngine` over `Bn254Field`, so that `PoseidonConstants`, `Poseidon`, and the sponge hash over it
+//! natively with neptune's own parameters, as for the Pasta fields. These are not circomlib's: `circom_constants`
+//! returns the parameters circomlib uses, those of the reference script `generate_parameters_grain.sage` for a 254-bit
+//! prime, and `circom_hash` hashes with them as circomlib's `poseidon` does, so that digests can be checked on-chain.
+//!
+//! The circuits of `circuit` require a pairing-friendly `Engine`, which `Bn254` does not implement.
+//!
+//! # Example
+//!
+//! ```
+//! use generic_array::typenum::U2;
+//! use neptune::bn254::{circom_constants, circom_hash, Bn254Field};
+//! use neptune::scalar_from_u64;
+//!
+//! let constants = circom_constants::<U2>();
+//! let preimage: [Bn254Field; 2] = [scalar_from_u64(1), scalar_from_u64(2)];
+//! assert_eq!(
+//!     "Bn254Field(0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a)",
+//!     circom_hash(&preimage, &constants).to_string()
+//! );
+//! ```
+
+use crate::grain::GrainLfsr;
+use crate::hash_type::HashType;
+use crate::matrix::{self, Matrix};
+use crate::poseidon::{permute, Arity, PoseidonConstants};
+use crate::{Sbox, Strength, FIELD};
+use ff::{Field, PrimeField, ScalarEngine};
+use generic_array::typenum::Unsigned;
+use generic_array::GenericArray;
+use std::sync::Arc;
+
+/// The scalar field of BN254, of modulus `0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001`.
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617"]
+#[PrimeFieldGenerator = "7"]
+pub struct Bn254Field(Bn254FieldRepr);
+
+/// The BN254 curve, as a `ScalarEngine` over `Bn254Field`.
+#[derive(Clone, Copy, Debug)]
+pub struct Bn254;
+
+impl ScalarEngine for Bn254 {
+    type Fr = Bn254Field;
+}
+
+/// The full rounds of circomlib's Poseidon, at every width.
+pub const CIRCOM_FULL_ROUNDS: usize = 8;
+
+/// The partial rounds of circomlib's Poseidon for widths 2 through 17, i.e. 1 through 16 inputs, indexed by `t - 2`.
+pub const CIRCOM_PARTIAL_ROUNDS: [usize; 16] = [
+    56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68,
+];
+
+// The S-box identifier with which the reference script seeds the Grain LFSR for `x^5`, rather than `Sbox::id`.
+const REFERENCE_SBOX: u8 = 0;
+
+/// Returns the constants of circomlib's Poseidon for arity `A`: `CIRCOM_FULL_ROUNDS` and `CIRCOM_PARTIAL_ROUNDS`, the
+/// `x^5` S-box, and the round constants and MDS matrix of the reference script, generated by the Grain LFSR seeded with
+/// the bit length of the field and the round numbers. The MDS matrix is the Cauchy matrix `1 / (x_i + y_j)` of points
+/// sampled from the LFSR after the round constants.
+///
+/// Hash with `circom_hash`: hashing with `Poseidon` puts a domain tag in the first element of the state and returns
+/// the second, so its digests are not circomlib's.
+///
+/// # Panics
+///
+/// Panics unless the arity is 1 through 16.
+pub fn circom_constants<A: Arity<Bn254Field>>() -> PoseidonConstants<Bn254, A> {
+    let t = A::to_usize() + 1;
+    assert!(
+        t >= 2 && t - 2 < CIRCOM_PARTIAL_ROUNDS.len(),
+        "circomlib's Poseidon supports 1 through 16 inputs, not {}",
+        t - 1
+    );
+    let (rf, rp) = (CIRCOM_FULL_ROUNDS, CIRCOM_PARTIAL_ROUNDS[t - 2]);
+
+    let mut grain = GrainLfsr::<Bn254>::from_seed(
+        FIELD,
+        REFERENCE_SBOX,
+        Bn254Field::NUM_BITS as u16,
+        t as u16,
+        rf as u16,
+        rp as u16,
+        None,
+    );
+    let round_constants = grain.by_ref().take(t * (rf + rp)).collect();
+    let mds_matrix = reference_mds_matrix(&mut grain, t);
+
+    PoseidonConstants::from_parts(
+        Strength::Custom {
+            full: rf,
+            partial: rp,
+        },
+        HashType::MerkleTree,
+        Sbox::Quintic,
+        rf,
+        rp,
+        round_constants,
+        // The reference script multiplies the state as a column vector, and the permutation as a row vector.
+        matrix::transpose::<Bn254>(&mds_matrix),
+        None,
+    )
+}
+
+// The identifier of circomlib's constants in the cache of `PoseidonConstants::shared`, after those of the strengths.
+const CIRCOM_SHARED_ID: u8 = 2;
+
+/// Returns the constants of `circom_constants` for arity `A`, created once per process and shared, as
+/// `PoseidonConstants::shared` shares neptune's own.
+///
+/// # Panics
+///
+/// Panics as `circom_constants` does.
+pub fn shared_circom_constants<A>() -> Arc<PoseidonConstants<Bn254, A>>
+where
+    A: Arity<Bn254Field> + Send + Sync + 'static,
+{
+    PoseidonConstants::shared_by(CIRCOM_SHARED_ID, circom_constants::<A>)
+}
+
+// The matrix of the reference script's `generate_matrix`: the Cauchy matrix `M[i][j] = 1 / (x_i + y_j)` of `2t`
+// distinct points sampled from `grain`, sampled again whenever two points coincide or a sum is zero, or the matrix
+// fails the script's checks against infinitely long subspace trails.
+fn reference_mds_matrix(grain: &mut GrainLfsr<Bn254>, t: usize) -> Matrix<Bn254Field> {
+    loop {
+        let points: Vec<Bn254Field> = (0..2 * t).map(|_| grain.next_reduced()).collect();
+        if (1..points.len()).any(|i| points[..i].contains(&points[i])) {
+            continue;
+        }
+
+        let (xs, ys) = points.split_at(t);
+        let matrix: Option<Matrix<Bn254Field>> = xs
+            .iter()
+            .map(|x| {
+                ys.iter()
+                    .map(|y| {
+                        let mut sum = *x;
+                        sum.add_assign(y);
+                        sum.inverse()
+                    })
+                    .collect()
+            })
+            .collect();
+        if let Some(matrix) = matrix {
+            let transposed = matrix::transpose::<Bn254>(&matrix);
+            if crate::mds::security::check_subspace_trails::<Bn254>(&transposed).is_ok() {
+                return matrix;
+            }
+        }
+    }
+}
+
+/// Hashes `preimage` as circomlib's `poseidon` does, with `constants` from `circom_constants`: the permutation is
+/// applied to a zero followed by the preimage, and the digest is the first element of the state.
+///
+/// # Panics
+///
+/// Panics unless the length of `preimage` is the arity.
+pub fn circom_hash<A: Arity<Bn254Field>>(
+    preimage: &[Bn254Field],
+    constants: &PoseidonConstants<Bn254, A>,
+) -> Bn254Field {
+    assert_eq!(
+        A::to_usize(),
+        preimage.len(),
+        "preimage length does not match the arity"
+    );
+    let mut elements = vec![Bn254Field::zero()];
+    elements.extend_from_slice(preimage);
+    let mut state = GenericArray::clone_from_slice(&elements);
+    permute(&mut state, constants);
+    state[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scalar_from_u64;
+    use generic_array::typenum::{U1, U17, U2, U4, U5, U6};
+
+    fn field(hex: &str) -> String {
+        format!("Bn254Field({})", hex)
+    }
+
+    // The circomlib digest of `1, 2, ..., arity`.
+    fn digest<A: Arity<Bn254Field>>() -> String {
+        let preimage = (1..=A::to_u64())
+            .map(scalar_from_u64::<Bn254Field>)
+            .collect::<Vec<_>>();
+        circom_hash(&preimage, &circom_constants::<A>()).to_string()
+    }
+
+    #[test]
+    fn circom_parameters() {
+        // The first and last round constants and the MDS matrix of circomlib's `poseidon_constants` for width 3.
+        let constants = circom_constants::<U2>();
+        assert_eq!((8, 57), (constants.full_rounds, constants.partial_rounds));
+        assert_eq!(195, constants.round_constants.len());
+        assert_eq!(
+            field("0x0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e"),
+            constants.round_constants[0].to_string()
+        );
+        assert_eq!(
+            field("0x1da55cc900f0d21f4a3e694391918a1b3c23b2ac773c6b3ef88e2e4228325161"),
+            constants.round_constants[194].to_string()
+        );
+
+        let expected = [
+            [
+                "0x109b7f411ba0e4c9b2b70caf5c36a7b194be7c11ad24378bfedb68592ba8118b",
+                "0x16ed41e13bb9c0c66ae119424fddbcbc9314dc9fdbdeea55d6c64543dc4903e0",
+                "0x2b90bba00fca0589f617e7dcbfe82e0df706ab640ceb247b791a93b74e36736d",
+            ],
+            [
+                "0x2969f27eed31a480b9c36c764379dbca2cc8fdd1415c3dded62940bcde0bd771",
+                "0x2e2419f9ec02ec394c9871c832963dc1b89d743c8c7b964029b2311687b1fe23",
+                "0x101071f0032379b697315876690f053d148d4e109f5fb065c8aacc55a0f89bfa",
+            ],
+            [
+                "0x143021ec686a3f330d5f9e654638065ce6cd79e28c5b3753326244ee65a1b1a7",
+                "0x176cc029695ad02582a70eff08a6fd99d057e12e58e7d7b6b16cdfabc8ee2911",
+                "0x19a3fc0a56702bf417ba7fee3802593fa644470307043f7773279cd71d25d5e0",
+            ],
+        ];
+        let mds_matrix = matrix::transpose::<Bn254>(&constants.mds_matrices.m);
+        for (row, expected_row) in mds_matrix.iter().zip(expected.iter()) {
+            for (entry, expected_entry) in row.iter().zip(expected_row.iter()) {
+                assert_eq!(field(expected_entry), entry.to_string());
+            }
+        }
+        constants.validate().unwrap();
+    }
+
+    #[test]
+    fn circom_permutation() {
+        // The test vector of the reference implementation for `poseidonperm_x5_254_3`.
+        let constants = circom_constants::<U2>();
+        let mut state = GenericArray::clone_from_slice(&[
+            scalar_from_u64::<Bn254Field>(0),
+            scalar_from_u64(1),
+            scalar_from_u64(2),
+        ]);
+        permute(&mut state, &constants);
+        let expected = [
+            "0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+            "0x0fca49b798923ab0239de1c9e7a4a9a2210312b6a2f616d18b5a87f9b628ae29",
+            "0x0e7ae82e40091e63cbd4f16a6d16310b3729d4b6e138fcf54110e2867045a30c",
+        ];
+        for (element, expected) in state.iter().zip(expected.iter()) {
+            assert_eq!(field(expected), element.to_string());
+        }
+    }
+
+    #[test]
+    fn circom_hashes() {
+        // The digests of circomlibjs's `poseidon` for the preimages `[1]`, `[1, 2]`, and so on.
+        assert_eq!(
+            field("0x29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133"),
+            digest::<U1>()
+        );
+        assert_eq!(
+            field("0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"),
+            digest::<U2>()
+        );
+        assert_eq!(
+            field("0x299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465"),
+            digest::<U4>()
+        );
+        assert_eq!(
+            field("0x0dab9449e4a1398a15224c0b15a49d598b2174d305a316c918125f8feeb123c0"),
+            digest::<U5>()
+        );
+        assert_eq!(
+            field("0x2d1a03850084442813c8ebf094dea47538490a68b05f2239134a4cca2f6302e1"),
+            digest::<U6>()
+        );
+    }
+
+    #[test]
+    fn shared_circom_constants_are_shared() {
+        let constants = shared_circom_constants::<U2>();
+        assert!(Arc::ptr_eq(&constants, &shared_circom_constants::<U2>()));
+        // They are cached apart from neptune's own constants of the same field and arity.
+        assert!(!Arc::ptr_eq(
+            &constants,
+            &PoseidonConstants::<Bn254, U2>::shared(Strength::Standard)
+        ));
+        assert_eq!(
+            circom_constants::<U2>().round_constants,
+            constants.round_constants
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "circomlib's Poseidon supports 1 through 16 inputs, not 17")]
+    fn circom_constants_unsupported_arity() {
+        circom_constants::<U17>();
+    }
+}
@@ -0,0 +1,274 @@
+use crate::hash_type::HashType;
+use crate::poseidon::{validate_width, Arity, Poseidon, PoseidonConstants};
+use crate::round_numbers::{
+    round_numbers_for_prime, validate_security_level, SecurityMargin, DEFAULT_SECURITY_MARGIN,
+};
+use crate::{
+    alpha_is_coprime, Error, Sbox, DEFAULT_SBOX, DEFAULT_SECURITY_LEVEL, DEFAULT_STRENGTH,
+};
+use ff::{PrimeField, ScalarEngine};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// `PoseidonBuilder` names each parameter of a hasher of arity `A` as it is set, rather than by the choice of a
+/// `PoseidonConstants` constructor, and checks them together when building:
+///
+/// - `security_level`, the security level in bits, 128 by default.
+/// - `security_margin`, `true` or a `SecurityMargin` for the default margin or another, `false` for none.
+/// - `alpha` or `sbox`, the S-box, `x^5` by default.
+/// - `domain_tag`, the first element of the state, that of `HashType::MerkleTree` by default.
+///
+/// `build` calculates the round numbers for the parameters, generates the round constants and MDS matrix, and
+/// validates them as `PoseidonConstants::validate` does. The constants are kept behind an `Arc`, so building again
+/// returns hashers sharing them, until a parameter is changed.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::{Bls12, Fr};
+/// use generic_array::typenum::U2;
+/// use neptune::builder::PoseidonBuilder;
+/// use neptune::poseidon::{Poseidon, PoseidonConstants};
+/// use neptune::scalar_from_u64;
+///
+/// let preimage: [Fr; 2] = [scalar_from_u64(1), scalar_from_u64(2)];
+/// let mut builder = PoseidonBuilder::<Bls12, U2>::new().security_level(128);
+/// let mut poseidon = builder.build().unwrap();
+/// poseidon.set_preimage(&preimage);
+///
+/// let constants = PoseidonConstants::<Bls12, U2>::new();
+/// assert_eq!(Poseidon::new_with_preimage(&preimage, &constants).hash(), poseidon.hash());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PoseidonBuilder<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    security_level: usize,
+    security_margin: SecurityMargin,
+    alpha: i64,
+    domain_tag: Option<E::Fr>,
+    constants: Option<Arc<PoseidonConstants<E, A>>>,
+}
+
+impl<E, A> PoseidonBuilder<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Creates a builder with the parameters of `PoseidonConstants::new()`.
+    pub fn new() -> Self {
+        PoseidonBuilder {
+            security_level: DEFAULT_SECURITY_LEVEL,
+            security_margin: DEFAULT_SECURITY_MARGIN,
+            alpha: DEFAULT_SBOX.alpha(),
+            domain_tag: None,
+            constants: None,
+        }
+    }
+
+    /// Sets the security level in bits, which must be within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`.
+    pub fn security_level(mut self, bits: usize) -> Self {
+        self.security_level = bits;
+        self.constants = None;
+        self
+    }
+
+    /// Sets the margin added to the round numbers: `true` for `DEFAULT_SECURITY_MARGIN`, `false` for
+    /// `NO_SECURITY_MARGIN`, or a `SecurityMargin`.
+    pub fn security_margin<M: Into<SecurityMargin>>(mut self, margin: M) -> Self {
+        self.security_margin = margin.into();
+        self.constants = None;
+        self
+    }
+
+    /// Sets the S-box to `x^alpha`, which must be `x^3` or `x^5`, and a permutation of the field. See `sbox` for the
+    /// inverse S-box. An `alpha` beyond `i64::MAX` is reported as `Error::InvalidAlpha(i64::MAX)`.
+    pub fn alpha(mut self, alpha: u64) -> Self {
+        // Casting would wrap `u64::MAX` to -1, selecting the inverse S-box.
+        self.alpha = i64::try_from(alpha).unwrap_or(i64::MAX);
+        self.constants = None;
+        self
+    }
+
+    /// Sets the S-box, which must be a permutation of the field.
+    pub fn sbox(mut self, sbox: Sbox) -> Self {
+        self.alpha = sbox.alpha();
+        self.constants = None;
+        self
+    }
+
+    /// Sets the domain tag, the first element of the state, in place of that of `HashType::MerkleTree`. See
+    /// `PoseidonConstants::with_domain_tag`.
+    pub fn domain_tag(mut self, domain_tag: E::Fr) -> Self {
+        self.domain_tag = Some(domain_tag);
+        self.constants = None;
+        self
+    }
+
+    /// Returns the constants of the parameters, created by the first call and shared by later ones.
+    ///
+    /// Returns `Error::InvalidWidth` for an unsupported arity, `Error::InvalidSecurityLevel` for a security level out
+    /// of range, `Error::InvalidAlpha` for an S-box which is not supported or not a permutation of the field, any
+    /// error calculating the round numbers, and `Error::InvalidConstants` if the constants fail validation.
+    pub fn constants(&mut self) -> Result<Arc<PoseidonConstants<E, A>>, Error> {
+        if let Some(constants) = &self.constants {
+            return Ok(constants.clone());
+        }
+
+        let arity = A::to_usize();
+        validate_width(arity + 1)?;
+        validate_security_level(self.security_level)?;
+        let sbox = match self.alpha {
+            3 => Sbox::Cubic,
+            5 => Sbox::Quintic,
+            -1 => Sbox::Inverse,
+            alpha => return Err(Error::InvalidAlpha(alpha)),
+        };
+        if self.alpha > 0 && !alpha_is_coprime::<E::Fr>(self.alpha as u64) {
+            return Err(Error::InvalidAlpha(self.alpha));
+        }
+
+        let (full_rounds, partial_rounds) = round_numbers_for_prime(
+            arity,
+            &DEFAULT_STRENGTH,
+            E::Fr::NUM_BITS as usize,
+            self.security_level,
+            self.alpha,
+            self.security_margin,
+        )?;
+        let mut constants = PoseidonConstants::new_with_round_numbers(
+            DEFAULT_STRENGTH,
+            HashType::MerkleTree,
+            sbox,
+            full_rounds,
+            partial_rounds,
+            None,
+        );
+        constants.security_level = self.security_level;
+        constants.security_margin = self.security_margin;
+        if let Some(domain_tag) = self.domain_tag {
+            constants.domain_tag = domain_tag;
+        }
+        constants.validate()?;
+
+        let constants = Arc::new(constants);
+        self.constants = Some(constants.clone());
+        Ok(constants)
+    }
+
+    /// Returns a hasher with the constants of the parameters, see `constants`.
+    pub fn build(&mut self) -> Result<Poseidon<'static, E, A>, Error>
+    where
+        A: 'static,
+    {
+        Ok(Poseidon::new(self.constants()?))
+    }
+}
+
+impl<E, A> Default for PoseidonBuilder<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::round_numbers::NO_SECURITY_MARGIN;
+    use crate::scalar_from_u64;
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U8};
+
+    fn hash<A: Arity<Fr>>(poseidon: &mut Poseidon<Bls12, A>) -> Fr {
+        let preimage = (0..A::to_u64()).map(scalar_from_u64).collect::<Vec<Fr>>();
+        poseidon.set_preimage(&preimage);
+        poseidon.hash()
+    }
+
+    #[test]
+    fn builder_defaults() {
+        let mut builder = PoseidonBuilder::<Bls12, U8>::new();
+        assert_eq!(
+            PoseidonConstants::<Bls12, U8>::new(),
+            *builder.constants().unwrap()
+        );
+
+        let constants = PoseidonConstants::<Bls12, U8>::new();
+        let mut expected = Poseidon::new(&constants);
+        assert_eq!(hash(&mut expected), hash(&mut builder.build().unwrap()));
+    }
+
+    #[test]
+    fn builder_parameters() {
+        let mut builder = PoseidonBuilder::<Bls12, U2>::new().security_level(80);
+        assert_eq!(
+            PoseidonConstants::<Bls12, U2>::new_with_security_level(80),
+            *builder.constants().unwrap()
+        );
+
+        let mut builder = PoseidonBuilder::<Bls12, U2>::new().security_margin(false);
+        assert_eq!(
+            PoseidonConstants::<Bls12, U2>::new_with_security_margin(NO_SECURITY_MARGIN),
+            *builder.constants().unwrap()
+        );
+
+        let mut builder = PoseidonBuilder::<Bls12, U2>::new().sbox(Sbox::Inverse);
+        let constants = builder.constants().unwrap();
+        assert_eq!(Sbox::Inverse, constants.sbox);
+
+        let tag: Fr = scalar_from_u64(42);
+        let mut builder = PoseidonBuilder::<Bls12, U2>::new().domain_tag(tag);
+        let default = PoseidonConstants::<Bls12, U2>::new();
+        assert_eq!(default.with_domain_tag(tag), *builder.constants().unwrap());
+        assert_ne!(
+            hash(&mut Poseidon::new(Arc::new(default))),
+            hash(&mut builder.build().unwrap())
+        );
+    }
+
+    #[test]
+    fn builder_caches_constants() {
+        let mut builder = PoseidonBuilder::<Bls12, U2>::new();
+        let constants = builder.constants().unwrap();
+        assert!(Arc::ptr_eq(&constants, &builder.constants().unwrap()));
+
+        // Changing a parameter builds them again.
+        let mut builder = builder.security_level(100);
+        assert!(!Arc::ptr_eq(&constants, &builder.constants().unwrap()));
+    }
+
+    #[test]
+    fn builder_errors() {
+        let error = |builder: PoseidonBuilder<Bls12, U2>| {
+            let mut builder = builder;
+            builder.constants().unwrap_err()
+        };
+
+        match error(PoseidonBuilder::new().security_level(300)) {
+            Error::InvalidSecurityLevel(300) => (),
+            e => panic!("unexpected error: {}", e),
+        }
+        // 3 divides p - 1 for BLS12-381, so `x^3` is not a permutation.
+        match error(PoseidonBuilder::new().alpha(3)) {
+            Error::InvalidAlpha(3) => (),
+            e => panic!("unexpected error: {}", e),
+        }
+        match error(PoseidonBuilder::new().alpha(7)) {
+            Error::InvalidAlpha(7) => (),
+            e => panic!("unexpected error: {}", e),
+        }
+        // Exponents beyond `i64::MAX` do not wrap around to another S-box.
+        for alpha in [u64::MAX, 1 << 63].iter() {
+            match error(PoseidonBuilder::new().alpha(*alpha)) {
+                Error::InvalidAlpha(i64::MAX) => (),
+                e => panic!("unexpected error: {}", e),
+            }
+        }
+    }
+}
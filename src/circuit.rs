@@ -2,6 +2,7 @@ use crate::hash_type::HashType;
 use crate::matrix::Matrix;
 use crate::mds::SparseMatrix;
 use crate::poseidon::{Arity, PoseidonConstants};
+use crate::Sbox;
 use bellperson::gadgets::boolean::Boolean;
 use bellperson::gadgets::num;
 use bellperson::gadgets::num::AllocatedNum;
@@ -337,6 +338,10 @@ where
 }
 
 /// Create circuit for Poseidon hash.
+///
+/// # Panics
+///
+/// Panics if `constants` do not use the quintic S-box, the only one supported in circuits.
 pub fn poseidon_hash<CS, E, A>(
     mut cs: CS,
     preimage: Vec<AllocatedNum<E>>,
@@ -347,6 +352,11 @@ where
     E: Engine,
     A: Arity<E::Fr>,
 {
+    assert_eq!(
+        constants.sbox,
+        Sbox::Quintic,
+        "only the quintic S-box is supported in circuits"
+    );
     let arity = A::to_usize();
     let tag_element = Elt::num_from_fr::<CS>(constants.domain_tag);
     let mut elements = Vec::with_capacity(arity + 1);
@@ -618,6 +628,7 @@ mod tests {
         test_poseidon_hash_aux::<typenum::U8>(Strength::Standard, 505, false);
         test_poseidon_hash_aux::<typenum::U16>(Strength::Standard, 761, false);
         test_poseidon_hash_aux::<typenum::U24>(Strength::Standard, 1009, false);
+        test_poseidon_hash_aux::<typenum::U32>(Strength::Standard, 1261, false);
         test_poseidon_hash_aux::<typenum::U36>(Strength::Standard, 1385, false);
 
         test_poseidon_hash_aux::<typenum::U2>(Strength::Strengthened, 367, false);
@@ -625,11 +636,38 @@ mod tests {
         test_poseidon_hash_aux::<typenum::U8>(Strength::Strengthened, 565, false);
         test_poseidon_hash_aux::<typenum::U16>(Strength::Strengthened, 821, false);
         test_poseidon_hash_aux::<typenum::U24>(Strength::Strengthened, 1069, false);
+        test_poseidon_hash_aux::<typenum::U32>(Strength::Strengthened, 1321, false);
         test_poseidon_hash_aux::<typenum::U36>(Strength::Strengthened, 1445, false);
 
         test_poseidon_hash_aux::<typenum::U15>(Strength::Standard, 730, true);
     }
 
+    #[test]
+    fn test_poseidon_hash_with_mds() {
+        let mds: Matrix<Fr> = [[1, 1, 1], [1, 2, 3], [1, 4, 2]]
+            .iter()
+            .map(|row| row.iter().map(|x| fr(*x)).collect())
+            .collect();
+        let constants = PoseidonConstants::<Bls12, typenum::U2>::new_with_mds(mds).unwrap();
+        let preimage = [fr(1), fr(2)];
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let data = preimage
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                AllocatedNum::alloc(cs.namespace(|| format!("data {}", i)), || Ok(*x)).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let out = poseidon_hash(&mut cs, data, &constants).expect("poseidon hashing failed");
+
+        assert!(cs.is_satisfied(), "constraints not satisfied");
+        assert_eq!(
+            Poseidon::new_with_preimage(&preimage, &constants).hash_in_mode(HashMode::Correct),
+            out.get_value().unwrap()
+        );
+    }
+
     fn test_poseidon_hash_aux<A>(
         strength: Strength,
         expected_constraints: usize,
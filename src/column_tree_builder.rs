@@ -14,7 +14,13 @@ where
     ColumnArity: Arity<Fr>,
     TreeArity: Arity<Fr>,
 {
+    /// Hashes each column into a leaf with the constants of `ColumnArity`, its length, and appends the leaves,
+    /// hashing at most the maximum batch size of the batcher at a time. Returns an error if the columns would exceed
+    /// the leaf count.
     fn add_columns(&mut self, columns: &[GenericArray<Fr, ColumnArity>]) -> Result<(), Error>;
+
+    /// Adds the last columns, then builds the tree of `TreeArity` over the leaves, and returns its base row and the
+    /// rows above it, ending with the root. The builder is reset for the next tree.
     fn add_final_columns(
         &mut self,
         columns: &[GenericArray<Fr, ColumnArity>],
@@ -23,6 +29,11 @@ where
     fn reset(&mut self);
 }
 
+/// Builds a tree whose leaves arrive as columns, each hashed into one leaf, as for the column commitments of
+/// Filecoin's SDR, where columns of 11 elements are committed to by a tree of arity 8.
+///
+/// The columns and the tree are hashed by `Batcher`s of their arities, on the CPU or a GPU, or one hash at a time
+/// without a batcher. With a GPU batcher for the columns, the tree is built on the same device.
 pub struct ColumnTreeBuilder<ColumnArity, TreeArity>
 where
     ColumnArity: Arity<Fr>,
@@ -54,7 +65,13 @@ where
 
         match self.column_batcher {
             Some(ref mut batcher) => {
-                batcher.hash_into_slice(&mut self.data[start..start + column_count], columns)?;
+                let max_batch_size = batcher.max_batch_size();
+                for (leaves, batch) in self.data[start..end]
+                    .chunks_mut(max_batch_size)
+                    .zip(columns.chunks(max_batch_size))
+                {
+                    batcher.hash_into_slice(leaves, batch)?;
+                }
             }
             None => columns.iter().enumerate().for_each(|(i, column)| {
                 self.data[start + i] =
@@ -84,29 +101,18 @@ where
         self.data.iter_mut().for_each(|place| *place = Fr::zero());
     }
 }
-fn as_generic_arrays<'a, A: Arity<Fr>>(vec: &'a [Fr]) -> &'a [GenericArray<Fr, A>] {
-    // It is a programmer error to call `as_generic_arrays` on a vector whose underlying data cannot be divided
-    // into an even number of `GenericArray<Fr, Arity>`.
-    assert_eq!(
-        0,
-        (vec.len() * std::mem::size_of::<Fr>()) % std::mem::size_of::<GenericArray<Fr, A>>()
-    );
-
-    // This block does not affect the underlying `Fr`s. It just groups them into `GenericArray`s of length `Arity`.
-    // We know by the assertion above that `vec` can be evenly divided into these units.
-    unsafe {
-        std::slice::from_raw_parts(
-            vec.as_ptr() as *const () as *const GenericArray<Fr, A>,
-            vec.len() / A::to_usize(),
-        )
-    }
-}
 
 impl<ColumnArity, TreeArity> ColumnTreeBuilder<ColumnArity, TreeArity>
 where
     ColumnArity: Arity<Fr>,
     TreeArity: Arity<Fr>,
 {
+    /// Creates a builder of trees of `leaf_count` leaves, hashing columns and tree rows with batchers of type `t`, if
+    /// any, in batches of at most `max_column_batch_size` and `max_tree_batch_size` preimages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaf_count` is not a power of the tree arity.
     pub fn new(
         t: Option<BatcherType>,
         leaf_count: usize,
@@ -118,31 +124,10 @@ where
             None => None,
         };
 
-        let tree_builder = match {
-            match &column_batcher {
-                #[cfg(feature = "gpu")]
-                Some(b) => b.futhark_context(),
-                #[cfg(feature = "opencl")]
-                Some(b) => b.device(),
-                None => None,
-            }
-        } {
-            #[cfg(feature = "gpu")]
-            Some(ctx) => TreeBuilder::<TreeArity>::new(
-                Some(BatcherType::FromFutharkContext(ctx)),
-                leaf_count,
-                max_tree_batch_size,
-                0,
-            )?,
-            #[cfg(feature = "opencl")]
-            Some(device) => TreeBuilder::<TreeArity>::new(
-                Some(BatcherType::FromDevice(device)),
-                leaf_count,
-                max_tree_batch_size,
-                0,
-            )?,
-            None => TreeBuilder::<TreeArity>::new(t, leaf_count, max_tree_batch_size, 0)?,
-        };
+        // Build the tree on the device hashing the columns, if any.
+        let device_type = column_batcher.as_ref().and_then(Batcher::device_type);
+        let tree_builder =
+            TreeBuilder::<TreeArity>::new(device_type.or(t), leaf_count, max_tree_batch_size, 0)?;
 
         let builder = Self {
             leaf_count,
@@ -173,7 +158,6 @@ where
     }
 }
 
-#[cfg(all(any(feature = "gpu", feature = "opencl"), not(target_os = "macos")))]
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,7 +166,9 @@ mod tests {
     use bellperson::bls::Fr;
     use ff::Field;
     use generic_array::sequence::GenericSequence;
-    use generic_array::typenum::{U11, U8};
+    use generic_array::typenum::{U11, U2, U8};
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
 
     #[test]
     fn test_column_tree_builder() {
@@ -190,13 +176,53 @@ mod tests {
         test_column_tree_builder_aux(None, 512, 32, 512, 512);
         test_column_tree_builder_aux(Some(BatcherType::CPU), 512, 32, 512, 512);
 
-        #[cfg(feature = "gpu")]
+        #[cfg(all(feature = "gpu", not(target_os = "macos")))]
         test_column_tree_builder_aux(Some(BatcherType::GPU), 512, 32, 512, 512);
 
-        #[cfg(feature = "opencl")]
+        #[cfg(all(feature = "opencl", not(target_os = "macos")))]
         test_column_tree_builder_aux(Some(BatcherType::OpenCL), 512, 32, 512, 512);
     }
 
+    #[test]
+    fn test_column_tree_builder_distinct_columns() {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+        let columns = (0..8)
+            .map(|_| GenericArray::<Fr, U11>::generate(|_| Fr::random(&mut rng)))
+            .collect::<Vec<_>>();
+
+        // Each column is hashed with the constants of arity 11, and each node of the tree with those of arity 2.
+        let column_constants = PoseidonConstants::<Bls12, U11>::new();
+        let tree_constants = PoseidonConstants::<Bls12, U2>::new();
+        let mut row = columns
+            .iter()
+            .map(|column| Poseidon::new_with_preimage(column, &column_constants).hash())
+            .collect::<Vec<_>>();
+        let leaves = row.clone();
+        let mut expected_tree = Vec::new();
+        while row.len() > 1 {
+            row = row
+                .chunks(2)
+                .map(|pair| Poseidon::new_with_preimage(pair, &tree_constants).hash())
+                .collect();
+            expected_tree.extend(row.iter().cloned());
+        }
+
+        for batcher_type in [None, Some(BatcherType::CPU)].iter() {
+            let mut builder =
+                ColumnTreeBuilder::<U11, U2>::new(batcher_type.clone(), 8, 2, 3).unwrap();
+            builder.add_columns(&columns[..3]).unwrap();
+            builder.add_columns(&columns[3..6]).unwrap();
+            let (base, tree) = builder.add_final_columns(&columns[6..]).unwrap();
+            assert_eq!(leaves, base);
+            assert_eq!(expected_tree, tree);
+
+            // The builder is reset, and rejects more columns than leaves.
+            builder.add_columns(&columns[..3]).unwrap();
+            builder.add_columns(&columns[..3]).unwrap();
+            assert!(builder.add_columns(&columns[..3]).is_err());
+        }
+    }
+
     fn test_column_tree_builder_aux(
         batcher_type: Option<BatcherType>,
         leaves: usize,
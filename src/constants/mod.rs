@@ -0,0 +1,44 @@
+use crate::{Sbox, Scalar};
+use ff::{PrimeField, PrimeFieldRepr, ScalarEngine};
+use std::any::TypeId;
+
+mod bls12_381;
+
+/// Returns the round constants of `round_constants::generate_constants` for the parameters if they are precomputed,
+/// as they are for BLS12-381 with the x^5 S-box and the standard round numbers of widths 2 through 17, 25, 33, and
+/// 37, see `bls12_381`. Each element is read from its canonical limbs, one multiplication into the representation of
+/// the field, rather than sampled bit by bit from the Grain LFSR.
+///
+/// A `const fn` conversion is declined on the pinned Rust 1.46: the elements cannot be built at compile time, from
+/// Montgomery limbs, as `Fr` is the scalar of the `pairing` or the `blst` backend of bellperson, whose limbs are
+/// private to the backend: fff 0.2's `PrimeField` only constructs an element with `from_repr`, which is not a
+/// `const fn`, and transmuting limbs is neither `const` on Rust 1.46 nor sound across backends. The multiplications
+/// cost microseconds, once for each `PoseidonConstants` created.
+pub(crate) fn precomputed<E: ScalarEngine>(
+    field: u8,
+    sbox: u8,
+    field_size: u16,
+    t: u16,
+    r_f: u16,
+    r_p: u16,
+) -> Option<Vec<E::Fr>> {
+    if TypeId::of::<E::Fr>() != TypeId::of::<Scalar>()
+        || field != 1
+        || sbox != Sbox::Quintic.id()
+        || field_size != Scalar::NUM_BITS as u16
+    {
+        return None;
+    }
+
+    let constants = bls12_381::round_constants(t, r_f, r_p)?;
+    Some(
+        constants
+            .iter()
+            .map(|limbs| {
+                let mut repr = <E::Fr as PrimeField>::Repr::default();
+                repr.as_mut().copy_from_slice(limbs);
+                E::Fr::from_repr(repr).expect("precomputed constants are canonical")
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,554 @@
+use crate::hash_type::HashType;
+use crate::mds::MDSMatrices;
+use crate::poseidon::{
+    validate_width, visit_arity, Arity, ArityVisitor, HashMode, Poseidon, PoseidonConstants,
+    DEFAULT_HASH_MODE,
+};
+use crate::{Error, Sbox, Strength, DEFAULT_STRENGTH};
+use ff::ScalarEngine;
+use std::any::Any;
+use std::fmt;
+
+/// The operations of `PoseidonConstants<E, A>` which `DynPoseidonConstants` needs without naming `A`.
+trait Constants<E: ScalarEngine>: fmt::Debug + Send + Sync {
+    fn arity(&self) -> usize;
+    fn strength(&self) -> Strength;
+    fn sbox(&self) -> Sbox;
+    fn domain_tag(&self) -> E::Fr;
+    fn full_rounds(&self) -> usize;
+    fn partial_rounds(&self) -> usize;
+    fn constant_length(&self) -> Option<usize>;
+    fn round_constants(&self) -> &[E::Fr];
+    fn mds_matrices(&self) -> &MDSMatrices<E>;
+    fn validate(&self) -> Result<(), Error>;
+    fn hash(&self, domain_tag: E::Fr, preimage: &[E::Fr], mode: HashMode) -> E::Fr;
+    fn clone_box(&self) -> Box<dyn Constants<E>>;
+    fn as_any(&self) -> &dyn Any;
+    fn eq_any(&self, other: &dyn Any) -> bool;
+}
+
+impl<E, A> Constants<E> for PoseidonConstants<E, A>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync + 'static,
+{
+    fn arity(&self) -> usize {
+        PoseidonConstants::arity(self)
+    }
+
+    fn strength(&self) -> Strength {
+        self.strength
+    }
+
+    fn sbox(&self) -> Sbox {
+        self.sbox
+    }
+
+    fn domain_tag(&self) -> E::Fr {
+        self.domain_tag
+    }
+
+    fn full_rounds(&self) -> usize {
+        self.full_rounds
+    }
+
+    fn partial_rounds(&self) -> usize {
+        self.partial_rounds
+    }
+
+    fn constant_length(&self) -> Option<usize> {
+        match self.hash_type {
+            HashType::ConstantLength(length) => Some(length),
+            _ => None,
+        }
+    }
+
+    fn round_constants(&self) -> &[E::Fr] {
+        &self.round_constants
+    }
+
+    fn mds_matrices(&self) -> &MDSMatrices<E> {
+        &self.mds_matrices
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        PoseidonConstants::validate(self)
+    }
+
+    fn hash(&self, domain_tag: E::Fr, preimage: &[E::Fr], mode: HashMode) -> E::Fr {
+        let mut poseidon = Poseidon::new_with_domain_tag(self, domain_tag);
+        for element in preimage {
+            poseidon
+                .input(*element)
+                .expect("preimage is no longer than the arity");
+        }
+        poseidon.hash_in_mode(mode)
+    }
+
+    fn clone_box(&self) -> Box<dyn Constants<E>> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_any(&self, other: &dyn Any) -> bool {
+        other
+            .downcast_ref::<Self>()
+            .map_or(false, |other| self == other)
+    }
+}
+
+/// Creates the constants of the typenum arity it is visited with.
+enum New {
+    MerkleTree(Strength),
+    ConstantLength(usize),
+}
+
+impl<E> ArityVisitor<E> for New
+where
+    E: ScalarEngine + Send + Sync,
+{
+    type Output = Box<dyn Constants<E>>;
+
+    fn visit<A: Arity<E::Fr> + Send + Sync + 'static>(self) -> Box<dyn Constants<E>> {
+        match self {
+            New::MerkleTree(strength) => {
+                Box::new(PoseidonConstants::<E, A>::new_with_strength(strength))
+            }
+            New::ConstantLength(length) => {
+                Box::new(PoseidonConstants::<E, A>::new_constant_length(length))
+            }
+        }
+    }
+}
+
+/// `DynPoseidonConstants` are the constants of a Poseidon hash whose arity is chosen at runtime rather than by a
+/// typenum parameter, for callers which read it from configuration or hash preimages of several arities.
+///
+/// The constants are those of `PoseidonConstants<E, A>` for the typenum `A` of the arity, and `DynPoseidon` hashes
+/// with them, so digests are identical to those of `Poseidon<E, A>` in every `HashMode`. Constants convert from
+/// `PoseidonConstants` with `From`, and back with `to_static`.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::{Bls12, Fr};
+/// use ff::Field;
+/// use generic_array::typenum::U4;
+/// use neptune::dyn_poseidon::{DynPoseidon, DynPoseidonConstants};
+/// use neptune::poseidon::{Poseidon, PoseidonConstants};
+///
+/// let arity = 4;
+/// let constants = DynPoseidonConstants::<Bls12>::new(arity);
+/// let preimage = vec![Fr::one(); arity];
+/// let digest = DynPoseidon::new_with_preimage(&preimage, &constants).hash();
+///
+/// let typed = PoseidonConstants::<Bls12, U4>::new();
+/// assert_eq!(digest, Poseidon::new_with_preimage(&preimage, &typed).hash());
+/// ```
+pub struct DynPoseidonConstants<E>
+where
+    E: ScalarEngine,
+{
+    constants: Box<dyn Constants<E>>,
+}
+
+impl<E> DynPoseidonConstants<E>
+where
+    E: ScalarEngine + Send + Sync,
+{
+    /// Creates the constants of `PoseidonConstants::new` for `arity`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `arity` is supported, from 1 through 127.
+    pub fn new(arity: usize) -> Self {
+        Self::new_with_strength(arity, DEFAULT_STRENGTH)
+    }
+
+    /// `try_new` creates constants as `PoseidonConstants::try_new` does for `arity`, returning
+    /// `Error::InvalidWidth` rather than panicking unless `arity` is supported, and any error of `validate`.
+    pub fn try_new(arity: usize) -> Result<Self, Error> {
+        validate_width(arity.saturating_add(1))?;
+        let constants = Self::new(arity);
+        constants.validate()?;
+        Ok(constants)
+    }
+
+    /// Creates the constants of `PoseidonConstants::new_with_strength` for `arity`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `arity` is supported, from 1 through 127.
+    pub fn new_with_strength(arity: usize, strength: Strength) -> Self {
+        DynPoseidonConstants {
+            constants: visit_arity::<E, _>(arity, New::MerkleTree(strength)),
+        }
+    }
+
+    /// Creates the constants of `PoseidonConstants::new_constant_length` for `arity`, hashing preimages of `length`
+    /// elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `arity` is supported, from 1 through 127, and `length` is at most `arity`.
+    pub fn new_constant_length(arity: usize, length: usize) -> Self {
+        DynPoseidonConstants {
+            constants: visit_arity::<E, _>(arity, New::ConstantLength(length)),
+        }
+    }
+
+    /// Returns the constants as `PoseidonConstants<E, A>`, or `Error::InvalidConstants` unless `A` is their arity.
+    pub fn to_static<A>(&self) -> Result<PoseidonConstants<E, A>, Error>
+    where
+        A: Arity<E::Fr> + Send + Sync + 'static,
+    {
+        self.constants
+            .as_any()
+            .downcast_ref::<PoseidonConstants<E, A>>()
+            .cloned()
+            .ok_or_else(|| {
+                Error::InvalidConstants(format!(
+                    "constants of arity {} cannot be used for arity {}",
+                    self.arity(),
+                    A::to_usize()
+                ))
+            })
+    }
+
+    /// Checks the constants as `PoseidonConstants::validate` does.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.constants.validate()
+    }
+
+    pub fn arity(&self) -> usize {
+        self.constants.arity()
+    }
+
+    pub fn width(&self) -> usize {
+        self.arity() + 1
+    }
+
+    pub fn strength(&self) -> Strength {
+        self.constants.strength()
+    }
+
+    pub fn sbox(&self) -> Sbox {
+        self.constants.sbox()
+    }
+
+    pub fn domain_tag(&self) -> E::Fr {
+        self.constants.domain_tag()
+    }
+
+    pub fn full_rounds(&self) -> usize {
+        self.constants.full_rounds()
+    }
+
+    pub fn partial_rounds(&self) -> usize {
+        self.constants.partial_rounds()
+    }
+
+    /// Returns the length of the preimages hashed, if the constants are for `HashType::ConstantLength`.
+    pub fn constant_length(&self) -> Option<usize> {
+        self.constants.constant_length()
+    }
+
+    pub fn round_constants(&self) -> &[E::Fr] {
+        self.constants.round_constants()
+    }
+
+    pub fn mds_matrices(&self) -> &MDSMatrices<E> {
+        self.constants.mds_matrices()
+    }
+}
+
+impl<E, A> From<PoseidonConstants<E, A>> for DynPoseidonConstants<E>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync + 'static,
+{
+    fn from(constants: PoseidonConstants<E, A>) -> Self {
+        DynPoseidonConstants {
+            constants: Box::new(constants),
+        }
+    }
+}
+
+impl<E> Clone for DynPoseidonConstants<E>
+where
+    E: ScalarEngine,
+{
+    fn clone(&self) -> Self {
+        DynPoseidonConstants {
+            constants: self.constants.clone_box(),
+        }
+    }
+}
+
+impl<E> fmt::Debug for DynPoseidonConstants<E>
+where
+    E: ScalarEngine,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.constants, f)
+    }
+}
+
+impl<E> PartialEq for DynPoseidonConstants<E>
+where
+    E: ScalarEngine,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.constants.eq_any(other.constants.as_any())
+    }
+}
+
+/// `DynPoseidon` hashes preimages with `DynPoseidonConstants`, as `Poseidon` does with `PoseidonConstants`: it
+/// accepts a number of inputs equal to the arity, absorbed with `input` or given to `new_with_preimage`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynPoseidon<'a, E>
+where
+    E: ScalarEngine,
+{
+    constants: &'a DynPoseidonConstants<E>,
+    domain_tag: E::Fr,
+    preimage: Vec<E::Fr>,
+}
+
+impl<'a, E> DynPoseidon<'a, E>
+where
+    E: ScalarEngine + Send + Sync,
+{
+    pub fn new(constants: &'a DynPoseidonConstants<E>) -> Self {
+        Self::new_with_domain_tag(constants, constants.domain_tag())
+    }
+
+    /// Creates a hasher whose capacity element is `domain_tag`, as `Poseidon::new_with_domain_tag` does.
+    pub fn new_with_domain_tag(constants: &'a DynPoseidonConstants<E>, domain_tag: E::Fr) -> Self {
+        DynPoseidon {
+            constants,
+            domain_tag,
+            preimage: Vec::with_capacity(constants.arity()),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `preimage` is longer than the arity.
+    pub fn new_with_preimage(preimage: &[E::Fr], constants: &'a DynPoseidonConstants<E>) -> Self {
+        let mut poseidon = Self::new(constants);
+        poseidon.set_preimage(preimage);
+        poseidon
+    }
+
+    /// Replaces the absorbed elements with `preimage`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `preimage` is longer than the arity.
+    pub fn set_preimage(&mut self, preimage: &[E::Fr]) {
+        assert!(
+            preimage.len() <= self.constants.arity(),
+            "Invalid preimage size"
+        );
+        self.preimage.clear();
+        self.preimage.extend_from_slice(preimage);
+    }
+
+    /// Restore the initial state
+    pub fn reset(&mut self) {
+        self.preimage.clear();
+    }
+
+    /// Absorbs `element`, returning its position within the arity, or `Error::FullBuffer` once `arity` elements have
+    /// been absorbed.
+    pub fn input(&mut self, element: E::Fr) -> Result<usize, Error> {
+        if self.preimage.len() >= self.constants.arity() {
+            return Err(Error::FullBuffer);
+        }
+        self.preimage.push(element);
+        Ok(self.preimage.len())
+    }
+
+    /// Hashes the absorbed elements in `mode`, padding them with zeros to the arity as `Poseidon::hash_in_mode`
+    /// does. The elements are kept, so hashing again returns the same digest until `reset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of absorbed elements is not `length`, for constants of `HashType::ConstantLength(length)`.
+    pub fn hash_in_mode(&mut self, mode: HashMode) -> E::Fr {
+        self.constants
+            .constants
+            .hash(self.domain_tag, &self.preimage, mode)
+    }
+
+    /// Hashes the absorbed elements in the default mode, `OptimizedStatic`.
+    pub fn hash(&mut self) -> E::Fr {
+        self.hash_in_mode(DEFAULT_HASH_MODE)
+    }
+}
+
+/// With the `zeroize` feature, the absorbed preimage is overwritten with zeros when the hasher is dropped, as the
+/// state of a `Poseidon` is.
+#[cfg(feature = "zeroize")]
+impl<'a, E> Drop for DynPoseidon<'a, E>
+where
+    E: ScalarEngine,
+{
+    fn drop(&mut self) {
+        use ff::Field;
+
+        crate::wipe::wipe_vec(&mut self.preimage, E::Fr::zero());
+        crate::wipe::wipe(std::slice::from_mut(&mut self.domain_tag), E::Fr::zero());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scalar_from_u64;
+    use bellperson::bls::{Bls12, Fr};
+    use ff::Field;
+    use generic_array::typenum::{U1, U11, U16, U2, U23, U24, U32, U36, U4, U8};
+
+    fn preimage(arity: usize) -> Vec<Fr> {
+        (0..arity as u64).map(scalar_from_u64::<Fr>).collect()
+    }
+
+    fn matches_static<A>()
+    where
+        A: Arity<Fr> + Send + Sync + 'static,
+    {
+        let arity = A::to_usize();
+        let typed = PoseidonConstants::<Bls12, A>::new();
+        let constants = DynPoseidonConstants::<Bls12>::new(arity);
+        assert_eq!(arity, constants.arity());
+        assert_eq!(arity + 1, constants.width());
+        assert_eq!(
+            (typed.full_rounds, typed.partial_rounds),
+            (constants.full_rounds(), constants.partial_rounds())
+        );
+        assert_eq!(typed.domain_tag, constants.domain_tag());
+        assert_eq!(typed, constants.to_static::<A>().unwrap());
+        assert_eq!(constants, DynPoseidonConstants::from(typed.clone()));
+
+        let preimage = preimage(arity);
+        // `HashMode` is not `Copy`, so each hasher is given its own.
+        let modes = || {
+            vec![
+                HashMode::Correct,
+                HashMode::OptimizedDynamic,
+                HashMode::OptimizedStatic,
+            ]
+        };
+        for (typed_mode, mode) in modes().into_iter().zip(modes()) {
+            let expected = Poseidon::new_with_preimage(&preimage, &typed).hash_in_mode(typed_mode);
+            let mut poseidon = DynPoseidon::new_with_preimage(&preimage, &constants);
+            // Hashing again returns the same digest, as the preimage is kept.
+            assert_eq!(expected, poseidon.hash_in_mode(HashMode::OptimizedStatic));
+            assert_eq!(expected, poseidon.hash_in_mode(mode));
+        }
+    }
+
+    #[test]
+    fn dyn_matches_static() {
+        matches_static::<U1>();
+        matches_static::<U2>();
+        matches_static::<U4>();
+        matches_static::<U8>();
+        matches_static::<U11>();
+        matches_static::<U16>();
+        matches_static::<U23>();
+        matches_static::<U24>();
+        matches_static::<U32>();
+        matches_static::<U36>();
+    }
+
+    #[test]
+    fn dyn_input() {
+        let constants = DynPoseidonConstants::<Bls12>::new(4);
+        let typed = PoseidonConstants::<Bls12, U4>::new();
+        let mut poseidon = DynPoseidon::new(&constants);
+        let mut typed_poseidon = Poseidon::new(&typed);
+        for element in preimage(3) {
+            poseidon.input(element).unwrap();
+            typed_poseidon.input(element).unwrap();
+        }
+        // Both pad the missing element with zero.
+        assert_eq!(typed_poseidon.hash(), poseidon.hash());
+
+        poseidon.input(Fr::one()).unwrap();
+        match poseidon.input(Fr::one()) {
+            Err(Error::FullBuffer) => (),
+            r => panic!("expected a full buffer, got {:?}", r),
+        }
+
+        poseidon.reset();
+        assert_eq!(
+            Poseidon::new(&typed).hash(),
+            poseidon.hash(),
+            "the empty preimage is all zeros"
+        );
+
+        let domain_tag = scalar_from_u64::<Fr>(5);
+        let mut poseidon = DynPoseidon::new_with_domain_tag(&constants, domain_tag);
+        poseidon.set_preimage(&preimage(4));
+        assert_eq!(
+            Poseidon::new_with_preimage_and_domain_tag(&preimage(4), domain_tag, &typed).hash(),
+            poseidon.hash()
+        );
+    }
+
+    #[test]
+    fn dyn_constant_length() {
+        let constants = DynPoseidonConstants::<Bls12>::new_constant_length(8, 3);
+        let typed = PoseidonConstants::<Bls12, U8>::new_constant_length(3);
+        assert_eq!(Some(3), constants.constant_length());
+        assert_eq!(
+            None,
+            DynPoseidonConstants::<Bls12>::new(8).constant_length()
+        );
+        assert_eq!(typed, constants.to_static::<U8>().unwrap());
+        assert_eq!(
+            Poseidon::new_with_preimage(&preimage(3), &typed).hash(),
+            DynPoseidon::new_with_preimage(&preimage(3), &constants).hash()
+        );
+    }
+
+    #[test]
+    fn dyn_try_new() {
+        assert_eq!(
+            DynPoseidonConstants::<Bls12>::new(2),
+            DynPoseidonConstants::<Bls12>::try_new(2).unwrap()
+        );
+        for (arity, width) in vec![(0, 1), (128, 129)] {
+            match DynPoseidonConstants::<Bls12>::try_new(arity) {
+                Err(Error::InvalidWidth(t)) => assert_eq!(width, t),
+                r => panic!("expected an invalid width, got {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Width 1 is outside of the supported range, 2 through 128.")]
+    fn dyn_unsupported_arity() {
+        DynPoseidonConstants::<Bls12>::new(0);
+    }
+
+    #[test]
+    fn dyn_to_static_wrong_arity() {
+        let constants = DynPoseidonConstants::<Bls12>::new(4);
+        assert_eq!(
+            "Invalid Poseidon constants: constants of arity 4 cannot be used for arity 8",
+            constants.to_static::<U8>().unwrap_err().to_string()
+        );
+        assert_ne!(constants, DynPoseidonConstants::<Bls12>::new(8));
+        assert_ne!(
+            constants,
+            DynPoseidonConstants::<Bls12>::new_with_strength(4, Strength::Strengthened)
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::poseidon::{MAX_WIDTH, MIN_WIDTH};
+use crate::round_numbers::SecurityInequality;
 #[cfg(feature = "gpu")]
 use crate::triton::cl;
 use std::{error, fmt};
@@ -42,6 +44,52 @@ impl fmt::Display for ClError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// Possible error states when calculating round numbers.
+pub enum RoundNumberError {
+    /// No round numbers within the search bounds satisfy the security inequalities for width `t`.
+    NoSecureParameters { t: usize },
+    /// The given round numbers `(rf, rp)` fail the security `inequality` for width `t`.
+    InsecureRoundNumbers {
+        t: usize,
+        rf: usize,
+        rp: usize,
+        inequality: SecurityInequality,
+    },
+    /// The `line` is not formatted as a line of round numbers, `t R_F R_P sbox_cost size_cost`.
+    MalformedLine { line: String },
+    /// The given number of full rounds `rf` is odd, so cannot be split evenly around the partial rounds.
+    OddFullRounds { rf: usize },
+}
+
+impl error::Error for RoundNumberError {}
+
+impl fmt::Display for RoundNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            RoundNumberError::NoSecureParameters { t } => {
+                write!(f, "No secure round numbers found for width {}.", t)
+            }
+            RoundNumberError::InsecureRoundNumbers {
+                t,
+                rf,
+                rp,
+                inequality,
+            } => write!(
+                f,
+                "Round numbers R_F = {}, R_P = {} for width {} fail the {} bound.",
+                rf, rp, t, inequality
+            ),
+            RoundNumberError::MalformedLine { line } => {
+                write!(f, "Malformed round numbers line: {:?}.", line)
+            }
+            RoundNumberError::OddFullRounds { rf } => {
+                write!(f, "Full rounds must be even: {}.", rf)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Possible error states for the hashing.
 pub enum Error {
@@ -56,9 +104,29 @@ pub enum Error {
     #[cfg(feature = "gpu")]
     TritonError(String),
     DecodingError,
+    /// The requested security level (in bits) is outside of the supported range.
+    InvalidSecurityLevel(usize),
+    /// The S-box exponent `alpha` is neither -1 nor an odd exponent of at least 3 coprime to `p - 1`.
+    InvalidAlpha(i64),
+    /// The width `t` of the permutation is outside of the supported range, `MIN_WIDTH..=MAX_WIDTH`.
+    InvalidWidth(usize),
+    /// A preimage of `found` elements was given to a hash of `expected` elements.
+    InvalidPreimageSize {
+        expected: usize,
+        found: usize,
+    },
+    RoundNumberError(RoundNumberError),
+    /// Loaded `PoseidonConstants` are inconsistent with their type or with each other.
+    InvalidConstants(String),
     Other(String),
 }
 
+impl From<RoundNumberError> for Error {
+    fn from(e: RoundNumberError) -> Self {
+        Self::RoundNumberError(e)
+    }
+}
+
 #[cfg(feature = "gpu")]
 impl From<ClError> for Error {
     fn from(e: ClError) -> Self {
@@ -89,6 +157,28 @@ impl fmt::Display for Error {
             #[cfg(feature = "gpu")]
             Error::TritonError(e) => write!(f, "Neptune-triton Error: {}", e),
             Error::DecodingError => write!(f, "PrimeFieldDecodingError"),
+            Error::InvalidSecurityLevel(bits) => write!(
+                f,
+                "Security level of {} bits is outside of the supported range.",
+                bits
+            ),
+            Error::InvalidAlpha(alpha) => write!(
+                f,
+                "S-box exponent {} does not define a permutation of the field.",
+                alpha
+            ),
+            Error::InvalidWidth(t) => write!(
+                f,
+                "Width {} is outside of the supported range, {} through {}.",
+                t, MIN_WIDTH, MAX_WIDTH
+            ),
+            Error::InvalidPreimageSize { expected, found } => write!(
+                f,
+                "Invalid preimage size: expected {} elements, found {}.",
+                expected, found
+            ),
+            Error::RoundNumberError(e) => write!(f, "Round number error: {}", e),
+            Error::InvalidConstants(s) => write!(f, "Invalid Poseidon constants: {}", s),
             Error::Other(s) => write!(f, "{}", s),
         }
     }
@@ -0,0 +1,182 @@
+use crate::matrix::transpose;
+use crate::poseidon::{Arity, PoseidonConstants};
+use crate::Error;
+use ff::{PrimeField, PrimeFieldRepr, ScalarEngine};
+use std::io::Write;
+
+/// Writes the round constants of `constants` as the reference script `create_rcs_grain.sage` prints them, byte for
+/// byte, so that its output can be compared with `diff`.
+///
+/// Following https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/create_rcs_grain.sage, the output
+/// is the round numbers, the number of round constants, then the round constants on one line as a Python list of
+/// strings. Each constant is big-endian hex, prefixed by `0x` and zero-padded to the bit length of the modulus, i.e. 64
+/// digits for BLS12-381. The constants are in the order they are added, round by round and element by element within
+/// a round. The domain tag is not a round constant, so it is not written.
+///
+/// Constants created with `PoseidonConstants::new_seeded` return `Error::InvalidConstants`, as the reference script
+/// cannot generate their round constants and its format has no place for the seed.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::Bls12;
+/// use generic_array::typenum::U2;
+/// use neptune::export;
+/// use neptune::poseidon::PoseidonConstants;
+///
+/// let mut output = Vec::new();
+/// export::write_round_constants(&PoseidonConstants::<Bls12, U2>::new(), &mut output).unwrap();
+/// let output = String::from_utf8(output).unwrap();
+/// assert!(output.starts_with("R_F: 8\nR_P: 55\n# Constants: 189\nRound constants for GF(p):\n['0x"));
+/// ```
+pub fn write_round_constants<E, A, W>(
+    constants: &PoseidonConstants<E, A>,
+    writer: &mut W,
+) -> Result<(), Error>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+    W: Write,
+{
+    if constants.seed.is_some() {
+        return Err(Error::InvalidConstants(
+            "seeded constants cannot be written in the format of the reference script".to_string(),
+        ));
+    }
+    let num_constants = constants.width() * (constants.full_rounds + constants.partial_rounds);
+    let output = format!(
+        "R_F: {}\nR_P: {}\n# Constants: {}\nRound constants for GF(p):\n{}\n",
+        constants.full_rounds,
+        constants.partial_rounds,
+        num_constants,
+        hex_list::<E>(&constants.round_constants[..num_constants])
+    );
+    write_all(writer, &output)
+}
+
+/// Writes the MDS matrix of `constants` in the notation of `write_round_constants`: `MDS matrix:`, then the rows on
+/// one line as a Python list of lists of hex strings. The matrix is written as the reference implementation applies
+/// it, so the matrix given to `PoseidonConstants::new_with_mds` is written as given.
+pub fn write_mds_matrix<E, A, W>(
+    constants: &PoseidonConstants<E, A>,
+    writer: &mut W,
+) -> Result<(), Error>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+    W: Write,
+{
+    // The permutation multiplies the state by `m` as a row vector, and the reference implementation by its matrix as
+    // a column vector.
+    let rows = transpose::<E>(&constants.mds_matrices.m)
+        .iter()
+        .map(|row| hex_list::<E>(row))
+        .collect::<Vec<_>>();
+    write_all(writer, &format!("MDS matrix:\n[{}]\n", rows.join(", ")))
+}
+
+/// Writes the parameters of `constants` for tools consuming the output of the reference scripts: the round
+/// constants, as `write_round_constants` does, followed by the MDS matrix, as `write_mds_matrix` does.
+pub fn write_parameters<E, A, W>(
+    constants: &PoseidonConstants<E, A>,
+    writer: &mut W,
+) -> Result<(), Error>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+    W: Write,
+{
+    write_round_constants(constants, writer)?;
+    write_mds_matrix(constants, writer)
+}
+
+// Formats the elements as a Python list of `0x`-prefixed big-endian hex strings, as the reference scripts print them.
+fn hex_list<E: ScalarEngine>(elements: &[E::Fr]) -> String {
+    // The digits of the modulus, as the scripts' `int(ceil(float(n) / 4))`.
+    let digits = (E::Fr::NUM_BITS as usize + 3) / 4;
+    let strings = elements
+        .iter()
+        .map(|element| {
+            let mut bytes = Vec::new();
+            element
+                .into_repr()
+                .write_be(&mut bytes)
+                .expect("writing to a Vec cannot fail");
+            let hex = bytes
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>();
+            format!("'0x{}'", &hex[hex.len() - digits..])
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", strings.join(", "))
+}
+
+fn write_all<W: Write>(writer: &mut W, output: &str) -> Result<(), Error> {
+    writer
+        .write_all(output.as_bytes())
+        .map_err(|e| Error::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U8};
+    use std::fs;
+
+    // The output of the reference script in `parameters/`, after the header naming the command that generated it.
+    fn reference_output(t: usize, rf: usize, rp: usize) -> String {
+        let path = format!(
+            "parameters/round_constants-1-1-255-{}-{}-{}-73EDA753299D7D483339D80809A1D80553BDA402FFFE5BFEFFFFFFFF00000001.txt",
+            t, rf, rp
+        );
+        let contents = fs::read_to_string(path).unwrap();
+        contents.splitn(5, '\n').nth(4).unwrap().to_string()
+    }
+
+    fn exported_round_constants<A: Arity<Fr>>() -> String {
+        let mut output = Vec::new();
+        write_round_constants(&PoseidonConstants::<Bls12, A>::new(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_round_constants_match_reference() {
+        assert_eq!(reference_output(3, 8, 55), exported_round_constants::<U2>());
+        assert_eq!(reference_output(9, 8, 57), exported_round_constants::<U8>());
+    }
+
+    #[test]
+    fn test_seeded_constants_are_not_exported() {
+        let constants = PoseidonConstants::<Bls12, U2>::new_seeded(b"my-protocol-v1");
+        let error = write_parameters(&constants, &mut Vec::new()).unwrap_err();
+        assert_eq!(
+            "Invalid Poseidon constants: seeded constants cannot be written in the format of the reference script",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_parameters() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let mut output = Vec::new();
+        write_parameters(&constants, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        // The MDS matrix of width 3 has entries `1 / (i + j + 3)`.
+        let inverses = [
+            "'0x4d491a377113a8daccd13ab0066be558e27e6d5755543d54aaaaaaaa00000001'",
+            "'0x56f23d7e5f361df6266b620607396203fece3b023ffec4ff3fffffff40000001'",
+            "'0x458e97984c2b4b2b51ef819e6c2de803323e959b66656a65cccccccc33333334'",
+            "'0x609b60c54d5893118005895c0806deaf1b1e08ad2aa94ca9d555555480000001'",
+            "'0x211f5460e751918257c7624b7077624aaa362edc49241a48db6db6db24924925'",
+        ];
+        let rows = (0..3)
+            .map(|i| format!("[{}]", inverses[i..i + 3].join(", ")))
+            .collect::<Vec<_>>();
+        let mds = format!("MDS matrix:\n[{}]\n", rows.join(", "));
+
+        assert_eq!(exported_round_constants::<U2>() + &mds, output);
+    }
+}
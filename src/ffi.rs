@@ -0,0 +1,150 @@
+//! C bindings for the Poseidon hash, for callers written in other languages.
+//!
+//! With the `ffi` feature, `cargo rustc --lib --release --features ffi --crate-type cdylib` builds a shared library,
+//! in `target/release`, exporting:
+//!
+//! ```c
+//! int32_t neptune_poseidon_hash(const uint8_t *inputs, size_t n_inputs, uint8_t *output);
+//! ```
+//!
+//! Field elements are 32-byte little-endian BLS12-381 scalars. The return value is `NEPTUNE_OK`, or one of the error
+//! codes below, in which case `output` is left unchanged.
+use crate::poseidon::{batch_hash, validate_width};
+use crate::{scalar_from_le_bytes, Scalar};
+use bellperson::bls::Bls12;
+use ff::{PrimeField, PrimeFieldRepr};
+use std::panic::{self, UnwindSafe};
+use std::slice;
+
+/// The number of bytes of a field element.
+pub const ELEMENT_BYTES: usize = 32;
+
+/// The hash was written to `output`.
+pub const NEPTUNE_OK: i32 = 0;
+/// `inputs` or `output` is null.
+pub const NEPTUNE_NULL_POINTER: i32 = 1;
+/// `n_inputs` is not a supported arity, from 1 through 127.
+pub const NEPTUNE_INVALID_LENGTH: i32 = 2;
+/// An input is not the canonical encoding of a field element.
+pub const NEPTUNE_INVALID_ELEMENT: i32 = 3;
+/// Hashing panicked. The panic is caught rather than unwinding into the caller.
+pub const NEPTUNE_PANIC: i32 = 4;
+
+/// Hashes the `n_inputs` field elements at `inputs` with the Poseidon constants of arity `n_inputs`, as
+/// `poseidon::batch_hash` does, and writes the 32-byte digest to `output`.
+///
+/// # Safety
+///
+/// Unless null, `inputs` must point to `32 * n_inputs` readable bytes, and `output` to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn neptune_poseidon_hash(
+    inputs: *const u8,
+    n_inputs: usize,
+    output: *mut u8,
+) -> i32 {
+    if inputs.is_null() || output.is_null() {
+        return NEPTUNE_NULL_POINTER;
+    }
+    if validate_width(n_inputs.saturating_add(1)).is_err() {
+        return NEPTUNE_INVALID_LENGTH;
+    }
+
+    let bytes = slice::from_raw_parts(inputs, n_inputs * ELEMENT_BYTES);
+    let preimage = match bytes
+        .chunks(ELEMENT_BYTES)
+        .map(scalar_from_le_bytes::<Scalar>)
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(preimage) => preimage,
+        None => return NEPTUNE_INVALID_ELEMENT,
+    };
+
+    let digest = match catch_panics(move || batch_hash::<Bls12>(&[preimage])[0]) {
+        Ok(digest) => digest,
+        Err(code) => return code,
+    };
+
+    let output = slice::from_raw_parts_mut(output, ELEMENT_BYTES);
+    digest
+        .into_repr()
+        .write_le(output)
+        .expect("digest fits in output");
+    NEPTUNE_OK
+}
+
+// Runs `f`, returning `NEPTUNE_PANIC` if it panics, as unwinding across the FFI boundary is undefined behavior.
+fn catch_panics<T, F: FnOnce() -> T + UnwindSafe>(f: F) -> Result<T, i32> {
+    panic::catch_unwind(f).map_err(|_| NEPTUNE_PANIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::{Poseidon, PoseidonConstants};
+    use crate::scalar_from_u64;
+    use generic_array::typenum::{U2, U8};
+    use std::ptr;
+
+    fn to_bytes(elements: &[Scalar]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for element in elements {
+            element.into_repr().write_le(&mut bytes).unwrap();
+        }
+        bytes
+    }
+
+    fn hash(inputs: &[u8], n_inputs: usize) -> Result<Scalar, i32> {
+        let mut output = [0u8; ELEMENT_BYTES];
+        match unsafe { neptune_poseidon_hash(inputs.as_ptr(), n_inputs, output.as_mut_ptr()) } {
+            NEPTUNE_OK => Ok(scalar_from_le_bytes::<Scalar>(&output).unwrap()),
+            code => Err(code),
+        }
+    }
+
+    #[test]
+    fn hash_matches_poseidon() {
+        let preimage: Vec<Scalar> = (0..8).map(scalar_from_u64).collect();
+
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let expected = Poseidon::new_with_preimage(&preimage[..2], &constants).hash();
+        assert_eq!(Ok(expected), hash(&to_bytes(&preimage[..2]), 2));
+
+        let constants = PoseidonConstants::<Bls12, U8>::new();
+        let expected = Poseidon::new_with_preimage(&preimage, &constants).hash();
+        assert_eq!(Ok(expected), hash(&to_bytes(&preimage), 8));
+    }
+
+    #[test]
+    fn hash_rejects_invalid_arguments() {
+        let inputs = to_bytes(&[scalar_from_u64(1), scalar_from_u64(2)]);
+        let mut output = [0u8; ELEMENT_BYTES];
+        unsafe {
+            assert_eq!(
+                NEPTUNE_NULL_POINTER,
+                neptune_poseidon_hash(ptr::null(), 2, output.as_mut_ptr())
+            );
+            assert_eq!(
+                NEPTUNE_NULL_POINTER,
+                neptune_poseidon_hash(inputs.as_ptr(), 2, ptr::null_mut())
+            );
+        }
+        assert_eq!(Err(NEPTUNE_INVALID_LENGTH), hash(&inputs, 0));
+        assert_eq!(Err(NEPTUNE_INVALID_LENGTH), hash(&inputs, 128));
+
+        // The modulus is not a canonical encoding.
+        let mut inputs = inputs;
+        Scalar::char()
+            .write_le(&mut inputs[ELEMENT_BYTES..])
+            .unwrap();
+        assert_eq!(Err(NEPTUNE_INVALID_ELEMENT), hash(&inputs, 2));
+    }
+
+    #[test]
+    fn panics_are_caught() {
+        assert_eq!(Ok(1), catch_panics(|| 1));
+        assert_eq!(
+            Err(NEPTUNE_PANIC),
+            catch_panics(|| -> i32 { panic!("hashing failed") })
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! The constants of the Poseidon widths which Filecoin's proofs hash with, as they create them with
+//! `PoseidonConstants::new()`: the standard strength and the Merkle tree hash type, over BLS12-381.
+//!
+//! They are checked against the test vectors of `tests/vectors/poseidon_bls12_381.json`, which are generated by
+//! `scripts/generate_test_vectors.py`, independently of neptune, but not published by Filecoin: no vectors of
+//! `rust-fil-proofs` are vendored in this repository, so these aliases only fix the parameters, not agreement with
+//! another implementation of them.
+use crate::poseidon::PoseidonConstants;
+use crate::Strength;
+use bellperson::bls::Bls12;
+use generic_array::typenum::{U11, U23};
+use std::sync::Arc;
+
+/// The constants of the Poseidon of width 12 over BLS12-381, with a rate of 11, and the domain tag as the capacity.
+/// These are the constants of `PoseidonConstants::new()`, so `R_F = 8` and `R_P = 57`.
+pub type FilecoinPoseidon12 = PoseidonConstants<Bls12, U11>;
+
+/// The constants of the Poseidon of width 24 over BLS12-381, with a rate of 23, otherwise as `FilecoinPoseidon12`:
+/// `R_F = 8` and `R_P = 59`.
+pub type FilecoinPoseidon24 = PoseidonConstants<Bls12, U23>;
+
+/// Returns the constants of `FilecoinPoseidon12`, those of `PoseidonConstants::shared`, created once per process.
+pub fn filecoin_poseidon_12() -> Arc<FilecoinPoseidon12> {
+    FilecoinPoseidon12::shared(Strength::Standard)
+}
+
+/// Returns the constants of `FilecoinPoseidon24`, those of `PoseidonConstants::shared`, created once per process.
+pub fn filecoin_poseidon_24() -> Arc<FilecoinPoseidon24> {
+    FilecoinPoseidon24::shared(Strength::Standard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_type::HashType;
+
+    #[test]
+    fn filecoin_parameters() {
+        let constants = filecoin_poseidon_12();
+        assert_eq!(12, constants.width());
+        assert_eq!((8, 57), (constants.full_rounds, constants.partial_rounds));
+        assert_eq!(Strength::Standard, constants.strength);
+        assert_eq!(HashType::MerkleTree, constants.hash_type);
+        assert_eq!(12 * (8 + 57), constants.round_constants.len());
+        constants.validate().unwrap();
+
+        let constants = filecoin_poseidon_24();
+        assert_eq!(24, constants.width());
+        assert_eq!((8, 59), (constants.full_rounds, constants.partial_rounds));
+        assert_eq!(Strength::Standard, constants.strength);
+        assert_eq!(HashType::MerkleTree, constants.hash_type);
+        assert_eq!(24 * (8 + 59), constants.round_constants.len());
+        constants.validate().unwrap();
+    }
+
+    #[test]
+    fn filecoin_constants_are_shared() {
+        assert!(Arc::ptr_eq(
+            &filecoin_poseidon_12(),
+            &filecoin_poseidon_12()
+        ));
+        assert!(Arc::ptr_eq(
+            &filecoin_poseidon_24(),
+            &PoseidonConstants::<Bls12, U23>::shared(Strength::Standard)
+        ));
+    }
+}
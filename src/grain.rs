@@ -0,0 +1,428 @@
+use crate::{Sbox, FIELD};
+use core::marker::PhantomData;
+use ff::{Field, PrimeField, PrimeFieldDecodingError, PrimeFieldRepr, ScalarEngine};
+
+// The BLAKE2s personalization with which seeds are hashed before they are mixed into the state.
+const SEED_PERSONAL: &[u8] = b"neptune";
+
+/// The Grain LFSR in self-shrinking mode, seeded with the parameters of a Poseidon instance, which yields the round
+/// constants of that instance as an unbounded sequence of field elements.
+///
+/// From the paper ():
+/// The round constants are generated using the Grain LFSR [23] in a self-shrinking
+/// mode:
+/// 1. Initialize the state with 80 bits b0, b1, . . . , b79, where
+/// (a) b0, b1 describe the field,
+/// (b) bi for 2 ≤ i ≤ 5 describe the S-Box,
+/// (c) bi for 6 ≤ i ≤ 17 are the binary representation of n,
+/// (d) bi for 18 ≤ i ≤ 29 are the binary representation of t,
+/// (e) bi for 30 ≤ i ≤ 39 are the binary representation of RF ,
+/// (f) bi for 40 ≤ i ≤ 49 are the binary representation of RP , and
+/// (g) bi for 50 ≤ i ≤ 79 are set to 1.
+/// 2. Update the bits using bi+80 = bi+62 ⊕ bi+51 ⊕ bi+38 ⊕ bi+23 ⊕ bi+13 ⊕ bi
+/// .
+/// 3. Discard the first 160 bits.
+/// 4. Evaluate bits in pairs: If the first bit is a 1, output the second bit. If it is a
+/// 0, discard the second bit.
+/// Using this method, the generation of round constants depends on the specific
+/// instance, and thus different round constants are used even if some of the chosen
+/// parameters (e.g., n and t) are the same.
+/// If a randomly sampled integer is not in Fp, we discard this value and take the
+/// next one. Note that cryptographically strong randomness is not needed for the
+/// round constants, and other methods can also be used.
+///
+/// Following https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/create_rcs_grain.sage
+///
+/// The field bits are `1`, a prime field, and the S-box bits are `Sbox::id`. Each parameter is written most
+/// significant bit first. As an iterator, the LFSR samples `prime_bitlen` output bits at a time, most significant bit
+/// first, and yields each sample below the modulus as an element of the field of `E`, discarding the others.
+/// `prime_bitlen` may be below the bit length of the field, but must fit in the representation of its elements.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::Bls12;
+/// use generic_array::typenum::U2;
+/// use neptune::grain::GrainLfsr;
+/// use neptune::poseidon::PoseidonConstants;
+/// use neptune::Sbox;
+///
+/// let constants = PoseidonConstants::<Bls12, U2>::new();
+/// let generated: Vec<_> = GrainLfsr::<Bls12>::new(255, Sbox::Quintic, 3, 8, 55)
+///     .take(constants.round_constants.len())
+///     .collect();
+/// assert_eq!(constants.round_constants, generated);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GrainLfsr<E: ScalarEngine> {
+    state: Vec<bool>,
+    field_size: u16,
+    _e: PhantomData<E>,
+}
+
+impl<E: ScalarEngine> GrainLfsr<E> {
+    /// Seeds the LFSR for a prime field of `prime_bitlen` bits, the `sbox`, width `t`, and `rf` full and `rp` partial
+    /// rounds. Field elements are sampled from `prime_bitlen` bits at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a parameter does not fit in its bits of the seed: 12 bits for `prime_bitlen` and `t`, and 10 bits for
+    /// `rf` and `rp`, or if `prime_bitlen` exceeds the bits of the representation of a field element.
+    pub fn new(prime_bitlen: usize, sbox: Sbox, t: usize, rf: usize, rp: usize) -> Self {
+        check_parameters(prime_bitlen, t, rf, rp);
+        Self::from_seed(
+            FIELD,
+            sbox.id(),
+            prime_bitlen as u16,
+            t as u16,
+            rf as u16,
+            rp as u16,
+            None,
+        )
+    }
+
+    /// Seeds the LFSR with the parameters as `new` does, then mixes in `seed`, so that instances with the same
+    /// parameters but different seeds yield independent round constants. See `PoseidonConstants::new_seeded`.
+    ///
+    /// The 256 bits of the BLAKE2s hash of `seed` are clocked into the state after it is initialized with the
+    /// parameters, each added to the bit fed back, before the first 160 bits are discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a parameter does not fit in its bits of the initial state, as `new` does.
+    pub fn new_seeded(
+        prime_bitlen: usize,
+        sbox: Sbox,
+        t: usize,
+        rf: usize,
+        rp: usize,
+        seed: &[u8],
+    ) -> Self {
+        check_parameters(prime_bitlen, t, rf, rp);
+        Self::from_seed(
+            FIELD,
+            sbox.id(),
+            prime_bitlen as u16,
+            t as u16,
+            rf as u16,
+            rp as u16,
+            Some(seed),
+        )
+    }
+
+    // Seeds the LFSR with the field and S-box identifiers of the reference script, and mixes in `seed` if there is one.
+    // Only the low bits of each parameter that fit in the seed are used.
+    pub(crate) fn from_seed(
+        field: u8,
+        sbox: u8,
+        field_size: u16,
+        t: u16,
+        r_f: u16,
+        r_p: u16,
+        seed: Option<&[u8]>,
+    ) -> Self {
+        let repr_bits = repr_bytes::<E>() * 8;
+        assert!(
+            field_size as usize <= repr_bits,
+            "prime bit length {} exceeds the {} bits of a field element",
+            field_size,
+            repr_bits
+        );
+
+        let mut init_sequence: Vec<bool> = Vec::new();
+        append_bits(&mut init_sequence, 2, field); // Bits 0-1
+        append_bits(&mut init_sequence, 4, sbox); // Bits 2-5
+        append_bits(&mut init_sequence, 12, field_size); // Bits 6-17
+        append_bits(&mut init_sequence, 12, t); // Bits 18-29
+        append_bits(&mut init_sequence, 10, r_f); // Bits 30-39
+        append_bits(&mut init_sequence, 10, r_p); // Bits 40-49
+        append_bits(&mut init_sequence, 30, 0b111111111111111111111111111111u128); // Bits 50-79
+        assert_eq!(80, init_sequence.len());
+
+        let mut g = GrainLfsr {
+            state: init_sequence,
+            field_size,
+            _e: PhantomData,
+        };
+        if let Some(seed) = seed {
+            let digest = blake2s_simd::Params::new()
+                .personal(SEED_PERSONAL)
+                .hash(seed);
+            for byte in digest.as_bytes() {
+                for i in (0..8).rev() {
+                    let bit = g.generate_new_bit() ^ ((byte >> i) & 1 != 0);
+                    *g.state.last_mut().unwrap() = bit;
+                }
+            }
+        }
+        for _ in 0..160 {
+            g.generate_new_bit();
+        }
+        assert_eq!(80, g.state.len());
+        g
+    }
+
+    fn generate_new_bit(&mut self) -> bool {
+        let new_bit =
+            self.bit(62) ^ self.bit(51) ^ self.bit(38) ^ self.bit(23) ^ self.bit(13) ^ self.bit(0);
+        self.state.remove(0);
+        self.state.push(new_bit);
+        new_bit
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.state[index]
+    }
+
+    // Returns the next output bit of the self-shrinking generator.
+    fn next_bit(&mut self) -> bool {
+        let mut new_bit = self.generate_new_bit();
+        while !new_bit {
+            let _new_bit = self.generate_new_bit();
+            new_bit = self.generate_new_bit();
+        }
+        self.generate_new_bit()
+    }
+
+    // Returns the next sample of `field_size` bits reduced modulo the field, discarding none, as the reference script's
+    // `create_mds_p` samples the points of its Cauchy matrix with `F(grain_random_bits(n))`.
+    pub(crate) fn next_reduced(&mut self) -> E::Fr {
+        let mut element = E::Fr::zero();
+        for _ in 0..self.field_size {
+            element.double();
+            if self.next_bit() {
+                element.add_assign(&E::Fr::one());
+            }
+        }
+        element
+    }
+
+    fn next_byte(&mut self, bit_count: usize) -> u8 {
+        // Accumulate bits from most to least significant, so the most significant bit is the one generated first by the bit stream.
+        let mut acc: u8 = 0;
+        for _ in 0..bit_count {
+            acc <<= 1;
+            if self.next_bit() {
+                acc += 1;
+            }
+        }
+
+        acc
+    }
+
+    fn get_next_bytes(&mut self, result: &mut [u8]) {
+        let full_bytes = self.field_size as usize / 8;
+        let remainder_bits = self.field_size as usize % 8;
+
+        // Prime fields will always have remainder bits,
+        // but other field types could be supported in the future.
+        if remainder_bits > 0 {
+            // If there is an unfull byte, it should be the first.
+            result[0] = self.next_byte(remainder_bits);
+
+            // Subsequent bytes are packed into result in the order generated.
+            for i in 1..=full_bytes {
+                result[i] = self.next_byte(8);
+            }
+        } else {
+            for i in 0..full_bytes {
+                result[i] = self.next_byte(8);
+            }
+        }
+    }
+}
+
+impl<E: ScalarEngine> Iterator for GrainLfsr<E> {
+    type Item = E::Fr;
+
+    /// Returns the next sample that is an element of the field, discarding those that are not.
+    fn next(&mut self) -> Option<Self::Item> {
+        // Smallest number of bytes which will hold one field element.
+        let element_bytes = (self.field_size / 8) + ((self.field_size % 8) > 0) as u16;
+        loop {
+            let mut bytes = vec![0u8; element_bytes as usize];
+            self.get_next_bytes(&mut bytes);
+            if let Ok(f) = bytes_into_fr::<E>(&bytes) {
+                return Some(f);
+            }
+        }
+    }
+}
+
+// Panics unless each parameter fits in its bits of the seed.
+fn check_parameters(prime_bitlen: usize, t: usize, rf: usize, rp: usize) {
+    for (name, value, bits) in [
+        ("prime bit length", prime_bitlen, 12),
+        ("width", t, 12),
+        ("full rounds", rf, 10),
+        ("partial rounds", rp, 10),
+    ]
+    .iter()
+    {
+        assert!(
+            *value < 1 << *bits,
+            "{} does not fit in {} bits: {}",
+            name,
+            bits,
+            value
+        );
+    }
+}
+
+fn append_bits<T: Into<u128>>(vec: &mut Vec<bool>, n: usize, from: T) {
+    let val = from.into() as u128;
+    for i in (0..n).rev() {
+        vec.push((val >> i) & 1 != 0);
+    }
+}
+
+// The number of bytes of the big-endian representation of a field element, e.g. 32 for BLS12-381.
+fn repr_bytes<E: ScalarEngine>() -> usize {
+    <<E::Fr as PrimeField>::Repr as Default>::default()
+        .as_ref()
+        .len()
+        * 8
+}
+
+// Takes a slice of at most `repr_bytes` big-endian bytes and returns an Fr if it does not overflow. Otherwise, returns
+// a BadFrBytesError.
+fn bytes_into_fr<E: ScalarEngine>(bytes: &[u8]) -> Result<E::Fr, PrimeFieldDecodingError> {
+    let mut padded = vec![0u8; repr_bytes::<E>()];
+    let start = padded.len() - bytes.len();
+    padded[start..].copy_from_slice(bytes);
+
+    let mut fr_repr = <<<E as ScalarEngine>::Fr as PrimeField>::Repr as Default>::default();
+    fr_repr
+        // Try to read one field element from big-endian bytes.
+        // Bytes are big-endian to agree with the integers generated by grain_random_bits in the reference implementation:
+        //
+        // def grain_random_bits(num_bits):
+        //     random_bits = [grain_gen.next() for i in range(0, num_bits)]
+        //     random_int = int("".join(str(i) for i in random_bits), 2)
+        //     return random_int
+        .read_be(&padded[..])
+        .map_err(|e| PrimeFieldDecodingError::NotInField(e.to_string()))?;
+
+    E::Fr::from_repr(fr_repr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::{Arity, PoseidonConstants};
+    use crate::Strength;
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U1, U11, U16, U2, U24, U32, U36, U4, U8};
+
+    #[test]
+    fn grain_matches_round_constants() {
+        let constants = PoseidonConstants::<Bls12, U8>::new();
+        let generated: Vec<_> = GrainLfsr::<Bls12>::new(255, Sbox::Quintic, 9, 8, 57)
+            .take(constants.round_constants.len())
+            .collect();
+        assert_eq!(constants.round_constants, generated);
+
+        let constants = PoseidonConstants::<Bls12, U2>::new_with_sbox(Sbox::Inverse);
+        let generated: Vec<_> = GrainLfsr::<Bls12>::new(
+            255,
+            Sbox::Inverse,
+            3,
+            constants.full_rounds,
+            constants.partial_rounds,
+        )
+        .take(constants.round_constants.len())
+        .collect();
+        assert_eq!(constants.round_constants, generated);
+    }
+
+    #[test]
+    fn grain_matches_standard_widths() {
+        fn check<A: Arity<Fr>>(strength: Strength) {
+            let constants = PoseidonConstants::<Bls12, A>::new_with_strength(strength);
+            let generated: Vec<_> = GrainLfsr::<Bls12>::new(
+                255,
+                Sbox::Quintic,
+                constants.width(),
+                constants.full_rounds,
+                constants.partial_rounds,
+            )
+            .take(constants.round_constants.len())
+            .collect();
+            assert_eq!(constants.round_constants, generated);
+        }
+
+        for strength in [Strength::Standard, Strength::Strengthened].iter() {
+            check::<U1>(*strength);
+            check::<U2>(*strength);
+            check::<U4>(*strength);
+            check::<U8>(*strength);
+            check::<U11>(*strength);
+            check::<U16>(*strength);
+            check::<U24>(*strength);
+            check::<U32>(*strength);
+            check::<U36>(*strength);
+        }
+    }
+
+    #[test]
+    fn grain_narrow_samples() {
+        // Every 64-bit sample is below the modulus, so none are discarded.
+        for element in GrainLfsr::<Bls12>::new(64, Sbox::Quintic, 3, 8, 55).take(16) {
+            assert!(element.into_repr().as_ref()[1..]
+                .iter()
+                .all(|limb| *limb == 0));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "prime bit length 300 exceeds the 256 bits of a field element")]
+    fn grain_prime_bitlen_too_large() {
+        GrainLfsr::<Bls12>::new(300, Sbox::Quintic, 3, 8, 55);
+    }
+
+    #[test]
+    fn grain_depends_on_every_parameter() {
+        let first = |prime_bitlen, sbox, t, rf, rp| {
+            GrainLfsr::<Bls12>::new(prime_bitlen, sbox, t, rf, rp)
+                .next()
+                .unwrap()
+        };
+        let base = first(255, Sbox::Quintic, 3, 8, 55);
+        assert_ne!(base, first(254, Sbox::Quintic, 3, 8, 55));
+        assert_ne!(base, first(255, Sbox::Cubic, 3, 8, 55));
+        assert_ne!(base, first(255, Sbox::Quintic, 4, 8, 55));
+        assert_ne!(base, first(255, Sbox::Quintic, 3, 10, 55));
+        assert_ne!(base, first(255, Sbox::Quintic, 3, 8, 56));
+    }
+
+    #[test]
+    fn grain_seeded() {
+        let take = |g: GrainLfsr<Bls12>| g.take(3).collect::<Vec<_>>();
+        let seeded = |seed: &[u8]| take(GrainLfsr::new_seeded(255, Sbox::Quintic, 3, 8, 55, seed));
+
+        assert_eq!(seeded(b"protocol"), seeded(b"protocol"));
+        assert_ne!(seeded(b"protocol"), seeded(b"protocol-v2"));
+        // Even the empty seed is mixed in, so seeded constants differ from those of the reference script.
+        assert_ne!(
+            take(GrainLfsr::new(255, Sbox::Quintic, 3, 8, 55)),
+            seeded(b"")
+        );
+        assert_ne!(
+            seeded(b"protocol"),
+            take(GrainLfsr::new_seeded(
+                255,
+                Sbox::Quintic,
+                3,
+                8,
+                56,
+                b"protocol"
+            ))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "partial rounds does not fit in 10 bits: 1024")]
+    fn grain_partial_rounds_too_large() {
+        GrainLfsr::<Bls12>::new(255, Sbox::Quintic, 3, 8, 1024);
+    }
+}
@@ -8,7 +8,7 @@
 /// where another is required.
 ///
 /// Because `neptune` also supports a first-class notion of `Strength`, we include a mechanism for composing
-/// `Strength` with `HashType` so that hashes with `Strength` other than `Standard` (currently only `Strengthened`)
+/// `Strength` with `HashType` so that hashes with `Strength` other than `Standard` (`Strengthened` and `Custom`)
 /// may still express the full range of hash function types.
 use crate::{scalar_from_u64, Arity, Strength};
 use ff::{Field, PrimeField, ScalarEngine};
@@ -20,6 +20,8 @@ pub enum HashType<Fr: PrimeField, A: Arity<Fr>> {
     VariableLength,
     ConstantLength(usize),
     Encryption,
+    /// Poseidon as a pseudo-random function of a secret key and an input. See `prf::PoseidonPrf`.
+    Prf,
     Custom(CType<Fr, A>),
 }
 
@@ -49,9 +51,9 @@ impl<Fr: PrimeField, A: Arity<Fr>> HashType<Fr, A> {
             }
             // 2^32
             HashType::Encryption => with_strength(pow2(32)),
-            // identifier * 2^40
-            // NOTE: in order to leave room for future `Strength` tags,
-            // we make identifier a multiple of 2^40 rather than 2^32.
+            // 2^96
+            HashType::Prf => with_strength(pow2(96)),
+            // identifier * 2^32, for identifiers from `MIN_ARBITRARY_ID` through `MAX_ARBITRARY_ID`
             HashType::Custom(ref ctype) => ctype.domain_tag(&strength),
         }
     }
@@ -61,6 +63,7 @@ impl<Fr: PrimeField, A: Arity<Fr>> HashType<Fr, A> {
             // Standard strength doesn't affect the base tag.
             Strength::Standard => 0,
             Strength::Strengthened => 1,
+            Strength::Custom { .. } => 2,
         };
 
         x_pow2::<Fr, A>(id, 32)
@@ -76,13 +79,23 @@ impl<Fr: PrimeField, A: Arity<Fr>> HashType<Fr, A> {
             HashType::VariableLength => false,
             HashType::ConstantLength(_) => true,
             HashType::Encryption => true,
+            HashType::Prf => true,
             HashType::Custom(_) => false,
         }
     }
 }
 
+/// The smallest identifier of `CType::Arbitrary`: the tags of smaller identifiers are zero and those of `Encryption`.
+pub const MIN_ARBITRARY_ID: u64 = 4;
+
+/// The largest identifier of `CType::Arbitrary`, whose tag, `identifier * 2^32`, stays below `2^64`, the smallest of
+/// the tags of `VariableLength`, `ConstantLength`, and `Prf`.
+pub const MAX_ARBITRARY_ID: u64 = (1 << 32) - 1;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum CType<Fr: PrimeField, A: Arity<Fr>> {
+    /// A custom hash type with its own identifier, from `MIN_ARBITRARY_ID` through `MAX_ARBITRARY_ID`, so that its
+    /// domain tag is distinct from those of the other hash types. `HashType::domain_tag` panics for other identifiers.
     Arbitrary(u64),
     _Phantom((Fr, A)),
 }
@@ -96,7 +109,15 @@ impl<Fr: PrimeField, A: Arity<Fr>> CType<Fr, A> {
     }
 
     fn domain_tag(&self, _strength: &Strength) -> Fr {
-        x_pow2::<Fr, A>(self.identifier(), 32)
+        let id = self.identifier();
+        assert!(
+            (MIN_ARBITRARY_ID..=MAX_ARBITRARY_ID).contains(&id),
+            "custom hash type identifiers must be from {} through {}, not {}",
+            MIN_ARBITRARY_ID,
+            MAX_ARBITRARY_ID,
+            id
+        );
+        x_pow2::<Fr, A>(id, 32)
     }
 }
 
@@ -143,10 +164,21 @@ mod tests {
         ]);
         assert_eq!(expected_merkle_strengthened, merkle_strengthened,);
 
+        let merkle_custom = HashType::MerkleTree::<Fr, U8>.domain_tag(&Strength::Custom {
+            full: 8,
+            partial: 57,
+        });
+        let expected_merkle_custom = scalar_from_u64s([
+            0x00000002000000ff,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+        ]);
+        assert_eq!(expected_merkle_custom, merkle_custom);
+
         // TODO: tests for
         // MerkleTreeSparse(u64),
         // VariableLength,
-        // Custom(CType<Fr, A>),
 
         let mut all_tags = Vec::new();
 
@@ -219,11 +251,61 @@ mod tests {
         ]);
         assert_eq!(expected_encryption_strengthened, encryption_strengthened);
 
+        let prf_standard = HashType::Prf::<Fr, U8>.domain_tag(&Strength::Standard);
+        let expected_prf_standard = scalar_from_u64s([
+            0x0000000000000000,
+            0x0000000100000000,
+            0x0000000000000000,
+            0x0000000000000000,
+        ]);
+        assert_eq!(expected_prf_standard, prf_standard);
+
+        let prf_strengthened = HashType::Prf::<Fr, U8>.domain_tag(&Strength::Strengthened);
+        let expected_prf_strengthened = scalar_from_u64s([
+            0x0000000100000000,
+            0x0000000100000000,
+            0x0000000000000000,
+            0x0000000000000000,
+        ]);
+        assert_eq!(expected_prf_strengthened, prf_strengthened);
+
+        // Custom tags are the identifier times 2^32, whatever the strength, between the tags of `Encryption` and of
+        // `VariableLength`, `ConstantLength`, and `Prf`.
+        let custom =
+            |id| HashType::Custom(CType::<Fr, U8>::Arbitrary(id)).domain_tag(&Strength::Standard);
+        let expected_custom_min = scalar_from_u64s([
+            0x0000000400000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+        ]);
+        let expected_custom_max = scalar_from_u64s([
+            0xffffffff00000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+        ]);
+        assert_eq!(expected_custom_min, custom(MIN_ARBITRARY_ID));
+        assert_eq!(expected_custom_max, custom(MAX_ARBITRARY_ID));
+        assert_eq!(
+            expected_custom_max,
+            HashType::Custom(CType::<Fr, U8>::Arbitrary(MAX_ARBITRARY_ID))
+                .domain_tag(&Strength::Strengthened)
+        );
+
         all_tags.extend(&[
+            HashType::VariableLength::<Fr, U8>.domain_tag(&Strength::Standard),
+            HashType::VariableLength::<Fr, U8>.domain_tag(&Strength::Strengthened),
+            expected_custom_min,
+            custom(MIN_ARBITRARY_ID + 1),
+            custom(MAX_ARBITRARY_ID - 1),
+            expected_custom_max,
             expected_merkle_standard,
             expected_merkle_strengthened,
             expected_encryption_standard,
             expected_encryption_strengthened,
+            expected_prf_standard,
+            expected_prf_strengthened,
         ]);
 
         let mut all_tags_set = HashSet::new();
@@ -235,4 +317,20 @@ mod tests {
         // hence no tag is duplicated.
         assert_eq!(all_tags.len(), all_tags_set.len());
     }
+
+    #[test]
+    #[should_panic(
+        expected = "custom hash type identifiers must be from 4 through 4294967295, not 4294967296"
+    )]
+    fn custom_tag_below_variable_length() {
+        HashType::Custom(CType::<Fr, U8>::Arbitrary(1 << 32)).domain_tag(&Strength::Standard);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "custom hash type identifiers must be from 4 through 4294967295, not 1"
+    )]
+    fn custom_tag_above_encryption() {
+        HashType::Custom(CType::<Fr, U8>::Arbitrary(1)).domain_tag(&Strength::Standard);
+    }
 }
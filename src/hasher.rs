@@ -0,0 +1,283 @@
+use crate::poseidon::{Arity, PoseidonConstants};
+use crate::sponge::PoseidonSponge;
+use crate::{scalar_from_u64, Strength};
+use ff::{PrimeField, PrimeFieldRepr, ScalarEngine};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+
+/// A `BuildHasher` of `PoseidonHasher`s, hashing the keys of a `HashMap` or `HashSet` with the Poseidon sponge.
+///
+/// Each `PoseidonBuildHasher` has a secret key, absorbed before the bytes of every value, as `RandomState` keys
+/// SipHash. As the round constants are public, the hash only resists flooding while the key is secret.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::Bls12;
+/// use generic_array::typenum::U2;
+/// use neptune::hasher::PoseidonBuildHasher;
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::with_hasher(PoseidonBuildHasher::<Bls12, U2>::new());
+/// map.insert("poseidon", 1);
+/// assert_eq!(Some(&1), map.get("poseidon"));
+/// ```
+pub struct PoseidonBuildHasher<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    constants: Arc<PoseidonConstants<E, A>>,
+    key: E::Fr,
+}
+
+impl<E, A> PoseidonBuildHasher<E, A>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync + 'static,
+{
+    /// Creates a `PoseidonBuildHasher` with a random key, from the randomness with which `RandomState` is seeded.
+    pub fn new() -> Self {
+        let mut bytes = Vec::with_capacity(16);
+        for _ in 0..2 {
+            let random = RandomState::new().build_hasher().finish();
+            bytes.extend_from_slice(&random.to_le_bytes());
+        }
+        Self::with_key(element_from_le_bytes::<E>(&bytes))
+    }
+
+    /// Creates a `PoseidonBuildHasher` with the given key, so that values hash the same in every process.
+    ///
+    /// The constants are those of `PoseidonConstants::shared`, so that build hashers of the same field and arity hold
+    /// a single copy of them.
+    pub fn with_key(key: E::Fr) -> Self {
+        PoseidonBuildHasher {
+            constants: PoseidonConstants::shared(Strength::Standard),
+            key,
+        }
+    }
+}
+
+impl<E, A> Default for PoseidonBuildHasher<E, A>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, A> Clone for PoseidonBuildHasher<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn clone(&self) -> Self {
+        PoseidonBuildHasher {
+            constants: self.constants.clone(),
+            key: self.key,
+        }
+    }
+}
+
+impl<E, A> BuildHasher for PoseidonBuildHasher<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    type Hasher = PoseidonHasher<E, A>;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        PoseidonHasher {
+            constants: self.constants.clone(),
+            elements: vec![self.key],
+            pending: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+/// A `Hasher` absorbing the bytes written to it into a `PoseidonSponge`, created by `PoseidonBuildHasher`.
+///
+/// Bytes are packed into field elements little-endian, `element_bytes` at a time, which is the number of whole bytes
+/// below the capacity of the field, so that every packing is an element. `finish` absorbs the key, then each packed
+/// element, then the trailing bytes zero-padded to an element, and last the number of bytes written, so that streams
+/// differing only in trailing zeros hash differently. It returns the low 64 bits of the first squeezed element.
+///
+/// The elements are absorbed when finishing, as the sponge borrows the constants, so writes are buffered.
+pub struct PoseidonHasher<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    constants: Arc<PoseidonConstants<E, A>>,
+    // The key, followed by the elements packed so far.
+    elements: Vec<E::Fr>,
+    // Bytes written since the last packed element, fewer than `element_bytes`.
+    pending: Vec<u8>,
+    len: u64,
+}
+
+impl<E, A> Hasher for PoseidonHasher<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn write(&mut self, bytes: &[u8]) {
+        self.len = self.len.wrapping_add(bytes.len() as u64);
+        for byte in bytes {
+            self.pending.push(*byte);
+            if self.pending.len() == element_bytes::<E>() {
+                self.elements
+                    .push(element_from_le_bytes::<E>(&self.pending));
+                self.pending.clear();
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut sponge = PoseidonSponge::new(&self.constants);
+        sponge.absorb_elements(&self.elements);
+        if !self.pending.is_empty() {
+            sponge.absorb(&element_from_le_bytes::<E>(&self.pending));
+        }
+        sponge.absorb(&scalar_from_u64(self.len));
+        sponge.squeeze().into_repr().as_ref()[0]
+    }
+}
+
+/// With the `zeroize` feature, the packed elements, including the key, and the pending bytes are overwritten with
+/// zeros when the hasher is dropped.
+#[cfg(feature = "zeroize")]
+impl<E, A> Drop for PoseidonHasher<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn drop(&mut self) {
+        use ff::Field;
+
+        crate::wipe::wipe_vec(&mut self.elements, E::Fr::zero());
+        crate::wipe::wipe_vec(&mut self.pending, 0);
+    }
+}
+
+/// The number of bytes packed into each field element by `PoseidonHasher`, e.g. 31 for BLS12-381.
+pub fn element_bytes<E: ScalarEngine>() -> usize {
+    E::Fr::CAPACITY as usize / 8
+}
+
+// Packs at most `element_bytes` little-endian bytes into a field element, zero-padding them.
+fn element_from_le_bytes<E: ScalarEngine>(bytes: &[u8]) -> E::Fr {
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    let mut padded = vec![0u8; repr.as_ref().len() * 8];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    repr.read_le(&padded[..])
+        .expect("padded to the size of a repr");
+    #[cfg(feature = "zeroize")]
+    crate::wipe::wipe_vec(&mut padded, 0);
+    E::Fr::from_repr(repr).expect("fewer bits than the capacity of the field")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U4};
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    fn hash_bytes<A: Arity<Fr>>(
+        build_hasher: &PoseidonBuildHasher<Bls12, A>,
+        writes: &[&[u8]],
+    ) -> u64 {
+        let mut hasher = build_hasher.build_hasher();
+        for bytes in writes {
+            hasher.write(bytes);
+        }
+        hasher.finish()
+    }
+
+    #[test]
+    fn hasher_matches_sponge() {
+        let build_hasher = PoseidonBuildHasher::<Bls12, U2>::with_key(scalar_from_u64(7));
+        let bytes: Vec<u8> = (0..40).collect();
+
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let mut sponge = PoseidonSponge::new(&constants);
+        sponge.absorb(&scalar_from_u64(7));
+        sponge.absorb(&element_from_le_bytes::<Bls12>(&bytes[..31]));
+        sponge.absorb(&element_from_le_bytes::<Bls12>(&bytes[31..]));
+        sponge.absorb(&scalar_from_u64(40));
+        let expected = sponge.squeeze().into_repr().as_ref()[0];
+
+        assert_eq!(31, element_bytes::<Bls12>());
+        assert_eq!(expected, hash_bytes(&build_hasher, &[&bytes[..]]));
+        // Only the bytes written matter, not how they are split between writes.
+        assert_eq!(
+            expected,
+            hash_bytes(&build_hasher, &[&bytes[..3], &bytes[3..35], &bytes[35..]])
+        );
+    }
+
+    #[test]
+    fn hasher_padding() {
+        let build_hasher = PoseidonBuildHasher::<Bls12, U4>::with_key(scalar_from_u64(7));
+        let zeros = [0u8; 63];
+
+        // Streams of zeros of every length up to two elements and a byte hash differently.
+        let mut digests: Vec<u64> = (0..=zeros.len())
+            .map(|n| hash_bytes(&build_hasher, &[&zeros[..n]]))
+            .collect();
+        digests.sort();
+        digests.dedup();
+        assert_eq!(zeros.len() + 1, digests.len());
+    }
+
+    #[test]
+    fn hasher_depends_on_key() {
+        let hash = |build_hasher: &PoseidonBuildHasher<Bls12, U2>| {
+            let mut hasher = build_hasher.build_hasher();
+            "poseidon".hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let keyed = PoseidonBuildHasher::with_key(scalar_from_u64(7));
+        assert_eq!(hash(&keyed), hash(&keyed.clone()));
+        assert_eq!(
+            hash(&keyed),
+            hash(&PoseidonBuildHasher::with_key(scalar_from_u64(7)))
+        );
+        assert_ne!(
+            hash(&keyed),
+            hash(&PoseidonBuildHasher::with_key(scalar_from_u64(8)))
+        );
+        assert_ne!(
+            hash(&PoseidonBuildHasher::new()),
+            hash(&PoseidonBuildHasher::new())
+        );
+    }
+
+    #[test]
+    fn hasher_shares_constants() {
+        let a = PoseidonBuildHasher::<Bls12, U2>::with_key(scalar_from_u64(7));
+        let b = PoseidonBuildHasher::<Bls12, U2>::new();
+        assert!(Arc::ptr_eq(&a.constants, &b.constants));
+        assert!(Arc::ptr_eq(&a.constants, &a.build_hasher().constants));
+    }
+
+    #[test]
+    fn hash_map_with_poseidon_hasher() {
+        let mut map = HashMap::with_hasher(PoseidonBuildHasher::<Bls12, U2>::default());
+        for i in 0..100u64 {
+            map.insert(i, i * i);
+        }
+        assert_eq!(100, map.len());
+        for i in 0..100u64 {
+            assert_eq!(Some(&(i * i)), map.get(&i));
+        }
+        assert_eq!(None, map.get(&100));
+    }
+}
@@ -0,0 +1,412 @@
+use crate::generate_mds_matrix;
+use crate::hash_type::HashType;
+use crate::matrix::{transpose, Matrix};
+use crate::mds::check_loaded_mds_matrix;
+use crate::poseidon::{Arity, PoseidonConstants};
+use crate::round_numbers::check_round_numbers;
+use crate::{
+    alpha_is_coprime, field_round_numbers, Error, Sbox, Strength, DEFAULT_SBOX,
+    DEFAULT_SECURITY_LEVEL,
+};
+use ff::{PrimeField, PrimeFieldRepr, ScalarEngine};
+use generic_array::typenum::Unsigned;
+use std::io::Read;
+
+// The header line naming the command that generated the round constants, as in `parameters/round_constants-*`.
+const COMMAND: &str = "# sage create_rcs_grain.sage ";
+
+impl<E, A> PoseidonConstants<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Reads constants for Merkle trees from the output of the reference script `create_rcs_grain.sage`, as written by
+    /// `export::write_parameters`, rather than generating them.
+    ///
+    /// The round numbers are read from the `R_F` and `R_P` lines, and the round constants from the line following
+    /// `Round constants for GF(p):`, as a list of big-endian hex strings. If an `MDS matrix:` line follows, the next
+    /// line is read as the MDS matrix, multiplying the state as a column vector as `export::write_mds_matrix` writes
+    /// it. Unless it is the Cauchy matrix of `generate_mds_matrix`, it must pass `check_mds_matrix`, as the matrix of
+    /// `PoseidonConstants::new_with_mds` must. Without the line, the Cauchy matrix is used. The optimized constants
+    /// are derived as for generated constants.
+    ///
+    /// Comments and blank lines are skipped, except for the header naming the command that generated the constants.
+    /// If present, its field must be the prime field of `E`, and its S-box identifier sets the S-box. The strength is
+    /// `Strength::Standard` or `Strength::Strengthened` if the round numbers are theirs, and `Strength::Custom`
+    /// otherwise.
+    ///
+    /// The round numbers must satisfy the security inequalities at the default security level, or
+    /// `Error::RoundNumberError` is returned. Malformed lines, another modulus or width, the wrong number of round
+    /// constants, or elements not below the modulus return `Error::InvalidConstants`.
+    pub fn from_reference_params<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let invalid = |message: String| Err(Error::InvalidConstants(message));
+
+        let mut contents = String::new();
+        reader
+            .read_to_string(&mut contents)
+            .map_err(|e| Error::Other(e.to_string()))?;
+
+        let width = A::ConstantsSize::to_usize();
+        let mut sbox = DEFAULT_SBOX;
+        let mut full_rounds = None;
+        let mut partial_rounds = None;
+        let mut declared_constants = None;
+        let mut round_constants = None;
+        let mut mds_matrix = None;
+
+        let mut lines = contents.lines().enumerate().map(|(i, line)| (i + 1, line));
+        while let Some((number, line)) = lines.next() {
+            let malformed =
+                || Error::InvalidConstants(format!("malformed line {}: {:?}", number, line));
+            let mut next_line = || match lines.next() {
+                Some((_, line)) => Ok(line),
+                None => Err(Error::InvalidConstants(format!(
+                    "expected a list after line {}",
+                    number
+                ))),
+            };
+
+            if line.starts_with(COMMAND) {
+                sbox = parse_command::<E>(&line[COMMAND.len()..], width).map_err(|message| {
+                    Error::InvalidConstants(format!("line {}: {}", number, message))
+                })?;
+            } else if line.starts_with("R_F: ") {
+                full_rounds = Some(line[5..].parse::<usize>().map_err(|_| malformed())?);
+            } else if line.starts_with("R_P: ") {
+                partial_rounds = Some(line[5..].parse::<usize>().map_err(|_| malformed())?);
+            } else if line.starts_with("# Constants: ") {
+                declared_constants = Some(line[13..].parse::<usize>().map_err(|_| malformed())?);
+            } else if line == "Round constants for GF(p):" {
+                round_constants = Some(parse_list::<E>(next_line()?).map_err(|message| {
+                    Error::InvalidConstants(format!("line {}: {}", number + 1, message))
+                })?);
+            } else if line == "MDS matrix:" {
+                mds_matrix = Some(parse_matrix::<E>(next_line()?).map_err(|message| {
+                    Error::InvalidConstants(format!("line {}: {}", number + 1, message))
+                })?);
+            } else if !line.trim().is_empty() && !line.starts_with('#') {
+                return Err(malformed());
+            }
+        }
+
+        let (full_rounds, partial_rounds) = match (full_rounds, partial_rounds) {
+            (Some(full_rounds), Some(partial_rounds)) => (full_rounds, partial_rounds),
+            _ => return invalid("missing R_F or R_P".to_string()),
+        };
+        let round_constants = match round_constants {
+            Some(round_constants) => round_constants,
+            None => return invalid("missing round constants".to_string()),
+        };
+        let expected = width * (full_rounds + partial_rounds);
+        if let Some(declared) = declared_constants {
+            if declared != expected {
+                return invalid(format!(
+                    "{} constants declared, but width {} with R_F = {}, R_P = {} has {}",
+                    declared, width, full_rounds, partial_rounds, expected
+                ));
+            }
+        }
+        if round_constants.len() != expected {
+            return invalid(format!(
+                "expected {} round constants, found {}",
+                expected,
+                round_constants.len()
+            ));
+        }
+
+        let mds_matrix = match mds_matrix {
+            // The permutation multiplies the state by the transpose as a row vector.
+            Some(mds_matrix) => {
+                if mds_matrix.iter().any(|row| row.len() != mds_matrix.len()) {
+                    return invalid(format!("MDS matrix must be {} by {}", width, width));
+                }
+                transpose::<E>(&mds_matrix)
+            }
+            None => generate_mds_matrix::<E>(width),
+        };
+        check_loaded_mds_matrix::<E>(&mds_matrix, width)?;
+
+        if full_rounds % 2 != 0 {
+            return invalid(format!("full rounds must be even: {}", full_rounds));
+        }
+        let arity = A::to_usize();
+        let strength = [Strength::Standard, Strength::Strengthened]
+            .iter()
+            .find(|strength| {
+                field_round_numbers::<E::Fr>(arity, strength, DEFAULT_SECURITY_LEVEL)
+                    == (full_rounds, partial_rounds)
+            })
+            .copied()
+            .unwrap_or(Strength::Custom {
+                full: full_rounds,
+                partial: partial_rounds,
+            });
+        let alpha = sbox.alpha();
+        if alpha > 0 && !alpha_is_coprime::<E::Fr>(alpha as u64) {
+            return Err(Error::InvalidAlpha(alpha));
+        }
+        check_round_numbers(
+            width,
+            full_rounds,
+            partial_rounds,
+            &strength,
+            E::Fr::NUM_BITS as usize,
+            DEFAULT_SECURITY_LEVEL,
+            alpha,
+        )?;
+
+        Ok(Self::from_parts(
+            strength,
+            HashType::MerkleTree,
+            sbox,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds_matrix,
+            None,
+        ))
+    }
+}
+
+// Parses the arguments of the command generating the constants, `field sbox n t R_F R_P modulus`, checking the field
+// and width, and returns the S-box.
+fn parse_command<E: ScalarEngine>(arguments: &str, width: usize) -> Result<Sbox, String> {
+    let arguments: Vec<&str> = arguments.split_whitespace().collect();
+    if arguments.len() != 7 {
+        return Err(format!("expected 7 arguments, found {}", arguments.len()));
+    }
+    if arguments[0] != "1" {
+        return Err(format!("field {} is not 1, a prime field", arguments[0]));
+    }
+    let modulus = parse_hex::<E>(arguments[6]);
+    if modulus.as_ref().ok() != Some(&E::Fr::char()) {
+        return Err(format!("modulus {} does not match the field", arguments[6]));
+    }
+    if arguments[3] != width.to_string() {
+        return Err(format!("width {} does not match {}", arguments[3], width));
+    }
+    match arguments[1] {
+        "0" => Ok(Sbox::Cubic),
+        "1" => Ok(Sbox::Quintic),
+        "2" => Ok(Sbox::Inverse),
+        sbox => Err(format!("unknown S-box: {}", sbox)),
+    }
+}
+
+// Parses a Python list of quoted hex strings, e.g. `['0x01', '0x02']`, into field elements.
+fn parse_list<E: ScalarEngine>(line: &str) -> Result<Vec<E::Fr>, String> {
+    let line = line.trim();
+    if !line.starts_with('[') || !line.ends_with(']') {
+        return Err("expected a list".to_string());
+    }
+    let items = &line[1..line.len() - 1];
+    if items.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    items
+        .split(',')
+        .map(|item| {
+            let item = item.trim().trim_matches(|c| c == '\'' || c == '"');
+            let repr = parse_hex::<E>(item)?;
+            E::Fr::from_repr(repr).map_err(|_| format!("{} is not below the modulus", item))
+        })
+        .collect()
+}
+
+// Parses a Python list of lists of quoted hex strings into a matrix.
+fn parse_matrix<E: ScalarEngine>(line: &str) -> Result<Matrix<E::Fr>, String> {
+    let line = line.trim();
+    if !line.starts_with("[[") || !line.ends_with("]]") {
+        return Err("expected a list of lists".to_string());
+    }
+    line[1..line.len() - 2]
+        .split(']')
+        .map(|row| {
+            parse_list::<E>(&format!(
+                "{}]",
+                row.trim_start_matches(|c| c == ',' || c == ' ')
+            ))
+        })
+        .collect()
+}
+
+// Parses a hex integer, with or without `0x`, into a repr, which may not be below the modulus.
+fn parse_hex<E: ScalarEngine>(hex: &str) -> Result<<E::Fr as PrimeField>::Repr, String> {
+    let digits = hex.trim_start_matches("0x");
+    let not_hex = || format!("{} is not a hex integer", hex);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(not_hex());
+    }
+
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    let repr_bytes = repr.as_ref().len() * 8;
+    let digits = digits.trim_start_matches('0');
+    if digits.len() > repr_bytes * 2 {
+        return Err(format!("{} is not below the modulus", hex));
+    }
+    let padded = format!("{:0>width$}", digits, width = repr_bytes * 2);
+    let bytes = (0..padded.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&padded[i..i + 2], 16).map_err(|_| not_hex()))
+        .collect::<Result<Vec<_>, _>>()?;
+    repr.read_be(&bytes[..]).map_err(|_| not_hex())?;
+    Ok(repr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::write_parameters;
+    use crate::poseidon::Poseidon;
+    use crate::{scalar_from_u64, scalar_from_u64s};
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U4, U8};
+    use std::fs::File;
+
+    const MODULUS: &str = "73EDA753299D7D483339D80809A1D80553BDA402FFFE5BFEFFFFFFFF00000001";
+
+    fn reference_file(t: usize, rf: usize, rp: usize) -> File {
+        File::open(format!(
+            "parameters/round_constants-1-1-255-{}-{}-{}-{}.txt",
+            t, rf, rp, MODULUS
+        ))
+        .unwrap()
+    }
+
+    fn hash<A: Arity<Fr>>(constants: &PoseidonConstants<Bls12, A>) -> Fr {
+        let preimage: Vec<Fr> = (0..A::to_usize())
+            .map(|i| scalar_from_u64(i as u64))
+            .collect();
+        Poseidon::new_with_preimage(&preimage, constants).hash()
+    }
+
+    fn import_error<A: Arity<Fr>>(contents: &str) -> String {
+        match PoseidonConstants::<Bls12, A>::from_reference_params(&mut contents.as_bytes()) {
+            Ok(_) => panic!("imported invalid parameters"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_import_reference_files() {
+        let arity_2 =
+            PoseidonConstants::<Bls12, U2>::from_reference_params(&mut reference_file(3, 8, 55))
+                .unwrap();
+        let arity_8 =
+            PoseidonConstants::<Bls12, U8>::from_reference_params(&mut reference_file(9, 8, 57))
+                .unwrap();
+
+        assert_eq!(PoseidonConstants::<Bls12, U2>::new(), arity_2);
+        assert_eq!(PoseidonConstants::<Bls12, U8>::new(), arity_8);
+        // The known answers of `poseidon::tests::hash_values`.
+        assert_eq!(
+            scalar_from_u64s([
+                0x2e203c369a02e7ff,
+                0xa6fba9339d05a69d,
+                0x739e0fd902efe161,
+                0x396508d75e76a56b
+            ]),
+            hash(&arity_2)
+        );
+        assert_eq!(
+            scalar_from_u64s([
+                0x2a9934f56d38a5e6,
+                0x4b682e9d9cc4aed9,
+                0x1201004211677077,
+                0x2394611da3a5de55
+            ]),
+            hash(&arity_8)
+        );
+    }
+
+    #[test]
+    fn test_import_exported_parameters() {
+        let strengthened =
+            PoseidonConstants::<Bls12, U4>::new_with_strength(Strength::Strengthened);
+        let mut exported = Vec::new();
+        write_parameters(&strengthened, &mut exported).unwrap();
+
+        let imported =
+            PoseidonConstants::<Bls12, U4>::from_reference_params(&mut &exported[..]).unwrap();
+        assert_eq!(strengthened, imported);
+        assert_eq!(hash(&strengthened), hash(&imported));
+
+        // A matrix which is not symmetric is written and read as it is given, and applied transposed.
+        let mds: Matrix<Fr> = [[1, 1, 1], [1, 2, 3], [1, 4, 2]]
+            .iter()
+            .map(|row| row.iter().map(|x| scalar_from_u64::<Fr>(*x)).collect())
+            .collect();
+        let custom = PoseidonConstants::<Bls12, U2>::new_with_mds(mds.clone()).unwrap();
+        let mut exported = Vec::new();
+        write_parameters(&custom, &mut exported).unwrap();
+        let row =
+            |x: [u64; 3]| format!("['0x{:064x}', '0x{:064x}', '0x{:064x}']", x[0], x[1], x[2]);
+        let written = format!(
+            "MDS matrix:\n[{}, {}, {}]\n",
+            row([1, 1, 1]),
+            row([1, 2, 3]),
+            row([1, 4, 2])
+        );
+        assert!(String::from_utf8(exported.clone())
+            .unwrap()
+            .ends_with(&written));
+
+        let imported =
+            PoseidonConstants::<Bls12, U2>::from_reference_params(&mut &exported[..]).unwrap();
+        assert_eq!(custom, imported);
+        assert_eq!(hash(&custom), hash(&imported));
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_parameters() {
+        let mut contents = String::new();
+        reference_file(3, 8, 55)
+            .read_to_string(&mut contents)
+            .unwrap();
+
+        let wrong_modulus = contents.replace(
+            MODULUS,
+            "73EDA753299D7D483339D80809A1D80553BDA402FFFE5BFEFFFFFFFF00000003",
+        );
+        assert_eq!(
+            "Invalid Poseidon constants: line 3: modulus \
+             73EDA753299D7D483339D80809A1D80553BDA402FFFE5BFEFFFFFFFF00000003 does not match the field",
+            import_error::<U2>(&wrong_modulus)
+        );
+
+        assert_eq!(
+            "Invalid Poseidon constants: line 3: width 3 does not match 5",
+            import_error::<U4>(&contents)
+        );
+
+        let wrong_count = contents.replace("# Constants: 189", "# Constants: 190");
+        assert_eq!(
+            "Invalid Poseidon constants: 190 constants declared, but width 3 with R_F = 8, R_P = 55 has 189",
+            import_error::<U2>(&wrong_count)
+        );
+
+        // Each constant is listed as `'0x` and 64 digits, followed by `', `.
+        let mut missing_constant = contents.clone();
+        let first = contents.find("['").unwrap() + 1;
+        missing_constant.replace_range(first..first + 70, "");
+        assert_eq!(
+            "Invalid Poseidon constants: expected 189 round constants, found 188",
+            import_error::<U2>(&missing_constant)
+        );
+
+        let malformed = contents.replace("R_P: 55", "R_P: fifty-five");
+        assert_eq!(
+            "Invalid Poseidon constants: malformed line 6: \"R_P: fifty-five\"",
+            import_error::<U2>(&malformed)
+        );
+
+        let mut not_mds = contents.clone();
+        not_mds.push_str(
+            "MDS matrix:\n[['0x1', '0x0', '0x0'], ['0x0', '0x0', '0x0'], ['0x0', '0x0', '0x1']]\n",
+        );
+        assert_eq!(
+            "Invalid Poseidon constants: MDS matrix is not invertible",
+            import_error::<U2>(&not_mds)
+        );
+    }
+}
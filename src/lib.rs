@@ -3,8 +3,24 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub use crate::mds::security::check_subspace_trails;
+pub use crate::mds::{
+    check_mds_matrix, generate_mds_matrix, is_mds, security, SparseMatrix, MAX_CHECKED_MDS_WIDTH,
+};
 pub use crate::poseidon::{Arity, Poseidon};
-use crate::round_constants::generate_constants;
+pub use crate::preprocessing::{optimize, OptimizedConstants};
+use crate::round_constants::{cached_constants, generate_seeded_constants};
+pub use crate::round_numbers::{
+    apply_security_margin, are_round_numbers_secure, are_round_numbers_secure_with_alpha,
+    binding_constraint, calc_round_numbers, calc_round_numbers_with_cost,
+    calc_round_numbers_with_options, check_round_number_security, check_rounds_are_secure,
+    const_calc_round_numbers, const_round_numbers, prime_bitlen, round_number_candidates,
+    round_number_security_report, round_numbers, round_numbers_with_security, size_cost, Precision,
+    RoundNumberOptions, RoundNumberSecurityReport, RoundNumbers, SecurityInequality,
+    SecurityMargin, StatisticalBound, TieBreak, DEFAULT_PRIME_BITLEN, DEFAULT_SECURITY_LEVEL,
+    DEFAULT_SECURITY_MARGIN, NO_SECURITY_MARGIN,
+};
+use crate::round_numbers::{round_numbers_for_prime, validate_alpha, DEFAULT_ALPHA};
 pub use bellperson::bls::Fr as Scalar;
 use bellperson::bls::FrRepr;
 pub use error::Error;
@@ -20,31 +36,96 @@ pub mod error;
 mod matrix;
 mod mds;
 
+/// Grain LFSR for generating round constants
+pub mod grain;
+
 /// Poseidon hash
 pub mod poseidon;
 mod poseidon_alt;
+
 mod preprocessing;
-mod round_constants;
+
+/// Round constants
+pub mod round_constants;
+
+/// Round numbers
+pub mod round_numbers;
+
+/// Merkle trees
+pub mod merkle;
+
+/// Poseidon sponge
+pub mod sponge;
+
+/// `std::hash::Hasher` over the Poseidon sponge
+pub mod hasher;
+
+/// Poseidon as a pseudo-random function
+pub mod prf;
+
+/// Poseidon of an arity chosen at runtime
+pub mod dyn_poseidon;
+
+/// Builder of Poseidon hashers
+pub mod builder;
+
+/// Summary and fingerprint of `PoseidonConstants`
+pub mod summary;
+
+/// The constants of the Poseidon widths Filecoin hashes with
+pub mod filecoin;
+
+/// The scalar fields of the Pasta curves
+#[cfg(feature = "pasta")]
+pub mod pasta;
+
+/// The scalar field of BN254, and the Poseidon parameters of circomlib
+#[cfg(feature = "bn254")]
+pub mod bn254;
 
 /// Hash types and domain separation tags.
 pub mod hash_type;
 
+/// Export of parameters in the format of the reference scripts
+pub mod export;
+
+/// Import of `PoseidonConstants` from the output of the reference scripts
+mod import;
+
+/// Serialization of `PoseidonConstants`
+#[cfg(feature = "serde")]
+mod serialization;
+
+/// Binary format of `PoseidonConstants`
+mod binary_format;
+
+/// Overwriting of buffers holding secrets with zeros
+mod wipe;
+
+/// Precomputed round constants
+mod constants;
+
+/// C bindings
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+/// JavaScript bindings
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 /// Tree Builder
-#[cfg(any(feature = "gpu", feature = "opencl"))]
 pub mod tree_builder;
 
 /// Column Tree Builder
-#[cfg(any(feature = "gpu", feature = "opencl"))]
 pub mod column_tree_builder;
 
 #[cfg(feature = "gpu")]
 pub mod triton;
 
 /// Batch Hasher
-#[cfg(any(feature = "gpu", feature = "opencl"))]
 pub mod batch_hasher;
 
-#[cfg(feature = "opencl")]
+#[cfg(any(feature = "opencl", feature = "cuda"))]
 pub mod proteus;
 
 pub(crate) const TEST_SEED: [u8; 16] = [
@@ -52,13 +133,69 @@ pub(crate) const TEST_SEED: [u8; 16] = [
 ];
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Strength {
     Standard,
     Strengthened,
+    /// Explicit round numbers, `full` full rounds and `partial` partial rounds, such as those of another
+    /// implementation. Like the round numbers of the other strengths, they must satisfy the security inequalities,
+    /// see `PoseidonConstants::new_with_strength_unchecked` to skip the check.
+    Custom {
+        full: usize,
+        partial: usize,
+    },
 }
 
 pub(crate) const DEFAULT_STRENGTH: Strength = Strength::Standard;
 
+/// The S-box applied to the state elements in each round of the permutation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sbox {
+    /// x^5
+    Quintic,
+    /// x^3, which is not a permutation of BLS12-381's scalar field, as 3 divides p - 1.
+    Cubic,
+    /// x^-1, mapping zero to itself.
+    Inverse,
+}
+
+pub(crate) const DEFAULT_SBOX: Sbox = Sbox::Quintic;
+
+impl Sbox {
+    /// Returns the exponent `alpha` of the S-box, which is -1 for `Sbox::Inverse`.
+    pub fn alpha(&self) -> i64 {
+        match self {
+            Sbox::Quintic => 5,
+            Sbox::Cubic => 3,
+            Sbox::Inverse => -1,
+        }
+    }
+
+    /// Returns the S-box of smallest exponent that is a permutation of the prime field `Fr`: `Sbox::Cubic` if
+    /// `gcd(3, p - 1) = 1`, otherwise `Sbox::Quintic` if `gcd(5, p - 1) = 1`, and `Sbox::Inverse` for any other
+    /// field. A smaller exponent costs fewer constraints per S-box in a circuit.
+    pub fn for_field<Fr: PrimeField>() -> Self {
+        if alpha_is_coprime::<Fr>(3) {
+            Sbox::Cubic
+        } else if alpha_is_coprime::<Fr>(5) {
+            Sbox::Quintic
+        } else {
+            Sbox::Inverse
+        }
+    }
+
+    /// The S-box identifier with which `GrainLfsr` is seeded, numbered as in the reference script: 0 for `Cubic`, 1
+    /// for `Quintic`, and 2 for `Inverse`.
+    pub fn id(&self) -> u8 {
+        match self {
+            Sbox::Cubic => 0,
+            Sbox::Quintic => 1,
+            Sbox::Inverse => 2,
+        }
+    }
+}
+
 pub trait BatchHasher<A>
 where
     A: Arity<Scalar>,
@@ -87,48 +224,129 @@ where
     }
 }
 
-// Round numbers for supported arities. Not all arities have been supplied.
-// If a new arity need be supported, the appropriate value should be included by running
-// the script: https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/calc_round_numbers.py
-fn round_numbers_base(arity: usize) -> (usize, usize) {
-    let width = arity + 1;
+/// Returns the batch hasher of arity `A` with the default strength, hashing at most `max_batch_size` preimages at a
+/// time, of the fastest backend available. See `select_hasher_with_strength`.
+pub fn select_hasher<A>(max_batch_size: usize) -> Result<Box<dyn BatchHasher<A>>, Error>
+where
+    A: Arity<Scalar> + 'static,
+{
+    select_hasher_with_strength(DEFAULT_STRENGTH, max_batch_size)
+}
 
-    let full_rounds = 8;
-    let partial_rounds = match width {
-        2 | 3 => 55,
-        4 | 5 | 6 | 7 => 56,
-        8 | 9 | 10 | 11 | 12 | 13 | 14 | 15 => 57,
-        16 | 17 | 25 => 59,
-        37 => 60,
-        65 => 61,
-        _ => panic!(format!("unsupported arity, {}", arity)),
-    };
+/// Returns the batch hasher of arity `A` and `strength`, hashing at most `max_batch_size` preimages at a time, of the
+/// first backend that is compiled in and finds a device, in order: CUDA, OpenCL, Futhark, and the CPU. All of them
+/// return the same digests, so callers generic over `BatchHasher` need not know which was selected. A backend which
+/// fails to start, for lack of a device or otherwise, is logged and skipped.
+pub fn select_hasher_with_strength<A>(
+    strength: Strength,
+    max_batch_size: usize,
+) -> Result<Box<dyn BatchHasher<A>>, Error>
+where
+    A: Arity<Scalar> + 'static,
+{
+    #[cfg(feature = "cuda")]
+    {
+        use crate::proteus::cuda::CudaBatchHasher;
+        match CudaBatchHasher::<A>::new_with_strength(0, strength, max_batch_size) {
+            Ok(hasher) => return Ok(Box::new(hasher)),
+            Err(e) => log::info!("CUDA batch hasher unavailable: {}", e),
+        }
+    }
+    #[cfg(feature = "opencl")]
+    {
+        use crate::proteus::gpu::{get_device, CLBatchHasher};
+        use rust_gpu_tools::opencl::GPUSelector;
+        match get_device(&GPUSelector::Index(0)).and_then(|device| {
+            CLBatchHasher::<A>::new_with_strength(device, strength, max_batch_size)
+        }) {
+            Ok(hasher) => return Ok(Box::new(hasher)),
+            Err(e) => log::info!("OpenCL batch hasher unavailable: {}", e),
+        }
+    }
+    #[cfg(feature = "gpu")]
+    {
+        use crate::triton::gpu::GPUBatchHasher;
+        match crate::triton::cl::default_futhark_context()
+            .map_err(Error::from)
+            .and_then(|ctx| GPUBatchHasher::<A>::new_with_strength(ctx, strength, max_batch_size))
+        {
+            Ok(hasher) => return Ok(Box::new(hasher)),
+            Err(e) => log::info!("Futhark batch hasher unavailable: {}", e),
+        }
+    }
 
-    (full_rounds, partial_rounds)
+    Ok(Box::new(
+        poseidon::SimplePoseidonBatchHasher::<A>::new_with_strength(strength, max_batch_size)?,
+    ))
 }
 
-// In case of newly-discovered attacks, we may need stronger security.
-// This option exists so we can preemptively create circuits in order to switch
-// to them quickly if needed.
-//
-// "A realistic alternative is to increase the number of partial rounds by 25%.
-// Then it is unlikely that a new attack breaks through this number,
-// but even if this happens then the complexity is almost surely above 2^64, and you will be safe."
-// - D Khovratovich
-fn round_numbers_strengthened(arity: usize) -> (usize, usize) {
-    let (full_round, partial_rounds) = round_numbers_base(arity);
+/// Returns the round numbers `(R_F, R_P)` for `arity` and `strength` over the prime field `Fr`, using the bit length
+/// of its modulus for `n` in the security inequalities.
+///
+/// # Panics
+///
+/// Panics if `security_level` is not within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`, or if no secure round numbers
+/// are found, see `round_numbers::calc_round_numbers`.
+pub fn field_round_numbers<Fr: PrimeField>(
+    arity: usize,
+    strength: &Strength,
+    security_level: usize,
+) -> (usize, usize) {
+    round_numbers_for_prime(
+        arity,
+        strength,
+        Fr::NUM_BITS as usize,
+        security_level,
+        DEFAULT_ALPHA,
+        DEFAULT_SECURITY_MARGIN,
+    )
+    .unwrap_or_else(|e| panic!("{}", e))
+}
 
-    // Increase by 25%, rounding up.
-    let strengthened_partial_rounds = f64::ceil(partial_rounds as f64 * 1.25) as usize;
+/// Returns the round numbers `(R_F, R_P)` for `arity` and `strength` over the prime field `Fr`, with an `x^alpha`
+/// S-box rather than `x^5`.
+///
+/// Returns `Error::InvalidAlpha` unless `alpha` is -1 (the inverse S-box), or odd, at least 3, and coprime to
+/// `p - 1`, where `p` is the modulus of `Fr`. For example, BLS12-381 requires `alpha = 5`, as 3 divides `p - 1`.
+/// Returns `Error::InvalidSecurityLevel` unless `security_level` is within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`.
+pub fn field_round_numbers_with_alpha<Fr: PrimeField>(
+    arity: usize,
+    strength: &Strength,
+    security_level: usize,
+    alpha: i64,
+) -> Result<(usize, usize), Error> {
+    validate_alpha(alpha)?;
+    if alpha > 0 && !alpha_is_coprime::<Fr>(alpha as u64) {
+        return Err(Error::InvalidAlpha(alpha));
+    }
 
-    (full_round, strengthened_partial_rounds)
+    Ok(round_numbers_for_prime(
+        arity,
+        strength,
+        Fr::NUM_BITS as usize,
+        security_level,
+        alpha,
+        DEFAULT_SECURITY_MARGIN,
+    )?)
 }
 
-pub fn round_numbers(arity: usize, strength: &Strength) -> (usize, usize) {
-    match strength {
-        Strength::Standard => round_numbers_base(arity),
-        Strength::Strengthened => round_numbers_strengthened(arity),
+/// Returns `true` if `gcd(alpha, p - 1) = 1`, where `p` is the modulus of `Fr`.
+pub(crate) fn alpha_is_coprime<Fr: PrimeField>(alpha: u64) -> bool {
+    let alpha = alpha as u128;
+    // Reduce the little-endian limbs of the modulus, starting with the most significant.
+    let p_mod_alpha = Fr::char()
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0u128, |acc, limb| ((acc << 64) + *limb as u128) % alpha);
+    let mut a = alpha;
+    let mut b = (p_mod_alpha + alpha - 1) % alpha;
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
     }
+    a == 1
 }
 
 /// convert
@@ -141,14 +359,28 @@ pub fn scalar_from_u64s(parts: [u64; 4]) -> Scalar {
     Scalar::from_repr(FrRepr(parts)).unwrap()
 }
 
-const SBOX: u8 = 1; // x^5
+/// Returns the field element of its little-endian bytes, 32 for BLS12-381 and BN254, or `None` unless they are its
+/// canonical encoding.
+#[cfg(any(feature = "ffi", feature = "wasm"))]
+pub(crate) fn scalar_from_le_bytes<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    use ff::PrimeFieldRepr;
+
+    let mut repr = F::Repr::default();
+    repr.read_le(bytes).ok()?;
+    F::from_repr(repr).ok()
+}
+
 const FIELD: u8 = 1; // Gf(p)
 
-fn round_constants<E: ScalarEngine>(arity: usize, strength: &Strength) -> Vec<E::Fr> {
+fn round_constants<E: ScalarEngine>(
+    arity: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    sbox: Sbox,
+    seed: Option<&[u8]>,
+) -> Vec<E::Fr> {
     let t = arity + 1;
 
-    let (full_rounds, partial_rounds) = round_numbers(arity, strength);
-
     let r_f = full_rounds as u16;
     let r_p = partial_rounds as u16;
 
@@ -159,11 +391,66 @@ fn round_constants<E: ScalarEngine>(arity: usize, strength: &Strength) -> Vec<E:
         fr_num_bits as u16
     };
 
-    generate_constants::<E>(FIELD, SBOX, field_size, t as u16, r_f, r_p)
+    // Only the constants of the default round numbers are cached, as by `PoseidonConstants::shared`, so that custom
+    // round numbers and seeds are not kept forever.
+    let cached = seed.is_none()
+        && [Strength::Standard, Strength::Strengthened]
+            .iter()
+            .any(|strength| {
+                round_numbers_for_prime(
+                    arity,
+                    strength,
+                    fr_num_bits as usize,
+                    DEFAULT_SECURITY_LEVEL,
+                    sbox.alpha(),
+                    DEFAULT_SECURITY_MARGIN,
+                )
+                .map_or(false, |rounds| rounds == (full_rounds, partial_rounds))
+            });
+    if cached {
+        cached_constants::<E>(FIELD, sbox.id(), field_size, t as u16, r_f, r_p)
+    } else {
+        generate_seeded_constants::<E>(FIELD, sbox.id(), field_size, t as u16, r_f, r_p, seed)
+    }
 }
 
-/// Apply the quintic S-Box (s^5) to a given item
-pub(crate) fn quintic_s_box<E: ScalarEngine>(
+/// Apply `sbox` to a given item
+pub(crate) fn s_box<E: ScalarEngine>(
+    sbox: Sbox,
+    l: &mut E::Fr,
+    pre_add: Option<&E::Fr>,
+    post_add: Option<&E::Fr>,
+) {
+    match sbox {
+        Sbox::Quintic => quintic_s_box::<E>(l, pre_add, post_add),
+        Sbox::Cubic => {
+            if let Some(x) = pre_add {
+                l.add_assign(x);
+            }
+            let mut tmp = l.clone();
+            tmp.square(); // l^2
+            l.mul_assign(&tmp); // l^3
+            if let Some(x) = post_add {
+                l.add_assign(x);
+            }
+        }
+        Sbox::Inverse => {
+            if let Some(x) = pre_add {
+                l.add_assign(x);
+            }
+            // Zero has no inverse, and is mapped to itself.
+            if let Some(inv) = l.inverse() {
+                *l = inv;
+            }
+            if let Some(x) = post_add {
+                l.add_assign(x);
+            }
+        }
+    }
+}
+
+/// Apply the quintic S-Box (s^5) to a given item, adding `pre_add` before and `post_add` after, if given
+pub fn quintic_s_box<E: ScalarEngine>(
     l: &mut E::Fr,
     pre_add: Option<&E::Fr>,
     post_add: Option<&E::Fr>,
@@ -183,35 +470,139 @@ pub(crate) fn quintic_s_box<E: ScalarEngine>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bellperson::bls::Bls12;
+
+    #[test]
+    fn test_select_hasher() {
+        use crate::poseidon::SimplePoseidonBatchHasher;
+        use generic_array::sequence::GenericSequence;
+        use generic_array::typenum::U8;
+
+        let preimages = (0..100u64)
+            .map(|i| GenericArray::<Scalar, U8>::generate(|j| scalar_from_u64(i * 8 + j as u64)))
+            .collect::<Vec<_>>();
+        let mut selected = select_hasher::<U8>(preimages.len()).unwrap();
+        let mut cpu = SimplePoseidonBatchHasher::<U8>::new(preimages.len()).unwrap();
+
+        assert_eq!(preimages.len(), selected.max_batch_size());
+        assert_eq!(
+            cpu.hash(&preimages).unwrap(),
+            selected.hash(&preimages).unwrap()
+        );
+    }
 
     #[test]
-    fn test_strengthened_round_constants() {
-        let cases = [
-            (1, 69),
-            (2, 69),
-            (3, 70),
-            (4, 70),
-            (5, 70),
-            (6, 70),
-            (7, 72),
-            (8, 72),
-            (9, 72),
-            (10, 72),
-            (11, 72),
-            (16, 74),
-            (24, 74),
-            (36, 75),
-            (64, 77),
-        ];
-
-        cases.iter().for_each(|(arity, expected_rounds)| {
-            let (full_rounds, actual_rounds) = round_numbers_strengthened(*arity);
-            assert_eq!(8, full_rounds);
+    fn test_field_round_numbers() {
+        // BLS12-381's 255-bit modulus yields the same round numbers as the default of 256 bits.
+        assert_eq!(255, Scalar::NUM_BITS);
+        for arity in [1, 2, 4, 8, 11, 16, 24, 36, 64].iter() {
+            for strength in [Strength::Standard, Strength::Strengthened].iter() {
+                assert_eq!(
+                    round_numbers(*arity, strength),
+                    field_round_numbers::<Scalar>(*arity, strength, DEFAULT_SECURITY_LEVEL)
+                );
+            }
+        }
+
+        // A 64-bit modulus has no effect on the round numbers at 128-bit security,
+        // while a 381-bit modulus requires more partial rounds.
+        let cases = [(64, 2, 55), (64, 8, 57), (381, 2, 81), (381, 8, 81)];
+        for (prime_bitlen, arity, rp) in cases.iter() {
             assert_eq!(
-                *expected_rounds, actual_rounds,
-                "wrong number of partial rounds for arity {}",
-                *arity
+                (8, *rp),
+                round_numbers_for_prime(
+                    *arity,
+                    &Strength::Standard,
+                    *prime_bitlen,
+                    DEFAULT_SECURITY_LEVEL,
+                    DEFAULT_ALPHA,
+                    DEFAULT_SECURITY_MARGIN
+                )
+                .unwrap()
             );
-        })
+        }
+    }
+
+    #[test]
+    fn test_s_box() {
+        let x: Scalar = scalar_from_u64(3);
+        let key: Scalar = scalar_from_u64(4);
+
+        let mut cubed = x;
+        s_box::<Bls12>(Sbox::Cubic, &mut cubed, None, None);
+        assert_eq!(scalar_from_u64::<Scalar>(27), cubed);
+
+        let mut quintic = x;
+        s_box::<Bls12>(Sbox::Quintic, &mut quintic, None, Some(&key));
+        assert_eq!(scalar_from_u64::<Scalar>(247), quintic);
+
+        // (3 + 4)^-1 * 7 = 1
+        let mut inverse = x;
+        s_box::<Bls12>(Sbox::Inverse, &mut inverse, Some(&key), None);
+        inverse.mul_assign(&scalar_from_u64::<Scalar>(7));
+        assert_eq!(Scalar::one(), inverse);
+
+        let mut zero = Scalar::zero();
+        s_box::<Bls12>(Sbox::Inverse, &mut zero, None, None);
+        assert_eq!(Scalar::zero(), zero);
+    }
+
+    #[test]
+    fn test_field_round_numbers_with_alpha() {
+        // BLS12-381's p - 1 is divisible by 3 and 11, but not by 5, 7, or 13.
+        for alpha in [5, 7, 13].iter() {
+            assert!(alpha_is_coprime::<Scalar>(*alpha));
+        }
+        for alpha in [3, 9, 11, 15].iter() {
+            assert!(!alpha_is_coprime::<Scalar>(*alpha));
+        }
+        // So the quintic S-box is the cheapest permutation of BLS12-381's scalar field.
+        assert_eq!(Sbox::Quintic, Sbox::for_field::<Scalar>());
+        for alpha in [-3, 3, 9, 11, 15].iter() {
+            match field_round_numbers_with_alpha::<Scalar>(2, &Strength::Standard, 128, *alpha) {
+                Err(Error::InvalidAlpha(a)) => assert_eq!(*alpha, a),
+                res => panic!("expected an invalid alpha error, got {:?}", res),
+            }
+        }
+
+        assert_eq!(
+            field_round_numbers::<Scalar>(8, &Strength::Strengthened, DEFAULT_SECURITY_LEVEL),
+            field_round_numbers_with_alpha::<Scalar>(
+                8,
+                &Strength::Strengthened,
+                DEFAULT_SECURITY_LEVEL,
+                DEFAULT_ALPHA
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            (8, 46),
+            field_round_numbers_with_alpha::<Scalar>(2, &Strength::Standard, 128, 7).unwrap()
+        );
+        // The inverse S-box is a permutation of any field.
+        assert_eq!(
+            (8, 63),
+            field_round_numbers_with_alpha::<Scalar>(2, &Strength::Standard, 128, -1).unwrap()
+        );
+        match field_round_numbers_with_alpha::<Scalar>(2, &Strength::Standard, 5000, 5) {
+            Err(Error::InvalidSecurityLevel(5000)) => (),
+            res => panic!("expected an invalid security level error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_field_round_numbers_max_security() {
+        // The highest supported security level is met with as many partial rounds as required.
+        let max = round_numbers::MAX_SECURITY_LEVEL;
+        assert_eq!(
+            calc_round_numbers(3, Scalar::NUM_BITS as usize, max, true, Sbox::Quintic).unwrap(),
+            field_round_numbers::<Scalar>(2, &Strength::Standard, max)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Security level of 5000 bits is outside of the supported range.")]
+    fn test_field_round_numbers_high_security() {
+        field_round_numbers::<Scalar>(2, &Strength::Standard, 5000);
     }
 }
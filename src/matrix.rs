@@ -243,6 +243,10 @@ fn eliminate<E: ScalarEngine>(
     let mut result = Vec::with_capacity(matrix.len());
     result.push(pivot.clone());
 
+    // The pivot row moves to the top, so its shadow row must move with it.
+    let shadow_pivot_row = shadow.remove(pivot_index);
+    shadow.insert(0, shadow_pivot_row);
+
     for (i, row) in matrix.iter().enumerate() {
         if i == pivot_index {
             continue;
@@ -259,10 +263,10 @@ fn eliminate<E: ScalarEngine>(
             let eliminated = vec_sub::<E>(row, &scaled_pivot);
             result.push(eliminated);
 
-            let shadow_pivot = &shadow[pivot_index];
-            let scaled_shadow_pivot = scalar_vec_mul::<E>(factor, shadow_pivot);
-            let shadow_row = &shadow[i];
-            shadow[i] = vec_sub::<E>(shadow_row, &scaled_shadow_pivot);
+            // Rows above the pivot have moved down by one in the shadow.
+            let shadow_index = if i < pivot_index { i + 1 } else { i };
+            let scaled_shadow_pivot = scalar_vec_mul::<E>(factor, &shadow[0]);
+            shadow[shadow_index] = vec_sub::<E>(&shadow[shadow_index], &scaled_shadow_pivot);
         }
     }
     Some(result)
@@ -516,6 +520,25 @@ mod tests {
         assert_eq!(add_after_apply, apply_after_add, "breakin' the law");
     }
 
+    #[test]
+    fn test_inverse_with_row_swap() {
+        let zero = scalar_from_u64::<Fr>(0);
+        let one = scalar_from_u64::<Fr>(1);
+        let two = scalar_from_u64::<Fr>(2);
+        let three = scalar_from_u64::<Fr>(3);
+
+        // The first pivot is in the second row, and the second pivot in the third.
+        let m = vec![
+            vec![zero, zero, two],
+            vec![three, one, zero],
+            vec![one, zero, one],
+        ];
+
+        let m_inv = invert::<Bls12>(&m).unwrap();
+        assert!(is_identity::<Bls12>(&mat_mul::<Bls12>(&m, &m_inv).unwrap()));
+        assert!(is_identity::<Bls12>(&mat_mul::<Bls12>(&m_inv, &m).unwrap()));
+    }
+
     #[test]
     fn test_eliminate() {
         //let one = scalar_from_u64::<Fr>(1);
@@ -0,0 +1,756 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use ff::{Field, ScalarEngine};
+
+use crate::matrix;
+use crate::matrix::{
+    invert, is_identity, is_square, left_apply_matrix, make_identity, mat_mul, minor, transpose,
+    Matrix, Scalar,
+};
+use crate::{scalar_from_u64, Error};
+
+/// Checks of MDS matrices against infinitely long subspace trails
+pub mod security;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MDSMatrices<E: ScalarEngine> {
+    pub m: Matrix<Scalar<E>>,
+    pub m_inv: Matrix<Scalar<E>>,
+    pub m_hat: Matrix<Scalar<E>>,
+    pub m_hat_inv: Matrix<Scalar<E>>,
+    pub m_prime: Matrix<Scalar<E>>,
+    pub m_double_prime: Matrix<Scalar<E>>,
+}
+
+pub fn create_mds_matrices<'a, E: ScalarEngine>(t: usize) -> MDSMatrices<E> {
+    let m = generate_mds_matrix::<E>(t);
+    derive_mds_matrices(m)
+}
+
+pub fn derive_mds_matrices<'a, E: ScalarEngine>(m: Matrix<Scalar<E>>) -> MDSMatrices<E> {
+    let m_inv = invert::<E>(&m).unwrap(); // m is MDS so invertible.
+    let m_hat = minor::<E>(&m, 0, 0);
+    let m_hat_inv = invert::<E>(&m_hat).unwrap(); // If this returns None, then `mds_matrix` was not correctly generated.
+    let m_prime = make_prime::<E>(&m);
+    let m_double_prime = make_double_prime::<E>(&m, &m_hat_inv);
+
+    MDSMatrices {
+        m,
+        m_inv,
+        m_hat,
+        m_hat_inv,
+        m_prime,
+        m_double_prime,
+    }
+}
+
+/// A `SparseMatrix` is specifically one of the form of M''.
+/// This means its first row and column are each dense, and the interior matrix
+/// (minor to the element in both the row and column) is the identity.
+/// We will pluralize this compact structure `sparse_matrixes` to distinguish from `sparse_matrices` from which they are created.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix<E: ScalarEngine> {
+    /// `w_hat` is the first column of the M'' matrix. It will be directly multiplied (scalar product) with a row of state elements.
+    pub w_hat: Vec<Scalar<E>>,
+    /// `v_rest` contains all but the first (already included in `w_hat`).
+    pub v_rest: Vec<Scalar<E>>,
+}
+
+impl<E: ScalarEngine> SparseMatrix<E> {
+    pub fn new(m_double_prime: Matrix<Scalar<E>>) -> Self {
+        assert!(Self::is_sparse_matrix(&m_double_prime));
+        let size = matrix::rows(&m_double_prime);
+
+        let w_hat = (0..size).map(|i| m_double_prime[i][0]).collect::<Vec<_>>();
+        let v_rest = m_double_prime[0][1..].to_vec();
+
+        Self { w_hat, v_rest }
+    }
+
+    pub fn is_sparse_matrix(m: &Matrix<Scalar<E>>) -> bool {
+        is_square(&m) && is_identity::<E>(&minor::<E>(&m, 0, 0))
+    }
+
+    pub fn size(&self) -> usize {
+        self.w_hat.len()
+    }
+
+    pub fn to_matrix(&self) -> Matrix<Scalar<E>> {
+        let mut m = matrix::make_identity::<E>(self.size());
+        for (j, elt) in self.w_hat.iter().enumerate() {
+            m[j][0] = *elt;
+        }
+        for (i, elt) in self.v_rest.iter().enumerate() {
+            m[0][i + 1] = *elt;
+        }
+        m
+    }
+}
+
+/// Applies `sparse_matrix` to `v` in place, giving the vector-matrix product of `matrix::apply_matrix` with its dense
+/// form, `sparse_matrix.to_matrix()`, in `2t - 1` multiplications rather than `t^2`.
+pub fn apply_sparse_mds<E: ScalarEngine>(sparse_matrix: &SparseMatrix<E>, v: &mut [Scalar<E>]) {
+    assert_eq!(
+        sparse_matrix.size(),
+        v.len(),
+        "Matrix can only be applied to vector of same size."
+    );
+
+    // First column is dense.
+    let mut first = Scalar::<E>::zero();
+    for (w, x) in sparse_matrix.w_hat.iter().zip(v.iter()) {
+        let mut tmp = *w;
+        tmp.mul_assign(x);
+        first.add_assign(&tmp);
+    }
+
+    // Except for first row/column, diagonals are one, and the first row is dense.
+    let v_0 = v[0];
+    for (x, v_rest) in v.iter_mut().skip(1).zip(&sparse_matrix.v_rest) {
+        let mut tmp = *v_rest;
+        tmp.mul_assign(&v_0);
+        x.add_assign(&tmp);
+    }
+    v[0] = first;
+}
+
+// - Having effectively moved the round-key additions into the S-boxes, refactor MDS matrices used for partial-round mix layer to use sparse matrices.
+// - This requires using a different (sparse) matrix at each partial round, rather than the same dense matrix at each.
+//   - The MDS matrix, M, for each such round, starting from the last, is factored into two components, such that M' x M'' = M.
+//   - M'' is sparse and replaces M for the round.
+//   - The previous layer's M is then replaced by M x M' = M*.
+//   - M* is likewise factored into M*' and M*'', and the process continues.
+pub fn factor_to_sparse_matrixes<E: ScalarEngine>(
+    base_matrix: Matrix<Scalar<E>>,
+    n: usize,
+) -> (Matrix<Scalar<E>>, Vec<SparseMatrix<E>>) {
+    let (pre_sparse, sparse_matrices) = factor_to_sparse_matrices::<E>(base_matrix, n);
+    let sparse_matrixes = sparse_matrices
+        .iter()
+        .map(|m| SparseMatrix::<E>::new(m.to_vec()))
+        .collect::<Vec<_>>();
+
+    (pre_sparse, sparse_matrixes)
+}
+
+pub fn factor_to_sparse_matrices<E: ScalarEngine>(
+    base_matrix: Matrix<Scalar<E>>,
+    n: usize,
+) -> (Matrix<Scalar<E>>, Vec<Matrix<Scalar<E>>>) {
+    let (pre_sparse, mut all) =
+        (0..n).fold((base_matrix.clone(), Vec::new()), |(curr, mut acc), _| {
+            // Only M' and M'' are needed, so only the minor of `curr` is inverted, halving the inversions.
+            let m_hat_inv = invert::<E>(&minor::<E>(&curr, 0, 0)).unwrap();
+            acc.push(make_double_prime::<E>(&curr, &m_hat_inv));
+            let new = mat_mul::<E>(&base_matrix, &make_prime::<E>(&curr)).unwrap();
+            (new, acc)
+        });
+    all.reverse();
+    (pre_sparse, all)
+}
+
+lazy_static! {
+    // Generated MDS matrices, keyed by width and the `TypeId` of the field. Each value is a `Matrix` over that field.
+    static ref MDS_MATRICES: Mutex<HashMap<(usize, TypeId), Box<dyn Any + Send>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Returns the `t x t` Cauchy MDS matrix `M[i][j] = 1 / (x_i + y_j)`, where `x_i = i` and `y_j = t + j`.
+///
+/// Every square submatrix of a Cauchy matrix is itself a Cauchy matrix, which is invertible when the `x_i` are
+/// distinct, the `y_j` are distinct, and no `x_i + y_j` is zero. These conditions are checked, establishing the MDS
+/// property without inverting each of the exponentially many submatrices.
+///
+/// With the `mds-security` feature, the matrix must also pass `security::check_subspace_trails`, as the reference
+/// script requires of its matrices. A matrix failing it is generated again with each `y_j` incremented, until one
+/// passes. The matrices of widths up to 16 pass as they are, so the feature only costs the time of the checks.
+///
+/// Matrices are generated once per width and field, and cached for the lifetime of the process.
+///
+/// # Panics
+///
+/// Panics if the conditions do not hold, which only happens in fields whose characteristic is below `3 * t`.
+pub fn generate_mds_matrix<E: ScalarEngine>(t: usize) -> Matrix<Scalar<E>> {
+    let key = (t, TypeId::of::<Scalar<E>>());
+    if let Some(m) = MDS_MATRICES.lock().unwrap().get(&key) {
+        return m.downcast_ref::<Matrix<Scalar<E>>>().unwrap().clone();
+    }
+
+    // The lock is not held while generating, so another thread may generate the same matrix and insert it first.
+    let m = generate_mds::<E>(t);
+    let cached: Box<dyn Any + Send> = Box::new(m.clone());
+    MDS_MATRICES.lock().unwrap().entry(key).or_insert(cached);
+    m
+}
+
+fn generate_mds<E: ScalarEngine>(t: usize) -> Matrix<Scalar<E>> {
+    if cfg!(feature = "mds-security") {
+        (0..)
+            .map(|offset| generate_cauchy::<E>(t, offset))
+            .find(|matrix| security::check_subspace_trails::<E>(matrix).is_ok())
+            .expect("a Cauchy matrix passes the subspace trail checks")
+    } else {
+        generate_cauchy::<E>(t, 0)
+    }
+}
+
+// Returns the Cauchy matrix with `x_i = i` and `y_j = t + offset + j`.
+fn generate_cauchy<E: ScalarEngine>(t: usize, offset: usize) -> Matrix<Scalar<E>> {
+    // Source: https://github.com/dusk-network/dusk-poseidon-merkle/commit/776c37734ea2e71bb608ce4bc58fdb5f208112a7#diff-2eee9b20fb23edcc0bf84b14167cbfdc
+    let mut matrix: Vec<Vec<E::Fr>> = Vec::with_capacity(t);
+    let mut xs: Vec<E::Fr> = Vec::with_capacity(t);
+    let mut ys: Vec<E::Fr> = Vec::with_capacity(t);
+
+    // Generate x and y values deterministically for the cauchy matrix
+    // where x[i] != y[i] to allow the values to be inverted
+    // and there are no duplicates in the x vector or y vector, so that the determinant is always non-zero
+    // [a b]
+    // [c d]
+    // det(M) = (ad - bc) ; if a == b and c == d => det(M) =0
+    // For an MDS matrix, every possible mxm submatrix, must have det(M) != 0
+    for i in 0..t {
+        let x = scalar_from_u64((i) as u64);
+        let y = scalar_from_u64((i + t + offset) as u64);
+        xs.push(x);
+        ys.push(y);
+    }
+
+    // Check that the matrix is MDS before inverting each `x_i + y_j`.
+    assert!(
+        is_cauchy::<E>(&xs, &ys),
+        "Cauchy matrix of width {} is not MDS in this field",
+        t
+    );
+
+    for i in 0..t {
+        let mut row: Vec<E::Fr> = Vec::with_capacity(t);
+        for j in 0..t {
+            // Generate the entry at (i,j)
+            let mut tmp = xs[i];
+            tmp.add_assign(&ys[j]);
+            let entry = tmp.inverse().unwrap();
+            row.insert(j, entry);
+        }
+        matrix.push(row);
+    }
+
+    // The permutation multiplies the state by the matrix as a row vector, which for a symmetric matrix is the same as
+    // multiplying it as a column vector, as the reference implementation does, so ensure it is symmetric.
+    assert_eq!(matrix, transpose::<E>(&matrix));
+    matrix
+}
+
+// Returns `true` if the `xs` are distinct, the `ys` are distinct, and no `x + y` is zero, so that the Cauchy matrix
+// they define is MDS.
+fn is_cauchy<E: ScalarEngine>(xs: &[Scalar<E>], ys: &[Scalar<E>]) -> bool {
+    let distinct = |v: &[Scalar<E>]| (0..v.len()).all(|i| !v[i + 1..].contains(&v[i]));
+
+    distinct(xs)
+        && distinct(ys)
+        && xs.iter().all(|x| {
+            ys.iter().all(|y| {
+                let mut sum = *x;
+                sum.add_assign(y);
+                !sum.is_zero()
+            })
+        })
+}
+
+/// Returns `true` if `matrix` is square and every square submatrix of it is invertible, i.e. it is maximum distance
+/// separable.
+///
+/// This inverts each of the submatrices, of which there are nearly `4^t`, so it is only practical for small widths.
+/// `generate_mds_matrix` establishes the property by construction instead.
+pub fn is_mds<E: ScalarEngine>(matrix: &Matrix<Scalar<E>>) -> bool {
+    if !is_square(matrix) {
+        return false;
+    }
+    let t = matrix::rows(matrix);
+    // Each submatrix is selected by equally many rows and columns, as bitmasks.
+    (1..(1usize << t)).all(|rows| {
+        (1..(1usize << t))
+            .filter(|columns| columns.count_ones() == rows.count_ones())
+            .all(|columns| {
+                let submatrix: Matrix<Scalar<E>> = (0..t)
+                    .filter(|i| rows & (1 << i) != 0)
+                    .map(|i| {
+                        (0..t)
+                            .filter(|j| columns & (1 << j) != 0)
+                            .map(|j| matrix[i][j])
+                            .collect()
+                    })
+                    .collect();
+                invert::<E>(&submatrix).is_some()
+            })
+    })
+}
+
+/// The widest matrix whose MDS property `check_mds_matrix` checks, by inverting each of its square submatrices.
+pub const MAX_CHECKED_MDS_WIDTH: usize = 12;
+
+/// Checks that `matrix` is a suitable MDS matrix for a permutation of width `width`, which multiplies the state by it
+/// as a row vector, as `matrix::apply_matrix` does. A matrix failing a check returns `Error::InvalidConstants`, naming
+/// the check:
+///
+/// - The matrix is `width` by `width` and invertible.
+/// - It is MDS, checked by `is_mds` up to `MAX_CHECKED_MDS_WIDTH`. Wider matrices are rejected.
+/// - It passes the three algorithms of the reference script `generate_params_poseidon.sage`, as
+///   `security::check_subspace_trails` runs them: no power `M^i`, for `0 < i < width`, is a multiple of the identity,
+///   and no infinitely long subspace trail passes through the partial rounds, of the matrix or of its powers up to
+///   `M^(4 * width)`.
+pub fn check_mds_matrix<E: ScalarEngine>(
+    matrix: &Matrix<Scalar<E>>,
+    width: usize,
+) -> Result<(), Error> {
+    let invalid = |message: String| Err(Error::InvalidConstants(message));
+
+    if matrix.len() != width || matrix.iter().any(|row| row.len() != width) {
+        return invalid(format!("MDS matrix must be {} by {}", width, width));
+    }
+    if invert::<E>(matrix).is_none() {
+        return invalid("MDS matrix is not invertible".to_string());
+    }
+    if width > MAX_CHECKED_MDS_WIDTH {
+        return invalid(format!(
+            "the MDS property cannot be checked for widths above {}",
+            MAX_CHECKED_MDS_WIDTH
+        ));
+    }
+    if !is_mds::<E>(matrix) {
+        return invalid("matrix is not MDS: a square submatrix is singular".to_string());
+    }
+
+    security::check_subspace_trails::<E>(matrix)
+}
+
+/// Checks a matrix loaded with constants, as the permutation applies it: the matrix of `generate_mds_matrix` is
+/// accepted as it is, at any width, and any other must pass `check_mds_matrix`, such as that of
+/// `PoseidonConstants::new_with_mds`.
+pub(crate) fn check_loaded_mds_matrix<E: ScalarEngine>(
+    matrix: &Matrix<Scalar<E>>,
+    width: usize,
+) -> Result<(), Error> {
+    if *matrix == generate_mds_matrix::<E>(width) {
+        return Ok(());
+    }
+    check_mds_matrix::<E>(matrix, width)
+}
+
+fn make_prime<E: ScalarEngine>(m: &Matrix<Scalar<E>>) -> Matrix<Scalar<E>> {
+    m.iter()
+        .enumerate()
+        .map(|(i, row)| match i {
+            0 => {
+                let mut new_row = vec![Scalar::<E>::zero(); row.len()];
+                new_row[0] = Scalar::<E>::one();
+                new_row
+            }
+            _ => {
+                let mut new_row = vec![Scalar::<E>::zero(); row.len()];
+                new_row[1..].copy_from_slice(&row[1..]);
+                new_row
+            }
+        })
+        .collect()
+}
+
+fn make_double_prime<E: ScalarEngine>(
+    m: &Matrix<Scalar<E>>,
+    m_hat_inv: &Matrix<Scalar<E>>,
+) -> Matrix<Scalar<E>> {
+    let (v, w) = make_v_w::<E>(m);
+    // The first column of `m` below the first row is `m_hat * w_hat`, so that `m` is `m_prime * m_double_prime`.
+    let w_hat = left_apply_matrix::<E>(m_hat_inv, &w);
+
+    m.iter()
+        .enumerate()
+        .map(|(i, row)| match i {
+            0 => {
+                let mut new_row = Vec::with_capacity(row.len());
+                new_row.push(row[0]);
+                new_row.extend(&v);
+                new_row
+            }
+            _ => {
+                let mut new_row = vec![Scalar::<E>::zero(); row.len()];
+                new_row[0] = w_hat[i - 1];
+                new_row[i] = Scalar::<E>::one();
+                new_row
+            }
+        })
+        .collect()
+}
+
+fn make_v_w<E: ScalarEngine>(m: &Matrix<Scalar<E>>) -> (Vec<Scalar<E>>, Vec<Scalar<E>>) {
+    let v = m[0][1..].to_vec();
+    let mut w = Vec::new();
+    for i in 1..m.len() {
+        w.push(m[i][0]);
+    }
+
+    (v, w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use bellperson::bls::{Bls12, Fr};
+    use matrix::{apply_matrix, left_apply_matrix};
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_generate_mds_matrix_is_mds() {
+        for t in 2..=5 {
+            assert!(is_mds::<Bls12>(&generate_mds_matrix::<Bls12>(t)));
+        }
+    }
+
+    #[test]
+    fn test_generate_mds_matrix_t3() {
+        // `1 / 3` through `1 / 7`, as `M[i][j] = 1 / (i + j + 3)`.
+        let inverses = [
+            [
+                0xaaaaaaaa00000001,
+                0xe27e6d5755543d54,
+                0xccd13ab0066be558,
+                0x4d491a377113a8da,
+            ],
+            [
+                0x3fffffff40000001,
+                0xfece3b023ffec4ff,
+                0x266b620607396203,
+                0x56f23d7e5f361df6,
+            ],
+            [
+                0xcccccccc33333334,
+                0x323e959b66656a65,
+                0x51ef819e6c2de803,
+                0x458e97984c2b4b2b,
+            ],
+            [
+                0xd555555480000001,
+                0x1b1e08ad2aa94ca9,
+                0x8005895c0806deaf,
+                0x609b60c54d589311,
+            ],
+            [
+                0xdb6db6db24924925,
+                0xaa362edc49241a48,
+                0x57c7624b7077624a,
+                0x211f5460e7519182,
+            ],
+        ];
+        let expected: Matrix<Fr> = (0..3)
+            .map(|i| (0..3).map(|j| scalar_from_u64s(inverses[i + j])).collect())
+            .collect();
+
+        assert_eq!(expected, generate_mds_matrix::<Bls12>(3));
+    }
+
+    #[test]
+    fn test_is_mds() {
+        let s = |rows: &[&[u64]]| -> Matrix<Fr> {
+            rows.iter()
+                .map(|row| row.iter().map(|x| scalar_from_u64::<Fr>(*x)).collect())
+                .collect()
+        };
+
+        assert!(is_mds::<Bls12>(&s(&[&[2, 3], &[3, 5]])));
+        // Invertible, but with zero entries, which are singular 1 by 1 submatrices.
+        assert!(!is_mds::<Bls12>(&s(&[&[1, 0], &[0, 1]])));
+        // Nonzero entries, but with a singular 2 by 2 submatrix.
+        assert!(!is_mds::<Bls12>(&s(&[&[1, 2, 3], &[2, 4, 5], &[3, 5, 7]])));
+        assert!(!is_mds::<Bls12>(&s(&[&[1, 2, 3], &[4, 5, 6]])));
+    }
+
+    #[test]
+    fn test_check_mds_matrix() {
+        let s = |rows: &[&[u64]]| -> Matrix<Fr> {
+            rows.iter()
+                .map(|row| row.iter().map(|x| scalar_from_u64::<Fr>(*x)).collect())
+                .collect()
+        };
+        let error = |matrix: &Matrix<Fr>| match check_mds_matrix::<Bls12>(matrix, 3) {
+            Err(Error::InvalidConstants(message)) => message,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        for t in 2..=9 {
+            check_mds_matrix::<Bls12>(&generate_mds_matrix::<Bls12>(t), t).unwrap();
+        }
+        check_mds_matrix::<Bls12>(&s(&[&[1, 1, 1], &[1, 2, 3], &[1, 4, 2]]), 3).unwrap();
+
+        assert_eq!(
+            "MDS matrix must be 3 by 3",
+            error(&generate_mds_matrix::<Bls12>(4))
+        );
+        assert_eq!(
+            "MDS matrix is not invertible",
+            error(&s(&[&[1, 2, 3], &[2, 3, 4], &[3, 4, 5]]))
+        );
+        assert_eq!(
+            "matrix is not MDS: a square submatrix is singular",
+            error(&make_identity::<Bls12>(3))
+        );
+        // MDS, but `(0, 1, -1)` is mapped to a multiple of itself, so its first element is always zero.
+        assert_eq!(
+            "MDS matrix admits an infinitely long subspace trail through the partial rounds",
+            error(&s(&[&[2, 1, 1], &[1, 2, 3], &[1, 3, 2]]))
+        );
+        assert_eq!(
+            "MDS matrix admits an infinitely long subspace trail through the partial rounds",
+            error(&s(&[&[2, 1, 1], &[1, 2, 1], &[1, 1, 2]]))
+        );
+        // A circulant matrix, MDS and passing the first two algorithms, but not the third.
+        assert_eq!(
+            "MDS matrix to the power 6 admits an infinitely long subspace trail through the partial rounds",
+            error(&s(&[&[2, 3, 4], &[4, 2, 3], &[3, 4, 2]]))
+        );
+    }
+
+    #[test]
+    fn test_generate_mds_matrix_cached() {
+        for t in [3, 12, 37].iter() {
+            let m = generate_mds_matrix::<Bls12>(*t);
+            assert_eq!(generate_mds::<Bls12>(*t), m);
+            assert_eq!(m, generate_mds_matrix::<Bls12>(*t));
+            assert!(MDS_MATRICES
+                .lock()
+                .unwrap()
+                .contains_key(&(*t, TypeId::of::<Fr>())));
+        }
+    }
+
+    #[test]
+    fn test_is_cauchy() {
+        let s = |xs: &[u64]| {
+            xs.iter()
+                .map(|x| scalar_from_u64::<Fr>(*x))
+                .collect::<Vec<_>>()
+        };
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+
+        assert!(is_cauchy::<Bls12>(&s(&[0, 1, 2]), &s(&[3, 4, 5])));
+        // Duplicate `x` or `y` values give equal rows or columns.
+        assert!(!is_cauchy::<Bls12>(&s(&[0, 1, 1]), &s(&[3, 4, 5])));
+        assert!(!is_cauchy::<Bls12>(&s(&[0, 1, 2]), &s(&[3, 5, 5])));
+        // An `x + y` of zero cannot be inverted.
+        assert!(!is_cauchy::<Bls12>(
+            &s(&[0, 1, 2]),
+            &[s(&[3])[0], minus_one]
+        ));
+    }
+
+    #[test]
+    fn test_mds_matrices_creation() {
+        for i in 2..5 {
+            test_mds_matrices_creation_aux(i);
+        }
+    }
+
+    fn test_mds_matrices_creation_aux(width: usize) {
+        let MDSMatrices {
+            m,
+            m_inv,
+            m_hat,
+            m_hat_inv: _,
+            m_prime,
+            m_double_prime,
+        } = create_mds_matrices::<Bls12>(width);
+
+        for i in 0..m_hat.len() {
+            for j in 0..m_hat[i].len() {
+                assert_eq!(m[i + 1][j + 1], m_hat[i][j], "MDS minor has wrong value.");
+            }
+        }
+
+        // M^-1 x M = I
+        assert!(matrix::is_identity::<Bls12>(
+            &matrix::mat_mul::<Bls12>(&m_inv, &m).unwrap()
+        ));
+
+        // M' x M'' = M
+        assert_eq!(
+            m,
+            matrix::mat_mul::<Bls12>(&m_prime, &m_double_prime).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_swapping() {
+        test_swapping_aux(3);
+    }
+
+    fn test_swapping_aux(width: usize) {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+
+        let MDSMatrices {
+            m,
+            m_inv: _,
+            m_hat: _,
+            m_hat_inv: _,
+            m_prime,
+            m_double_prime,
+        } = create_mds_matrices::<Bls12>(width);
+
+        let mut base = Vec::with_capacity(width);
+        for _ in 0..width {
+            base.push(Fr::random(&mut rng));
+        }
+
+        let mut x = base.clone();
+        x[0] = Fr::random(&mut rng);
+
+        let mut y = base.clone();
+        y[0] = Fr::random(&mut rng);
+
+        let qx = apply_matrix::<Bls12>(&m_prime, &x);
+        let qy = apply_matrix::<Bls12>(&m_prime, &y);
+        assert_eq!(qx[0], x[0]);
+        assert_eq!(qy[0], y[0]);
+        assert_eq!(qx[1..], qy[1..]);
+
+        let mx = left_apply_matrix::<Bls12>(&m, &x);
+        let m1_m2_x =
+            left_apply_matrix::<Bls12>(&m_prime, &left_apply_matrix::<Bls12>(&m_double_prime, &x));
+        assert_eq!(mx, m1_m2_x);
+
+        let xm = apply_matrix::<Bls12>(&m, &x);
+        let x_m1_m2 = apply_matrix::<Bls12>(&m_double_prime, &apply_matrix::<Bls12>(&m_prime, &x));
+        assert_eq!(xm, x_m1_m2);
+
+        let mut rk = Vec::with_capacity(width);
+        for _ in 0..width {
+            rk.push(Fr::random(&mut rng));
+        }
+    }
+
+    #[test]
+    fn test_factor_to_sparse_matrices() {
+        for width in 3..9 {
+            test_factor_to_sparse_matrices_aux(width, 3);
+        }
+    }
+
+    fn test_factor_to_sparse_matrices_aux(width: usize, n: usize) {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+
+        let m = generate_mds::<Bls12>(width);
+        let m2 = m.clone();
+
+        let (pre_sparse, mut sparse) = factor_to_sparse_matrices::<Bls12>(m, n);
+        assert_eq!(n, sparse.len());
+
+        let mut initial = Vec::with_capacity(width);
+        for _ in 0..width {
+            initial.push(Fr::random(&mut rng));
+        }
+
+        let mut round_keys = Vec::with_capacity(width);
+        for _ in 0..(n + 1) {
+            round_keys.push(Fr::random(&mut rng));
+        }
+
+        let expected = std::iter::repeat(m2).take(n + 1).zip(&round_keys).fold(
+            initial.clone(),
+            |mut acc, (m, rk)| {
+                acc = apply_matrix::<Bls12>(&m, &acc);
+                quintic_s_box::<Bls12>(&mut acc[0], None, Some(&rk));
+                acc
+            },
+        );
+
+        sparse.insert(0, pre_sparse);
+        let actual = sparse
+            .iter()
+            .zip(&round_keys)
+            .fold(initial.clone(), |mut acc, (m, rk)| {
+                acc = apply_matrix::<Bls12>(&m, &acc);
+                quintic_s_box::<Bls12>(&mut acc[0], None, Some(&rk));
+                acc
+            });
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_apply_sparse_mds() {
+        for width in 3..=12 {
+            test_apply_sparse_mds_aux(width, 3);
+        }
+    }
+
+    fn test_apply_sparse_mds_aux(width: usize, n: usize) {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+
+        // The dense and sparse matrices are derived from the same Cauchy matrix.
+        let m = generate_mds_matrix::<Bls12>(width);
+        let (pre_sparse, sparse_matrixes) = factor_to_sparse_matrixes::<Bls12>(m.clone(), n);
+
+        let initial: Vec<Fr> = (0..width).map(|_| Fr::random(&mut rng)).collect();
+        let round_keys: Vec<Fr> = (0..(n + 1)).map(|_| Fr::random(&mut rng)).collect();
+
+        for sparse_matrix in sparse_matrixes.iter() {
+            let mut actual = initial.clone();
+            apply_sparse_mds::<Bls12>(sparse_matrix, &mut actual);
+            assert_eq!(
+                apply_matrix::<Bls12>(&sparse_matrix.to_matrix(), &initial),
+                actual
+            );
+        }
+
+        let expected = round_keys.iter().fold(initial.clone(), |mut acc, rk| {
+            acc = apply_matrix::<Bls12>(&m, &acc);
+            quintic_s_box::<Bls12>(&mut acc[0], None, Some(&rk));
+            acc
+        });
+
+        let mut actual = apply_matrix::<Bls12>(&pre_sparse, &initial);
+        quintic_s_box::<Bls12>(&mut actual[0], None, Some(&round_keys[0]));
+        for (sparse_matrix, rk) in sparse_matrixes.iter().zip(&round_keys[1..]) {
+            apply_sparse_mds::<Bls12>(sparse_matrix, &mut actual);
+            quintic_s_box::<Bls12>(&mut actual[0], None, Some(&rk));
+        }
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_factor_to_sparse_matrixes() {
+        for width in 3..9 {
+            test_factor_to_sparse_matrixes_aux(width, 3);
+        }
+    }
+
+    fn test_factor_to_sparse_matrixes_aux(width: usize, n: usize) {
+        let m = generate_mds::<Bls12>(width);
+        let m2 = m.clone();
+
+        let (pre_sparse, sparse_matrices) = factor_to_sparse_matrices::<Bls12>(m, n);
+        assert_eq!(n, sparse_matrices.len());
+
+        let (pre_sparse2, sparse_matrixes) = factor_to_sparse_matrixes::<Bls12>(m2, n);
+
+        assert_eq!(pre_sparse, pre_sparse2);
+
+        let matrices_again = sparse_matrixes
+            .iter()
+            .map(|m| m.to_matrix())
+            .collect::<Vec<_>>();
+        dbg!(&sparse_matrixes, &sparse_matrices);
+
+        let _ = sparse_matrices
+            .iter()
+            .zip(matrices_again.iter())
+            .map(|(a, b)| {
+                dbg!(&a, &b);
+                assert_eq!(a, b)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(sparse_matrices, matrices_again);
+    }
+}
@@ -0,0 +1,228 @@
+//! Checks of an MDS matrix against infinitely long subspace trails, following "Proving Resistance Against Infinitely
+//! Long Subspace Trails: How to Choose the Linear Layer" by Grassi, Rechberger, and Schofnegger, as the reference
+//! script `generate_params_poseidon.sage` runs them on each candidate matrix.
+//!
+//! The partial rounds apply the S-box to the first element of the state alone. A subspace of differences whose first
+//! element stays zero through every multiplication by the matrix activates no S-box, so it passes through any number
+//! of partial rounds, and an attacker can choose the differences of the input accordingly. The matrix multiplies the
+//! state as a row vector, as the permutation does, so such a subspace exists exactly when the first columns of `M^0`
+//! through `M^(t - 1)` are linearly dependent, which is checked directly rather than by searching for subspaces as the
+//! script does.
+
+use ff::{Field, ScalarEngine};
+
+use crate::matrix::{
+    self, invert, is_square, left_apply_matrix, make_identity, mat_mul, Matrix, Scalar,
+};
+use crate::Error;
+
+/// Returns the smallest `i`, for `0 < i < t`, such that `M^i` is a multiple of the identity, if any, as algorithm 1 of
+/// the reference script checks. Every subspace is then invariant through `i` rounds.
+///
+/// # Panics
+///
+/// Panics if `matrix` is not square.
+pub fn scalar_power<E: ScalarEngine>(matrix: &Matrix<Scalar<E>>) -> Option<usize> {
+    assert!(is_square(matrix), "matrix must be square");
+    let width = matrix::rows(matrix);
+    let mut power = matrix.clone();
+    for i in 1..width {
+        let scalar = power[0][0];
+        let is_scalar = power.iter().enumerate().all(|(j, row)| {
+            row.iter()
+                .enumerate()
+                .all(|(k, x)| if j == k { *x == scalar } else { x.is_zero() })
+        });
+        if is_scalar {
+            return Some(i);
+        }
+        power = mat_mul::<E>(&power, matrix).unwrap();
+    }
+    None
+}
+
+/// Returns `true` if an infinitely long subspace trail passes through the partial rounds of `matrix`, as algorithm 2
+/// of the reference script checks: the first columns of `M^0` through `M^(t - 1)` are linearly dependent.
+///
+/// # Panics
+///
+/// Panics if `matrix` is not square.
+pub fn has_subspace_trail<E: ScalarEngine>(matrix: &Matrix<Scalar<E>>) -> bool {
+    assert!(is_square(matrix), "matrix must be square");
+    let width = matrix::rows(matrix);
+    // The first column of `M^(i + 1)` is `M` times the first column of `M^i`.
+    let mut column = make_identity::<E>(width).swap_remove(0);
+    let mut first_columns = Vec::with_capacity(width);
+    for _ in 0..width {
+        let next = left_apply_matrix::<E>(matrix, &column);
+        first_columns.push(column);
+        column = next;
+    }
+    invert::<E>(&first_columns).is_none()
+}
+
+/// Returns the smallest `r`, for `2 <= r <= 4 * t`, such that an infinitely long subspace trail passes through `M^r`,
+/// if any, as algorithm 3 of the reference script checks by running algorithm 2, `has_subspace_trail`, on each power.
+/// A trail through `M^r` leaves the first element of the differences zero after every `r`-th partial round.
+///
+/// This multiplies `4 * t` matrices and checks as many trails, so its cost grows as `t^4`.
+///
+/// # Panics
+///
+/// Panics if `matrix` is not square.
+pub fn power_with_subspace_trail<E: ScalarEngine>(matrix: &Matrix<Scalar<E>>) -> Option<usize> {
+    assert!(is_square(matrix), "matrix must be square");
+    let width = matrix::rows(matrix);
+    let mut power = matrix.clone();
+    for r in 2..=4 * width {
+        power = mat_mul::<E>(&power, matrix).unwrap();
+        if has_subspace_trail::<E>(&power) {
+            return Some(r);
+        }
+    }
+    None
+}
+
+/// Runs the three algorithms of the reference script on `matrix`, which multiplies the state as a row vector, in
+/// turn: `scalar_power`, `has_subspace_trail`, and `power_with_subspace_trail`. A matrix failing one returns
+/// `Error::InvalidConstants`, naming it, as does a matrix which is not square.
+///
+/// `check_mds_matrix` runs these checks after checking the MDS property, and with the `mds-security` feature, the
+/// generated MDS matrices pass them. See `power_with_subspace_trail` for their cost.
+pub fn check_subspace_trails<E: ScalarEngine>(matrix: &Matrix<Scalar<E>>) -> Result<(), Error> {
+    let invalid = |message: String| Err(Error::InvalidConstants(message));
+
+    if !is_square(matrix) {
+        return invalid("MDS matrix must be square".to_string());
+    }
+    if let Some(i) = scalar_power::<E>(matrix) {
+        return invalid(format!(
+            "MDS matrix to the power {} is a multiple of the identity",
+            i
+        ));
+    }
+    if has_subspace_trail::<E>(matrix) {
+        return invalid(
+            "MDS matrix admits an infinitely long subspace trail through the partial rounds"
+                .to_string(),
+        );
+    }
+    if let Some(r) = power_with_subspace_trail::<E>(matrix) {
+        return invalid(format!(
+            "MDS matrix to the power {} admits an infinitely long subspace trail through the partial rounds",
+            r
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mds::generate_mds_matrix;
+    use crate::scalar_from_u64;
+    use bellperson::bls::{Bls12, Fr};
+
+    // Entries are signed, as some of the weak matrices have negative entries.
+    fn matrix(rows: &[&[i64]]) -> Matrix<Fr> {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|x| {
+                        let mut element = scalar_from_u64::<Fr>(x.abs() as u64);
+                        if *x < 0 {
+                            element.negate();
+                        }
+                        element
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn error(matrix: &Matrix<Fr>) -> String {
+        check_subspace_trails::<Bls12>(matrix)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn generated_matrices_pass() {
+        for t in 2..=16 {
+            check_subspace_trails::<Bls12>(&generate_mds_matrix::<Bls12>(t)).unwrap();
+        }
+    }
+
+    #[test]
+    fn circulant_matrix_fails_algorithm_3() {
+        // MDS, and passing the first two algorithms.
+        let m = matrix(&[&[2, 3, 4], &[4, 2, 3], &[3, 4, 2]]);
+        assert!(crate::mds::is_mds::<Bls12>(&m));
+        assert_eq!(None, scalar_power::<Bls12>(&m));
+        assert!(!has_subspace_trail::<Bls12>(&m));
+        assert_eq!(Some(6), power_with_subspace_trail::<Bls12>(&m));
+        assert!(crate::mds::check_mds_matrix::<Bls12>(&m, 3).is_err());
+    }
+
+    #[test]
+    fn scalar_powers() {
+        let swap = matrix(&[&[0, 1, 0], &[1, 0, 0], &[0, 0, 1]]);
+        let rotation = matrix(&[&[0, 1, 0], &[0, 0, 1], &[1, 0, 0]]);
+        assert_eq!(Some(1), scalar_power::<Bls12>(&make_identity::<Bls12>(3)));
+        assert_eq!(Some(2), scalar_power::<Bls12>(&swap));
+        // The third power is the identity, but only powers below the width are checked.
+        assert_eq!(None, scalar_power::<Bls12>(&rotation));
+        assert_eq!(
+            None,
+            scalar_power::<Bls12>(&generate_mds_matrix::<Bls12>(3))
+        );
+
+        assert_eq!(
+            "Invalid Poseidon constants: MDS matrix to the power 1 is a multiple of the identity",
+            error(&make_identity::<Bls12>(3))
+        );
+        assert_eq!(
+            "Invalid Poseidon constants: MDS matrix to the power 2 is a multiple of the identity",
+            error(&swap)
+        );
+    }
+
+    #[test]
+    fn subspace_trail() {
+        // `(0, 1, -1)` is mapped to a multiple of itself, so its first element is always zero.
+        let m = matrix(&[&[2, 1, 1], &[1, 2, 3], &[1, 3, 2]]);
+        assert!(has_subspace_trail::<Bls12>(&m));
+        assert_eq!(
+            "Invalid Poseidon constants: MDS matrix admits an infinitely long subspace trail through the partial rounds",
+            error(&m)
+        );
+    }
+
+    #[test]
+    fn subspace_trail_through_a_power() {
+        // `S D S^-1`, where `S` is `[[1, 1, 1], [1, 2, 3], [1, 3, 6]]` and `D` is diagonal with entries 1, -1, and 2.
+        // It is MDS and passes the first two algorithms, but the eigenvalue 1 of `M^2` has an eigenspace of dimension
+        // 2, which meets the subspace of differences whose first element is zero.
+        let m = matrix(&[&[8, -12, 5], &[15, -25, 11], &[24, -42, 19]]);
+        assert!(crate::mds::is_mds::<Bls12>(&m));
+        assert_eq!(None, scalar_power::<Bls12>(&m));
+        assert!(!has_subspace_trail::<Bls12>(&m));
+        assert_eq!(Some(2), power_with_subspace_trail::<Bls12>(&m));
+        assert!(has_subspace_trail::<Bls12>(
+            &mat_mul::<Bls12>(&m, &m).unwrap()
+        ));
+        assert_eq!(
+            "Invalid Poseidon constants: MDS matrix to the power 2 admits an infinitely long subspace trail through the \
+             partial rounds",
+            error(&m)
+        );
+    }
+
+    #[test]
+    fn not_square() {
+        assert_eq!(
+            "Invalid Poseidon constants: MDS matrix must be square",
+            error(&matrix(&[&[1, 2, 3], &[4, 5, 6]]))
+        );
+    }
+}
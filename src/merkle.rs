@@ -0,0 +1,607 @@
+use crate::hash_type::HashType;
+#[cfg(not(feature = "parallel"))]
+use crate::poseidon::batch_hash_fixed;
+#[cfg(feature = "parallel")]
+use crate::poseidon::par_batch_hash_fixed;
+use crate::poseidon::{Arity, Poseidon, PoseidonConstants};
+use ff::{Field, ScalarEngine};
+use generic_array::typenum::Unsigned;
+use generic_array::GenericArray;
+use std::marker::PhantomData;
+
+/// A Merkle tree of arity `A`, whose nodes are the Poseidon hashes of their `A` children.
+///
+/// Each row with a number of nodes that is not a multiple of `A` is padded with the root of an empty subtree of the
+/// same height: `padding_leaf` for the leaves, and the hash of `A` padding elements of the row below for each row
+/// above.
+/// A tree of a single leaf has a root of height one.
+///
+/// As the padding of the leaves is not zero, the root of `[a]` differs from that of `[a, 0]`. The root of `[a]` is
+/// still that of `[a, padding_leaf(..)]`, so a root commits to the number of leaves only where no leaf is the padding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleTree<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// The rows of the tree, from the (padded) leaves to the root.
+    rows: Vec<Vec<E::Fr>>,
+    leaf_count: usize,
+    constants: PoseidonConstants<E, A>,
+}
+
+/// The siblings of a leaf along its path to the root, from the leaves upwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleProof<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// The index of the proven leaf.
+    pub leaf_index: usize,
+    /// The `A - 1` siblings of the node on the path at each height, in order, without the node itself.
+    pub siblings: Vec<Vec<E::Fr>>,
+    _a: PhantomData<A>,
+}
+
+impl<E, A> MerkleTree<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Builds the tree of `leaves` with the default constants for `A`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new(leaves: Vec<E::Fr>) -> Self {
+        Self::new_with_constants(leaves, PoseidonConstants::new())
+    }
+
+    /// Builds the tree of `leaves`, hashing with `constants`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaves` is empty.
+    pub fn new_with_constants(leaves: Vec<E::Fr>, constants: PoseidonConstants<E, A>) -> Self {
+        assert!(
+            !leaves.is_empty(),
+            "a Merkle tree must have at least one leaf"
+        );
+        let arity = A::to_usize();
+        let leaf_count = leaves.len();
+
+        let mut padding = padding_leaf(&constants);
+        let mut rows = vec![leaves];
+        loop {
+            let row = rows.last_mut().unwrap();
+            let padded_len = (row.len() + arity - 1) / arity * arity;
+            row.resize(padded_len, padding);
+
+            let next: Vec<E::Fr> = row
+                .chunks(arity)
+                .map(|children| Poseidon::new_with_preimage(children, &constants).hash())
+                .collect();
+            padding = Poseidon::new_with_preimage(&vec![padding; arity], &constants).hash();
+
+            let done = next.len() == 1;
+            rows.push(next);
+            if done {
+                break;
+            }
+        }
+
+        Self {
+            rows,
+            leaf_count,
+            constants,
+        }
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> E::Fr {
+        self.rows.last().unwrap()[0]
+    }
+
+    /// Returns the number of leaves, excluding padding.
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Returns the number of rows of the tree above the leaves.
+    pub fn height(&self) -> usize {
+        self.rows.len() - 1
+    }
+
+    /// Replaces the leaf at `leaf_index` with `leaf`, hashing again only the `height` nodes on its path to the root.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaf_index` is out of bounds, including indices of padding.
+    pub fn set_leaf(&mut self, leaf_index: usize, leaf: E::Fr) {
+        assert!(
+            leaf_index < self.leaf_count,
+            "leaf index out of bounds: {}",
+            leaf_index
+        );
+        self.rows[0][leaf_index] = leaf;
+        self.update_path(leaf_index);
+    }
+
+    /// Appends `leaf`, returning its index, and hashes again only the nodes on its path to the root. The tree has the
+    /// same rows and root as one built with all of its leaves.
+    ///
+    /// The leaf replaces padding where there is any. Otherwise the rows are padded further, and a full tree grows by
+    /// a row, whose root hashes the previous root with padding.
+    pub fn append_leaf(&mut self, leaf: E::Fr) -> usize {
+        let arity = A::to_usize();
+        let leaf_index = self.leaf_count;
+
+        // Make room for the path of the new leaf, padding each row with the root of an empty subtree of its height.
+        let mut index = leaf_index;
+        let mut padding = padding_leaf(&self.constants);
+        for height in 0.. {
+            if height == self.height() {
+                if index == 0 {
+                    break;
+                }
+                // The tree is full, so its root is the first child of a new root.
+                self.rows[height].resize(arity, padding);
+                self.rows.push(vec![E::Fr::zero()]);
+            } else if index == self.rows[height].len() {
+                let padded_len = self.rows[height].len() + arity;
+                self.rows[height].resize(padded_len, padding);
+            }
+            padding = Poseidon::new_with_preimage(&vec![padding; arity], &self.constants).hash();
+            index /= arity;
+        }
+
+        self.rows[0][leaf_index] = leaf;
+        self.leaf_count += 1;
+        self.update_path(leaf_index);
+        leaf_index
+    }
+
+    // Hashes again each node on the path from the leaf at `leaf_index` to the root.
+    fn update_path(&mut self, leaf_index: usize) {
+        let arity = A::to_usize();
+        let mut index = leaf_index;
+        for height in 0..self.height() {
+            let start = index - index % arity;
+            let node = Poseidon::new_with_preimage(
+                &self.rows[height][start..start + arity],
+                &self.constants,
+            )
+            .hash();
+            index /= arity;
+            self.rows[height + 1][index] = node;
+        }
+    }
+
+    /// Returns the proof of inclusion of the leaf at `leaf_index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leaf_index` is out of bounds, including indices of padding.
+    pub fn proof(&self, leaf_index: usize) -> MerkleProof<E, A> {
+        let arity = A::to_usize();
+        assert!(
+            leaf_index < self.leaf_count,
+            "leaf index out of bounds: {}",
+            leaf_index
+        );
+
+        let mut index = leaf_index;
+        let siblings = self.rows[..self.height()]
+            .iter()
+            .map(|row| {
+                let start = index - index % arity;
+                let siblings = row[start..start + arity]
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| start + i != index)
+                    .map(|(_, sibling)| *sibling)
+                    .collect();
+                index /= arity;
+                siblings
+            })
+            .collect();
+
+        MerkleProof {
+            leaf_index,
+            siblings,
+            _a: PhantomData,
+        }
+    }
+
+    /// Returns `true` if `proof` proves the inclusion of `leaf` in a tree with `root`, using this tree's constants.
+    pub fn verify(&self, leaf: E::Fr, proof: &MerkleProof<E, A>, root: E::Fr) -> bool {
+        proof.verify(leaf, root, &self.constants)
+    }
+}
+
+impl<E, A> MerkleProof<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Returns `true` if this proves the inclusion of `leaf` in a tree with `root` hashed with `constants`.
+    pub fn verify(&self, leaf: E::Fr, root: E::Fr, constants: &PoseidonConstants<E, A>) -> bool {
+        let arity = A::to_usize();
+
+        let mut index = self.leaf_index;
+        let mut node = leaf;
+        for siblings in self.siblings.iter() {
+            if siblings.len() != arity - 1 {
+                return false;
+            }
+            let mut children = siblings.clone();
+            children.insert(index % arity, node);
+            node = Poseidon::new_with_preimage(&children, constants).hash();
+            index /= arity;
+        }
+
+        index == 0 && node == root
+    }
+}
+
+/// Builds the rows of a `MerkleTree` above its leaves from leaves added in batches, so that the leaves of a large tree
+/// never need to be held in memory at once.
+///
+/// The leaves of each batch are hashed as soon as a group of `A` is complete, and so are the nodes of each row above,
+/// with the batch hasher `batch_hash_fixed`, or `par_batch_hash_fixed` with the `parallel` feature. Only leaves of an
+/// incomplete group are kept until the next batch. `finalize` pads the rows as `MerkleTree` does, so the root and rows
+/// are those of `MerkleTree::new` of all the leaves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamingTreeBuilder<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// The leaves added since the last complete group of `A`.
+    leaves: Vec<E::Fr>,
+    /// The rows of the tree above the leaves, from the lowest upwards.
+    rows: Vec<Vec<E::Fr>>,
+    /// The number of nodes of each row already hashed into the row above.
+    hashed: Vec<usize>,
+    leaf_count: usize,
+    constants: PoseidonConstants<E, A>,
+}
+
+impl<E, A> StreamingTreeBuilder<E, A>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync,
+{
+    /// Creates a builder hashing with the default constants for `A`, whose round numbers are calculated for the
+    /// width `A + 1`.
+    pub fn new() -> Self {
+        Self::new_with_constants(PoseidonConstants::new())
+    }
+
+    /// Creates a builder hashing with `constants`.
+    pub fn new_with_constants(constants: PoseidonConstants<E, A>) -> Self {
+        Self {
+            leaves: Vec::new(),
+            rows: Vec::new(),
+            hashed: Vec::new(),
+            leaf_count: 0,
+            constants,
+        }
+    }
+
+    /// Returns the number of leaves added so far.
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Adds `leaves` after those added so far, hashing every complete group of `A` nodes in each row.
+    pub fn add_leaves(&mut self, leaves: &[E::Fr]) {
+        let arity = A::to_usize();
+        self.leaf_count += leaves.len();
+        self.leaves.extend_from_slice(leaves);
+
+        let complete = self.leaves.len() - self.leaves.len() % arity;
+        let nodes = self.hash_groups(&self.leaves[..complete]);
+        self.leaves.drain(..complete);
+        self.push_nodes(0, nodes);
+
+        let mut height = 0;
+        while height < self.rows.len() {
+            let (start, end) = (self.hashed[height], self.rows[height].len());
+            let complete = end - (end - start) % arity;
+            let nodes = self.hash_groups(&self.rows[height][start..complete]);
+            self.hashed[height] = complete;
+            self.push_nodes(height + 1, nodes);
+            height += 1;
+        }
+    }
+
+    /// Hashes the remaining nodes of each row, padded as `MerkleTree` pads them, and returns the root along with the
+    /// rows of the tree above the leaves, from the lowest up to the row of the root.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no leaves were added.
+    pub fn finalize(mut self) -> (E::Fr, Vec<Vec<E::Fr>>) {
+        assert!(
+            self.leaf_count > 0,
+            "a Merkle tree must have at least one leaf"
+        );
+        let arity = A::to_usize();
+
+        let mut padding = padding_leaf(&self.constants);
+        let mut remaining = std::mem::replace(&mut self.leaves, Vec::new());
+        let mut height = 0;
+        loop {
+            if !remaining.is_empty() {
+                let padded_len = (remaining.len() + arity - 1) / arity * arity;
+                if height > 0 {
+                    // The rows above the leaves are returned, so they include their padding.
+                    let row = &mut self.rows[height - 1];
+                    let row_len = row.len() + padded_len - remaining.len();
+                    row.resize(row_len, padding);
+                }
+                remaining.resize(padded_len, padding);
+                let nodes = self.hash_groups(&remaining);
+                self.push_nodes(height, nodes);
+            }
+            let row = &self.rows[height];
+            if row.len() == 1 {
+                break;
+            }
+            remaining = row[self.hashed[height]..].to_vec();
+            padding = Poseidon::new_with_preimage(&vec![padding; arity], &self.constants).hash();
+            height += 1;
+        }
+
+        let root = self.rows[height][0];
+        (root, self.rows)
+    }
+
+    // Appends `nodes` to `rows[index]`, the row at height `index + 1`, adding the row for its first nodes.
+    fn push_nodes(&mut self, index: usize, nodes: Vec<E::Fr>) {
+        if nodes.is_empty() {
+            return;
+        }
+        if index == self.rows.len() {
+            self.rows.push(Vec::new());
+            self.hashed.push(0);
+        }
+        self.rows[index].extend(nodes);
+    }
+
+    // Hashes each group of `A` of `nodes`, whose length is a multiple of `A`, with the batch hasher.
+    fn hash_groups(&self, nodes: &[E::Fr]) -> Vec<E::Fr> {
+        let preimages: Vec<GenericArray<E::Fr, A>> = nodes
+            .chunks(A::to_usize())
+            .map(GenericArray::clone_from_slice)
+            .collect();
+
+        #[cfg(feature = "parallel")]
+        let nodes = par_batch_hash_fixed(&preimages, &self.constants);
+        #[cfg(not(feature = "parallel"))]
+        let nodes = batch_hash_fixed(&preimages, &self.constants);
+
+        nodes
+    }
+}
+
+impl<E, A> Default for StreamingTreeBuilder<E, A>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the element padding the leaves of a tree hashed with `constants`: the hash of `A` zeros with the domain tag
+/// of `HashType::MerkleTreeSparse(0)`, a node none of whose children are present. Its domain tag differs from that of
+/// every node of the tree, so that padding is not a node, nor zero.
+pub fn padding_leaf<E, A>(constants: &PoseidonConstants<E, A>) -> E::Fr
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    let domain_tag = HashType::<E::Fr, A>::MerkleTreeSparse(0).domain_tag(&constants.strength);
+    let zeros = vec![E::Fr::zero(); A::to_usize()];
+    Poseidon::new_with_preimage_and_domain_tag(&zeros, domain_tag, constants).hash()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scalar_from_u64;
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U4, U8};
+
+    fn leaves(n: usize) -> Vec<Fr> {
+        (0..n as u64).map(scalar_from_u64::<Fr>).collect()
+    }
+
+    fn hash<A: Arity<Fr>>(preimage: &[Fr], constants: &PoseidonConstants<Bls12, A>) -> Fr {
+        Poseidon::new_with_preimage(preimage, constants).hash()
+    }
+
+    #[test]
+    fn merkle_root() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let l = leaves(4);
+        let expected = hash(
+            &[hash(&l[0..2], &constants), hash(&l[2..4], &constants)],
+            &constants,
+        );
+
+        let tree = MerkleTree::<Bls12, U2>::new(l.clone());
+        assert_eq!(2, tree.height());
+        assert_eq!(expected, tree.root());
+
+        // A single leaf is hashed with padding.
+        let tree = MerkleTree::<Bls12, U2>::new(vec![l[1]]);
+        assert_eq!(1, tree.height());
+        assert_eq!(
+            hash(&[l[1], padding_leaf(&constants)], &constants),
+            tree.root()
+        );
+    }
+
+    #[test]
+    fn merkle_padding_is_not_zero() {
+        // Padding with zero would give a tree of one leaf the root of the tree of that leaf and zero.
+        let a = scalar_from_u64::<Fr>(7);
+        assert_ne!(
+            MerkleTree::<Bls12, U2>::new(vec![a]).root(),
+            MerkleTree::<Bls12, U2>::new(vec![a, Fr::zero()]).root()
+        );
+        assert_ne!(
+            MerkleTree::<Bls12, U4>::new(vec![a]).root(),
+            MerkleTree::<Bls12, U4>::new(vec![a, Fr::zero(), Fr::zero(), Fr::zero()]).root()
+        );
+
+        // The padding is neither zero nor the hash of zeros, the padding of the row above in a tree of zeros.
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let padding = padding_leaf(&constants);
+        assert_ne!(Fr::zero(), padding);
+        assert_ne!(hash(&[Fr::zero(), Fr::zero()], &constants), padding);
+    }
+
+    #[test]
+    fn merkle_unbalanced_root() {
+        // Five leaves of a binary tree are padded to eight, so the padding of the second row is the hash of two
+        // padding leaves.
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let l = leaves(5);
+        let padding = padding_leaf(&constants);
+        let padding_1 = hash(&[padding, padding], &constants);
+        let expected = hash(
+            &[
+                hash(
+                    &[hash(&l[0..2], &constants), hash(&l[2..4], &constants)],
+                    &constants,
+                ),
+                hash(&[hash(&[l[4], padding], &constants), padding_1], &constants),
+            ],
+            &constants,
+        );
+        let tree = MerkleTree::<Bls12, U2>::new(l);
+        assert_eq!(3, tree.height());
+        assert_eq!(expected, tree.root());
+    }
+
+    fn merkle_proofs_aux<A: Arity<Fr>>(n: usize) {
+        let l = leaves(n);
+        let tree = MerkleTree::<Bls12, A>::new(l.clone());
+        let root = tree.root();
+
+        for (i, leaf) in l.iter().enumerate() {
+            let proof = tree.proof(i);
+            assert_eq!(tree.height(), proof.siblings.len());
+            assert!(tree.verify(*leaf, &proof, root));
+
+            // The proof does not verify another leaf, or the leaf at another index.
+            assert!(!tree.verify(scalar_from_u64::<Fr>(n as u64), &proof, root));
+            let mut moved = proof.clone();
+            moved.leaf_index = (i + 1) % n;
+            if n > 1 {
+                assert!(!tree.verify(*leaf, &moved, root));
+            }
+        }
+    }
+
+    #[test]
+    fn merkle_proofs() {
+        merkle_proofs_aux::<U2>(8);
+        merkle_proofs_aux::<U2>(5);
+        merkle_proofs_aux::<U4>(7);
+        merkle_proofs_aux::<U8>(9);
+        merkle_proofs_aux::<U8>(1);
+    }
+
+    fn merkle_append_aux<A: Arity<Fr>>(n: usize) {
+        let l = leaves(n);
+        let mut tree = MerkleTree::<Bls12, A>::new(vec![l[0]]);
+        for (i, leaf) in l.iter().enumerate().skip(1) {
+            assert_eq!(i, tree.append_leaf(*leaf));
+            assert_eq!(MerkleTree::<Bls12, A>::new(l[..=i].to_vec()), tree);
+        }
+    }
+
+    #[test]
+    fn merkle_append_leaf() {
+        merkle_append_aux::<U2>(17);
+        merkle_append_aux::<U4>(22);
+        merkle_append_aux::<U8>(10);
+    }
+
+    #[test]
+    fn merkle_set_leaf() {
+        let mut l = leaves(11);
+        let mut tree = MerkleTree::<Bls12, U4>::new(l.clone());
+        for i in [0, 5, 10].iter() {
+            l[*i] = scalar_from_u64::<Fr>(100 + *i as u64);
+            tree.set_leaf(*i, l[*i]);
+            assert_eq!(MerkleTree::<Bls12, U4>::new(l.clone()), tree);
+
+            let proof = tree.proof(*i);
+            assert!(tree.verify(l[*i], &proof, tree.root()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "leaf index out of bounds: 5")]
+    fn merkle_set_padding() {
+        MerkleTree::<Bls12, U2>::new(leaves(5)).set_leaf(5, Fr::zero());
+    }
+
+    #[test]
+    #[should_panic(expected = "leaf index out of bounds: 5")]
+    fn merkle_proof_of_padding() {
+        MerkleTree::<Bls12, U2>::new(leaves(5)).proof(5);
+    }
+
+    #[test]
+    #[should_panic(expected = "a Merkle tree must have at least one leaf")]
+    fn merkle_no_leaves() {
+        MerkleTree::<Bls12, U2>::new(vec![]);
+    }
+
+    fn streaming_aux<A: Arity<Fr> + Send + Sync>(n: usize, batch_size: usize) {
+        let l = leaves(n);
+        let tree = MerkleTree::<Bls12, A>::new(l.clone());
+
+        let mut builder = StreamingTreeBuilder::<Bls12, A>::new();
+        for (i, batch) in l.chunks(batch_size).enumerate() {
+            builder.add_leaves(batch);
+            assert_eq!(usize::min(n, (i + 1) * batch_size), builder.leaf_count());
+        }
+        // Adding no leaves changes nothing.
+        builder.add_leaves(&[]);
+
+        let (root, rows) = builder.finalize();
+        assert_eq!(tree.root(), root);
+        assert_eq!(&tree.rows[1..], &rows[..]);
+    }
+
+    #[test]
+    fn streaming_tree_builder() {
+        for batch_size in [1, 3, 8, 100].iter() {
+            for n in [1, 2, 5, 8, 16, 17].iter() {
+                streaming_aux::<U2>(*n, *batch_size);
+            }
+            for n in [1, 4, 7, 16, 30, 64].iter() {
+                streaming_aux::<U4>(*n, *batch_size);
+            }
+            for n in [1, 8, 9, 64, 65].iter() {
+                streaming_aux::<U8>(*n, *batch_size);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "a Merkle tree must have at least one leaf")]
+    fn streaming_no_leaves() {
+        StreamingTreeBuilder::<Bls12, U2>::new().finalize();
+    }
+}
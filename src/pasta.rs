@@ -0,0 +1,143 @@
+//! The scalar fields of the Pasta curves, Pallas and Vesta, which folding schemes such as Nova and SuperNova cycle
+//! between: the scalar field of each curve is the base field of the other, and both moduli are 255-bit primes.
+//!
+//! `Pallas` and `Vesta` are `ScalarEngine`s over these fields, so that `PoseidonConstants`, `Poseidon`, and the sponge
+//! hash over them natively. Being 255-bit primes with `gcd(5, p - 1) = 1`, as the modulus of BLS12-381, they take the
+//! same round numbers and the `x^5` S-box, and their round constants are generated by the same Grain LFSR, seeded with
+//! the same parameters. The constants of widths 3 and 9 are published in `parameters/`, as written by
+//! `scripts/generate_pasta_parameters.py`, and `PoseidonConstants::from_reference_params` reads them back.
+//!
+//! The circuits of `circuit` require a pairing-friendly `Engine`, which the Pasta curves are not.
+//!
+//! # Example
+//!
+//! Any `ScalarEngine` whose field has a 5th-power S-box can be used in the same way: the round numbers are calculated
+//! for the bit length of its modulus, `PrimeField::NUM_BITS`, rather than for `DEFAULT_PRIME_BITLEN`.
+//!
+//! ```
+//! use generic_array::typenum::U2;
+//! use neptune::pasta::{Pallas, PallasField};
+//! use neptune::poseidon::{Poseidon, PoseidonConstants};
+//! use neptune::{field_round_numbers, scalar_from_u64, Strength};
+//!
+//! let constants = PoseidonConstants::<Pallas, U2>::new();
+//! assert_eq!(
+//!     (constants.full_rounds, constants.partial_rounds),
+//!     field_round_numbers::<PallasField>(2, &Strength::Standard, 128)
+//! );
+//!
+//! let preimage: [PallasField; 2] = [scalar_from_u64(0), scalar_from_u64(1)];
+//! let digest = Poseidon::new_with_preimage(&preimage, &constants).hash();
+//! assert_eq!(
+//!     "PallasField(0x2b7db7fdc2e46d2821dfcd49113949f195ee9daf6d747322af95319a9facd9b2)",
+//!     digest.to_string()
+//! );
+//! ```
+
+use ff::{PrimeField, ScalarEngine};
+
+/// The scalar field of Pallas, which is the base field of Vesta, of modulus
+/// `0x40000000000000000000000000000000224698fc0994a8dd8c46eb2100000001`.
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "28948022309329048855892746252171976963363056481941647379679742748393362948097"]
+#[PrimeFieldGenerator = "5"]
+pub struct PallasField(PallasFieldRepr);
+
+/// The scalar field of Vesta, which is the base field of Pallas, of modulus
+/// `0x40000000000000000000000000000000224698fc094cf91b992d30ed00000001`.
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "28948022309329048855892746252171976963363056481941560715954676764349967630337"]
+#[PrimeFieldGenerator = "5"]
+pub struct VestaField(VestaFieldRepr);
+
+/// The Pallas curve, as a `ScalarEngine` over `PallasField`.
+#[derive(Clone, Copy, Debug)]
+pub struct Pallas;
+
+impl ScalarEngine for Pallas {
+    type Fr = PallasField;
+}
+
+/// The Vesta curve, as a `ScalarEngine` over `VestaField`.
+#[derive(Clone, Copy, Debug)]
+pub struct Vesta;
+
+impl ScalarEngine for Vesta {
+    type Fr = VestaField;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::{Arity, Poseidon, PoseidonConstants};
+    use crate::scalar_from_u64;
+    use crate::Strength;
+    use generic_array::typenum::{Unsigned, U2, U8};
+    use std::fs::File;
+
+    fn reference_constants<E: ScalarEngine, A: Arity<E::Fr>>(
+        modulus: &str,
+    ) -> PoseidonConstants<E, A> {
+        let t = A::to_usize() + 1;
+        let (rf, rp) = if t == 3 { (8, 55) } else { (8, 57) };
+        let path = format!(
+            "parameters/round_constants-1-1-255-{}-{}-{}-{}.txt",
+            t, rf, rp, modulus
+        );
+        PoseidonConstants::from_reference_params(&mut File::open(path).unwrap()).unwrap()
+    }
+
+    fn check_parameters<E: ScalarEngine, A: Arity<E::Fr>>(modulus: &str) {
+        let constants = PoseidonConstants::<E, A>::new();
+        let reference = reference_constants::<E, A>(modulus);
+        assert_eq!(Strength::Standard, reference.strength);
+        assert_eq!(
+            (constants.full_rounds, constants.partial_rounds),
+            (reference.full_rounds, reference.partial_rounds)
+        );
+        assert_eq!(constants.round_constants, reference.round_constants);
+        constants.validate().unwrap();
+    }
+
+    // The hash of `0, 1, ..., arity - 1` with the constants of `PoseidonConstants::new()`.
+    fn hash<E: ScalarEngine, A: Arity<E::Fr>>() -> String {
+        let constants = PoseidonConstants::<E, A>::new();
+        let preimage = (0..A::to_u64())
+            .map(scalar_from_u64::<E::Fr>)
+            .collect::<Vec<_>>();
+        Poseidon::new_with_preimage(&preimage, &constants)
+            .hash()
+            .to_string()
+    }
+
+    #[test]
+    fn pasta_parameters() {
+        let pallas = "40000000000000000000000000000000224698FC0994A8DD8C46EB2100000001";
+        let vesta = "40000000000000000000000000000000224698FC094CF91B992D30ED00000001";
+        check_parameters::<Pallas, U2>(pallas);
+        check_parameters::<Pallas, U8>(pallas);
+        check_parameters::<Vesta, U2>(vesta);
+        check_parameters::<Vesta, U8>(vesta);
+    }
+
+    #[test]
+    fn pasta_hashes() {
+        // Computed by the permutation of `scripts/generate_test_vectors.py` over each modulus.
+        assert_eq!(
+            "PallasField(0x2b7db7fdc2e46d2821dfcd49113949f195ee9daf6d747322af95319a9facd9b2)",
+            hash::<Pallas, U2>()
+        );
+        assert_eq!(
+            "PallasField(0x094c2c517e806619e05a160fc85d410af956142f5ad80cc6c28364143251df45)",
+            hash::<Pallas, U8>()
+        );
+        assert_eq!(
+            "VestaField(0x3c06fe79d4d0c13874bba873163e9ad82e5d60d75b6737867a664cfef49cc6c9)",
+            hash::<Vesta, U2>()
+        );
+        assert_eq!(
+            "VestaField(0x0710097d88720fd35c4eb1662cf10285c3817c7b65e06fd284b1fd6b8565329b)",
+            hash::<Vesta, U8>()
+        );
+    }
+}
@@ -1,18 +1,57 @@
+use crate::error::RoundNumberError;
 use crate::hash_type::HashType;
 use crate::matrix::Matrix;
-use crate::mds::{create_mds_matrices, factor_to_sparse_matrixes, MDSMatrices, SparseMatrix};
+use crate::mds::{
+    apply_sparse_mds, check_mds_matrix, derive_mds_matrices, generate_mds_matrix, MDSMatrices,
+    SparseMatrix,
+};
 use crate::poseidon_alt::{hash_correct, hash_optimized_dynamic};
-use crate::preprocessing::compress_round_constants;
-use crate::{matrix, quintic_s_box, BatchHasher, Strength, DEFAULT_STRENGTH};
-use crate::{round_constants, round_numbers, scalar_from_u64, Error};
+use crate::preprocessing::{optimize_with_mds_matrices, OptimizedConstants};
+use crate::round_numbers::{
+    calc_round_numbers_with_options, check_round_numbers, round_numbers_for_prime,
+    RoundNumberOptions, RoundNumbers, SecurityMargin, DEFAULT_ALPHA, DEFAULT_SECURITY_MARGIN,
+};
+use crate::{
+    alpha_is_coprime, matrix, quintic_s_box, s_box, BatchHasher, Strength, DEFAULT_STRENGTH,
+};
+use crate::{
+    field_round_numbers, field_round_numbers_with_alpha, round_constants, scalar_from_u64, Error,
+    DEFAULT_SECURITY_LEVEL,
+};
+use crate::{Sbox, DEFAULT_SBOX};
 use bellperson::bls::{Bls12, Fr};
 use ff::{Field, PrimeField, ScalarEngine};
 use generic_array::{sequence::GenericSequence, typenum, ArrayLength, GenericArray};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::any::{Any, TypeId};
+use std::collections::{BTreeMap, HashMap};
 use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "ct")]
+use subtle::ConstantTimeEq;
 use typenum::marker_traits::Unsigned;
 use typenum::*;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
-/// Available arities for the Poseidon hasher.
+/// The smallest supported width `t` of the permutation, for an arity of 1.
+pub const MIN_WIDTH: usize = 2;
+
+/// The largest supported width `t` of the permutation, for an arity of 127.
+pub const MAX_WIDTH: usize = 128;
+
+/// Returns `Error::InvalidWidth` unless `t` is within `MIN_WIDTH..=MAX_WIDTH`, the widths for which constants are
+/// generated and hashing is tested.
+pub fn validate_width(t: usize) -> Result<(), Error> {
+    if t < MIN_WIDTH || t > MAX_WIDTH {
+        return Err(Error::InvalidWidth(t));
+    }
+    Ok(())
+}
+
+/// Available arities for the Poseidon hasher, from 1 through 127, i.e. the widths `MIN_WIDTH..=MAX_WIDTH`.
 pub trait Arity<T>: ArrayLength<T> {
     /// Must be Arity + 1.
     type ConstantsSize: ArrayLength<T>;
@@ -26,8 +65,11 @@ macro_rules! impl_arity {
             impl<Fr: PrimeField> Arity<Fr> for $a {
                 type ConstantsSize = $b;
 
+                // 2^arity - 1, computed in the field as it overflows a `u64` from arity 64.
                 fn tag() -> Fr {
-                    scalar_from_u64::<Fr>((1 << <$a as Unsigned>::to_usize()) - 1)
+                    let mut tag = scalar_from_u64::<Fr>(2).pow([<$a as Unsigned>::to_u64()]);
+                    tag.sub_assign(&Fr::one());
+                    tag
                 }
             }
         )*
@@ -44,6 +86,7 @@ impl<Fr: PrimeField> Arity<Fr> for U0 {
 }
 
 impl_arity!(
+    U1 => U2,
     U2 => U3,
     U3 => U4,
     U4 => U5,
@@ -78,10 +121,105 @@ impl_arity!(
     U33 => U34,
     U34 => U35,
     U35 => U36,
-    U36 => U37
+    U36 => U37,
+    U37 => U38,
+    U38 => U39,
+    U39 => U40,
+    U40 => U41,
+    U41 => U42,
+    U42 => U43,
+    U43 => U44,
+    U44 => U45,
+    U45 => U46,
+    U46 => U47,
+    U47 => U48,
+    U48 => U49,
+    U49 => U50,
+    U50 => U51,
+    U51 => U52,
+    U52 => U53,
+    U53 => U54,
+    U54 => U55,
+    U55 => U56,
+    U56 => U57,
+    U57 => U58,
+    U58 => U59,
+    U59 => U60,
+    U60 => U61,
+    U61 => U62,
+    U62 => U63,
+    U63 => U64,
+    U64 => U65,
+    U65 => U66,
+    U66 => U67,
+    U67 => U68,
+    U68 => U69,
+    U69 => U70,
+    U70 => U71,
+    U71 => U72,
+    U72 => U73,
+    U73 => U74,
+    U74 => U75,
+    U75 => U76,
+    U76 => U77,
+    U77 => U78,
+    U78 => U79,
+    U79 => U80,
+    U80 => U81,
+    U81 => U82,
+    U82 => U83,
+    U83 => U84,
+    U84 => U85,
+    U85 => U86,
+    U86 => U87,
+    U87 => U88,
+    U88 => U89,
+    U89 => U90,
+    U90 => U91,
+    U91 => U92,
+    U92 => U93,
+    U93 => U94,
+    U94 => U95,
+    U95 => U96,
+    U96 => U97,
+    U97 => U98,
+    U98 => U99,
+    U99 => U100,
+    U100 => U101,
+    U101 => U102,
+    U102 => U103,
+    U103 => U104,
+    U104 => U105,
+    U105 => U106,
+    U106 => U107,
+    U107 => U108,
+    U108 => U109,
+    U109 => U110,
+    U110 => U111,
+    U111 => U112,
+    U112 => U113,
+    U113 => U114,
+    U114 => U115,
+    U115 => U116,
+    U116 => U117,
+    U117 => U118,
+    U118 => U119,
+    U119 => U120,
+    U120 => U121,
+    U121 => U122,
+    U122 => U123,
+    U123 => U124,
+    U124 => U125,
+    U125 => U126,
+    U126 => U127,
+    U127 => U128
 );
 
 /// The `Poseidon` structure will accept a number of inputs equal to the arity.
+///
+/// The state has the domain tag as its first element, the capacity element, followed by the preimage. The tag is that
+/// of the constants unless given to `new_with_domain_tag` or `new_with_preimage_and_domain_tag`, and is restored by
+/// `reset`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Poseidon<'a, E, A = U2>
 where
@@ -93,22 +231,74 @@ where
     /// the elements to permute
     pub elements: GenericArray<E::Fr, A::ConstantsSize>,
     pos: usize,
-    pub(crate) constants: &'a PoseidonConstants<E, A>,
+    domain_tag: E::Fr,
+    pub(crate) constants: ConstantsRef<'a, E, A>,
     _e: PhantomData<E>,
 }
 
+/// The constants of a `Poseidon`: `Borrowed` for the lifetime of the hasher, or `Shared` through an `Arc`, as
+/// `PoseidonConstants::shared` returns them. Either dereferences to the `PoseidonConstants`.
 #[derive(Debug, Clone, PartialEq)]
+pub enum ConstantsRef<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    Borrowed(&'a PoseidonConstants<E, A>),
+    Shared(Arc<PoseidonConstants<E, A>>),
+}
+
+impl<'a, E, A> Deref for ConstantsRef<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    type Target = PoseidonConstants<E, A>;
+
+    fn deref(&self) -> &PoseidonConstants<E, A> {
+        match self {
+            ConstantsRef::Borrowed(constants) => *constants,
+            ConstantsRef::Shared(constants) => &**constants,
+        }
+    }
+}
+
+impl<'a, E, A> From<&'a PoseidonConstants<E, A>> for ConstantsRef<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn from(constants: &'a PoseidonConstants<E, A>) -> Self {
+        ConstantsRef::Borrowed(constants)
+    }
+}
+
+impl<'a, E, A> From<Arc<PoseidonConstants<E, A>>> for ConstantsRef<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn from(constants: Arc<PoseidonConstants<E, A>>) -> Self {
+        ConstantsRef::Shared(constants)
+    }
+}
+
+/// `Display` and `Debug` format a one-line summary of the constants, see `summary`.
+#[derive(Clone, PartialEq)]
 pub struct PoseidonConstants<E, A>
 where
     E: ScalarEngine,
     A: Arity<E::Fr>,
 {
-    pub mds_matrices: MDSMatrices<E>,
+    // The matrices and preprocessed constants are shared between clones, as they are large and never modified.
+    pub mds_matrices: Arc<MDSMatrices<E>>,
     pub round_constants: Vec<E::Fr>,
-    pub compressed_round_constants: Vec<E::Fr>,
-    pub pre_sparse_matrix: Matrix<E::Fr>,
-    pub sparse_matrixes: Vec<SparseMatrix<E>>,
+    pub compressed_round_constants: Arc<Vec<E::Fr>>,
+    pub pre_sparse_matrix: Arc<Matrix<E::Fr>>,
+    pub sparse_matrixes: Arc<Vec<SparseMatrix<E>>>,
     pub strength: Strength,
+    /// The S-box applied in each round.
+    pub sbox: Sbox,
     /// The domain tag is the first element of a Poseidon permutation.
     /// This extra element is necessary for 128-bit security.
     pub domain_tag: E::Fr,
@@ -116,7 +306,13 @@ where
     pub half_full_rounds: usize,
     pub partial_rounds: usize,
     pub hash_type: HashType<E::Fr, A>,
-    _a: PhantomData<A>,
+    /// The seed mixed into the Grain LFSR generating the round constants, if any. See `new_seeded`.
+    pub seed: Option<Vec<u8>>,
+    /// The security level in bits the round numbers were chosen for, against which they are checked when loaded.
+    pub security_level: usize,
+    /// The margin added to the secure round numbers.
+    pub security_margin: SecurityMargin,
+    pub(crate) _a: PhantomData<A>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -128,6 +324,11 @@ pub enum HashMode {
     // algorithm, if not the code implementing. Its purpose is for use during refactoring/development.
     OptimizedDynamic,
     // Consumes statically pre-processed constants for simplest operation.
+    //
+    // This is the optimized permutation of Appendix B of the Poseidon paper, with the round constants and MDS matrices
+    // derived by `preprocessing::optimize`: after the first round, a single constant is added in each partial round,
+    // and each partial round multiplies by a sparse matrix. `hash_values` checks that it hashes bit for bit as
+    // `Correct` does.
     OptimizedStatic,
 }
 use HashMode::{Correct, OptimizedDynamic, OptimizedStatic};
@@ -143,6 +344,40 @@ where
         Self::new_with_strength(DEFAULT_STRENGTH)
     }
 
+    /// `try_new` creates constants as `new` does, but returns an error rather than panicking: `Error::InvalidWidth`
+    /// unless the width `t = arity + 1` is supported, and otherwise any error calculating the round numbers for `t`,
+    /// or `Error::InvalidConstants` unless the round numbers, round constants, and MDS matrix agree on `t`, as checked
+    /// by `validate`.
+    pub fn try_new() -> Result<Self, Error> {
+        let constants = Self::try_new_with_strength(DEFAULT_STRENGTH)?;
+        constants.validate()?;
+        Ok(constants)
+    }
+
+    /// `try_new_with_strength` creates constants as `new_with_strength` does, but returns `Error::InvalidWidth` unless
+    /// the width is supported, and `Error::RoundNumberError` if no secure round numbers are found or those of
+    /// `Strength::Custom` are not secure, rather than panicking.
+    pub fn try_new_with_strength(strength: Strength) -> Result<Self, Error> {
+        let arity = A::to_usize();
+        validate_width(arity + 1)?;
+        let (full_rounds, partial_rounds) = round_numbers_for_prime(
+            arity,
+            &strength,
+            E::Fr::NUM_BITS as usize,
+            DEFAULT_SECURITY_LEVEL,
+            DEFAULT_ALPHA,
+            DEFAULT_SECURITY_MARGIN,
+        )?;
+        Ok(Self::new_with_round_numbers(
+            strength,
+            HashType::MerkleTree,
+            DEFAULT_SBOX,
+            full_rounds,
+            partial_rounds,
+            None,
+        ))
+    }
+
     /// `new_constant_length` creates constants for hashing a constant-sized preimage which is <= the max
     /// supported by the permutation width.
     pub fn new_constant_length(length: usize) -> Self {
@@ -169,55 +404,343 @@ where
         }
     }
 
+    /// `with_domain_tag` returns these constants with `domain_tag` as the initial capacity element in place of the
+    /// tag of their `HashType`, separating hashes made for different purposes with otherwise identical constants.
+    /// The hash type, and with it the padding of the preimage, is unchanged.
+    pub fn with_domain_tag(&self, domain_tag: E::Fr) -> Self {
+        Self {
+            domain_tag,
+            ..self.clone()
+        }
+    }
+
     pub fn new_with_strength(strength: Strength) -> Self {
         Self::new_with_strength_and_type(strength, HashType::MerkleTree)
     }
 
-    pub fn new_with_strength_and_type(strength: Strength, hash_type: HashType<E::Fr, A>) -> Self {
-        assert!(hash_type.is_supported());
-        let arity = A::to_usize();
-        let width = arity + 1;
+    /// `new_with_strength_unchecked` creates constants for `strength` like `new_with_strength`, but uses the round
+    /// numbers of `Strength::Custom` whether or not they are secure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the round numbers of `Strength::Custom` are not an even, non-zero number of full rounds and at least
+    /// one partial round.
+    pub fn new_with_strength_unchecked(strength: Strength) -> Self {
+        match strength {
+            Strength::Custom { full, partial } => {
+                assert!(
+                    full > 0 && full % 2 == 0,
+                    "full rounds must be even and non-zero: {}",
+                    full
+                );
+                assert!(partial > 0, "partial rounds must be non-zero");
+                Self::new_with_round_numbers(
+                    strength,
+                    HashType::MerkleTree,
+                    DEFAULT_SBOX,
+                    full,
+                    partial,
+                    None,
+                )
+            }
+            _ => Self::new_with_strength(strength),
+        }
+    }
+
+    /// `new_with_security_level` creates constants whose round numbers target a security level of
+    /// `security_level` bits, rather than the default of 128 bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `security_level` is not within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`.
+    pub fn new_with_security_level(security_level: usize) -> Self {
+        let (full_rounds, partial_rounds) =
+            field_round_numbers::<E::Fr>(A::to_usize(), &DEFAULT_STRENGTH, security_level);
+        Self {
+            security_level,
+            ..Self::new_with_round_numbers(
+                DEFAULT_STRENGTH,
+                HashType::MerkleTree,
+                DEFAULT_SBOX,
+                full_rounds,
+                partial_rounds,
+                None,
+            )
+        }
+    }
 
-        let mds_matrices = create_mds_matrices::<E>(width);
+    /// `new_with_security_margin` creates constants whose round numbers include `margin`, rather than the default
+    /// margin of two full rounds and 7.5% more partial rounds. See `round_numbers::SecurityMargin`.
+    pub fn new_with_security_margin(margin: SecurityMargin) -> Self {
+        let (full_rounds, partial_rounds) = round_numbers_for_prime(
+            A::to_usize(),
+            &DEFAULT_STRENGTH,
+            E::Fr::NUM_BITS as usize,
+            DEFAULT_SECURITY_LEVEL,
+            DEFAULT_ALPHA,
+            margin,
+        )
+        .unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            security_margin: margin,
+            ..Self::new_with_round_numbers(
+                DEFAULT_STRENGTH,
+                HashType::MerkleTree,
+                DEFAULT_SBOX,
+                full_rounds,
+                partial_rounds,
+                None,
+            )
+        }
+    }
 
-        let (full_rounds, partial_rounds) = round_numbers(arity, &strength);
-        let half_full_rounds = full_rounds / 2;
-        let round_constants = round_constants::<E>(arity, &strength);
-        let compressed_round_constants = compress_round_constants::<E>(
-            width,
+    /// `new_with_even_partial_rounds` creates constants with an even number of partial rounds, the cheapest secure
+    /// round numbers for which partial rounds can be computed two at a time, as GPU kernels do. See
+    /// `round_numbers::RoundNumberOptions::even_partial_rounds`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no secure round numbers are found for the width.
+    pub fn new_with_even_partial_rounds() -> Self {
+        let options = RoundNumberOptions {
+            prime_bitlen: E::Fr::NUM_BITS as usize,
+            even_partial_rounds: true,
+            ..RoundNumberOptions::default()
+        };
+        let (full_rounds, partial_rounds) =
+            calc_round_numbers_with_options(A::ConstantsSize::to_usize(), &options)
+                .unwrap_or_else(|e| panic!("{}", e));
+        Self::new_with_round_numbers(
+            DEFAULT_STRENGTH,
+            HashType::MerkleTree,
+            DEFAULT_SBOX,
             full_rounds,
             partial_rounds,
-            &round_constants,
-            &mds_matrices,
+            None,
+        )
+    }
+
+    /// # Panics
+    ///
+    /// Panics if no secure round numbers are found for the width, see `round_numbers::calc_round_numbers`, or if the
+    /// round numbers of `Strength::Custom` have an odd number of full rounds or fail the security inequalities at the
+    /// default security level for the field.
+    pub fn new_with_strength_and_type(strength: Strength, hash_type: HashType<E::Fr, A>) -> Self {
+        let (full_rounds, partial_rounds) =
+            field_round_numbers::<E::Fr>(A::to_usize(), &strength, DEFAULT_SECURITY_LEVEL);
+        Self::new_with_round_numbers(
+            strength,
+            hash_type,
+            DEFAULT_SBOX,
+            full_rounds,
             partial_rounds,
-        );
+            None,
+        )
+    }
+
+    /// `new_with_sbox` creates constants for a permutation applying `sbox` rather than the default quintic S-box,
+    /// with round numbers calculated for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sbox` is not a permutation of the field, e.g. `Sbox::Cubic` for BLS12-381.
+    pub fn new_with_sbox(sbox: Sbox) -> Self {
+        let (full_rounds, partial_rounds) = field_round_numbers_with_alpha::<E::Fr>(
+            A::to_usize(),
+            &DEFAULT_STRENGTH,
+            DEFAULT_SECURITY_LEVEL,
+            sbox.alpha(),
+        )
+        .unwrap_or_else(|e| match e {
+            Error::InvalidAlpha(_) => panic!("{:?} S-box is not a permutation of the field", sbox),
+            e => panic!("{}", e),
+        });
+        Self::new_with_round_numbers(
+            DEFAULT_STRENGTH,
+            HashType::MerkleTree,
+            sbox,
+            full_rounds,
+            partial_rounds,
+            None,
+        )
+    }
+
+    /// `new_with_rounds` creates constants with the given round numbers, rather than calculating them, such as those of
+    /// another implementation. See `round_numbers::check_rounds_are_secure`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `full_rounds` is odd, or if the round numbers fail the security inequalities at the default security
+    /// level for the field, naming the inequality that fails. Use `new_with_rounds_unchecked` to skip the check.
+    pub fn new_with_rounds(full_rounds: usize, partial_rounds: usize) -> Self {
+        Self::try_new_with_rounds(full_rounds, partial_rounds).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let (pre_sparse_matrix, sparse_matrixes) =
-            factor_to_sparse_matrixes::<E>(mds_matrices.m.clone(), partial_rounds);
+    /// `try_new_with_rounds` creates constants as `new_with_rounds` does, but returns an error rather than panicking:
+    /// `Error::InvalidWidth` unless the width is supported, and `Error::RoundNumberError` if `full_rounds` is odd or
+    /// the round numbers fail the security inequalities, naming the inequality that fails.
+    pub fn try_new_with_rounds(full_rounds: usize, partial_rounds: usize) -> Result<Self, Error> {
+        validate_width(A::ConstantsSize::to_usize())?;
+        if full_rounds % 2 != 0 {
+            return Err(RoundNumberError::OddFullRounds { rf: full_rounds }.into());
+        }
+        check_round_numbers(
+            A::ConstantsSize::to_usize(),
+            full_rounds,
+            partial_rounds,
+            &DEFAULT_STRENGTH,
+            E::Fr::NUM_BITS as usize,
+            DEFAULT_SECURITY_LEVEL,
+            DEFAULT_ALPHA,
+        )?;
+        Ok(Self::new_with_rounds_unchecked(full_rounds, partial_rounds))
+    }
 
-        // Ensure we have enough constants for the sbox rounds
+    /// `new_with_rounds_unchecked` creates constants with the given round numbers, whether or not they are secure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `full_rounds` is odd.
+    pub fn new_with_rounds_unchecked(full_rounds: usize, partial_rounds: usize) -> Self {
         assert!(
-            width * (full_rounds + partial_rounds) <= round_constants.len(),
-            "Not enough round constants"
+            full_rounds % 2 == 0,
+            "full rounds must be even: {}",
+            full_rounds
         );
+        Self::new_with_round_numbers(
+            DEFAULT_STRENGTH,
+            HashType::MerkleTree,
+            DEFAULT_SBOX,
+            full_rounds,
+            partial_rounds,
+            None,
+        )
+    }
 
-        assert_eq!(
-            full_rounds * width + partial_rounds,
-            compressed_round_constants.len()
-        );
+    /// `new_seeded` creates constants as `new` does, but with round constants generated by a Grain LFSR into which
+    /// `seed` is mixed, see `grain::GrainLfsr::new_seeded`. Protocols choosing different seeds, such as
+    /// `b"my-protocol-v1"`, hash with independent round constants, while the MDS matrix and round numbers are those
+    /// of `new`.
+    ///
+    /// The seed is kept in the constants, so that constants of different seeds are not equal, and is written by
+    /// `write_bytes` and serde.
+    pub fn new_seeded(seed: &[u8]) -> Self {
+        let (full_rounds, partial_rounds) =
+            field_round_numbers::<E::Fr>(A::to_usize(), &DEFAULT_STRENGTH, DEFAULT_SECURITY_LEVEL);
+        Self::new_with_round_numbers(
+            DEFAULT_STRENGTH,
+            HashType::MerkleTree,
+            DEFAULT_SBOX,
+            full_rounds,
+            partial_rounds,
+            Some(seed.to_vec()),
+        )
+    }
 
-        Self {
-            mds_matrices,
+    /// `new_with_mds` creates constants as `new` does, but with `mds_matrix` in place of the generated Cauchy matrix,
+    /// such as the circulant matrix of another implementation. The matrix multiplies the state as a column vector, as
+    /// in the reference implementation, so `mds_matrices.m` holds its transpose, by which the permutation multiplies
+    /// the state as a row vector. The optimized constants, and so `OptimizedStatic` hashing and the circuit, are
+    /// derived from it.
+    ///
+    /// The matrix is rejected with `Error::InvalidConstants`, naming the failed check, unless it passes
+    /// `check_mds_matrix`: it must be MDS, and admit no infinitely long subspace trail through the partial rounds, by
+    /// itself or by its powers.
+    pub fn new_with_mds(mds_matrix: Matrix<E::Fr>) -> Result<Self, Error> {
+        let arity = A::to_usize();
+        let width = arity + 1;
+        validate_width(width)?;
+        // The checks are of the matrix as the permutation applies it.
+        let mds_matrix = matrix::transpose::<E>(&mds_matrix);
+        check_mds_matrix::<E>(&mds_matrix, width)?;
+
+        let (full_rounds, partial_rounds) =
+            field_round_numbers::<E::Fr>(arity, &DEFAULT_STRENGTH, DEFAULT_SECURITY_LEVEL);
+        let round_constants =
+            round_constants::<E>(arity, full_rounds, partial_rounds, DEFAULT_SBOX, None);
+
+        Ok(Self::from_parts(
+            DEFAULT_STRENGTH,
+            HashType::MerkleTree,
+            DEFAULT_SBOX,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds_matrix,
+            None,
+        ))
+    }
+
+    pub(crate) fn new_with_round_numbers(
+        strength: Strength,
+        hash_type: HashType<E::Fr, A>,
+        sbox: Sbox,
+        full_rounds: usize,
+        partial_rounds: usize,
+        seed: Option<Vec<u8>>,
+    ) -> Self {
+        assert!(hash_type.is_supported());
+        let arity = A::to_usize();
+        let width = arity + 1;
+        validate_width(width).unwrap_or_else(|e| panic!("{}", e));
+
+        let mds_matrix = generate_mds_matrix::<E>(width);
+        let round_constants =
+            round_constants::<E>(arity, full_rounds, partial_rounds, sbox, seed.as_deref());
+
+        Self::from_parts(
+            strength,
+            hash_type,
+            sbox,
+            full_rounds,
+            partial_rounds,
             round_constants,
+            mds_matrix,
+            seed,
+        )
+    }
+
+    // Derives the optimized constants from the round constants and MDS matrix, whether generated or deserialized.
+    pub(crate) fn from_parts(
+        strength: Strength,
+        hash_type: HashType<E::Fr, A>,
+        sbox: Sbox,
+        full_rounds: usize,
+        partial_rounds: usize,
+        round_constants: Vec<E::Fr>,
+        mds_matrix: Matrix<E::Fr>,
+        seed: Option<Vec<u8>>,
+    ) -> Self {
+        let mds_matrices = derive_mds_matrices::<E>(mds_matrix);
+
+        let half_full_rounds = full_rounds / 2;
+        let OptimizedConstants {
             compressed_round_constants,
             pre_sparse_matrix,
             sparse_matrixes,
+        } = optimize_with_mds_matrices(
+            &mds_matrices,
+            &round_constants,
+            full_rounds,
+            partial_rounds,
+        );
+
+        Self {
+            mds_matrices: Arc::new(mds_matrices),
+            round_constants,
+            compressed_round_constants: Arc::new(compressed_round_constants),
+            pre_sparse_matrix: Arc::new(pre_sparse_matrix),
+            sparse_matrixes: Arc::new(sparse_matrixes),
             strength,
+            sbox,
             domain_tag: hash_type.domain_tag(&strength),
             full_rounds,
             half_full_rounds,
             partial_rounds,
             hash_type,
+            seed,
+            security_level: DEFAULT_SECURITY_LEVEL,
+            security_margin: DEFAULT_SECURITY_MARGIN,
             _a: PhantomData::<A>,
         }
     }
@@ -233,6 +756,159 @@ where
     pub fn width(&self) -> usize {
         A::ConstantsSize::to_usize()
     }
+
+    /// Returns the round numbers used by these constants. The size cost is computed using the bit length of the
+    /// field's modulus.
+    pub fn round_numbers(&self) -> RoundNumbers {
+        RoundNumbers::from_rounds(
+            self.width(),
+            self.full_rounds,
+            self.partial_rounds,
+            E::Fr::NUM_BITS as usize,
+        )
+    }
+
+    /// `validate` checks that the parts of these constants agree on the width `t = arity + 1`. As the fields are
+    /// public, constants may be assembled from parts of different arities, such as the round numbers of arity 8 with
+    /// the MDS matrix of arity 4, which would otherwise hash without complaint, or panic while hashing.
+    ///
+    /// Returns `Error::InvalidWidth` unless `t` is supported, and `Error::InvalidConstants` naming the first part of
+    /// the wrong size: the MDS matrices, the round constants for the round numbers, the compressed round constants, or
+    /// the matrices of the partial rounds.
+    pub fn validate(&self) -> Result<(), Error> {
+        let width = self.width();
+        validate_width(width)?;
+        let invalid = |message: String| Err(Error::InvalidConstants(message));
+
+        if self.full_rounds % 2 != 0 || self.half_full_rounds * 2 != self.full_rounds {
+            return invalid(format!(
+                "full rounds {} must be even and twice the half full rounds {}",
+                self.full_rounds, self.half_full_rounds
+            ));
+        }
+
+        let mds = &self.mds_matrices;
+        let matrices = [
+            ("MDS matrix", &mds.m, width),
+            ("inverse MDS matrix", &mds.m_inv, width),
+            ("minor of the MDS matrix", &mds.m_hat, width - 1),
+            ("inverse minor of the MDS matrix", &mds.m_hat_inv, width - 1),
+            ("MDS matrix M'", &mds.m_prime, width),
+            ("MDS matrix M''", &mds.m_double_prime, width),
+            ("pre-sparse matrix", &*self.pre_sparse_matrix, width),
+        ];
+        for (name, matrix, size) in matrices.iter() {
+            if matrix.len() != *size || matrix.iter().any(|row| row.len() != *size) {
+                return invalid(format!(
+                    "{} must be {} by {} for width {}",
+                    name, size, size, width
+                ));
+            }
+        }
+
+        let rounds = self.full_rounds + self.partial_rounds;
+        if self.round_constants.len() != width * rounds {
+            return invalid(format!(
+                "expected {} round constants for width {} and {} rounds, found {}",
+                width * rounds,
+                width,
+                rounds,
+                self.round_constants.len()
+            ));
+        }
+        let compressed = width * self.full_rounds + self.partial_rounds;
+        if self.compressed_round_constants.len() != compressed {
+            return invalid(format!(
+                "expected {} compressed round constants for width {}, found {}",
+                compressed,
+                width,
+                self.compressed_round_constants.len()
+            ));
+        }
+
+        if self.sparse_matrixes.len() != self.partial_rounds {
+            return invalid(format!(
+                "expected a sparse matrix for each of the {} partial rounds, found {}",
+                self.partial_rounds,
+                self.sparse_matrixes.len()
+            ));
+        }
+        if self
+            .sparse_matrixes
+            .iter()
+            .any(|sparse| sparse.w_hat.len() != width || sparse.v_rest.len() != width - 1)
+        {
+            return invalid(format!(
+                "sparse matrices must have {} elements in the first column and {} in the rest of the first row",
+                width,
+                width - 1
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// The type of shared constants, and the identifier of their parameters: that of their strength for
+// `PoseidonConstants::shared`, or another given to `PoseidonConstants::shared_by`.
+type SharedConstantsKey = (TypeId, u8);
+
+// An entry of `SHARED_CONSTANTS`, holding the constants of its key once created.
+type SharedConstantsEntry = Arc<Mutex<Option<Arc<dyn Any + Send + Sync>>>>;
+
+lazy_static! {
+    static ref SHARED_CONSTANTS: Mutex<HashMap<SharedConstantsKey, SharedConstantsEntry>> =
+        Mutex::new(HashMap::new());
+}
+
+impl<E, A> PoseidonConstants<E, A>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync + 'static,
+{
+    /// Returns the constants of `new_with_strength(strength)`, created once per process and shared by every caller
+    /// for the same field, arity, and strength, so that components hashing with them hold a single copy of their
+    /// matrices. `Poseidon::new` accepts the `Arc` as well as a reference.
+    ///
+    /// The cache is thread-safe: it is only locked to find the entry of the constants, and concurrent callers wait for
+    /// a single creation of the same constants. Only `Strength::Standard` and `Strength::Strengthened` are cached, so
+    /// the cache holds at most two constants per field and arity; constants of `Strength::Custom` are created anew.
+    ///
+    /// # Panics
+    ///
+    /// Panics as `new_with_strength` does.
+    pub fn shared(strength: Strength) -> Arc<Self> {
+        let strength_id = match strength {
+            Strength::Standard => 0,
+            Strength::Strengthened => 1,
+            Strength::Custom { .. } => return Arc::new(Self::new_with_strength(strength)),
+        };
+        Self::shared_by(strength_id, || Self::new_with_strength(strength))
+    }
+
+    // Returns the constants of this type cached under `id`, created by `create` once per process, so that constants
+    // other than those of a strength are shared through the same cache. The identifiers 0 and 1 are those of the
+    // strengths cached by `shared`.
+    pub(crate) fn shared_by<F: FnOnce() -> Self>(id: u8, create: F) -> Arc<Self> {
+        let entry = SHARED_CONSTANTS
+            .lock()
+            .unwrap()
+            .entry((TypeId::of::<Self>(), id))
+            .or_default()
+            .clone();
+
+        let mut constants = entry.lock().unwrap();
+        if constants.is_none() {
+            let created: Arc<dyn Any + Send + Sync> = Arc::new(create());
+            *constants = Some(created);
+        }
+
+        constants
+            .clone()
+            .unwrap()
+            .downcast::<Self>()
+            .expect("shared constants are keyed by their type")
+    }
 }
 
 impl<'a, E, A> Poseidon<'a, E, A>
@@ -240,32 +916,85 @@ where
     E: ScalarEngine,
     A: Arity<E::Fr>,
 {
-    pub fn new(constants: &'a PoseidonConstants<E, A>) -> Self {
-        let elements = GenericArray::generate(|i| {
-            if i == 0 {
-                constants.domain_tag
-            } else {
-                E::Fr::zero()
-            }
-        });
+    /// `new` creates a hasher with the domain tag of `constants`, which are either borrowed, as
+    /// `&PoseidonConstants`, or shared, as the `Arc<PoseidonConstants>` of `PoseidonConstants::shared`, so that a
+    /// long-lived hasher need not borrow them from a local. See `ConstantsRef`.
+    pub fn new<C>(constants: C) -> Self
+    where
+        C: Into<ConstantsRef<'a, E, A>>,
+    {
+        let constants = constants.into();
+        let domain_tag = constants.domain_tag;
+        Self::with_constants(constants, domain_tag)
+    }
+
+    /// `new_with_domain_tag` creates a hasher whose first element, the capacity element, is `domain_tag` rather than
+    /// the domain tag of `constants`, so that protocols hashing the same preimages with the same constants produce
+    /// different digests. The preimage is absorbed into the following elements, so every element of the arity remains
+    /// available to it. See `PoseidonConstants::with_domain_tag` to change the tag of the constants instead, as the
+    /// circuit and GPU hashers read it from there.
+    pub fn new_with_domain_tag(constants: &'a PoseidonConstants<E, A>, domain_tag: E::Fr) -> Self {
+        Self::with_constants(ConstantsRef::Borrowed(constants), domain_tag)
+    }
+
+    fn with_constants(constants: ConstantsRef<'a, E, A>, domain_tag: E::Fr) -> Self {
+        let elements = GenericArray::generate(|i| if i == 0 { domain_tag } else { E::Fr::zero() });
         Poseidon {
             constants_offset: 0,
             current_round: 0,
             elements,
             pos: 1,
+            domain_tag,
             constants,
             _e: PhantomData::<E>,
         }
     }
 
     pub fn new_with_preimage(preimage: &[E::Fr], constants: &'a PoseidonConstants<E, A>) -> Self {
+        Self::new_with_preimage_and_domain_tag(preimage, constants.domain_tag, constants)
+    }
+
+    /// `new_with_preimage_and_domain_tag` creates a hasher of `preimage`, as `new_with_preimage` does, with
+    /// `domain_tag` as the first element. See `new_with_domain_tag`.
+    pub fn new_with_preimage_and_domain_tag(
+        preimage: &[E::Fr],
+        domain_tag: E::Fr,
+        constants: &'a PoseidonConstants<E, A>,
+    ) -> Self {
+        Self::try_new_with_preimage_and_domain_tag(preimage, domain_tag, constants)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// `try_new_with_preimage` creates a hasher of `preimage` as `new_with_preimage` does, but returns
+    /// `Error::InvalidPreimageSize` rather than panicking unless `preimage` has the length hashed by the constants: the
+    /// arity, or `length` for `HashType::ConstantLength(length)`.
+    pub fn try_new_with_preimage(
+        preimage: &[E::Fr],
+        constants: &'a PoseidonConstants<E, A>,
+    ) -> Result<Self, Error> {
+        Self::try_new_with_preimage_and_domain_tag(preimage, constants.domain_tag, constants)
+    }
+
+    /// `try_new_with_preimage_and_domain_tag` creates a hasher of `preimage` with `domain_tag` as the first element,
+    /// as `new_with_preimage_and_domain_tag` does, returning errors as `try_new_with_preimage` does.
+    pub fn try_new_with_preimage_and_domain_tag(
+        preimage: &[E::Fr],
+        domain_tag: E::Fr,
+        constants: &'a PoseidonConstants<E, A>,
+    ) -> Result<Self, Error> {
+        let invalid_size = |expected: usize| Error::InvalidPreimageSize {
+            expected,
+            found: preimage.len(),
+        };
         let elements = match constants.hash_type {
             HashType::ConstantLength(constant_len) => {
-                assert_eq!(constant_len, preimage.len(), "Invalid preimage size");
+                if constant_len != preimage.len() {
+                    return Err(invalid_size(constant_len));
+                }
 
                 GenericArray::generate(|i| {
                     if i == 0 {
-                        constants.domain_tag
+                        domain_tag
                     } else if i > preimage.len() {
                         E::Fr::zero()
                     } else {
@@ -273,42 +1002,57 @@ where
                     }
                 })
             }
-            HashType::VariableLength => panic!("variable-length hashes are not yet supported."),
+            HashType::VariableLength => {
+                return Err(Error::Other(
+                    "variable-length hashes are not yet supported.".to_string(),
+                ))
+            }
             _ => {
-                assert_eq!(preimage.len(), A::to_usize(), "Invalid preimage size");
+                if preimage.len() != A::to_usize() {
+                    return Err(invalid_size(A::to_usize()));
+                }
 
-                GenericArray::generate(|i| {
-                    if i == 0 {
-                        constants.domain_tag
-                    } else {
-                        preimage[i - 1]
-                    }
-                })
+                GenericArray::generate(|i| if i == 0 { domain_tag } else { preimage[i - 1] })
             }
         };
         let width = preimage.len();
 
-        Poseidon {
+        Ok(Poseidon {
             constants_offset: 0,
             current_round: 0,
             elements,
-            pos: width,
-            constants,
+            // The domain tag is always present, so `pos` counts it along with the preimage, as after `input`.
+            pos: width + 1,
+            domain_tag,
+            constants: ConstantsRef::Borrowed(constants),
             _e: PhantomData::<E>,
-        }
+        })
     }
 
     /// Replace the elements with the provided optional items.
     ///
     /// # Panics
     ///
-    /// Panics if the provided slice is bigger than the arity.
+    /// Panics if the length of the provided slice is not the arity. See `try_set_preimage`.
     pub fn set_preimage(&mut self, preimage: &[E::Fr]) {
         self.reset();
         self.elements[1..].copy_from_slice(&preimage);
         self.pos = self.elements.len();
     }
 
+    /// Replaces the elements as `set_preimage` does, but returns `Error::InvalidPreimageSize`, leaving the hasher
+    /// unchanged, rather than panicking if the length of `preimage` is not the arity.
+    pub fn try_set_preimage(&mut self, preimage: &[E::Fr]) -> Result<(), Error> {
+        if preimage.len() != A::to_usize() {
+            return Err(Error::InvalidPreimageSize {
+                expected: A::to_usize(),
+                found: preimage.len(),
+            });
+        }
+        self.set_preimage(preimage);
+        Ok(())
+    }
+
     /// Restore the initial state
     pub fn reset(&mut self) {
         self.constants_offset = 0;
@@ -316,10 +1060,19 @@ where
         self.elements[1..]
             .iter_mut()
             .for_each(|l| *l = scalar_from_u64::<E::Fr>(0u64));
-        self.elements[0] = self.constants.domain_tag;
+        self.elements[0] = self.domain_tag;
         self.pos = 1;
     }
 
+    /// Overwrites the elements with zeros, by writes the compiler cannot remove, then restores the initial state as
+    /// `reset` does, so that a hasher reused for secret inputs does not keep the last of them until the next preimage
+    /// is set. The domain tag, part of the parameters of the hasher, is kept: `Zeroize::zeroize`, with the `zeroize`
+    /// feature, overwrites it too.
+    pub fn wipe(&mut self) {
+        crate::wipe::wipe(&mut self.elements, E::Fr::zero());
+        self.reset();
+    }
+
     /// The returned `usize` represents the element position (within arity) for the input operation
     pub fn input(&mut self, element: E::Fr) -> Result<usize, Error> {
         // Cannot input more elements than the defined arity
@@ -334,6 +1087,44 @@ where
         Ok(self.pos - 1)
     }
 
+    /// Absorbs `element` into the next free position of the preimage, for preimages whose elements arrive one at a
+    /// time.
+    ///
+    /// Returns `Error::FullBuffer` once the preimage is complete, i.e. holds `arity` elements, or `length` for a
+    /// `HashType::ConstantLength(length)` hash. It must then be squeezed with `squeeze` before absorbing more.
+    pub fn absorb_one(&mut self, element: E::Fr) -> Result<(), Error> {
+        let max_len = match self.constants.hash_type {
+            HashType::ConstantLength(l) => l,
+            _ => self.constants.arity(),
+        };
+        if self.pos > max_len {
+            return Err(Error::FullBuffer);
+        }
+
+        self.input(element).map(|_| ())
+    }
+
+    /// Hashes the elements absorbed so far and returns the digest, starting a new preimage whose first element is
+    /// that digest. This chains the hashes of successive chunks, so streams of any length can be absorbed.
+    pub fn squeeze(&mut self) -> E::Fr {
+        let digest = self.hash();
+        self.reset();
+        self.elements[1] = digest;
+        self.pos = 2;
+
+        digest
+    }
+
+    /// Consumes the hasher and returns the hash of the elements absorbed since the last `squeeze`, padded as by
+    /// `hash`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer than `length` elements were absorbed for a `HashType::ConstantLength(length)` hash.
+    pub fn finish(mut self) -> E::Fr {
+        self.hash()
+    }
+
     pub fn hash_in_mode(&mut self, mode: HashMode) -> E::Fr {
         self.apply_padding();
         match mode {
@@ -343,15 +1134,32 @@ where
         }
     }
 
+    /// Hashes the absorbed preimage in the default mode, `OptimizedStatic`.
+    ///
+    /// Hashing does not allocate on the heap: the state is a `GenericArray` and the constants are only read. Only
+    /// creating the `PoseidonConstants` allocates, so hashers for a fixed arity can reuse constants created once.
     pub fn hash(&mut self) -> E::Fr {
         self.hash_in_mode(DEFAULT_HASH_MODE)
     }
 
+    /// Applies the permutation to `elements`, without padding, and restores the round state so that the permutation
+    /// can be applied again.
+    pub(crate) fn permute(&mut self) {
+        match DEFAULT_HASH_MODE {
+            Correct => hash_correct(self),
+            OptimizedDynamic => hash_optimized_dynamic(self),
+            OptimizedStatic => self.hash_optimized_static(),
+        };
+        self.constants_offset = 0;
+        self.current_round = 0;
+    }
+
     fn apply_padding(&mut self) {
         match self.constants.hash_type {
             HashType::ConstantLength(l) => {
                 assert_eq!(
-                    self.pos, l,
+                    self.pos - 1,
+                    l,
                     "preimage length does not match constant length required for hash"
                 );
                 // There is nothing to do here, but only because the state elements were
@@ -409,6 +1217,7 @@ where
                 needed
             );
         }
+        let sbox = self.constants.sbox;
         self.elements
             .iter_mut()
             .zip(post_round_keys)
@@ -419,13 +1228,13 @@ where
                 } else {
                     Some(post)
                 };
-                quintic_s_box::<E>(l, None, post_key);
+                s_box::<E>(sbox, l, None, post_key);
             });
         // We need this because post_round_keys will have been empty, so it didn't happen in the for_each. :(
         if last_round {
             self.elements
                 .iter_mut()
-                .for_each(|l| quintic_s_box::<E>(l, None, None));
+                .for_each(|l| s_box::<E>(sbox, l, None, None));
         } else {
             self.constants_offset += self.elements.len();
         }
@@ -436,8 +1245,13 @@ where
     fn partial_round(&mut self) {
         let post_round_key = self.constants.compressed_round_constants[self.constants_offset];
 
-        // Apply the quintic S-Box to the first element
-        quintic_s_box::<E>(&mut self.elements[0], None, Some(&post_round_key));
+        // Apply the S-Box to the first element
+        s_box::<E>(
+            self.constants.sbox,
+            &mut self.elements[0],
+            None,
+            Some(&post_round_key),
+        );
         self.constants_offset += 1;
 
         self.round_product_mds();
@@ -461,7 +1275,7 @@ where
         let full_half = self.constants.half_full_rounds;
         let sparse_offset = full_half - 1;
         if self.current_round == sparse_offset {
-            self.product_mds_with_matrix(&self.constants.pre_sparse_matrix);
+            Self::product_with_matrix(&mut self.elements, &self.constants.pre_sparse_matrix);
         } else {
             if (self.current_round > sparse_offset)
                 && (self.current_round < full_half + self.constants.partial_rounds)
@@ -469,7 +1283,8 @@ where
                 let index = self.current_round - sparse_offset - 1;
                 let sparse_matrix = &self.constants.sparse_matrixes[index];
 
-                self.product_mds_with_sparse_matrix(&sparse_matrix);
+                // Sparse matrix in this context means one of the form, M''.
+                apply_sparse_mds::<E>(sparse_matrix, &mut self.elements);
             } else {
                 self.product_mds();
             }
@@ -480,53 +1295,68 @@ where
 
     /// Set the provided elements with the result of the product between the elements and the constant
     /// MDS matrix.
-    pub(crate) fn product_mds(&mut self) {
-        self.product_mds_with_matrix(&self.constants.mds_matrices.m);
+    pub fn product_mds(&mut self) {
+        Self::product_with_matrix(&mut self.elements, &self.constants.mds_matrices.m);
     }
 
     /// NOTE: This calculates a vector-matrix product (`elements * matrix`) rather than the
-    /// expected matrix-vector `(matrix * elements)`. This is a performance optimization which
-    /// exploits the fact that our MDS matrices are symmetric by construction.
-    pub(crate) fn product_mds_with_matrix(&mut self, matrix: &Matrix<E::Fr>) {
+    /// matrix-vector `(matrix * elements)` of the reference implementation. The two agree for the
+    /// symmetric matrices of `generate_mds_matrix`, and `PoseidonConstants::new_with_mds` transposes
+    /// other matrices.
+    ///
+    /// This takes the elements rather than `self`, so that the matrix may be borrowed from the constants.
+    fn product_with_matrix(
+        elements: &mut GenericArray<E::Fr, A::ConstantsSize>,
+        matrix: &Matrix<E::Fr>,
+    ) {
         let mut result = GenericArray::<E::Fr, A::ConstantsSize>::generate(|_| E::Fr::zero());
 
         for (j, val) in result.iter_mut().enumerate() {
             for (i, row) in matrix.iter().enumerate() {
                 let mut tmp = row[j];
-                tmp.mul_assign(&self.elements[i]);
+                tmp.mul_assign(&elements[i]);
                 val.add_assign(&tmp);
             }
         }
 
-        let _ = std::mem::replace(&mut self.elements, result);
+        elements.copy_from_slice(&result);
+        // The product is left in `result`, on the stack.
+        #[cfg(feature = "zeroize")]
+        crate::wipe::wipe(&mut result, E::Fr::zero());
     }
 
-    // Sparse matrix in this context means one of the form, M''.
-    fn product_mds_with_sparse_matrix(&mut self, sparse_matrix: &SparseMatrix<E>) {
-        let mut result = GenericArray::<E::Fr, A::ConstantsSize>::generate(|_| E::Fr::zero());
-
-        // First column is dense.
-        for (i, val) in sparse_matrix.w_hat.iter().enumerate() {
-            let mut tmp = *val;
-            tmp.mul_assign(&self.elements[i]);
-            result[0].add_assign(&tmp);
-        }
+    fn debug(&self, msg: &str) {
+        dbg!(msg, &self.constants_offset, &self.elements);
+    }
+}
 
-        for (j, val) in result.iter_mut().enumerate().skip(1) {
-            // Except for first row/column, diagonals are one.
-            val.add_assign(&self.elements[j]);
-
-            // First row is dense.
-            let mut tmp = sparse_matrix.v_rest[j - 1];
-            tmp.mul_assign(&self.elements[0]);
-            val.add_assign(&tmp);
-        }
-
-        let _ = std::mem::replace(&mut self.elements, result);
+/// With the `zeroize` feature, `zeroize` overwrites the elements and the domain tag with zeros, so that secret inputs,
+/// such as the key of a `PoseidonPrf`, do not outlive the hasher in memory, and restores the initial state for the
+/// zero domain tag. The constants are not secret, and are kept.
+#[cfg(feature = "zeroize")]
+impl<'a, E, A> Zeroize for Poseidon<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn zeroize(&mut self) {
+        crate::wipe::wipe(std::slice::from_mut(&mut self.domain_tag), E::Fr::zero());
+        self.wipe();
     }
+}
 
-    fn debug(&self, msg: &str) {
-        dbg!(msg, &self.constants_offset, &self.elements);
+/// With the `zeroize` feature, the hasher is zeroized when dropped. The scratch state of each product with a dense
+/// matrix while hashing is overwritten too; other copies, in registers or single elements on the stack, and the
+/// returned digest are not. This is the guarantee of `ZeroizeOnDrop`, which `zeroize` only provides in releases
+/// requiring a newer compiler than this crate supports.
+#[cfg(feature = "zeroize")]
+impl<'a, E, A> Drop for Poseidon<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn drop(&mut self) {
+        self.zeroize();
     }
 }
 
@@ -543,14 +1373,14 @@ impl<A> SimplePoseidonBatchHasher<A>
 where
     A: Arity<Fr>,
 {
-    pub(crate) fn new(max_batch_size: usize) -> Result<Self, Error> {
+    /// Creates a batch hasher on the CPU with the default strength, hashing at most `max_batch_size` preimages at a
+    /// time.
+    pub fn new(max_batch_size: usize) -> Result<Self, Error> {
         Self::new_with_strength(DEFAULT_STRENGTH, max_batch_size)
     }
 
-    pub(crate) fn new_with_strength(
-        strength: Strength,
-        max_batch_size: usize,
-    ) -> Result<Self, Error> {
+    /// Creates a batch hasher on the CPU with the constants of `strength`, the reference for the GPU batch hashers.
+    pub fn new_with_strength(strength: Strength, max_batch_size: usize) -> Result<Self, Error> {
         Ok(Self {
             constants: PoseidonConstants::<Bls12, A>::new_with_strength(strength),
             max_batch_size,
@@ -562,10 +1392,7 @@ where
     A: Arity<Fr>,
 {
     fn hash(&mut self, preimages: &[GenericArray<Fr, A>]) -> Result<Vec<Fr>, Error> {
-        Ok(preimages
-            .iter()
-            .map(|preimage| Poseidon::new_with_preimage(&preimage, &self.constants).hash())
-            .collect())
+        Ok(batch_hash_fixed(preimages, &self.constants))
     }
 
     fn max_batch_size(&self) -> usize {
@@ -573,6 +1400,441 @@ where
     }
 }
 
+/// Applies the permutation of `constants` to the whole `state` in place: half the full rounds, the partial rounds, then
+/// the other half, with the S-box of `constants`.
+///
+/// Unlike hashing, the first element of the state is not set to the domain tag, and all `t` elements are returned.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::{Bls12, Fr};
+/// use ff::Field;
+/// use generic_array::typenum::U2;
+/// use generic_array::GenericArray;
+/// use neptune::poseidon::{permute, PoseidonConstants};
+///
+/// let constants = PoseidonConstants::<Bls12, U2>::new();
+/// let mut state = GenericArray::clone_from_slice(&[Fr::zero(), Fr::one(), Fr::one()]);
+/// permute(&mut state, &constants);
+/// assert_ne!(Fr::zero(), state[0]);
+/// ```
+pub fn permute<E, A>(
+    state: &mut GenericArray<E::Fr, A::ConstantsSize>,
+    constants: &PoseidonConstants<E, A>,
+) where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    let mut p = Poseidon::new(constants);
+    p.elements = state.clone();
+    p.permute();
+    // Copied rather than moved, as `Poseidon` implements `Drop` with the `zeroize` feature.
+    state.copy_from_slice(&p.elements);
+}
+
+/// `PoseidonPermutation` is the bare Poseidon permutation of width `A + 1`, for custom sponge or duplex constructions:
+/// `permute` applies half the full rounds, the partial rounds, then the other half to a whole state, with the round
+/// constants and MDS matrices of its constants, and no domain tag, padding, or rate and capacity split.
+///
+/// The rounds branch only on the round numbers, never on the state, and the power S-boxes are computed with a fixed
+/// sequence of multiplications. Whether a field multiplication takes constant time is up to the field implementation,
+/// and `Sbox::Inverse` branches on whether an element is zero.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::{Bls12, Fr};
+/// use ff::Field;
+/// use generic_array::typenum::U2;
+/// use neptune::poseidon::PoseidonPermutation;
+///
+/// let permutation = PoseidonPermutation::<Bls12, U2>::new();
+/// let mut state = [Fr::zero(), Fr::one(), Fr::one()];
+/// permutation.permute_slice(&mut state);
+/// assert_ne!(Fr::zero(), state[0]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoseidonPermutation<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    constants: PoseidonConstants<E, A>,
+}
+
+impl<E, A> PoseidonPermutation<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Creates the permutation of `PoseidonConstants::new`, as hashing with the default constants applies.
+    pub fn new() -> Self {
+        Self::new_with_constants(PoseidonConstants::new())
+    }
+
+    /// Creates the permutation whose round numbers, round constants, MDS matrices, and S-box are those of
+    /// `constants`. Their hash type and domain tag are not used.
+    pub fn new_with_constants(constants: PoseidonConstants<E, A>) -> Self {
+        PoseidonPermutation { constants }
+    }
+
+    pub fn constants(&self) -> &PoseidonConstants<E, A> {
+        &self.constants
+    }
+
+    /// Returns the width.
+    pub fn width(&self) -> usize {
+        self.constants.width()
+    }
+
+    /// Applies the permutation to `state` in place.
+    pub fn permute(&self, state: &mut GenericArray<E::Fr, A::ConstantsSize>) {
+        permute(state, &self.constants);
+    }
+
+    /// Applies the permutation to `state`, e.g. an array of `[E::Fr; T]`, in place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `state` is not the width.
+    pub fn permute_slice(&self, state: &mut [E::Fr]) {
+        assert_eq!(self.width(), state.len(), "Invalid state size");
+        let mut elements = GenericArray::clone_from_slice(state);
+        self.permute(&mut elements);
+        state.copy_from_slice(&elements);
+    }
+}
+
+impl<E, A> Default for PoseidonPermutation<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hashes each of `preimages` with the default constants for the arity of its length, returning the digests in the
+/// same order.
+///
+/// Preimages of different lengths may be mixed in one batch: they are grouped by arity, and each group is hashed with
+/// the constants of `PoseidonConstants::shared`, created once per process rather than for each batch. With the
+/// `parallel` feature, the preimages of each group are hashed on the rayon thread pool.
+///
+/// # Panics
+///
+/// Panics if the length of a preimage is not a supported arity, from 1 through 127, before hashing any preimage.
+pub fn batch_hash<E>(preimages: &[Vec<E::Fr>]) -> Vec<E::Fr>
+where
+    E: ScalarEngine + Send + Sync,
+{
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, preimage) in preimages.iter().enumerate() {
+        groups
+            .entry(preimage.len())
+            .or_insert_with(Vec::new)
+            .push(i);
+    }
+    for arity in groups.keys() {
+        validate_width(arity + 1).unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    let mut digests = vec![E::Fr::zero(); preimages.len()];
+    for (arity, indices) in groups {
+        let group: Vec<&[E::Fr]> = indices.iter().map(|i| &preimages[*i][..]).collect();
+        for (i, digest) in indices.iter().zip(batch_hash_arity::<E>(arity, &group)) {
+            digests[*i] = digest;
+        }
+    }
+    digests
+}
+
+fn batch_hash_arity<E>(arity: usize, preimages: &[&[E::Fr]]) -> Vec<E::Fr>
+where
+    E: ScalarEngine + Send + Sync,
+{
+    struct HashGroup<'a, E: ScalarEngine>(&'a [&'a [E::Fr]]);
+
+    impl<'a, E> ArityVisitor<E> for HashGroup<'a, E>
+    where
+        E: ScalarEngine + Send + Sync,
+    {
+        type Output = Vec<E::Fr>;
+
+        fn visit<A: Arity<E::Fr> + Send + Sync + 'static>(self) -> Vec<E::Fr> {
+            batch_hash_group::<E, A>(self.0)
+        }
+    }
+
+    visit_arity::<E, _>(arity, HashGroup(preimages))
+}
+
+/// A computation generic over the arity, which `visit_arity` runs for an arity only known at runtime.
+pub(crate) trait ArityVisitor<E: ScalarEngine> {
+    type Output;
+
+    fn visit<A: Arity<E::Fr> + Send + Sync + 'static>(self) -> Self::Output;
+}
+
+/// Runs `visitor` with the typenum arity whose value is `arity`.
+///
+/// # Panics
+///
+/// Panics unless `arity` is supported, from 1 through 127.
+pub(crate) fn visit_arity<E, V>(arity: usize, visitor: V) -> V::Output
+where
+    E: ScalarEngine,
+    V: ArityVisitor<E>,
+{
+    macro_rules! dispatch {
+        ($($n:literal => $a:ty),*) => {
+            match arity {
+                $($n => visitor.visit::<$a>(),)*
+                _ => panic!("{}", Error::InvalidWidth(arity + 1)),
+            }
+        };
+    }
+
+    dispatch!(
+        1 => U1,
+        2 => U2,
+        3 => U3,
+        4 => U4,
+        5 => U5,
+        6 => U6,
+        7 => U7,
+        8 => U8,
+        9 => U9,
+        10 => U10,
+        11 => U11,
+        12 => U12,
+        13 => U13,
+        14 => U14,
+        15 => U15,
+        16 => U16,
+        17 => U17,
+        18 => U18,
+        19 => U19,
+        20 => U20,
+        21 => U21,
+        22 => U22,
+        23 => U23,
+        24 => U24,
+        25 => U25,
+        26 => U26,
+        27 => U27,
+        28 => U28,
+        29 => U29,
+        30 => U30,
+        31 => U31,
+        32 => U32,
+        33 => U33,
+        34 => U34,
+        35 => U35,
+        36 => U36,
+        37 => U37,
+        38 => U38,
+        39 => U39,
+        40 => U40,
+        41 => U41,
+        42 => U42,
+        43 => U43,
+        44 => U44,
+        45 => U45,
+        46 => U46,
+        47 => U47,
+        48 => U48,
+        49 => U49,
+        50 => U50,
+        51 => U51,
+        52 => U52,
+        53 => U53,
+        54 => U54,
+        55 => U55,
+        56 => U56,
+        57 => U57,
+        58 => U58,
+        59 => U59,
+        60 => U60,
+        61 => U61,
+        62 => U62,
+        63 => U63,
+        64 => U64,
+        65 => U65,
+        66 => U66,
+        67 => U67,
+        68 => U68,
+        69 => U69,
+        70 => U70,
+        71 => U71,
+        72 => U72,
+        73 => U73,
+        74 => U74,
+        75 => U75,
+        76 => U76,
+        77 => U77,
+        78 => U78,
+        79 => U79,
+        80 => U80,
+        81 => U81,
+        82 => U82,
+        83 => U83,
+        84 => U84,
+        85 => U85,
+        86 => U86,
+        87 => U87,
+        88 => U88,
+        89 => U89,
+        90 => U90,
+        91 => U91,
+        92 => U92,
+        93 => U93,
+        94 => U94,
+        95 => U95,
+        96 => U96,
+        97 => U97,
+        98 => U98,
+        99 => U99,
+        100 => U100,
+        101 => U101,
+        102 => U102,
+        103 => U103,
+        104 => U104,
+        105 => U105,
+        106 => U106,
+        107 => U107,
+        108 => U108,
+        109 => U109,
+        110 => U110,
+        111 => U111,
+        112 => U112,
+        113 => U113,
+        114 => U114,
+        115 => U115,
+        116 => U116,
+        117 => U117,
+        118 => U118,
+        119 => U119,
+        120 => U120,
+        121 => U121,
+        122 => U122,
+        123 => U123,
+        124 => U124,
+        125 => U125,
+        126 => U126,
+        127 => U127
+    )
+}
+
+fn batch_hash_group<E, A>(preimages: &[&[E::Fr]]) -> Vec<E::Fr>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync + 'static,
+{
+    let constants = PoseidonConstants::<E, A>::shared(Strength::Standard);
+    let hash = |preimage: &&[E::Fr]| Poseidon::new_with_preimage(preimage, &constants).hash();
+
+    #[cfg(feature = "parallel")]
+    let preimages = preimages.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let preimages = preimages.iter();
+
+    preimages.map(hash).collect()
+}
+
+/// The number of preimages each task of `par_batch_hash_fixed` hashes with one state.
+pub const BATCH_CHUNK_SIZE: usize = 1024;
+
+/// Hashes each of `preimages`, all of the arity of `constants`, returning the digests in the same order.
+///
+/// One hasher state is allocated and reset for each preimage, rather than created for each, as hashing in a loop
+/// with `Poseidon::new_with_preimage` does. The hash type of `constants` must take preimages of the full arity, as
+/// `HashType::MerkleTree` does.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::{Bls12, Fr};
+/// use ff::Field;
+/// use generic_array::typenum::U2;
+/// use generic_array::GenericArray;
+/// use neptune::poseidon::{batch_hash_fixed, Poseidon, PoseidonConstants};
+///
+/// let constants = PoseidonConstants::<Bls12, U2>::new();
+/// let pairs = vec![GenericArray::clone_from_slice(&[Fr::zero(), Fr::one()]); 3];
+/// let digests = batch_hash_fixed(&pairs, &constants);
+/// assert_eq!(Poseidon::new_with_preimage(&pairs[0], &constants).hash(), digests[2]);
+/// ```
+pub fn batch_hash_fixed<E, A>(
+    preimages: &[GenericArray<E::Fr, A>],
+    constants: &PoseidonConstants<E, A>,
+) -> Vec<E::Fr>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    let mut poseidon = Poseidon::new(constants);
+    preimages
+        .iter()
+        .map(|preimage| {
+            poseidon.set_preimage(preimage);
+            poseidon.hash()
+        })
+        .collect()
+}
+
+/// Hashes each of `preimages` as `batch_hash_fixed` does, on the rayon thread pool. The preimages are split into
+/// chunks of `BATCH_CHUNK_SIZE`, each hashed by one task with one state.
+#[cfg(feature = "parallel")]
+pub fn par_batch_hash_fixed<E, A>(
+    preimages: &[GenericArray<E::Fr, A>],
+    constants: &PoseidonConstants<E, A>,
+) -> Vec<E::Fr>
+where
+    E: ScalarEngine + Send + Sync,
+    A: Arity<E::Fr> + Send + Sync,
+{
+    let chunks: Vec<Vec<E::Fr>> = preimages
+        .par_chunks(BATCH_CHUNK_SIZE)
+        .map(|chunk| batch_hash_fixed(chunk, constants))
+        .collect();
+
+    let mut digests = Vec::with_capacity(preimages.len());
+    for chunk in chunks {
+        digests.extend(chunk);
+    }
+    digests
+}
+
+/// Returns `true` if `preimage` hashes to `expected_hash` with `constants`, comparing the digests in constant time
+/// with `subtle`, so that the time taken does not reveal how much of a stored hash a guess matched.
+///
+/// Only the comparison is guaranteed to be constant-time. Hashing performs the same field operations for every
+/// preimage of a length, but the constant-time behaviour of the field arithmetic is that of its implementation, and
+/// the `Sbox::Inverse` S-box branches on zero elements.
+///
+/// # Panics
+///
+/// Panics if the length of `preimage` is not that of a preimage of `constants`, as `Poseidon::new_with_preimage` does.
+#[cfg(feature = "ct")]
+pub fn hash_preimage_equal<E, A>(
+    preimage: &[E::Fr],
+    expected_hash: E::Fr,
+    constants: &PoseidonConstants<E, A>,
+) -> bool
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    let hash = Poseidon::new_with_preimage(preimage, constants).hash();
+    hash.into_repr()
+        .as_ref()
+        .ct_eq(expected_hash.into_repr().as_ref())
+        .into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -638,6 +1900,7 @@ mod tests {
         hash_values_aux::<typenum::U11>(strength);
         hash_values_aux::<typenum::U16>(strength);
         hash_values_aux::<typenum::U24>(strength);
+        hash_values_aux::<typenum::U32>(strength);
         hash_values_aux::<typenum::U36>(strength);
     }
 
@@ -712,6 +1975,12 @@ mod tests {
                         0x066f39bf783f3d9f,
                         0x63beb8831f11ae15,
                     ]),
+                    32 => scalar_from_u64s([
+                        0x0a9093dea08f39be,
+                        0xf468805f3eb409d5,
+                        0xbeb34ec3ca32f372,
+                        0x33b55b3daf16f165,
+                    ]),
                     36 => scalar_from_u64s([
                         0x4473606dfa4e8140,
                         0x75cd368df8a8ac3c,
@@ -764,6 +2033,12 @@ mod tests {
                         0xeab0b00318558589,
                         0x70015999f995274e,
                     ]),
+                    32 => scalar_from_u64s([
+                        0x19650414b9e7e619,
+                        0x66ff3ef4ca70f469,
+                        0x97bca0a9c32670e4,
+                        0x31c0ca43fe6765cd,
+                    ]),
                     36 => scalar_from_u64s([
                         0x80098c6336781a9a,
                         0x591e29eb290a5b8e,
@@ -776,11 +2051,302 @@ mod tests {
                     }
                 }
             }
+            Strength::Custom { .. } => panic!("Custom strength lacks test vectors"),
         };
         dbg!(test_arity);
         assert_eq!(expected, digest);
     }
 
+    #[test]
+    fn absorb_one() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64::<Fr>(2)];
+        let expected = Poseidon::new_with_preimage(&preimage, &constants).hash();
+
+        let mut h = Poseidon::new(&constants);
+        for element in preimage.iter() {
+            h.absorb_one(*element).unwrap();
+        }
+        // The rate is exhausted until the state is squeezed.
+        assert!(h.absorb_one(Fr::one()).is_err());
+        assert_eq!(expected, h.squeeze());
+
+        // Squeezing chains the digest into the next preimage.
+        h.absorb_one(Fr::one()).unwrap();
+        assert!(h.absorb_one(Fr::one()).is_err());
+        assert_eq!(
+            Poseidon::new_with_preimage(&[expected, Fr::one()], &constants).hash(),
+            h.finish()
+        );
+    }
+
+    #[test]
+    fn absorb_one_constant_length() {
+        let constants = PoseidonConstants::<Bls12, U4>::new_constant_length(3);
+        let preimage = [
+            scalar_from_u64::<Fr>(1),
+            scalar_from_u64::<Fr>(2),
+            scalar_from_u64::<Fr>(3),
+        ];
+
+        let mut h = Poseidon::new(&constants);
+        for element in preimage.iter() {
+            h.absorb_one(*element).unwrap();
+        }
+        assert!(h.absorb_one(Fr::one()).is_err());
+        assert_eq!(
+            Poseidon::new_with_preimage(&preimage, &constants).hash(),
+            h.finish()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "preimage length does not match constant length")]
+    fn finish_incomplete_constant_length() {
+        let constants = PoseidonConstants::<Bls12, U4>::new_constant_length(3);
+        let mut h = Poseidon::new(&constants);
+        h.absorb_one(Fr::one()).unwrap();
+        h.finish();
+    }
+
+    #[test]
+    fn input_after_preimage() {
+        // `pos` counts the domain tag, so a complete preimage leaves no position for `input` to overwrite.
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64::<Fr>(2)];
+        let expected = Poseidon::new_with_preimage(&preimage, &constants).hash();
+
+        let mut h = Poseidon::new_with_preimage(&preimage, &constants);
+        match h.input(Fr::one()) {
+            Err(Error::FullBuffer) => (),
+            _ => panic!("an element was input past the arity"),
+        }
+        assert_eq!(expected, h.hash());
+
+        // After `set_preimage` as well.
+        let mut h = Poseidon::new(&constants);
+        h.set_preimage(&preimage);
+        assert!(h.input(Fr::one()).is_err());
+        assert_eq!(expected, h.hash());
+    }
+
+    #[test]
+    fn constant_length_partial_preimage() {
+        let constants = PoseidonConstants::<Bls12, U4>::new_constant_length(2);
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64::<Fr>(2)];
+        let mut h = Poseidon::new_with_preimage(&preimage, &constants);
+        let mut correct = h.clone();
+        let digest = h.hash();
+        assert_eq!(digest, correct.hash_in_mode(Correct));
+
+        // The preimage is padded with zeros to the arity, under the domain tag of its length.
+        let padded = [preimage[0], preimage[1], Fr::zero(), Fr::zero()];
+        let merkle_constants = PoseidonConstants::<Bls12, U4>::new();
+        assert_eq!(
+            digest,
+            Poseidon::new_with_preimage_and_domain_tag(
+                &padded,
+                constants.domain_tag,
+                &merkle_constants
+            )
+            .hash()
+        );
+
+        // Absorbing the preimage one element at a time pads it the same way.
+        let mut absorbed = Poseidon::new(&constants);
+        for element in preimage.iter() {
+            absorbed.absorb_one(*element).unwrap();
+        }
+        assert!(absorbed.absorb_one(Fr::one()).is_err());
+        assert_eq!(digest, absorbed.finish());
+    }
+
+    #[test]
+    fn inverse_sbox() {
+        let constants = PoseidonConstants::<Bls12, U2>::new_with_sbox(Sbox::Inverse);
+        assert_eq!(Sbox::Inverse, constants.sbox);
+        assert_eq!((8, 63), (constants.full_rounds, constants.partial_rounds));
+
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64::<Fr>(2)];
+        let mut h = Poseidon::<Bls12, U2>::new_with_preimage(&preimage, &constants);
+        let mut h2 = h.clone();
+        let mut h3 = h.clone();
+
+        let correct = h.hash_in_mode(Correct);
+        assert_eq!(correct, h2.hash_in_mode(OptimizedDynamic));
+        assert_eq!(correct, h3.hash_in_mode(OptimizedStatic));
+
+        let quintic_constants = PoseidonConstants::<Bls12, U2>::new();
+        assert_ne!(
+            correct,
+            Poseidon::new_with_preimage(&preimage, &quintic_constants).hash()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cubic S-box is not a permutation of the field")]
+    fn cubic_sbox_bls12_381() {
+        PoseidonConstants::<Bls12, U2>::new_with_sbox(Sbox::Cubic);
+    }
+
+    #[test]
+    fn permute() {
+        let constants = PoseidonConstants::<Bls12, U4>::new();
+        let preimage: Vec<Fr> = (0..4).map(|i| scalar_from_u64::<Fr>(i)).collect();
+        let mut p = Poseidon::<Bls12, U4>::new_with_preimage(&preimage, &constants);
+        let mut expected = p.clone();
+
+        // The whole state, not only the digest, matches the reference permutation.
+        p.permute();
+        expected.hash_in_mode(Correct);
+        assert_eq!(expected.elements, p.elements);
+
+        // The permutation can be applied repeatedly.
+        p.permute();
+        expected.constants_offset = 0;
+        expected.current_round = 0;
+        expected.hash_in_mode(Correct);
+        assert_eq!(expected.elements, p.elements);
+    }
+
+    #[test]
+    fn permute_test_vector() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        assert_eq!((8, 55), (constants.full_rounds, constants.partial_rounds));
+        let input = GenericArray::generate(|i| scalar_from_u64::<Fr>(i as u64));
+
+        let mut state = input.clone();
+        super::permute(&mut state, &constants);
+        let expected = [
+            scalar_from_u64s([
+                0xea6afa2dbb82c193,
+                0xd932a576cd0ee7fb,
+                0xd7f0b8099264c423,
+                0x2436d8dceb6b34e9,
+            ]),
+            scalar_from_u64s([
+                0x409ad8e7db8d8e9b,
+                0x43d253af0207f417,
+                0xc68235834c3a46f8,
+                0x0106f5f58a687d47,
+            ]),
+            scalar_from_u64s([
+                0x06a8a09925c9042a,
+                0x5579806a7ea061fa,
+                0xac025ebda67ea46c,
+                0x12593a925abf3a28,
+            ]),
+        ];
+        assert_eq!(&expected[..], &state[..]);
+
+        let mut p = Poseidon::<Bls12, U2>::new(&constants);
+        p.elements = input;
+        p.hash_in_mode(Correct);
+        assert_eq!(p.elements, state);
+    }
+
+    #[test]
+    fn clone_shares_matrices() {
+        let constants = PoseidonConstants::<Bls12, U8>::new();
+        let clone = constants.clone();
+        assert!(Arc::ptr_eq(&constants.mds_matrices, &clone.mds_matrices));
+        assert!(Arc::ptr_eq(
+            &constants.compressed_round_constants,
+            &clone.compressed_round_constants
+        ));
+        assert!(Arc::ptr_eq(
+            &constants.pre_sparse_matrix,
+            &clone.pre_sparse_matrix
+        ));
+        assert!(Arc::ptr_eq(
+            &constants.sparse_matrixes,
+            &clone.sparse_matrixes
+        ));
+
+        // Constants created separately are equal, but do not share storage.
+        let other = PoseidonConstants::<Bls12, U8>::new();
+        assert_eq!(constants, other);
+        assert!(!Arc::ptr_eq(&constants.mds_matrices, &other.mds_matrices));
+
+        let preimage = vec![Fr::one(); 8];
+        assert_eq!(
+            Poseidon::new_with_preimage(&preimage, &constants).hash(),
+            Poseidon::new_with_preimage(&preimage, &clone).hash()
+        );
+    }
+
+    #[test]
+    fn shared_constants() {
+        use std::thread;
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                thread::spawn(move || {
+                    let constants = PoseidonConstants::<Bls12, U11>::shared(Strength::Standard);
+                    let preimage = vec![scalar_from_u64::<Fr>(i % 2); 11];
+                    let digest = Poseidon::new_with_preimage(&preimage, &constants).hash();
+                    (constants, digest)
+                })
+            })
+            .collect();
+        let shared: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        // Every caller observes the same allocation, and hashes the same preimage alike.
+        for (i, (constants, digest)) in shared.iter().enumerate() {
+            assert!(Arc::ptr_eq(&shared[0].0, constants));
+            assert_eq!(shared[i % 2].1, *digest);
+        }
+        assert_ne!(shared[0].1, shared[1].1);
+        assert_eq!(PoseidonConstants::<Bls12, U11>::new(), *shared[0].0);
+
+        // The `Arc` may be given to `Poseidon::new`, and hashes as a reference to the same constants does.
+        let constants = PoseidonConstants::<Bls12, U11>::shared(Strength::Standard);
+        let mut p = Poseidon::new(constants.clone());
+        let mut q = Poseidon::new(&*constants);
+        assert_eq!(q.hash(), p.hash());
+
+        let strengthened = PoseidonConstants::<Bls12, U11>::shared(Strength::Strengthened);
+        assert!(!Arc::ptr_eq(&constants, &strengthened));
+        assert_eq!(Strength::Strengthened, strengthened.strength);
+
+        // Custom round numbers are not cached.
+        let custom = Strength::Custom {
+            full: 8,
+            partial: 60,
+        };
+        assert!(!Arc::ptr_eq(
+            &PoseidonConstants::<Bls12, U2>::shared(custom),
+            &PoseidonConstants::<Bls12, U2>::shared(custom)
+        ));
+    }
+
+    #[test]
+    fn permutation() {
+        let constants = PoseidonConstants::<Bls12, U4>::new();
+        let permutation = PoseidonPermutation::new_with_constants(constants.clone());
+        assert_eq!(permutation, PoseidonPermutation::<Bls12, U4>::new());
+        assert_eq!(5, permutation.width());
+
+        let input: Vec<Fr> = (10..15).map(|i| scalar_from_u64::<Fr>(i)).collect();
+        let mut state = GenericArray::clone_from_slice(&input);
+        permutation.permute(&mut state);
+        let mut p = Poseidon::<Bls12, U4>::new(&constants);
+        p.elements = GenericArray::clone_from_slice(&input);
+        p.hash_in_mode(Correct);
+        assert_eq!(p.elements, state);
+
+        let mut array = [Fr::zero(); 5];
+        array.copy_from_slice(&input);
+        permutation.permute_slice(&mut array);
+        assert_eq!(&state[..], &array[..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid state size")]
+    fn permutation_state_size() {
+        PoseidonPermutation::<Bls12, U4>::new().permute_slice(&mut [Fr::zero(); 4]);
+    }
+
     #[test]
     fn hash_compare_optimized() {
         let constants = PoseidonConstants::<Bls12, U2>::new();
@@ -804,6 +2370,472 @@ mod tests {
         assert_eq!(digest_correct, digest_optimized_static);
     }
 
+    #[test]
+    fn hash_compare_optimized_wide() {
+        hash_compare_optimized_aux::<U63>();
+        hash_compare_optimized_aux::<U127>();
+    }
+
+    fn hash_compare_optimized_aux<A>()
+    where
+        A: Arity<Fr>,
+    {
+        let constants = PoseidonConstants::<Bls12, A>::new();
+        let preimage: Vec<Fr> = (0..A::to_usize())
+            .map(|i| scalar_from_u64::<Fr>(i as u64))
+            .collect();
+        let p = Poseidon::<Bls12, A>::new_with_preimage(&preimage, &constants);
+
+        let digest_correct = p.clone().hash_in_mode(Correct);
+        assert_eq!(digest_correct, p.clone().hash_in_mode(OptimizedStatic));
+        assert_eq!(digest_correct, batch_hash::<Bls12>(&[preimage])[0]);
+    }
+
+    #[test]
+    fn arity_tag() {
+        assert_eq!(scalar_from_u64::<Fr>(3), <U2 as Arity<Fr>>::tag());
+        assert_eq!(
+            scalar_from_u64::<Fr>(u64::MAX >> 1),
+            <U63 as Arity<Fr>>::tag()
+        );
+
+        // 2^127 - 1
+        let tag = scalar_from_u64s([u64::MAX, u64::MAX >> 1, 0, 0]);
+        assert_eq!(tag, <U127 as Arity<Fr>>::tag());
+    }
+
+    #[test]
+    fn width_bounds() {
+        assert_eq!(2, MIN_WIDTH);
+        assert_eq!(128, MAX_WIDTH);
+        for t in MIN_WIDTH..=MAX_WIDTH {
+            assert!(validate_width(t).is_ok());
+        }
+        for t in [0, 1, 129, 256].iter() {
+            match validate_width(*t) {
+                Err(Error::InvalidWidth(width)) => assert_eq!(*t, width),
+                _ => panic!("width {} should be invalid", t),
+            }
+        }
+    }
+
+    #[test]
+    fn try_new() {
+        assert_eq!(
+            PoseidonConstants::<Bls12, U1>::new(),
+            PoseidonConstants::<Bls12, U1>::try_new().unwrap()
+        );
+        assert_eq!(
+            PoseidonConstants::<Bls12, U8>::new(),
+            PoseidonConstants::<Bls12, U8>::try_new().unwrap()
+        );
+        match PoseidonConstants::<Bls12, U0>::try_new() {
+            Err(Error::InvalidWidth(1)) => (),
+            _ => panic!("width 1 should be invalid"),
+        }
+    }
+
+    #[test]
+    fn validate_mismatched_arities() {
+        let constants = PoseidonConstants::<Bls12, U4>::new();
+        let other = PoseidonConstants::<Bls12, U8>::new();
+        assert!(constants.validate().is_ok());
+        assert!(other.validate().is_ok());
+        let error =
+            |constants: PoseidonConstants<Bls12, U4>| constants.validate().unwrap_err().to_string();
+
+        let mixed = PoseidonConstants {
+            mds_matrices: other.mds_matrices.clone(),
+            ..constants.clone()
+        };
+        assert_eq!(
+            "Invalid Poseidon constants: MDS matrix must be 5 by 5 for width 5",
+            error(mixed)
+        );
+
+        let mixed = PoseidonConstants {
+            round_constants: other.round_constants.clone(),
+            ..constants.clone()
+        };
+        assert_eq!(
+            "Invalid Poseidon constants: expected 320 round constants for width 5 and 64 rounds, found 585",
+            error(mixed)
+        );
+
+        // The round numbers of arity 8, R_P = 57, with the constants of arity 4, R_P = 56.
+        let mixed = PoseidonConstants {
+            partial_rounds: other.partial_rounds,
+            ..constants.clone()
+        };
+        assert_eq!(
+            "Invalid Poseidon constants: expected 325 round constants for width 5 and 65 rounds, found 320",
+            error(mixed)
+        );
+
+        let mixed = PoseidonConstants {
+            half_full_rounds: 3,
+            ..constants.clone()
+        };
+        assert_eq!(
+            "Invalid Poseidon constants: full rounds 8 must be even and twice the half full rounds 3",
+            error(mixed)
+        );
+
+        let mut truncated = constants;
+        Arc::make_mut(&mut truncated.sparse_matrixes).pop();
+        assert_eq!(
+            "Invalid Poseidon constants: expected a sparse matrix for each of the 56 partial rounds, found 55",
+            error(truncated)
+        );
+    }
+
+    #[test]
+    fn security_level() {
+        let default_constants = PoseidonConstants::<Bls12, U8>::new();
+        let constants_128 = PoseidonConstants::<Bls12, U8>::new_with_security_level(128);
+        assert_eq!(default_constants, constants_128);
+
+        let constants_256 = PoseidonConstants::<Bls12, U8>::new_with_security_level(256);
+        assert_eq!(
+            (8, 117),
+            (constants_256.full_rounds, constants_256.partial_rounds)
+        );
+        assert_eq!(
+            constants_256.width() * (8 + 117),
+            constants_256.round_constants.len()
+        );
+
+        let preimage = [Scalar::one(); 8];
+        let mut h = Poseidon::<Bls12, U8>::new_with_preimage(&preimage, &constants_256);
+        let mut h2 = h.clone();
+        let mut h3 = Poseidon::<Bls12, U8>::new_with_preimage(&preimage, &default_constants);
+
+        let digest = h.hash_in_mode(Correct);
+        assert_eq!(digest, h2.hash_in_mode(OptimizedStatic));
+        assert_ne!(digest, h3.hash());
+    }
+
+    #[test]
+    fn batch_hash_mixed_arities() {
+        let preimages: Vec<Vec<Fr>> = [2, 8, 2, 4, 11, 8, 2]
+            .iter()
+            .enumerate()
+            .map(|(i, arity)| {
+                (0..*arity)
+                    .map(|j| scalar_from_u64::<Fr>((i * 100 + j) as u64))
+                    .collect()
+            })
+            .collect();
+
+        let constants_2 = PoseidonConstants::<Bls12, U2>::new();
+        let constants_4 = PoseidonConstants::<Bls12, U4>::new();
+        let constants_8 = PoseidonConstants::<Bls12, U8>::new();
+        let constants_11 = PoseidonConstants::<Bls12, U11>::new();
+        let expected: Vec<Fr> = preimages
+            .iter()
+            .map(|preimage| match preimage.len() {
+                2 => Poseidon::new_with_preimage(preimage, &constants_2).hash(),
+                4 => Poseidon::new_with_preimage(preimage, &constants_4).hash(),
+                8 => Poseidon::new_with_preimage(preimage, &constants_8).hash(),
+                11 => Poseidon::new_with_preimage(preimage, &constants_11).hash(),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(expected, batch_hash::<Bls12>(&preimages));
+        assert!(batch_hash::<Bls12>(&[]).is_empty());
+    }
+
+    fn batch_hash_fixed_aux<A: Arity<Fr> + Send + Sync>(n: usize) {
+        let constants = PoseidonConstants::<Bls12, A>::new();
+        let preimages: Vec<GenericArray<Fr, A>> = (0..n)
+            .map(|i| GenericArray::generate(|j| scalar_from_u64((i * A::to_usize() + j) as u64)))
+            .collect();
+        let expected: Vec<Fr> = preimages
+            .iter()
+            .map(|preimage| Poseidon::new_with_preimage(preimage, &constants).hash())
+            .collect();
+
+        assert_eq!(expected, batch_hash_fixed(&preimages, &constants));
+        #[cfg(feature = "parallel")]
+        assert_eq!(expected, par_batch_hash_fixed(&preimages, &constants));
+    }
+
+    #[test]
+    fn batch_hash_fixed_matches_hash() {
+        batch_hash_fixed_aux::<U2>(100);
+        batch_hash_fixed_aux::<U8>(10);
+        batch_hash_fixed_aux::<U2>(0);
+        // More than one chunk, the last of them partial.
+        #[cfg(feature = "parallel")]
+        batch_hash_fixed_aux::<U2>(2 * BATCH_CHUNK_SIZE + 3);
+    }
+
+    #[cfg(feature = "ct")]
+    #[test]
+    fn hash_preimage_equal_ct() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64(2)];
+        let hash = Poseidon::new_with_preimage(&preimage, &constants).hash();
+
+        assert!(hash_preimage_equal(&preimage, hash, &constants));
+        assert!(!hash_preimage_equal(
+            &[preimage[1], preimage[0]],
+            hash,
+            &constants
+        ));
+        assert!(!hash_preimage_equal(&preimage, Fr::zero(), &constants));
+    }
+
+    #[test]
+    #[should_panic(expected = "Width 129 is outside of the supported range, 2 through 128.")]
+    fn batch_hash_unsupported_arity() {
+        batch_hash::<Bls12>(&[vec![Fr::one(); 2], vec![Fr::one(); 128]]);
+    }
+
+    #[test]
+    fn security_margin() {
+        let default_constants = PoseidonConstants::<Bls12, U8>::new();
+        assert_eq!(
+            default_constants,
+            PoseidonConstants::<Bls12, U8>::new_with_security_margin(DEFAULT_SECURITY_MARGIN)
+        );
+
+        let margin = SecurityMargin {
+            extra_full_rounds: 2,
+            partial_multiplier: 1.25,
+        };
+        let constants = PoseidonConstants::<Bls12, U8>::new_with_security_margin(margin);
+        assert_eq!((8, 67), (constants.full_rounds, constants.partial_rounds));
+        assert_eq!(
+            constants.width() * (8 + 67),
+            constants.round_constants.len()
+        );
+
+        let preimage = [Scalar::one(); 8];
+        let mut h = Poseidon::<Bls12, U8>::new_with_preimage(&preimage, &constants);
+        let mut h2 = h.clone();
+        assert_eq!(h.hash_in_mode(Correct), h2.hash_in_mode(OptimizedStatic));
+    }
+
+    #[test]
+    fn even_partial_rounds() {
+        let constants = PoseidonConstants::<Bls12, U2>::new_with_even_partial_rounds();
+        assert_eq!((8, 56), (constants.full_rounds, constants.partial_rounds));
+
+        let constants = PoseidonConstants::<Bls12, U8>::new_with_even_partial_rounds();
+        assert_eq!((8, 58), (constants.full_rounds, constants.partial_rounds));
+        assert_eq!(
+            constants.width() * (8 + 58),
+            constants.round_constants.len()
+        );
+
+        let preimage = [Scalar::one(); 8];
+        let mut h = Poseidon::<Bls12, U8>::new_with_preimage(&preimage, &constants);
+        let mut h2 = h.clone();
+        assert_eq!(h.hash_in_mode(Correct), h2.hash_in_mode(OptimizedStatic));
+    }
+
+    #[test]
+    fn new_with_rounds() {
+        // The calculated round numbers pass the check.
+        assert_eq!(
+            PoseidonConstants::<Bls12, U2>::new(),
+            PoseidonConstants::<Bls12, U2>::new_with_rounds(8, 55)
+        );
+
+        let constants = PoseidonConstants::<Bls12, U2>::new_with_rounds_unchecked(8, 40);
+        assert_eq!((8, 40), (constants.full_rounds, constants.partial_rounds));
+
+        let preimage = [Scalar::one(); 2];
+        let mut h = Poseidon::<Bls12, U2>::new_with_preimage(&preimage, &constants);
+        let mut h2 = h.clone();
+        assert_eq!(h.hash_in_mode(Correct), h2.hash_in_mode(OptimizedStatic));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Round numbers R_F = 8, R_P = 40 for width 3 fail the interpolation bound."
+    )]
+    fn new_with_insecure_rounds() {
+        PoseidonConstants::<Bls12, U2>::new_with_rounds(8, 40);
+    }
+
+    #[test]
+    fn with_domain_tag() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let tag = scalar_from_u64::<Fr>(12345);
+        let tagged = constants.with_domain_tag(tag);
+        assert_eq!(tag, tagged.domain_tag);
+        assert_eq!(constants.hash_type, tagged.hash_type);
+        assert_eq!(constants.round_constants, tagged.round_constants);
+
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64::<Fr>(2)];
+        let mut p = Poseidon::new_with_preimage(&preimage, &tagged);
+        assert_eq!(tag, p.elements[0]);
+
+        // The tag separates the digests, whichever mode they are computed in.
+        let digest = p.hash_in_mode(HashMode::Correct);
+        p.set_preimage(&preimage);
+        assert_eq!(digest, p.hash_in_mode(HashMode::OptimizedStatic));
+        assert_ne!(
+            Poseidon::new_with_preimage(&preimage, &constants).hash(),
+            digest
+        );
+
+        // The default tag restores the original constants.
+        assert_eq!(constants, tagged.with_domain_tag(constants.domain_tag));
+    }
+
+    #[test]
+    fn new_with_domain_tag() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let tag = scalar_from_u64::<Fr>(12345);
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64::<Fr>(2)];
+
+        let mut p = Poseidon::new_with_preimage_and_domain_tag(&preimage, tag, &constants);
+        assert_eq!(tag, p.elements[0]);
+        assert_eq!(&preimage[..], &p.elements[1..]);
+        let digest = p.hash_in_mode(HashMode::Correct);
+
+        // The tag is the same as the tag of the constants, and is restored by `reset`.
+        let tagged = constants.with_domain_tag(tag);
+        assert_eq!(
+            Poseidon::new_with_preimage(&preimage, &tagged).hash(),
+            digest
+        );
+        p.set_preimage(&preimage);
+        assert_eq!(digest, p.hash());
+        assert_ne!(
+            Poseidon::new_with_preimage(&preimage, &constants).hash(),
+            digest
+        );
+
+        let mut q = Poseidon::new_with_domain_tag(&constants, tag);
+        for element in preimage.iter() {
+            q.input(*element).unwrap();
+        }
+        assert_eq!(digest, q.hash());
+    }
+
+    #[test]
+    fn new_with_mds() {
+        // An MDS matrix passing the checks of `check_mds_matrix`, and not symmetric, so that its transpose differs.
+        let mds: Matrix<Fr> = [[1, 1, 1], [1, 2, 3], [1, 4, 2]]
+            .iter()
+            .map(|row| row.iter().map(|x| scalar_from_u64::<Fr>(*x)).collect())
+            .collect();
+        let constants = PoseidonConstants::<Bls12, U2>::new_with_mds(mds.clone()).unwrap();
+        let default = PoseidonConstants::<Bls12, U2>::new();
+        assert_eq!(matrix::transpose::<Bls12>(&mds), constants.mds_matrices.m);
+        assert_eq!(default.round_constants, constants.round_constants);
+
+        // The naive permutation, multiplying the state as a column vector by the matrix.
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64::<Fr>(2)];
+        let mut state = vec![constants.domain_tag, preimage[0], preimage[1]];
+        let rounds = constants.full_rounds + constants.partial_rounds;
+        for (round, keys) in constants.round_constants.chunks(3).take(rounds).enumerate() {
+            let partial = round >= constants.half_full_rounds
+                && round < constants.half_full_rounds + constants.partial_rounds;
+            for (i, (element, key)) in state.iter_mut().zip(keys).enumerate() {
+                element.add_assign(key);
+                if i == 0 || !partial {
+                    quintic_s_box::<Bls12>(element, None, None);
+                }
+            }
+            state = matrix::left_apply_matrix::<Bls12>(&mds, &state);
+        }
+
+        let mut p = Poseidon::new_with_preimage(&preimage, &constants);
+        assert_eq!(state[1], p.hash_in_mode(HashMode::Correct));
+        p.set_preimage(&preimage);
+        assert_eq!(state[1], p.hash_in_mode(HashMode::OptimizedStatic));
+        assert_ne!(
+            state[1],
+            Poseidon::new_with_preimage(&preimage, &default).hash()
+        );
+
+        let identity = matrix::make_identity::<Bls12>(3);
+        match PoseidonConstants::<Bls12, U2>::new_with_mds(identity) {
+            Err(e) => assert_eq!(
+                "Invalid Poseidon constants: matrix is not MDS: a square submatrix is singular",
+                e.to_string()
+            ),
+            Ok(_) => panic!("accepted the identity"),
+        }
+        let wide = generate_mds_matrix::<Bls12>(5);
+        assert!(PoseidonConstants::<Bls12, U2>::new_with_mds(wide).is_err());
+    }
+
+    #[test]
+    fn new_seeded() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        assert_eq!(None, constants.seed);
+
+        let seeded = PoseidonConstants::<Bls12, U2>::new_seeded(b"my-protocol-v1");
+        assert_eq!(Some(b"my-protocol-v1".to_vec()), seeded.seed);
+        assert_eq!(
+            seeded,
+            PoseidonConstants::<Bls12, U2>::new_seeded(b"my-protocol-v1")
+        );
+        assert_eq!(constants.round_numbers(), seeded.round_numbers());
+        assert_eq!(constants.mds_matrices, seeded.mds_matrices);
+        assert_eq!(constants.domain_tag, seeded.domain_tag);
+
+        let other = PoseidonConstants::<Bls12, U2>::new_seeded(b"my-protocol-v2");
+        assert_ne!(seeded.round_constants, other.round_constants);
+        assert_ne!(constants.round_constants, seeded.round_constants);
+
+        let preimage = [scalar_from_u64::<Fr>(1), scalar_from_u64::<Fr>(2)];
+        let hash = |constants: &PoseidonConstants<Bls12, U2>| {
+            let mut p = Poseidon::new_with_preimage(&preimage, constants);
+            let digest = p.hash_in_mode(HashMode::Correct);
+            p.set_preimage(&preimage);
+            assert_eq!(digest, p.hash_in_mode(HashMode::OptimizedStatic));
+            digest
+        };
+        assert_ne!(hash(&seeded), hash(&other));
+        assert_ne!(hash(&constants), hash(&seeded));
+    }
+
+    #[test]
+    fn constants_round_numbers() {
+        constants_round_numbers_aux::<U2>();
+        constants_round_numbers_aux::<U4>();
+        constants_round_numbers_aux::<U8>();
+        constants_round_numbers_aux::<U11>();
+        constants_round_numbers_aux::<U16>();
+        constants_round_numbers_aux::<U24>();
+        constants_round_numbers_aux::<U32>();
+        constants_round_numbers_aux::<U36>();
+    }
+
+    fn constants_round_numbers_aux<A>()
+    where
+        A: Arity<Fr>,
+    {
+        for strength in [Strength::Standard, Strength::Strengthened].iter() {
+            let constants = PoseidonConstants::<Bls12, A>::new_with_strength(*strength);
+            let width = constants.width();
+            let expected = RoundNumbers::new(width, *strength);
+            let actual = constants.round_numbers();
+
+            assert_eq!(expected.full_rounds, actual.full_rounds);
+            assert_eq!(expected.partial_rounds, actual.partial_rounds);
+            assert_eq!(expected.sbox_cost, actual.sbox_cost);
+            assert_eq!(actual.sbox_cost * 255, actual.size_cost);
+
+            assert_eq!(constants.full_rounds, actual.full_rounds);
+            assert_eq!(constants.partial_rounds, actual.partial_rounds);
+            assert_eq!(
+                width * (actual.full_rounds + actual.partial_rounds),
+                constants.round_constants.len()
+            );
+            // One compressed round constant is added after each S-box.
+            assert_eq!(actual.sbox_cost, constants.compressed_round_constants.len());
+        }
+    }
+
     #[test]
     fn default_is_standard() {
         let default_constants = PoseidonConstants::<Bls12, U8>::new();
@@ -815,4 +2847,163 @@ mod tests {
             default_constants.partial_rounds
         );
     }
+
+    #[test]
+    fn custom_strength() {
+        let strength = Strength::Custom {
+            full: 8,
+            partial: 57,
+        };
+        let constants = PoseidonConstants::<Bls12, U2>::new_with_strength(strength);
+        assert_eq!((8, 57), (constants.full_rounds, constants.partial_rounds));
+        assert_eq!(strength, constants.strength);
+        assert_eq!(
+            HashType::<Fr, U2>::MerkleTree.domain_tag(&strength),
+            constants.domain_tag
+        );
+        assert_eq!(
+            constants,
+            PoseidonConstants::<Bls12, U2>::new_with_strength_unchecked(strength)
+        );
+        assert_ne!(
+            PoseidonConstants::<Bls12, U2>::new_with_rounds(8, 57).domain_tag,
+            constants.domain_tag
+        );
+
+        custom_strength_hashes_consistently::<U2>(strength);
+        custom_strength_hashes_consistently::<U8>(Strength::Custom {
+            full: 10,
+            partial: 60,
+        });
+
+        // The preprocessed constants are derived for any number of partial rounds, not only secure ones.
+        for partial in [1, 2, 3, 5, 100].iter() {
+            custom_strength_hashes_consistently::<U2>(Strength::Custom {
+                full: 4,
+                partial: *partial,
+            });
+            custom_strength_hashes_consistently::<U8>(Strength::Custom {
+                full: 2,
+                partial: *partial,
+            });
+        }
+    }
+
+    fn custom_strength_hashes_consistently<A>(strength: Strength)
+    where
+        A: Arity<Fr>,
+    {
+        let constants = PoseidonConstants::<Bls12, A>::new_with_strength_unchecked(strength);
+        let preimage: Vec<Fr> = (0..A::to_usize())
+            .map(|i| scalar_from_u64::<Fr>(i as u64))
+            .collect();
+        let p = Poseidon::<Bls12, A>::new_with_preimage(&preimage, &constants);
+
+        let correct = p.clone().hash_in_mode(Correct);
+        assert_eq!(
+            correct,
+            p.clone().hash_in_mode(OptimizedDynamic),
+            "{:?}",
+            strength
+        );
+        assert_eq!(
+            correct,
+            p.clone().hash_in_mode(OptimizedStatic),
+            "{:?}",
+            strength
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Round numbers R_F = 8, R_P = 40 for width 3 fail the")]
+    fn custom_strength_insecure() {
+        PoseidonConstants::<Bls12, U2>::new_with_strength(Strength::Custom {
+            full: 8,
+            partial: 40,
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Full rounds must be even: 9.")]
+    fn custom_strength_odd_full_rounds() {
+        PoseidonConstants::<Bls12, U2>::new_with_strength(Strength::Custom {
+            full: 9,
+            partial: 57,
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "partial rounds must be non-zero")]
+    fn custom_strength_unchecked_no_partial_rounds() {
+        PoseidonConstants::<Bls12, U2>::new_with_strength_unchecked(Strength::Custom {
+            full: 8,
+            partial: 0,
+        });
+    }
+
+    #[test]
+    fn try_constructors() {
+        assert_eq!(
+            PoseidonConstants::<Bls12, U2>::new_with_strength(Strength::Strengthened),
+            PoseidonConstants::<Bls12, U2>::try_new_with_strength(Strength::Strengthened).unwrap()
+        );
+        assert_eq!(
+            PoseidonConstants::<Bls12, U2>::new_with_rounds(8, 55),
+            PoseidonConstants::<Bls12, U2>::try_new_with_rounds(8, 55).unwrap()
+        );
+
+        match PoseidonConstants::<Bls12, U2>::try_new_with_strength(Strength::Custom {
+            full: 8,
+            partial: 40,
+        }) {
+            Err(Error::RoundNumberError(RoundNumberError::InsecureRoundNumbers { .. })) => (),
+            _ => panic!("insecure round numbers were accepted"),
+        }
+        match PoseidonConstants::<Bls12, U2>::try_new_with_rounds(9, 57) {
+            Err(Error::RoundNumberError(RoundNumberError::OddFullRounds { rf: 9 })) => (),
+            _ => panic!("odd full rounds were accepted"),
+        }
+        match PoseidonConstants::<Bls12, U2>::try_new_with_rounds(8, 40) {
+            Err(Error::RoundNumberError(RoundNumberError::InsecureRoundNumbers { .. })) => (),
+            _ => panic!("insecure round numbers were accepted"),
+        }
+    }
+
+    #[test]
+    fn try_preimage() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let preimage = [Fr::one(), Fr::zero()];
+        let mut poseidon = Poseidon::try_new_with_preimage(&preimage, &constants).unwrap();
+        let digest = poseidon.hash();
+        assert_eq!(
+            Poseidon::new_with_preimage(&preimage, &constants).hash(),
+            digest
+        );
+
+        match Poseidon::try_new_with_preimage(&preimage[..1], &constants) {
+            Err(Error::InvalidPreimageSize {
+                expected: 2,
+                found: 1,
+            }) => (),
+            _ => panic!("a short preimage was accepted"),
+        }
+        let constant_length = PoseidonConstants::<Bls12, U2>::new_constant_length(1);
+        match Poseidon::try_new_with_preimage(&preimage, &constant_length) {
+            Err(e @ Error::InvalidPreimageSize { .. }) => assert_eq!(
+                "Invalid preimage size: expected 1 elements, found 2.",
+                e.to_string()
+            ),
+            _ => panic!("a long preimage was accepted"),
+        }
+
+        match poseidon.try_set_preimage(&[Fr::one(); 3]) {
+            Err(Error::InvalidPreimageSize {
+                expected: 2,
+                found: 3,
+            }) => (),
+            _ => panic!("a long preimage was accepted"),
+        }
+        poseidon.try_set_preimage(&preimage).unwrap();
+        assert_eq!(digest, poseidon.hash());
+    }
 }
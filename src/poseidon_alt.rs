@@ -2,7 +2,7 @@
 //! These are tested (in `poseidon::test`) to be equivalent to the 'static optimized' version
 //! used for actual hashing by the neptune library.
 use crate::poseidon::{Arity, Poseidon};
-use crate::{matrix, quintic_s_box};
+use crate::{matrix, s_box};
 use ff::{Field, ScalarEngine};
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -53,11 +53,12 @@ where
         .skip(p.constants_offset)
         .map(|x| Some(x));
 
+    let sbox = p.constants.sbox;
     p.elements
         .iter_mut()
         .zip(pre_round_keys)
         .for_each(|(l, pre)| {
-            quintic_s_box::<E>(l, pre, None);
+            s_box::<E>(sbox, l, pre, None);
         });
 
     p.constants_offset += p.elements.len();
@@ -77,7 +78,7 @@ where
     add_round_constants(p);
 
     // Apply the quintic S-Box to the first element
-    quintic_s_box::<E>(&mut p.elements[0], None, None);
+    s_box::<E>(p.constants.sbox, &mut p.elements[0], None, None);
 
     // Multiply the elements by the constant MDS matrix
     p.product_mds();
@@ -142,6 +143,7 @@ pub fn full_round_dynamic<'a, E, A>(
             }
         });
 
+    let sbox = p.constants.sbox;
     if absorb_next_round_keys {
         // Using the notation from `test_inverse` in matrix.rs:
         // S
@@ -181,14 +183,14 @@ pub fn full_round_dynamic<'a, E, A>(
             .iter_mut()
             .zip(pre_round_keys.zip(post_round_keys))
             .for_each(|(l, (pre, post))| {
-                quintic_s_box::<E>(l, pre, Some(post));
+                s_box::<E>(sbox, l, pre, Some(post));
             });
     } else {
         p.elements
             .iter_mut()
             .zip(pre_round_keys)
             .for_each(|(l, pre)| {
-                quintic_s_box::<E>(l, pre, None);
+                s_box::<E>(sbox, l, pre, None);
             });
     }
     let mut consumed = 0;
@@ -214,7 +216,7 @@ where
     A: Arity<E::Fr>,
 {
     // Apply the quintic S-Box to the first element
-    quintic_s_box::<E>(&mut p.elements[0], None, None);
+    s_box::<E>(p.constants.sbox, &mut p.elements[0], None, None);
 
     // Multiply the elements by the constant MDS matrix
     p.product_mds();
@@ -1,15 +1,85 @@
-use crate::matrix::{apply_matrix, vec_add};
-use crate::mds::MDSMatrices;
+use crate::matrix::{apply_matrix, vec_add, Matrix};
+use crate::mds::{derive_mds_matrices, factor_to_sparse_matrixes, MDSMatrices, SparseMatrix};
 use crate::quintic_s_box;
 use ff::{Field, ScalarEngine};
 
+/// The constants of the optimized permutation of Appendix B of the Poseidon paper, derived by `optimize`.
+///
+/// The permutation adds the first `t` compressed round constants, then adds the rest after the S-boxes: `t` after each
+/// full round but the last, and one after each partial round. The last full round of the first half multiplies by
+/// `pre_sparse_matrix` rather than the MDS matrix, and each partial round by its sparse matrix, with `2t - 1`
+/// multiplications rather than `t^2`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptimizedConstants<E: ScalarEngine> {
+    /// `t * R_F + R_P` round constants, in the order they are added.
+    pub compressed_round_constants: Vec<E::Fr>,
+    /// The dense matrix ending the first half of the full rounds.
+    pub pre_sparse_matrix: Matrix<E::Fr>,
+    /// The sparse matrix of each partial round, in order.
+    pub sparse_matrixes: Vec<SparseMatrix<E>>,
+}
+
+/// Derives the constants of the optimized permutation from the `t x t` MDS matrix and the `t * (R_F + R_P)` round
+/// constants of the naive permutation, computing the same function.
+///
+/// # Panics
+///
+/// Panics if there are fewer round constants than the rounds use, or if the MDS matrix or its minor is not
+/// invertible.
+pub fn optimize<E: ScalarEngine>(
+    mds_matrix: &Matrix<E::Fr>,
+    round_constants: &[E::Fr],
+    full_rounds: usize,
+    partial_rounds: usize,
+) -> OptimizedConstants<E> {
+    let mds_matrices = derive_mds_matrices::<E>(mds_matrix.clone());
+    optimize_with_mds_matrices(&mds_matrices, round_constants, full_rounds, partial_rounds)
+}
+
+// `optimize`, for callers that have already derived the MDS matrices.
+pub(crate) fn optimize_with_mds_matrices<E: ScalarEngine>(
+    mds_matrices: &MDSMatrices<E>,
+    round_constants: &[E::Fr],
+    full_rounds: usize,
+    partial_rounds: usize,
+) -> OptimizedConstants<E> {
+    let width = mds_matrices.m.len();
+    // Ensure we have enough constants for the sbox rounds
+    assert!(
+        width * (full_rounds + partial_rounds) <= round_constants.len(),
+        "Not enough round constants"
+    );
+
+    let compressed_round_constants = compress_round_constants::<E>(
+        width,
+        full_rounds,
+        partial_rounds,
+        round_constants,
+        mds_matrices,
+        partial_rounds,
+    );
+    assert_eq!(
+        full_rounds * width + partial_rounds,
+        compressed_round_constants.len()
+    );
+
+    let (pre_sparse_matrix, sparse_matrixes) =
+        factor_to_sparse_matrixes::<E>(mds_matrices.m.clone(), partial_rounds);
+
+    OptimizedConstants {
+        compressed_round_constants,
+        pre_sparse_matrix,
+        sparse_matrixes,
+    }
+}
+
 // - Compress constants by pushing them back through linear layers and through the identity components of partial layers.
 // - As a result, constants need only be added after each S-box.
 pub(crate) fn compress_round_constants<E: ScalarEngine>(
     width: usize,
     full_rounds: usize,
     partial_rounds: usize,
-    round_constants: &Vec<E::Fr>,
+    round_constants: &[E::Fr],
     mds_matrices: &MDSMatrices<E>,
     partial_preprocessed: usize,
 ) -> Vec<E::Fr> {
@@ -170,3 +240,127 @@ pub(crate) fn compress_round_constants<E: ScalarEngine>(
 
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mds::{apply_sparse_mds, generate_mds_matrix};
+    use crate::round_constants::generate;
+    use bellperson::bls::{Bls12, Fr};
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    // Adds the round constants before the S-boxes of each round, then multiplies by the MDS matrix.
+    fn permute_naive(
+        m: &Matrix<Fr>,
+        round_constants: &[Fr],
+        full_rounds: usize,
+        partial_rounds: usize,
+        mut state: Vec<Fr>,
+    ) -> Vec<Fr> {
+        let width = state.len();
+        for round in 0..(full_rounds + partial_rounds) {
+            let keys = &round_constants[round * width..(round + 1) * width];
+            let full = round < full_rounds / 2 || round >= full_rounds / 2 + partial_rounds;
+            for (i, (x, key)) in state.iter_mut().zip(keys).enumerate() {
+                if full || i == 0 {
+                    quintic_s_box::<Bls12>(x, Some(key), None);
+                } else {
+                    x.add_assign(key);
+                }
+            }
+            state = apply_matrix::<Bls12>(m, &state);
+        }
+        state
+    }
+
+    fn permute_optimized(
+        m: &Matrix<Fr>,
+        optimized: &OptimizedConstants<Bls12>,
+        full_rounds: usize,
+        mut state: Vec<Fr>,
+    ) -> Vec<Fr> {
+        let width = state.len();
+        let mut keys = optimized.compressed_round_constants.iter();
+        for x in state.iter_mut() {
+            x.add_assign(keys.next().unwrap());
+        }
+
+        for round in 0..(full_rounds / 2) {
+            for x in state.iter_mut() {
+                quintic_s_box::<Bls12>(x, None, keys.next());
+            }
+            let matrix = if round == full_rounds / 2 - 1 {
+                &optimized.pre_sparse_matrix
+            } else {
+                m
+            };
+            state = apply_matrix::<Bls12>(matrix, &state);
+        }
+        for sparse_matrix in optimized.sparse_matrixes.iter() {
+            quintic_s_box::<Bls12>(&mut state[0], None, keys.next());
+            apply_sparse_mds::<Bls12>(sparse_matrix, &mut state);
+        }
+        for round in 0..(full_rounds / 2) {
+            let last = round == full_rounds / 2 - 1;
+            for x in state.iter_mut() {
+                let key = if last { None } else { keys.next() };
+                quintic_s_box::<Bls12>(x, None, key);
+            }
+            state = apply_matrix::<Bls12>(m, &state);
+        }
+
+        assert_eq!(
+            None,
+            keys.next(),
+            "All compressed round constants are used."
+        );
+        assert_eq!(width, state.len());
+        state
+    }
+
+    #[test]
+    fn test_optimize_matches_naive_permutation() {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+
+        for &(width, full_rounds, partial_rounds) in [
+            (3, 8, 55),
+            (5, 8, 56),
+            (9, 8, 57),
+            (12, 4, 7),
+            (17, 8, 59),
+            (25, 8, 59),
+            (33, 8, 60),
+            (37, 8, 60),
+        ]
+        .iter()
+        {
+            let m = generate_mds_matrix::<Bls12>(width);
+            let round_constants = generate::<Bls12>(width, full_rounds, partial_rounds);
+            let optimized = optimize::<Bls12>(&m, &round_constants, full_rounds, partial_rounds);
+
+            assert_eq!(partial_rounds, optimized.sparse_matrixes.len());
+            for _ in 0..10 {
+                let state: Vec<Fr> = (0..width).map(|_| Fr::random(&mut rng)).collect();
+                assert_eq!(
+                    permute_naive(
+                        &m,
+                        &round_constants,
+                        full_rounds,
+                        partial_rounds,
+                        state.clone()
+                    ),
+                    permute_optimized(&m, &optimized, full_rounds, state),
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough round constants")]
+    fn test_optimize_too_few_round_constants() {
+        let m = generate_mds_matrix::<Bls12>(3);
+        let round_constants = generate::<Bls12>(3, 8, 55);
+        optimize::<Bls12>(&m, &round_constants, 8, 56);
+    }
+}
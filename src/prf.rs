@@ -0,0 +1,139 @@
+use crate::hash_type::HashType;
+use crate::poseidon::{Poseidon, PoseidonConstants};
+use crate::{Strength, DEFAULT_STRENGTH};
+use ff::{Field, ScalarEngine};
+use generic_array::typenum::U1;
+
+/// `PoseidonPrf` evaluates Poseidon as a pseudo-random function `F(key, input)`, by the permutation of width 2: the
+/// capacity element is the key, and the rate the single input. The output is the element after the capacity, as for
+/// hashing.
+///
+/// The constants have `HashType::Prf`, whose domain tag is added to the key in the capacity element. The key of an
+/// evaluation is then the domain tag of a hash of arity 1 only with negligible probability, so that outputs cannot be
+/// passed off as hashes, nor hashes as outputs, even for keys such as zero.
+///
+/// # Security
+///
+/// The output is pseudo-random only while the key is uniformly random and secret. As the round constants are public,
+/// anyone knowing the key can evaluate the function and invert it in the input, and a key with low entropy can be
+/// found by evaluating every candidate. Derive keys from a cryptographically secure random number generator, and use
+/// a key for a single purpose.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::{Bls12, Fr};
+/// use neptune::prf::PoseidonPrf;
+/// use neptune::scalar_from_u64;
+///
+/// let prf = PoseidonPrf::<Bls12>::new();
+/// let key: Fr = scalar_from_u64(0x5ec2e7);
+/// assert_ne!(prf.eval(key, scalar_from_u64(1)), prf.eval(key, scalar_from_u64(2)));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoseidonPrf<E>
+where
+    E: ScalarEngine,
+{
+    constants: PoseidonConstants<E, U1>,
+}
+
+impl<E> PoseidonPrf<E>
+where
+    E: ScalarEngine,
+{
+    pub fn new() -> Self {
+        Self::new_with_strength(DEFAULT_STRENGTH)
+    }
+
+    pub fn new_with_strength(strength: Strength) -> Self {
+        PoseidonPrf {
+            constants: PoseidonConstants::new_with_strength_and_type(strength, HashType::Prf),
+        }
+    }
+
+    pub fn constants(&self) -> &PoseidonConstants<E, U1> {
+        &self.constants
+    }
+
+    /// Evaluates the function for `key` at `input`.
+    pub fn eval(&self, key: E::Fr, input: E::Fr) -> E::Fr {
+        let mut capacity = key;
+        capacity.add_assign(&self.constants.domain_tag);
+        Poseidon::new_with_preimage_and_domain_tag(&[input], capacity, &self.constants).hash()
+    }
+}
+
+impl<E> Default for PoseidonPrf<E>
+where
+    E: ScalarEngine,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::HashMode;
+    use crate::scalar_from_u64;
+    use bellperson::bls::{Bls12, Fr};
+
+    #[test]
+    fn prf_constants() {
+        let prf = PoseidonPrf::<Bls12>::new();
+        let constants = prf.constants();
+        assert_eq!(2, constants.width());
+        assert_eq!((8, 55), (constants.full_rounds, constants.partial_rounds));
+        assert_eq!(
+            HashType::Prf.domain_tag(&Strength::Standard),
+            constants.domain_tag
+        );
+    }
+
+    #[test]
+    fn prf_eval() {
+        let prf = PoseidonPrf::<Bls12>::new();
+        let key: Fr = scalar_from_u64(123);
+        let input: Fr = scalar_from_u64(456);
+        let output = prf.eval(key, input);
+
+        assert_eq!(output, prf.eval(key, input));
+        assert_ne!(output, prf.eval(scalar_from_u64(124), input));
+        assert_ne!(output, prf.eval(key, scalar_from_u64(457)));
+
+        // The optimized permutation of width 2 agrees with the reference one.
+        let mut capacity = key;
+        capacity.add_assign(&prf.constants().domain_tag);
+        let mut poseidon =
+            Poseidon::new_with_preimage_and_domain_tag(&[input], capacity, prf.constants());
+        assert_eq!(output, poseidon.hash_in_mode(HashMode::Correct));
+    }
+
+    #[test]
+    fn prf_is_not_the_hash() {
+        let prf = PoseidonPrf::<Bls12>::new();
+        let constants = PoseidonConstants::<Bls12, U1>::new();
+        let input: Fr = scalar_from_u64(7);
+        let hash = Poseidon::new_with_preimage(&[input], &constants).hash();
+
+        // Neither the zero key nor the domain tag of the hash evaluate to it.
+        assert_ne!(hash, prf.eval(Fr::zero(), input));
+        assert_ne!(hash, prf.eval(constants.domain_tag, input));
+
+        // Only the key adding up to the domain tag of the hash does, as the permutations are the same.
+        let mut key = constants.domain_tag;
+        key.sub_assign(&prf.constants().domain_tag);
+        assert_eq!(hash, prf.eval(key, input));
+    }
+
+    #[test]
+    fn prf_strength() {
+        let standard = PoseidonPrf::<Bls12>::new();
+        let strengthened = PoseidonPrf::<Bls12>::new_with_strength(Strength::Strengthened);
+        let key: Fr = scalar_from_u64(1);
+        let input: Fr = scalar_from_u64(2);
+        assert_ne!(standard.eval(key, input), strengthened.eval(key, input));
+    }
+}
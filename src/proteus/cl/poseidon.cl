@@ -86,12 +86,12 @@ state apply_sparse_matrix (__constant {field} sm[{sparse_matrix_size}], state s)
 
 state apply_round_matrix (__constant {field} constants[{constants_elements}], state s) {{
     if (s.current_round == {sparse_offset}) {{
-        s = apply_matrix(constants + {pre_sparse_matrix_offset}, s);
+        s = apply_matrix((__constant {field} (*)[{width}])(constants + {pre_sparse_matrix_offset}), s);
       }} else if ((s.current_round > {sparse_offset}) && (s.current_round < {full_half} + {partial_rounds})) {{
         int index = s.current_round - {sparse_offset} - 1;
         s = apply_sparse_matrix(constants + {sparse_matrixes_offset} + (index * {sparse_matrix_size}), s);
       }} else {{
-        s = apply_matrix(constants + {mds_matrix_offset}, s);
+        s = apply_matrix((__constant {field} (*)[{width}])(constants + {mds_matrix_offset}), s);
       }}
     return s;
   }}
@@ -0,0 +1,168 @@
+use crate::poseidon::PoseidonConstants;
+use crate::Arity;
+use bellperson::bls::{Bls12, Fr};
+use ff::Field;
+
+/// The constants of a hasher, as laid out in the single buffer read by the kernels of every GPU backend.
+#[derive(Debug)]
+pub(crate) struct GPUConstants<A>(pub(crate) PoseidonConstants<Bls12, A>)
+where
+    A: Arity<Fr>;
+
+pub struct DerivedConstants {
+    pub arity: usize,
+    pub partial_rounds: usize,
+    pub width: usize,
+    pub sparse_matrix_size: usize,
+    pub full_half: usize,
+    pub sparse_offset: usize,
+    pub constants_elements: usize,
+
+    // Offsets
+    pub domain_tag_offset: usize,
+    pub round_keys_offset: usize,
+    pub mds_matrix_offset: usize,
+    pub pre_sparse_matrix_offset: usize,
+    pub sparse_matrixes_offset: usize,
+    pub w_hat_offset: usize,
+    pub v_rest_offset: usize,
+}
+
+impl<A> GPUConstants<A>
+where
+    A: Arity<Fr>,
+{
+    pub(crate) fn derived_constants(&self) -> DerivedConstants {
+        let c = &self.0;
+        let arity = c.arity();
+        let full_rounds = c.full_rounds;
+        let partial_rounds = c.partial_rounds;
+        let sparse_count = partial_rounds;
+        let width = arity + 1;
+        let sparse_matrix_size = 2 * width - 1;
+        let rk_count = width * full_rounds + partial_rounds;
+        let full_half = full_rounds / 2;
+        let sparse_offset = full_half - 1;
+        let constants_elements =
+            1 + rk_count + (width * width) + (width * width) + (sparse_count * sparse_matrix_size);
+
+        let matrix_size = width * width;
+        let mut offset = 0;
+        let domain_tag_offset = offset;
+        offset += 1;
+        let round_keys_offset = offset;
+        offset += rk_count;
+        let mds_matrix_offset = offset;
+        offset += matrix_size;
+        let pre_sparse_matrix_offset = offset;
+        offset += matrix_size;
+        let sparse_matrixes_offset = offset;
+
+        let w_hat_offset = 0;
+        let v_rest_offset = width;
+
+        DerivedConstants {
+            arity,
+            partial_rounds,
+            width,
+            sparse_matrix_size,
+            full_half,
+            sparse_offset,
+            constants_elements,
+            domain_tag_offset,
+            round_keys_offset,
+            mds_matrix_offset,
+            pre_sparse_matrix_offset,
+            sparse_matrixes_offset,
+            w_hat_offset,
+            v_rest_offset,
+        }
+    }
+
+    /// Returns the elements of the constants buffer, each at its offset of `derived_constants`, for a backend to
+    /// upload to its device.
+    pub(crate) fn to_vec(&self) -> Vec<Fr> {
+        let DerivedConstants {
+            constants_elements,
+            domain_tag_offset,
+            round_keys_offset,
+            mds_matrix_offset,
+            pre_sparse_matrix_offset,
+            sparse_matrixes_offset,
+            ..
+        } = self.derived_constants();
+
+        let c = &self.0;
+        let mut elements = vec![Fr::zero(); constants_elements];
+        let mut write = |offset: usize, values: &[Fr]| {
+            elements[offset..offset + values.len()].copy_from_slice(values)
+        };
+
+        write(domain_tag_offset, &[c.domain_tag]);
+        write(round_keys_offset, &c.compressed_round_constants);
+        write(
+            mds_matrix_offset,
+            &c.mds_matrices
+                .m
+                .iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+        write(
+            pre_sparse_matrix_offset,
+            &c.pre_sparse_matrix
+                .iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+        let mut sm_elts = Vec::new();
+        for sm in c.sparse_matrixes.iter() {
+            sm_elts.extend(sm.w_hat.iter());
+            sm_elts.extend(sm.v_rest.iter());
+        }
+        write(sparse_matrixes_offset, &sm_elts);
+
+        elements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Strength;
+    use generic_array::typenum::{U2, U8};
+
+    fn check_layout<A: Arity<Fr>>() {
+        let constants = GPUConstants(PoseidonConstants::<Bls12, A>::new_with_strength(
+            Strength::Standard,
+        ));
+        let derived = constants.derived_constants();
+        let elements = constants.to_vec();
+        let c = &constants.0;
+
+        assert_eq!(derived.constants_elements, elements.len());
+        assert_eq!(c.domain_tag, elements[derived.domain_tag_offset]);
+        assert_eq!(
+            c.compressed_round_constants[..],
+            elements[derived.round_keys_offset..derived.mds_matrix_offset]
+        );
+        assert_eq!(
+            c.mds_matrices.m[0][..],
+            elements[derived.mds_matrix_offset..derived.mds_matrix_offset + derived.width]
+        );
+        // The sparse matrices fill the rest of the buffer.
+        let last = c.sparse_matrixes.last().unwrap();
+        assert_eq!(
+            last.v_rest[..],
+            elements[derived.constants_elements - (derived.width - 1)..]
+        );
+    }
+
+    #[test]
+    fn constants_layout() {
+        check_layout::<U2>();
+        check_layout::<U8>();
+    }
+}
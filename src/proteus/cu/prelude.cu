@@ -0,0 +1,20 @@
+// Definitions of the OpenCL types, qualifiers, and built-ins used by the field arithmetic of ff-cl-gen and by
+// cl/poseidon.cl, so that the same sources compile as CUDA. NVRTC is given `-default-device`, so functions without an
+// execution space are device functions, as every function of an OpenCL program is.
+
+typedef unsigned char uchar;
+typedef unsigned int uint;
+typedef unsigned long long ulong;
+
+#define __kernel extern "C" __global__
+#define __global
+#define __constant
+#define get_global_id(dim) (blockIdx.x * blockDim.x + threadIdx.x)
+
+uint mad_hi(uint a, uint b, uint c) {
+  return __umulhi(a, b) + c;
+}
+
+ulong mad_hi(ulong a, ulong b, ulong c) {
+  return __umul64hi(a, b) + c;
+}
@@ -0,0 +1,235 @@
+use super::constants::GPUConstants;
+use super::nvrtc;
+use super::sources::generate_cuda_program;
+use crate::error::Error;
+use crate::poseidon::PoseidonConstants;
+use crate::{Arity, BatchHasher, Strength, DEFAULT_STRENGTH};
+use bellperson::bls::{Bls12, Fr};
+use ff::Field;
+use generic_array::GenericArray;
+use log::info;
+use rustacuda::context::{Context, ContextFlags, CurrentContext};
+use rustacuda::device::{Device, DeviceAttribute};
+use rustacuda::error::CudaError;
+use rustacuda::launch;
+use rustacuda::memory::{CopyDestination, DeviceBuffer, DeviceCopy};
+use rustacuda::module::Module;
+use rustacuda::stream::{Stream, StreamFlags};
+use rustacuda::CudaFlags;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::mem;
+use std::slice;
+
+/// An element as copied to and from the device: the Montgomery form of `Fr`, which the kernel reads as it is, as
+/// the OpenCL backend copies it.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+struct DeviceFr(Fr);
+
+unsafe impl DeviceCopy for DeviceFr {}
+
+/// Hashes batches of preimages with a CUDA kernel, compiled from the same program as that of `CLBatchHasher` with
+/// NVRTC, for NVIDIA devices without OpenCL.
+pub struct CudaBatchHasher<A>
+where
+    A: Arity<Fr>,
+{
+    constants_buffer: DeviceBuffer<DeviceFr>,
+    module: Module,
+    stream: Stream,
+    max_batch_size: usize,
+    _a: PhantomData<A>,
+    // Dropped last, after the buffer, module, and stream created in it.
+    context: Context,
+}
+
+fn cuda_error(e: CudaError) -> Error {
+    Error::GPUError(format!("{:?}", e))
+}
+
+/// Returns the CUDA device of index `device_index`. Returns `Error::GPUError` if CUDA cannot be initialized, as when
+/// the machine has no NVIDIA device, or if there is no device of that index.
+pub fn get_device(device_index: usize) -> Result<Device, Error> {
+    rustacuda::init(CudaFlags::empty()).map_err(cuda_error)?;
+    let count = Device::num_devices().map_err(cuda_error)? as usize;
+    if device_index >= count {
+        return Err(Error::GPUError(format!(
+            "CUDA device {} not found, {} available.",
+            device_index, count
+        )));
+    }
+    let device = Device::get_device(device_index as u32).map_err(cuda_error)?;
+    info!("device: {:?}", device.name());
+    Ok(device)
+}
+
+impl<A> CudaBatchHasher<A>
+where
+    A: Arity<Fr>,
+{
+    /// Create a new `CudaBatchHasher` on the CUDA device of index `device_index` and initialize it with state
+    /// corresponding with its `A`, hashing at most `max_batch_size` preimages at a time.
+    pub fn new(device_index: usize, max_batch_size: usize) -> Result<Self, Error> {
+        Self::new_with_strength(device_index, DEFAULT_STRENGTH, max_batch_size)
+    }
+
+    /// Create a new `CudaBatchHasher` on the CUDA device of index `device_index` with the constants of `strength`.
+    /// The kernel is compiled for the constants and the compute capability of the device, and the constants are
+    /// uploaded to it once, in the layout of `CLBatchHasher`, so digests are those of `SimplePoseidonBatchHasher` for
+    /// the same strength.
+    pub fn new_with_strength(
+        device_index: usize,
+        strength: Strength,
+        max_batch_size: usize,
+    ) -> Result<Self, Error> {
+        let device = get_device(device_index)?;
+        let context =
+            Context::create_and_push(ContextFlags::MAP_HOST | ContextFlags::SCHED_AUTO, device)
+                .map_err(cuda_error)?;
+        let compute_capability = (
+            device
+                .get_attribute(DeviceAttribute::ComputeCapabilityMajor)
+                .map_err(cuda_error)?,
+            device
+                .get_attribute(DeviceAttribute::ComputeCapabilityMinor)
+                .map_err(cuda_error)?,
+        );
+
+        let constants = GPUConstants(PoseidonConstants::<Bls12, A>::new_with_strength(strength));
+        let src = generate_cuda_program::<Fr>(true, constants.derived_constants());
+        let ptx = nvrtc::compile(&src, compute_capability)?;
+        let module = Module::load_from_string(&ptx).map_err(cuda_error)?;
+        let stream = Stream::new(StreamFlags::NON_BLOCKING, None).map_err(cuda_error)?;
+
+        let elements = constants
+            .to_vec()
+            .into_iter()
+            .map(DeviceFr)
+            .collect::<Vec<_>>();
+        let constants_buffer = DeviceBuffer::from_slice(&elements).map_err(cuda_error)?;
+        Ok(Self {
+            constants_buffer,
+            module,
+            stream,
+            max_batch_size,
+            _a: PhantomData,
+            context,
+        })
+    }
+}
+
+// The elements of `preimages`, in order.
+fn preimage_elements<A: Arity<Fr>>(preimages: &[GenericArray<Fr, A>]) -> &[DeviceFr] {
+    assert_eq!(
+        mem::size_of::<GenericArray<Fr, A>>(),
+        A::to_usize() * mem::size_of::<DeviceFr>()
+    );
+    // A `GenericArray` is laid out as an array of its elements, and `DeviceFr` as `Fr`.
+    unsafe {
+        slice::from_raw_parts(
+            preimages.as_ptr() as *const DeviceFr,
+            preimages.len() * A::to_usize(),
+        )
+    }
+}
+
+const BLOCK_SIZE: usize = 256;
+impl<A> BatchHasher<A> for CudaBatchHasher<A>
+where
+    A: Arity<Fr>,
+{
+    fn hash(&mut self, preimages: &[GenericArray<Fr, A>]) -> Result<Vec<Fr>, Error> {
+        let block_size = BLOCK_SIZE;
+        let max_batch_size = self.max_batch_size;
+        let batch_size = preimages.len();
+        assert!(batch_size <= max_batch_size);
+        if batch_size == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Set `grid_size` to the number of blocks covering `batch_size`, the last of which may be partial.
+        let grid_size = (batch_size / block_size) + (batch_size % block_size != 0) as usize;
+
+        CurrentContext::set_current(&self.context).map_err(cuda_error)?;
+
+        let mut preimages_buffer =
+            DeviceBuffer::from_slice(preimage_elements(preimages)).map_err(cuda_error)?;
+        let mut digests_buffer =
+            unsafe { DeviceBuffer::<DeviceFr>::uninitialized(batch_size) }.map_err(cuda_error)?;
+
+        let name = CString::new("hash_preimages").unwrap();
+        let function = self.module.get_function(&name).map_err(cuda_error)?;
+        let stream = &self.stream;
+        unsafe {
+            launch!(function<<<grid_size as u32, block_size as u32, 0, stream>>>(
+                self.constants_buffer.as_device_ptr(),
+                preimages_buffer.as_device_ptr(),
+                digests_buffer.as_device_ptr(),
+                batch_size as i32
+            ))
+        }
+        .map_err(cuda_error)?;
+        stream.synchronize().map_err(cuda_error)?;
+
+        let mut digests = vec![DeviceFr(<Fr as Field>::zero()); batch_size];
+        digests_buffer
+            .copy_to(&mut digests[..])
+            .map_err(cuda_error)?;
+        Ok(digests.into_iter().map(|digest| digest.0).collect())
+    }
+
+    fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::poseidon::SimplePoseidonBatchHasher;
+    use generic_array::sequence::GenericSequence;
+    use generic_array::typenum::{U11, U2, U8};
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    fn test_batch_hash<A: Arity<Fr>>(batch_size: usize) {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+
+        let mut cuda_hasher =
+            CudaBatchHasher::<A>::new_with_strength(0, Strength::Standard, batch_size).unwrap();
+        let mut simple_hasher =
+            SimplePoseidonBatchHasher::<A>::new_with_strength(Strength::Standard, batch_size)
+                .unwrap();
+
+        let preimages = (0..batch_size)
+            .map(|_| GenericArray::<Fr, A>::generate(|_| Fr::random(&mut rng)))
+            .collect::<Vec<_>>();
+
+        let cuda_hashes = cuda_hasher.hash(&preimages).unwrap();
+        let expected_hashes: Vec<_> = simple_hasher.hash(&preimages).unwrap();
+
+        assert_eq!(expected_hashes, cuda_hashes);
+    }
+
+    #[test]
+    fn test_batch_hash_2() {
+        // NOTE: `batch_size` is not a multiple of `BLOCK_SIZE`.
+        test_batch_hash::<U2>(1025);
+    }
+
+    #[test]
+    fn test_batch_hash_many() {
+        test_batch_hash::<U2>(4099);
+        test_batch_hash::<U8>(4099);
+        test_batch_hash::<U11>(4099);
+    }
+
+    #[test]
+    fn test_missing_device() {
+        match CudaBatchHasher::<U2>::new(usize::MAX, 1) {
+            Err(Error::GPUError(_)) => (),
+            _ => panic!("expected a GPUError for a missing device"),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub use super::constants::DerivedConstants;
+use super::constants::GPUConstants;
 use super::sources::generate_program;
 use crate::error::{ClError, Error};
 use crate::hash_type::HashType;
@@ -13,11 +15,6 @@ use std::collections::HashMap;
 use std::marker::PhantomData;
 use typenum::{U11, U2, U8};
 
-#[derive(Debug)]
-struct GPUConstants<A>(PoseidonConstants<Bls12, A>)
-where
-    A: Arity<Fr>;
-
 pub struct CLBatchHasher<A>
 where
     A: Arity<Fr>,
@@ -29,149 +26,17 @@ where
     program: opencl::Program,
 }
 
-pub struct DerivedConstants {
-    pub arity: usize,
-    pub partial_rounds: usize,
-    pub width: usize,
-    pub sparse_matrix_size: usize,
-    pub full_half: usize,
-    pub sparse_offset: usize,
-    pub constants_elements: usize,
-
-    // Offsets
-    pub domain_tag_offset: usize,
-    pub round_keys_offset: usize,
-    pub mds_matrix_offset: usize,
-    pub pre_sparse_matrix_offset: usize,
-    pub sparse_matrixes_offset: usize,
-    pub w_hat_offset: usize,
-    pub v_rest_offset: usize,
-}
-
 impl<A> GPUConstants<A>
 where
     A: Arity<Fr>,
 {
-    fn derived_constants(&self) -> DerivedConstants {
-        let c = &self.0;
-        let arity = c.arity();
-        let full_rounds = c.full_rounds;
-        let partial_rounds = c.partial_rounds;
-        let sparse_count = partial_rounds;
-        let width = arity + 1;
-        let sparse_matrix_size = 2 * width - 1;
-        let rk_count = width * full_rounds + partial_rounds;
-        let full_half = full_rounds / 2;
-        let sparse_offset = full_half - 1;
-        let constants_elements =
-            1 + rk_count + (width * width) + (width * width) + (sparse_count * sparse_matrix_size);
-
-        let matrix_size = width * width;
-        let mut offset = 0;
-        let domain_tag_offset = offset;
-        offset += 1;
-        let round_keys_offset = offset;
-        offset += rk_count;
-        let mds_matrix_offset = offset;
-        offset += matrix_size;
-        let pre_sparse_matrix_offset = offset;
-        offset += matrix_size;
-        let sparse_matrixes_offset = offset;
-
-        let w_hat_offset = 0;
-        let v_rest_offset = width;
-
-        DerivedConstants {
-            arity,
-            partial_rounds,
-            width,
-            sparse_matrix_size,
-            full_half,
-            sparse_offset,
-            constants_elements,
-            domain_tag_offset,
-            round_keys_offset,
-            mds_matrix_offset,
-            pre_sparse_matrix_offset,
-            sparse_matrixes_offset,
-            w_hat_offset,
-            v_rest_offset,
-        }
-    }
-}
-
-impl<A> GPUConstants<A>
-where
-    A: Arity<Fr>,
-{
-    fn full_rounds(&self) -> usize {
-        self.0.full_rounds
-    }
-
-    fn partial_rounds(&self) -> usize {
-        self.0.partial_rounds
-    }
-
     fn to_buffer(&self, program: &opencl::Program) -> Result<opencl::Buffer<Fr>, Error> {
-        let DerivedConstants {
-            arity: _,
-            partial_rounds: _,
-            width: _,
-            sparse_matrix_size: _,
-            full_half: _,
-            sparse_offset: _,
-            constants_elements,
-            domain_tag_offset,
-            round_keys_offset,
-            mds_matrix_offset,
-            pre_sparse_matrix_offset,
-            sparse_matrixes_offset,
-            w_hat_offset: _,
-            v_rest_offset: _,
-        } = self.derived_constants();
-
+        let elements = self.to_vec();
         let mut buffer = program
-            .create_buffer::<Fr>(constants_elements)
-            .map_err(|e| Error::GPUError(format!("{:?}", e)))?;
-
-        let c = &self.0;
-
-        buffer
-            .write_from(domain_tag_offset, &[c.domain_tag])
+            .create_buffer::<Fr>(elements.len())
             .map_err(|e| Error::GPUError(format!("{:?}", e)))?;
         buffer
-            .write_from(round_keys_offset, &c.compressed_round_constants)
-            .map_err(|e| Error::GPUError(format!("{:?}", e)))?;
-        buffer
-            .write_from(
-                mds_matrix_offset,
-                c.mds_matrices
-                    .m
-                    .iter()
-                    .flatten()
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            )
-            .map_err(|e| Error::GPUError(format!("{:?}", e)))?;
-        buffer
-            .write_from(
-                pre_sparse_matrix_offset,
-                c.pre_sparse_matrix
-                    .iter()
-                    .flatten()
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            )
-            .map_err(|e| Error::GPUError(format!("{:?}", e)))?;
-        let mut sm_elts = Vec::new();
-        for sm in c.sparse_matrixes.iter() {
-            sm_elts.extend(sm.w_hat.iter());
-            sm_elts.extend(sm.v_rest.iter());
-        }
-        buffer
-            .write_from(sparse_matrixes_offset, &sm_elts)
+            .write_from(0, &elements)
             .map_err(|e| Error::GPUError(format!("{:?}", e)))?;
 
         Ok(buffer)
@@ -191,13 +56,17 @@ impl<A> CLBatchHasher<A>
 where
     A: Arity<Fr>,
 {
-    /// Create a new `GPUBatchHasher` and initialize it with state corresponding with its `A`.
-    pub(crate) fn new(selector: &GPUSelector, max_batch_size: usize) -> Result<Self, Error> {
+    /// Create a new `CLBatchHasher` on the device of `selector` and initialize it with state corresponding with its
+    /// `A`, hashing at most `max_batch_size` preimages at a time.
+    pub fn new(selector: &GPUSelector, max_batch_size: usize) -> Result<Self, Error> {
         let device = get_device(selector)?;
         Self::new_with_strength(device, DEFAULT_STRENGTH, max_batch_size)
     }
 
-    pub(crate) fn new_with_strength(
+    /// Create a new `CLBatchHasher` on `device` with the constants of `strength`. The kernel is generated for the
+    /// constants, and the round constants and matrices are uploaded to the device once, so digests are those of
+    /// `SimplePoseidonBatchHasher` for the same strength.
+    pub fn new_with_strength(
         device: &opencl::Device,
         strength: Strength,
         max_batch_size: usize,
@@ -285,22 +154,18 @@ mod test {
     use rand::SeedableRng;
     use rand_xorshift::XorShiftRng;
 
-    #[test]
-    fn test_batch_hash_2() {
+    fn test_batch_hash<A: Arity<Fr>>(batch_size: usize) {
         let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
         let device = get_device(&GPUSelector::Index(0)).unwrap();
 
-        // NOTE: `batch_size` is not a multiple of `LOCAL_WORK_SIZE`.
-        let batch_size = 1025;
-
         let mut cl_hasher =
-            CLBatchHasher::<U2>::new_with_strength(device, Strength::Standard, batch_size).unwrap();
+            CLBatchHasher::<A>::new_with_strength(device, Strength::Standard, batch_size).unwrap();
         let mut simple_hasher =
-            SimplePoseidonBatchHasher::<U2>::new_with_strength(Strength::Standard, batch_size)
+            SimplePoseidonBatchHasher::<A>::new_with_strength(Strength::Standard, batch_size)
                 .unwrap();
 
         let preimages = (0..batch_size)
-            .map(|_| GenericArray::<Fr, U2>::generate(|_| Fr::random(&mut rng)))
+            .map(|_| GenericArray::<Fr, A>::generate(|_| Fr::random(&mut rng)))
             .collect::<Vec<_>>();
 
         let cl_hashes = cl_hasher.hash(&preimages).unwrap();
@@ -315,4 +180,18 @@ mod test {
 
         assert_eq!(expected_hashes, cl_hashes);
     }
+
+    #[test]
+    fn test_batch_hash_2() {
+        // NOTE: `batch_size` is not a multiple of `LOCAL_WORK_SIZE`.
+        test_batch_hash::<U2>(1025);
+    }
+
+    #[test]
+    fn test_batch_hash_many() {
+        // A few thousand preimages of each arity, in several work groups, the last of them partial.
+        test_batch_hash::<U2>(4099);
+        test_batch_hash::<U8>(4099);
+        test_batch_hash::<U11>(4099);
+    }
 }
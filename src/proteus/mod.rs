@@ -1,2 +1,8 @@
+pub mod constants;
+#[cfg(feature = "cuda")]
+pub mod cuda;
+#[cfg(feature = "opencl")]
 pub mod gpu;
+#[cfg(feature = "cuda")]
+mod nvrtc;
 pub mod sources;
@@ -0,0 +1,123 @@
+//! Bindings to the few functions of NVRTC, the runtime compiler of the CUDA toolkit, needed to compile the generated
+//! program to PTX for the device it runs on, as OpenCL compiles its programs.
+
+use crate::error::Error;
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+#[allow(non_camel_case_types)]
+type nvrtcResult = c_int;
+#[allow(non_camel_case_types)]
+type nvrtcProgram = *mut c_void;
+
+const NVRTC_SUCCESS: nvrtcResult = 0;
+
+#[link(name = "nvrtc")]
+extern "C" {
+    fn nvrtcGetErrorString(result: nvrtcResult) -> *const c_char;
+    fn nvrtcCreateProgram(
+        prog: *mut nvrtcProgram,
+        src: *const c_char,
+        name: *const c_char,
+        num_headers: c_int,
+        headers: *const *const c_char,
+        include_names: *const *const c_char,
+    ) -> nvrtcResult;
+    fn nvrtcDestroyProgram(prog: *mut nvrtcProgram) -> nvrtcResult;
+    fn nvrtcCompileProgram(
+        prog: nvrtcProgram,
+        num_options: c_int,
+        options: *const *const c_char,
+    ) -> nvrtcResult;
+    fn nvrtcGetPTXSize(prog: nvrtcProgram, ptx_size: *mut usize) -> nvrtcResult;
+    fn nvrtcGetPTX(prog: nvrtcProgram, ptx: *mut c_char) -> nvrtcResult;
+    fn nvrtcGetProgramLogSize(prog: nvrtcProgram, log_size: *mut usize) -> nvrtcResult;
+    fn nvrtcGetProgramLog(prog: nvrtcProgram, log: *mut c_char) -> nvrtcResult;
+}
+
+fn error_message(result: nvrtcResult) -> String {
+    // NVRTC returns a static string for every result.
+    let message = unsafe { CStr::from_ptr(nvrtcGetErrorString(result)) };
+    format!("NVRTC: {}", message.to_string_lossy())
+}
+
+fn check(result: nvrtcResult) -> Result<(), Error> {
+    if result == NVRTC_SUCCESS {
+        Ok(())
+    } else {
+        Err(Error::GPUError(error_message(result)))
+    }
+}
+
+// A program, destroyed when dropped.
+struct Program(nvrtcProgram);
+
+impl Program {
+    fn new(source: &str) -> Result<Self, Error> {
+        let source = CString::new(source).map_err(|e| Error::GPUError(format!("{:?}", e)))?;
+        let name = CString::new("poseidon.cu").unwrap();
+        let mut program = ptr::null_mut();
+        check(unsafe {
+            nvrtcCreateProgram(
+                &mut program,
+                source.as_ptr(),
+                name.as_ptr(),
+                0,
+                ptr::null(),
+                ptr::null(),
+            )
+        })?;
+        Ok(Program(program))
+    }
+
+    fn log(&self) -> Result<String, Error> {
+        let mut size = 0;
+        check(unsafe { nvrtcGetProgramLogSize(self.0, &mut size) })?;
+        let mut log = vec![0u8; size];
+        check(unsafe { nvrtcGetProgramLog(self.0, log.as_mut_ptr() as *mut c_char) })?;
+        Ok(String::from_utf8_lossy(&log)
+            .trim_end_matches('\0')
+            .to_string())
+    }
+
+    fn ptx(&self) -> Result<CString, Error> {
+        let mut size = 0;
+        check(unsafe { nvrtcGetPTXSize(self.0, &mut size) })?;
+        let mut ptx = vec![0u8; size];
+        check(unsafe { nvrtcGetPTX(self.0, ptx.as_mut_ptr() as *mut c_char) })?;
+        // The size includes the terminating null byte.
+        CStr::from_bytes_with_nul(&ptx)
+            .map(CStr::to_owned)
+            .map_err(|e| Error::GPUError(format!("{:?}", e)))
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        unsafe { nvrtcDestroyProgram(&mut self.0) };
+    }
+}
+
+/// Compiles the CUDA `source` to PTX for devices of compute capability `major.minor`. Functions without an execution
+/// space are compiled as device functions. A compilation error is returned with the compiler's log.
+pub(crate) fn compile(source: &str, (major, minor): (i32, i32)) -> Result<CString, Error> {
+    let program = Program::new(source)?;
+    let options = [
+        CString::new(format!("--gpu-architecture=compute_{}{}", major, minor)).unwrap(),
+        CString::new("-default-device").unwrap(),
+    ];
+    let options = options.iter().map(|o| o.as_ptr()).collect::<Vec<_>>();
+
+    let result =
+        unsafe { nvrtcCompileProgram(program.0, options.len() as c_int, options.as_ptr()) };
+    if result != NVRTC_SUCCESS {
+        let log = program.log()?;
+        return Err(Error::GPUError(format!(
+            "{}\n{}",
+            error_message(result),
+            log
+        )));
+    }
+    program.ptx()
+}
@@ -1,4 +1,4 @@
-use super::gpu::DerivedConstants;
+use super::constants::DerivedConstants;
 use bellperson::bls::{Engine, Fr};
 use ff::PrimeField;
 use itertools::join;
@@ -45,19 +45,42 @@ fn poseidon_source(field: &str, derived_constants: DerivedConstants) -> String {
     )
 }
 
-pub fn generate_program<Fr>(limb64: bool, derived_constants: DerivedConstants) -> String
+fn field_source<Fr>(limb64: bool) -> String
 where
     Fr: PrimeField,
 {
-    let field_source = if limb64 {
+    if limb64 {
         ff_cl_gen::field::<Fr, ff_cl_gen::Limb64>("Fr")
     } else {
         ff_cl_gen::field::<Fr, ff_cl_gen::Limb32>("Fr")
-    };
+    }
+}
+
+pub fn generate_program<Fr>(limb64: bool, derived_constants: DerivedConstants) -> String
+where
+    Fr: PrimeField,
+{
+    join(
+        &[
+            config(),
+            field_source::<Fr>(limb64),
+            poseidon_source("Fr", derived_constants),
+        ],
+        "\n",
+    )
+}
+
+/// Generates the same program as `generate_program`, preceded by definitions of the OpenCL built-ins it uses, to be
+/// compiled as CUDA.
+pub fn generate_cuda_program<Fr>(limb64: bool, derived_constants: DerivedConstants) -> String
+where
+    Fr: PrimeField,
+{
     join(
         &[
+            include_str!("cu/prelude.cu").to_string(),
             config(),
-            field_source,
+            field_source::<Fr>(limb64),
             poseidon_source("Fr", derived_constants),
         ],
         "\n",
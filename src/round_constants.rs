@@ -1,30 +1,31 @@
+use crate::grain::GrainLfsr;
 pub use crate::Error;
+use crate::DEFAULT_SBOX;
 pub use bellperson::bls::Fr as Scalar;
-use ff::{PrimeField, PrimeFieldDecodingError, PrimeFieldRepr, ScalarEngine};
-
-/// From the paper ():
-/// The round constants are generated using the Grain LFSR [23] in a self-shrinking
-/// mode:
-/// 1. Initialize the state with 80 bits b0, b1, . . . , b79, where
-/// (a) b0, b1 describe the field,
-/// (b) bi for 2 ≤ i ≤ 5 describe the S-Box,
-/// (c) bi for 6 ≤ i ≤ 17 are the binary representation of n,
-/// (d) bi for 18 ≤ i ≤ 29 are the binary representation of t,
-/// (e) bi for 30 ≤ i ≤ 39 are the binary representation of RF ,
-/// (f) bi for 40 ≤ i ≤ 49 are the binary representation of RP , and
-/// (g) bi for 50 ≤ i ≤ 79 are set to 1.
-/// 2. Update the bits using bi+80 = bi+62 ⊕ bi+51 ⊕ bi+38 ⊕ bi+23 ⊕ bi+13 ⊕ bi
-/// .
-/// 3. Discard the first 160 bits.
-/// 4. Evaluate bits in pairs: If the first bit is a 1, output the second bit. If it is a
-/// 0, discard the second bit.
-/// Using this method, the generation of round constants depends on the specific
-/// instance, and thus different round constants are used even if some of the chosen
-/// parameters (e.g., n and t) are the same.
-/// If a randomly sampled integer is not in Fp, we discard this value and take the
-/// next one. Note that cryptographically strong randomness is not needed for the
-/// round constants, and other methods can also be used.
+use ff::{PrimeField, ScalarEngine};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
+// The field type and the parameters of `generate_constants`.
+type ConstantsKey = (TypeId, u8, u8, u16, u16, u16, u16);
+
+// A cache entry, holding the `Vec` of round constants over the field of its key once generated.
+type ConstantsEntry = Arc<Mutex<Option<Box<dyn Any + Send>>>>;
+
+lazy_static! {
+    static ref ROUND_CONSTANTS: Mutex<HashMap<ConstantsKey, ConstantsEntry>> =
+        Mutex::new(HashMap::new());
+}
+
+#[cfg(test)]
+lazy_static! {
+    // The key of each generation by `cached_constants`, to check that each is generated once.
+    static ref GENERATED: Mutex<Vec<ConstantsKey>> = Mutex::new(Vec::new());
+}
+
+/// Generates the `(r_f + r_p) * t` round constants of an instance with the Grain LFSR, see `GrainLfsr`.
+///
 /// Following https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/create_rcs_grain.sage
 pub fn generate_constants<E: ScalarEngine>(
     field: u8,
@@ -34,127 +35,107 @@ pub fn generate_constants<E: ScalarEngine>(
     r_f: u16,
     r_p: u16,
 ) -> Vec<E::Fr> {
+    generate_seeded_constants::<E>(field, sbox, field_size, t, r_f, r_p, None)
+}
+
+// Generates the constants of `generate_constants`, mixing `seed` into the Grain LFSR if there is one.
+pub(crate) fn generate_seeded_constants<E: ScalarEngine>(
+    field: u8,
+    sbox: u8,
+    field_size: u16,
+    t: u16,
+    r_f: u16,
+    r_p: u16,
+    seed: Option<&[u8]>,
+) -> Vec<E::Fr> {
+    if seed.is_none() {
+        if let Some(constants) =
+            crate::constants::precomputed::<E>(field, sbox, field_size, t, r_f, r_p)
+        {
+            return constants;
+        }
+    }
+
     let num_constants = (r_f + r_p) * t;
-    let mut init_sequence: Vec<bool> = Vec::new();
-    append_bits(&mut init_sequence, 2, field); // Bits 0-1
-    append_bits(&mut init_sequence, 4, sbox); // Bits 2-5
-    append_bits(&mut init_sequence, 12, field_size); // Bits 6-17
-    append_bits(&mut init_sequence, 12, t); // Bits 18-29
-    append_bits(&mut init_sequence, 10, r_f); // Bits 30-39
-    append_bits(&mut init_sequence, 10, r_p); // Bits 40-49
-    append_bits(&mut init_sequence, 30, 0b111111111111111111111111111111u128); // Bits 50-79
-
-    let mut grain = Grain::new(init_sequence, field_size);
-    let mut round_constants: Vec<E::Fr> = Vec::new();
     match field {
-        1 => {
-            let element_bytes = (field_size / 8) + ((field_size % 8) > 0) as u16;
-            for _ in 0..num_constants {
-                while {
-                    // Smallest number of bytes which will hold one field element.
-                    let mut bytes = vec![0u8; element_bytes as usize];
-                    grain.get_next_bytes(&mut bytes);
-                    if let Ok(f) = bytes_into_fr::<E>(&mut bytes) {
-                        round_constants.push(f);
-                        false
-                    } else {
-                        true
-                    }
-                } {}
-            }
-        }
+        1 => GrainLfsr::<E>::from_seed(field, sbox, field_size, t, r_f, r_p, seed)
+            .take(num_constants as usize)
+            .collect(),
         _ => {
             panic!("Only prime fields are supported.");
         }
     }
-    return round_constants;
 }
 
-fn append_bits<T: Into<u128>>(vec: &mut Vec<bool>, n: usize, from: T) {
-    let val = from.into() as u128;
-    for i in (0..n).rev() {
-        vec.push((val >> i) & 1 != 0);
-    }
+/// Generates the `t * (rf + rp)` round constants of the reference script's `calc_round_constants` for width `t` with
+/// `rf` full and `rp` partial rounds, over the field of `E` and with the default `x^5` S-box. The constants are
+/// generated by the Grain LFSR seeded with the bit length of the field and the round numbers, see `GrainLfsr` for
+/// other S-boxes.
+///
+/// # Example
+///
+/// ```
+/// use bellperson::bls::Bls12;
+/// use generic_array::typenum::U8;
+/// use neptune::poseidon::PoseidonConstants;
+/// use neptune::round_constants;
+/// use neptune::round_numbers::{calc_round_numbers, DEFAULT_SECURITY_LEVEL};
+/// use neptune::Sbox;
+///
+/// let (rf, rp) = calc_round_numbers(9, 255, DEFAULT_SECURITY_LEVEL, true, Sbox::Quintic).unwrap();
+/// let constants = round_constants::generate::<Bls12>(9, rf, rp);
+/// assert_eq!(PoseidonConstants::<Bls12, U8>::new().round_constants, constants);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a parameter does not fit in its bits of the seed: 12 bits for `t`, and 10 bits for `rf` and `rp`.
+pub fn generate<E: ScalarEngine>(t: usize, rf: usize, rp: usize) -> Vec<E::Fr> {
+    GrainLfsr::<E>::new(E::Fr::NUM_BITS as usize, DEFAULT_SBOX, t, rf, rp)
+        .take(t * (rf + rp))
+        .collect()
 }
 
-struct Grain {
-    state: Vec<bool>,
+/// Returns the constants of `generate_constants`, generating them only once per field and parameters. The cache is
+/// never emptied, so it is only used for the round numbers of `Strength::Standard` and `Strength::Strengthened`, see
+/// `round_constants` in the crate root.
+///
+/// The map of entries is only locked to find the entry, and each entry is locked while it is generated, so
+/// concurrent callers wait for a single generation of the same constants without blocking other parameters.
+pub(crate) fn cached_constants<E: ScalarEngine>(
+    field: u8,
+    sbox: u8,
     field_size: u16,
-}
-
-impl Grain {
-    fn new(init_sequence: Vec<bool>, field_size: u16) -> Self {
-        assert_eq!(80, init_sequence.len());
-        let mut g = Grain {
-            state: init_sequence,
-            field_size,
-        };
-        for _ in 0..160 {
-            g.generate_new_bit();
-        }
-        assert_eq!(80, g.state.len());
-        g
-    }
-
-    fn generate_new_bit(&mut self) -> bool {
-        let new_bit =
-            self.bit(62) ^ self.bit(51) ^ self.bit(38) ^ self.bit(23) ^ self.bit(13) ^ self.bit(0);
-        self.state.remove(0);
-        self.state.push(new_bit);
-        new_bit
-    }
-
-    fn bit(&self, index: usize) -> bool {
-        self.state[index]
-    }
-
-    fn next_byte(&mut self, bit_count: usize) -> u8 {
-        // Accumulate bits from most to least significant, so the most significant bit is the one generated first by the bit stream.
-        let mut acc: u8 = 0;
-        self.take(bit_count).for_each(|bit| {
-            acc <<= 1;
-            if bit {
-                acc += 1;
-            }
-        });
-
-        acc
-    }
-
-    fn get_next_bytes(&mut self, result: &mut [u8]) {
-        let full_bytes = self.field_size as usize / 8;
-        let remainder_bits = self.field_size as usize % 8;
+    t: u16,
+    r_f: u16,
+    r_p: u16,
+) -> Vec<E::Fr> {
+    let key = (TypeId::of::<E::Fr>(), field, sbox, field_size, t, r_f, r_p);
+    #[cfg(test)]
+    let generated_key = key.clone();
+    let entry = ROUND_CONSTANTS
+        .lock()
+        .unwrap()
+        .entry(key)
+        .or_default()
+        .clone();
 
-        // Prime fields will always have remainder bits,
-        // but other field types could be supported in the future.
-        if remainder_bits > 0 {
-            // If there is an unfull byte, it should be the first.
-            result[0] = self.next_byte(remainder_bits);
+    let mut constants = entry.lock().unwrap();
+    if constants.is_none() {
+        #[cfg(test)]
+        GENERATED.lock().unwrap().push(generated_key);
 
-            // Subsequent bytes are packed into result in the order generated.
-            for i in 1..=full_bytes {
-                result[i] = self.next_byte(8);
-            }
-        } else {
-            for i in 0..full_bytes {
-                result[i] = self.next_byte(8);
-            }
-        }
+        let generated: Box<dyn Any + Send> = Box::new(generate_constants::<E>(
+            field, sbox, field_size, t, r_f, r_p,
+        ));
+        *constants = Some(generated);
     }
-}
-
-impl Iterator for Grain {
-    type Item = bool;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut new_bit = self.generate_new_bit();
-        while !new_bit {
-            let _new_bit = self.generate_new_bit();
-            new_bit = self.generate_new_bit();
-        }
-        new_bit = self.generate_new_bit();
-        Some(new_bit)
-    }
+    constants
+        .as_ref()
+        .and_then(|constants| constants.downcast_ref::<Vec<E::Fr>>())
+        .unwrap()
+        .clone()
 }
 
 #[allow(dead_code)]
@@ -184,26 +165,6 @@ pub fn bits_to_bytes(bits: &[bool]) -> Vec<u8> {
         .collect()
 }
 
-// Takes a slice of bytes and returns an Fr if byte slice is exactly 32 bytes and does not overflow.
-// Otherwise, returns a BadFrBytesError.
-fn bytes_into_fr<E: ScalarEngine>(bytes: &[u8]) -> Result<E::Fr, PrimeFieldDecodingError> {
-    assert_eq!(bytes.len(), 32);
-
-    let mut fr_repr = <<<E as ScalarEngine>::Fr as PrimeField>::Repr as Default>::default();
-    fr_repr
-        // Try to read one field element from big-endian bytes.
-        // Bytes are big-endian to agree with the integers generated by grain_random_bits in the reference implementation:
-        //
-        // def grain_random_bits(num_bits):
-        //     random_bits = [grain_gen.next() for i in range(0, num_bits)]
-        //     random_int = int("".join(str(i) for i in random_bits), 2)
-        //     return random_int
-        .read_be(bytes)
-        .map_err(|e| PrimeFieldDecodingError::NotInField(e.to_string()))?;
-
-    E::Fr::from_repr(fr_repr)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,6 +173,99 @@ mod tests {
     use std::fs::File;
     use std::io::{BufRead, BufReader};
     use std::path::Path;
+    #[test]
+    fn test_cached_constants() {
+        use crate::poseidon::PoseidonConstants;
+        use crate::Strength;
+        use generic_array::typenum::U5;
+        use std::thread;
+
+        let constants: Vec<_> = (0..8)
+            .map(|_| {
+                thread::spawn(|| {
+                    PoseidonConstants::<Bls12, U5>::new_with_strength(Strength::Strengthened)
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        for c in constants.iter() {
+            assert_eq!(constants[0], *c);
+        }
+        let (full_rounds, partial_rounds) = (
+            constants[0].full_rounds as u16,
+            constants[0].partial_rounds as u16,
+        );
+        assert_eq!(
+            generate_constants::<Bls12>(1, 1, 255, 6, full_rounds, partial_rounds),
+            constants[0].round_constants
+        );
+
+        let key = (
+            TypeId::of::<Scalar>(),
+            1,
+            1,
+            255,
+            6,
+            full_rounds,
+            partial_rounds,
+        );
+        let generations = GENERATED
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|k| **k == key)
+            .count();
+        assert_eq!(1, generations);
+    }
+
+    #[test]
+    fn test_custom_constants_are_not_cached() {
+        use crate::poseidon::PoseidonConstants;
+        use generic_array::typenum::U5;
+
+        // Round numbers of neither strength at width 6.
+        let constants = PoseidonConstants::<Bls12, U5>::new_with_rounds_unchecked(8, 61);
+        assert_eq!(
+            generate_constants::<Bls12>(1, 1, 255, 6, 8, 61),
+            constants.round_constants
+        );
+        let seeded = PoseidonConstants::<Bls12, U5>::new_seeded(b"abc");
+        assert_ne!(
+            PoseidonConstants::<Bls12, U5>::new().round_constants,
+            seeded.round_constants
+        );
+
+        let key = (TypeId::of::<Scalar>(), 1, 1, 255, 6, 8, 61);
+        assert!(!ROUND_CONSTANTS.lock().unwrap().contains_key(&key));
+        assert!(!GENERATED.lock().unwrap().contains(&key));
+    }
+
+    #[test]
+    fn test_generate() {
+        use crate::round_numbers::{calc_round_numbers, DEFAULT_SECURITY_LEVEL};
+        use crate::Sbox;
+
+        // The first constants of the reference script for BLS12-381 at width 9, as in `parameters/round_constants-*`.
+        let (rf, rp) =
+            calc_round_numbers(9, 255, DEFAULT_SECURITY_LEVEL, true, Sbox::Quintic).unwrap();
+        assert_eq!((8, 57), (rf, rp));
+        let constants = generate::<Bls12>(9, rf, rp);
+        assert_eq!(9 * (8 + 57), constants.len());
+        let expected = [
+            "Fr(0x6ce90d12c4045fe08c3caddb776dd84ac52b4ae7e48cd49443984154f2f4c2f9)",
+            "Fr(0x0ccd9b480c84022b25a75b52c8e89d80b4fa6378d32e58f3a175504940bcec39)",
+            "Fr(0x513ad11016ab7bb8c7ce9ae51256040e062487edf7168d206ba1b86dfe963629)",
+        ];
+        for (constant, expected) in constants.iter().zip(expected.iter()) {
+            assert_eq!(*expected, constant.to_string());
+        }
+
+        assert_eq!(generate_constants::<Bls12>(1, 1, 255, 9, 8, 57), constants);
+    }
+
     #[test]
     fn test_round_constants() {
         // Bls12_381 modulus = 52435875175126190479447740508185965837690552500527637822603658699938581184513
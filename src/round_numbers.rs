@@ -0,0 +1,3342 @@
+use crate::error::RoundNumberError;
+use crate::{Error, Sbox, Strength};
+use core::fmt;
+use core::ops::{Add, Div, Mul, Sub};
+
+/// The default number of bits of the Poseidon prime field modulus. Denoted `n` in the Poseidon paper
+/// (where `n = ceil(log2(p))`). Note that BLS12-381's scalar field modulus is 255 bits, however we
+/// use 256 bits for simplicity when operating on bytes as the single bit difference does not affect
+/// the round number security properties.
+pub const DEFAULT_PRIME_BITLEN: usize = 256;
+
+/// Returns `DEFAULT_PRIME_BITLEN`, the prime bit length `n` for which `round_numbers` and `RoundNumbers::new` give
+/// round numbers and costs.
+pub const fn prime_bitlen() -> usize {
+    DEFAULT_PRIME_BITLEN
+}
+
+/// The default security level (in bits), denoted `M` in the Poseidon paper.
+pub const DEFAULT_SECURITY_LEVEL: usize = 128;
+
+/// The default S-box exponent, `alpha`, of the `x^alpha` S-box used by `neptune`.
+pub const DEFAULT_ALPHA: i64 = 5;
+
+/// The minimum security level (in bits) accepted by `calc_round_numbers_with_options`.
+pub const MIN_SECURITY_LEVEL: usize = 80;
+
+/// The maximum security level (in bits) accepted by `calc_round_numbers_with_options`.
+pub const MAX_SECURITY_LEVEL: usize = 256;
+
+/// The security margin recommended by the Poseidon paper: two more full rounds and 7.5% more partial rounds.
+pub const DEFAULT_SECURITY_MARGIN: SecurityMargin = SecurityMargin {
+    extra_full_rounds: 2,
+    partial_multiplier: 1.075,
+};
+
+/// No security margin, leaving the minimal secure round numbers unchanged.
+pub const NO_SECURITY_MARGIN: SecurityMargin = SecurityMargin {
+    extra_full_rounds: 0,
+    partial_multiplier: 1.0,
+};
+
+/// The margin added to each secure candidate `(R_F, R_P)` before the S-box cost is minimized: `R_F` is increased by
+/// `extra_full_rounds`, and `R_P` is multiplied by `partial_multiplier`, rounding up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SecurityMargin {
+    /// The number of full rounds added. Must be even, as the full rounds are split evenly around the partial rounds.
+    pub extra_full_rounds: usize,
+    /// The factor by which partial rounds are multiplied. Must be at least 1.
+    pub partial_multiplier: f32,
+}
+
+impl Default for SecurityMargin {
+    fn default() -> Self {
+        DEFAULT_SECURITY_MARGIN
+    }
+}
+
+impl From<bool> for SecurityMargin {
+    /// Returns `DEFAULT_SECURITY_MARGIN` for `true`, and `NO_SECURITY_MARGIN` for `false`.
+    fn from(security_margin: bool) -> Self {
+        if security_margin {
+            DEFAULT_SECURITY_MARGIN
+        } else {
+            NO_SECURITY_MARGIN
+        }
+    }
+}
+
+impl SecurityMargin {
+    fn validate(&self) {
+        assert!(
+            self.extra_full_rounds % 2 == 0,
+            "extra full rounds must be even: {}",
+            self.extra_full_rounds
+        );
+        assert!(
+            self.partial_multiplier >= 1.0,
+            "partial round multiplier must be at least 1: {}",
+            self.partial_multiplier
+        );
+    }
+
+    // Checks the margin as `validate` does, but returns an error rather than panicking, for margins read with
+    // constants.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if self.extra_full_rounds % 2 != 0
+            || self.partial_multiplier.is_nan()
+            || self.partial_multiplier < 1.0
+        {
+            return Err(Error::InvalidConstants(format!(
+                "invalid security margin: {:?}",
+                self
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the round numbers `(rf, rp)` with this margin added: `rf + extra_full_rounds` full rounds, and
+    /// `ceil(partial_multiplier * rp)` partial rounds, multiplying in `f32` as the reference script does.
+    pub fn apply(&self, rf: usize, rp: usize) -> (usize, usize) {
+        (
+            rf + self.extra_full_rounds,
+            (self.partial_multiplier * rp as f32).ceil() as usize,
+        )
+    }
+}
+
+/// Returns the round numbers `(rf, rp)` with `DEFAULT_SECURITY_MARGIN` added: two more full rounds, and 7.5% more
+/// partial rounds, rounding up. This applies the margin of `calc_round_numbers` to round numbers found elsewhere.
+///
+/// As 1.075 is not exact in `f32`, the partial rounds round up once more than `ceil(43 * rp / 40)` for some multiples
+/// of 40 from 200.
+///
+/// # Example
+///
+/// ```
+/// use neptune::round_numbers::apply_security_margin;
+///
+/// assert_eq!(apply_security_margin(6, 51), (8, 55));
+/// ```
+pub fn apply_security_margin(rf: usize, rp: usize) -> (usize, usize) {
+    DEFAULT_SECURITY_MARGIN.apply(rf, rp)
+}
+
+/// How the round number search chooses among the secure candidates of lowest S-box cost, `t * R_F + R_P`, after the
+/// security margin is applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Prefer fewer full rounds. This is the default, and the choice of the reference script.
+    MinFullRounds,
+    /// Prefer fewer partial rounds, and so more full rounds.
+    MinPartialRounds,
+    /// Prefer the fewest S-boxes before the security margin is applied, then fewer full rounds. As the margin only adds
+    /// rounds, this usually agrees with `MinFullRounds`, differing where the margin's rounding favors more full rounds.
+    MinSboxes,
+}
+
+impl Default for TieBreak {
+    fn default() -> Self {
+        TieBreak::MinFullRounds
+    }
+}
+
+/// The floating point precision in which the security inequalities are evaluated for S-boxes other than `x^5`, whose
+/// bounds involve `log2(alpha)`. The inequalities for `x^5` are evaluated exactly in integers with either precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// `f32`, as in the round numbers of earlier versions. This is the default.
+    F32,
+    /// `f64`, as the reference script evaluates the inequalities with Python floats.
+    F64,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Precision::F32
+    }
+}
+
+/// The statistical bound of the Poseidon paper, used by the reference script.
+pub const DEFAULT_STATISTICAL_BOUND: StatisticalBound = StatisticalBound {
+    threshold_offset: 3,
+    full_rounds_below: 6,
+    full_rounds_above: 10,
+};
+
+/// The bound on `R_F` from statistical (differential and linear) attacks, which does not depend on `R_P`:
+/// `full_rounds_below` full rounds when `M <= (n - threshold_offset) * (t + 1)`, and `full_rounds_above` otherwise.
+///
+/// The Poseidon paper (https://eprint.iacr.org/2019/458), in its analysis of statistical attacks (Section 5.5.1),
+/// requires 6 full rounds when `M <= (floor(log2(p)) - C) * (t + 1)`, where `C = log2(alpha - 1)`, and 10 otherwise.
+/// The reference script evaluates the threshold as `floor(log2(p) - (alpha - 1) / 2)`, which for `x^5` and a prime of
+/// `n` bits is `n - 3`. These are the values of `DEFAULT_STATISTICAL_BOUND`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatisticalBound {
+    /// Subtracted from the prime bit length `n` in the threshold `(n - threshold_offset) * (t + 1)`.
+    pub threshold_offset: usize,
+    /// The full rounds required when the security level `M` is at most the threshold.
+    pub full_rounds_below: usize,
+    /// The full rounds required when the security level `M` exceeds the threshold.
+    pub full_rounds_above: usize,
+}
+
+impl Default for StatisticalBound {
+    fn default() -> Self {
+        DEFAULT_STATISTICAL_BOUND
+    }
+}
+
+impl StatisticalBound {
+    /// Returns the fewest full rounds this bound requires for width `t`, a prime of `n` bits, and a security level of
+    /// `m` bits.
+    pub const fn full_rounds(&self, t: usize, n: usize, m: usize) -> usize {
+        if m as i64 <= (n as i64 - self.threshold_offset as i64) * (t as i64 + 1) {
+            self.full_rounds_below
+        } else {
+            self.full_rounds_above
+        }
+    }
+}
+
+/// The number of S-boxes (also called the "cost") given by equation (14) in the Poseidon paper:
+/// `cost = t * R_F + R_P`.
+pub fn n_sboxes(t: usize, rf: usize, rp: usize) -> usize {
+    t * rf + rp
+}
+
+/// The size cost of the reference script: the S-box cost multiplied by the prime bit length, `n * (t * R_F + R_P)`.
+/// This compares the cost of round numbers across fields of different sizes.
+pub fn size_cost(t: usize, rf: usize, rp: usize, prime_bitlen: usize) -> usize {
+    n_sboxes(t, rf, rp) * prime_bitlen
+}
+
+/// The cost of a Poseidon permutation, for comparing configurations. See `poseidon_cost`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoseidonCost {
+    /// The total number of S-boxes, `t * R_F + R_P`, of equation (14) of the Poseidon paper. See `n_sboxes`.
+    pub n_sboxes: usize,
+    /// The S-boxes of the full rounds, `t * R_F`.
+    pub n_full_round_sboxes: usize,
+    /// The S-boxes of the partial rounds, `R_P`.
+    pub n_partial_round_sboxes: usize,
+    /// The number of R1CS constraints of the permutation with an `x^5` S-box, `3 * (t * R_F + R_P)`.
+    pub circuit_constraints_estimate: usize,
+    /// The S-box cost multiplied by the prime bit length, `n * (t * R_F + R_P)`. See `size_cost`.
+    pub size_cost_bits: usize,
+}
+
+/// Returns the cost of a permutation of width `t` with `rf` full and `rp` partial rounds over a prime field of
+/// `prime_bitlen` bits.
+///
+/// The circuit constraints are estimated as in the Poseidon paper: the `x^5` S-box takes three multiplications,
+/// `x^2`, `x^4`, and `x^5`, each an R1CS constraint, while the round constants and linear layers are linear
+/// combinations, which need none. `circuit::poseidon_hash` needs three fewer, as the first S-box is applied to the
+/// constant domain tag, and one more for each element output by a linear layer that is not an S-box input, i.e.
+/// `3 * (t * R_F + R_P - 1) + t * R_F + R_P - (t - 1)` in all.
+///
+/// For the security inequalities which the round numbers must satisfy, see `round_number_security_report`.
+///
+/// # Example
+///
+/// ```
+/// use neptune::round_numbers::poseidon_cost;
+///
+/// let cost = poseidon_cost(3, 8, 55, 255);
+/// assert_eq!(79, cost.n_sboxes);
+/// assert_eq!(3 * 79, cost.circuit_constraints_estimate);
+/// ```
+pub fn poseidon_cost(t: usize, rf: usize, rp: usize, prime_bitlen: usize) -> PoseidonCost {
+    let n_sboxes = n_sboxes(t, rf, rp);
+    PoseidonCost {
+        n_sboxes,
+        n_full_round_sboxes: t * rf,
+        n_partial_round_sboxes: rp,
+        circuit_constraints_estimate: 3 * n_sboxes,
+        size_cost_bits: size_cost(t, rf, rp, prime_bitlen),
+    }
+}
+
+/// Returns the round numbers `(R_F, R_P)` for a given width `t`, in that order, over a prime field of
+/// `prime_bitlen` bits (`n` in the Poseidon paper) at a security level of `security_level` bits (`M`).
+///
+/// These are the secure round numbers minimizing the S-box cost, with ties broken in favor of fewer full rounds. When
+/// `security_margin` is `true`, two full rounds and 7.5% more partial rounds (rounded up) are added to each secure
+/// candidate, as recommended by the Poseidon paper. See `calc_round_numbers_with_options` to configure the margin, the
+/// tie break, and the other parameters of the search.
+///
+/// Note that `security_margin` does not select the 'strengthened' round numbers, see `Strength::Strengthened`.
+///
+/// The security inequalities depend on the `sbox`: see `RoundNumberOptions::alpha` for `x^alpha`. The inverse
+/// S-box bounds constrain `R_P` given `R_F`, following the reference script for `x^-1`.
+///
+/// For each even number of full rounds that could yield the lowest cost, the search takes the fewest secure partial
+/// rounds (at least 4), computed from the security inequalities rather than by trying each `R_P` in turn. There is no
+/// upper bound on `R_P`, so high security levels are met with as many partial rounds as required.
+///
+/// Returns `Error::InvalidSecurityLevel` unless `security_level` is within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`,
+/// and `Error::RoundNumberError` if no secure candidate is found.
+///
+/// # Example
+///
+/// ```
+/// use neptune::round_numbers::{calc_round_numbers, DEFAULT_PRIME_BITLEN, DEFAULT_SECURITY_LEVEL};
+/// use neptune::Sbox;
+///
+/// let (rf, rp) = calc_round_numbers(
+///     3,
+///     DEFAULT_PRIME_BITLEN,
+///     DEFAULT_SECURITY_LEVEL,
+///     true,
+///     Sbox::Quintic,
+/// )
+/// .unwrap();
+/// assert_eq!((rf, rp), (8, 55));
+/// ```
+pub fn calc_round_numbers(
+    t: usize,
+    prime_bitlen: usize,
+    security_level: usize,
+    security_margin: bool,
+    sbox: Sbox,
+) -> Result<(usize, usize), Error> {
+    validate_security_level(security_level)?;
+    Ok(round_numbers_for_alpha(
+        t,
+        prime_bitlen,
+        security_level,
+        security_margin.into(),
+        sbox.alpha(),
+    )?)
+}
+
+/// The parameters of the round number search of `calc_round_numbers_with_options`.
+///
+/// The default options are those of `calc_round_numbers` with the security margin: an `x^5` S-box over a field of
+/// `DEFAULT_PRIME_BITLEN` bits at a security level of `DEFAULT_SECURITY_LEVEL` bits, with `DEFAULT_SECURITY_MARGIN`
+/// and `DEFAULT_STATISTICAL_BOUND`. Set only the options that differ:
+///
+/// ```
+/// use neptune::round_numbers::{calc_round_numbers_with_options, RoundNumberOptions, TieBreak};
+///
+/// let options = RoundNumberOptions {
+///     tie_break: TieBreak::MinPartialRounds,
+///     ..RoundNumberOptions::default()
+/// };
+/// let (rf, rp) = calc_round_numbers_with_options(3, &options).unwrap();
+/// assert_eq!((rf, rp), (8, 55));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoundNumberOptions {
+    /// The bit length of the prime modulus, `n` in the Poseidon paper.
+    pub prime_bitlen: usize,
+    /// The security level in bits, `M` in the Poseidon paper, within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`.
+    pub security_level: usize,
+    /// The margin added to each secure candidate. The candidate minimizing the S-box cost is chosen after the margin is
+    /// applied, so a larger margin may select different minimal round numbers.
+    pub margin: SecurityMargin,
+    /// The exponent of the `x^alpha` S-box: -1 for `Sbox::Inverse`, or odd and at least 3. The interpolation and
+    /// Gröbner basis bounds scale with `log_alpha(2)`, so a larger exponent requires fewer partial rounds. Note that
+    /// the field must also satisfy `gcd(alpha, p - 1) = 1` for `x^alpha` to be a permutation, which can only be
+    /// checked against the modulus itself, see `field_round_numbers_with_alpha`.
+    pub alpha: i64,
+    /// How to choose among the candidates of lowest S-box cost.
+    pub tie_break: TieBreak,
+    /// The bound on `R_F` from statistical attacks, such as an alternative threshold from a later analysis.
+    pub statistical_bound: StatisticalBound,
+    /// The precision in which the security inequalities are evaluated. Near the boundary of a bound, rounding in `f32`
+    /// can move its ceiling by one, and with it the fewest secure partial rounds. Only S-boxes other than `x^5` are
+    /// affected, as `x^5` is evaluated exactly in integers.
+    pub precision: Precision,
+    /// Whether the partial rounds must be even, for implementations computing partial rounds two at a time. The
+    /// partial rounds of each candidate are rounded up to an even number after the margin is applied, and before the
+    /// candidate of lowest S-box cost is chosen, so the round numbers are the cheapest with even partial rounds, rather
+    /// than those of odd partial rounds rounded up.
+    pub even_partial_rounds: bool,
+}
+
+impl Default for RoundNumberOptions {
+    fn default() -> Self {
+        RoundNumberOptions {
+            prime_bitlen: DEFAULT_PRIME_BITLEN,
+            security_level: DEFAULT_SECURITY_LEVEL,
+            margin: DEFAULT_SECURITY_MARGIN,
+            alpha: DEFAULT_ALPHA,
+            tie_break: TieBreak::default(),
+            statistical_bound: DEFAULT_STATISTICAL_BOUND,
+            precision: Precision::default(),
+            even_partial_rounds: false,
+        }
+    }
+}
+
+/// Returns the round numbers `(R_F, R_P)` for a given width `t` found by the search of `calc_round_numbers`,
+/// configured by `options`.
+///
+/// Returns `Error::InvalidSecurityLevel` unless the security level is within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`,
+/// `Error::InvalidAlpha` unless `alpha` is -1, or odd and at least 3, and `Error::RoundNumberError` if no secure round
+/// numbers are found.
+///
+/// # Example
+///
+/// ```
+/// use neptune::round_numbers::{
+///     calc_round_numbers_with_options, RoundNumberOptions, StatisticalBound, NO_SECURITY_MARGIN,
+/// };
+///
+/// // Require 10 full rounds before the margin at any security level.
+/// let options = RoundNumberOptions {
+///     statistical_bound: StatisticalBound {
+///         threshold_offset: 3,
+///         full_rounds_below: 10,
+///         full_rounds_above: 10,
+///     },
+///     ..RoundNumberOptions::default()
+/// };
+/// let (rf, _) = calc_round_numbers_with_options(3, &options).unwrap();
+/// assert_eq!(rf, 12);
+///
+/// // The inverse S-box, without the security margin, and with even partial rounds.
+/// let options = RoundNumberOptions {
+///     alpha: -1,
+///     margin: NO_SECURITY_MARGIN,
+///     even_partial_rounds: true,
+///     ..RoundNumberOptions::default()
+/// };
+/// let (_, rp) = calc_round_numbers_with_options(3, &options).unwrap();
+/// assert_eq!(rp % 2, 0);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `margin.extra_full_rounds` is odd, or `margin.partial_multiplier` is less than 1.
+pub fn calc_round_numbers_with_options(
+    t: usize,
+    options: &RoundNumberOptions,
+) -> Result<(usize, usize), Error> {
+    let security_level = options.security_level;
+    validate_security_level(security_level)?;
+    validate_alpha(options.alpha)?;
+
+    let defaults = RoundNumberOptions::default();
+    if options.tie_break == defaults.tie_break
+        && options.statistical_bound == defaults.statistical_bound
+        && options.precision == defaults.precision
+        && !options.even_partial_rounds
+    {
+        // The round numbers of the default search may be precomputed.
+        return Ok(round_numbers_for_alpha(
+            t,
+            options.prime_bitlen,
+            security_level,
+            options.margin,
+            options.alpha,
+        )?);
+    }
+
+    options.margin.validate();
+    let inequalities =
+        SecurityInequalities::new(t, options.prime_bitlen, security_level, options.alpha)
+            .with_statistical_bound(options.statistical_bound)
+            .with_precision(options.precision);
+    Ok(search_inequalities(
+        &inequalities,
+        options.margin,
+        options.tie_break,
+        options.even_partial_rounds,
+    )?)
+}
+
+pub(crate) fn validate_security_level(security_level: usize) -> Result<(), Error> {
+    if security_level < MIN_SECURITY_LEVEL || security_level > MAX_SECURITY_LEVEL {
+        return Err(Error::InvalidSecurityLevel(security_level));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_alpha(alpha: i64) -> Result<(), Error> {
+    if alpha != -1 && (alpha < 3 || alpha % 2 == 0) {
+        return Err(Error::InvalidAlpha(alpha));
+    }
+    Ok(())
+}
+
+fn round_numbers_for_alpha(
+    t: usize,
+    prime_bitlen: usize,
+    security_level: usize,
+    margin: SecurityMargin,
+    alpha: i64,
+) -> Result<(usize, usize), RoundNumberError> {
+    margin.validate();
+
+    if PRECOMPUTED_PRIME_BITLENS.contains(&prime_bitlen)
+        && security_level == DEFAULT_SECURITY_LEVEL
+        && margin == DEFAULT_SECURITY_MARGIN
+        && alpha == DEFAULT_ALPHA
+    {
+        // The table is indexed by `t - 2`, as the smallest width is 2.
+        if let Some((_, rf, rp)) = t
+            .checked_sub(2)
+            .and_then(|i| PRECOMPUTED_ROUND_NUMBERS.get(i))
+        {
+            return Ok((*rf, *rp));
+        }
+    }
+
+    search_round_numbers(
+        t,
+        prime_bitlen,
+        security_level,
+        margin,
+        alpha,
+        TieBreak::default(),
+    )
+}
+
+/// The prime bit lengths for which `PRECOMPUTED_ROUND_NUMBERS` hold: `DEFAULT_PRIME_BITLEN` and the 255 bits of
+/// BLS12-381's scalar field modulus, which `PoseidonConstants` uses. Both yield the same round numbers.
+const PRECOMPUTED_PRIME_BITLENS: [usize; 2] = [255, DEFAULT_PRIME_BITLEN];
+
+/// Round numbers `(t, R_F, R_P)` for every width with a typenum `Arity`, as returned by `search_round_numbers` at
+/// the default security level and S-box exponent, with the security margin, and for each of `PRECOMPUTED_PRIME_BITLENS`.
+///
+/// These are the first three columns of `parameters/round_numbers.txt`: regenerate it with
+/// `cargo run --bin gen_round_numbers`, and copy them.
+const PRECOMPUTED_ROUND_NUMBERS: [(usize, usize, usize); 36] = [
+    (2, 8, 55),
+    (3, 8, 55),
+    (4, 8, 56),
+    (5, 8, 56),
+    (6, 8, 56),
+    (7, 8, 56),
+    (8, 8, 57),
+    (9, 8, 57),
+    (10, 8, 57),
+    (11, 8, 57),
+    (12, 8, 57),
+    (13, 8, 57),
+    (14, 8, 57),
+    (15, 8, 57),
+    (16, 8, 59),
+    (17, 8, 59),
+    (18, 8, 59),
+    (19, 8, 59),
+    (20, 8, 59),
+    (21, 8, 59),
+    (22, 8, 59),
+    (23, 8, 59),
+    (24, 8, 59),
+    (25, 8, 59),
+    (26, 8, 59),
+    (27, 8, 59),
+    (28, 8, 59),
+    (29, 8, 59),
+    (30, 8, 59),
+    (31, 8, 59),
+    (32, 8, 60),
+    (33, 8, 60),
+    (34, 8, 60),
+    (35, 8, 60),
+    (36, 8, 60),
+    (37, 8, 60),
+];
+
+/// The smallest number of partial rounds considered by the search.
+const MIN_PARTIAL_ROUNDS: usize = 4;
+
+// Searches for the round numbers minimizing the S-box cost, breaking ties by `tie_break`. See `calc_round_numbers`.
+fn search_round_numbers(
+    t: usize,
+    prime_bitlen: usize,
+    security_level: usize,
+    margin: SecurityMargin,
+    alpha: i64,
+    tie_break: TieBreak,
+) -> Result<(usize, usize), RoundNumberError> {
+    let inequalities = SecurityInequalities::new(t, prime_bitlen, security_level, alpha);
+    search_inequalities(&inequalities, margin, tie_break, false)
+}
+
+// Searches for the round numbers minimizing the S-box cost subject to `inequalities`, rounding the partial rounds of
+// each candidate up to an even number after the margin if `even_partial_rounds`. See `search_round_numbers`.
+fn search_inequalities(
+    inequalities: &SecurityInequalities,
+    margin: SecurityMargin,
+    tie_break: TieBreak,
+    even_partial_rounds: bool,
+) -> Result<(usize, usize), RoundNumberError> {
+    let t = inequalities.t;
+
+    // The best secure candidate so far, as `((cost, tie, R_F), R_P)`: ordering these tuples minimizes the cost, then
+    // the quantity selected by `tie_break`, then the full rounds.
+    let mut best: Option<((usize, usize, usize), usize)> = None;
+
+    let max_full_rounds = match max_full_rounds(&inequalities, margin) {
+        // Rounding up adds at most one partial round to the cost of `(rf_stat, rp_min)`.
+        Some(max_full_rounds) if even_partial_rounds => max_full_rounds + 1,
+        Some(max_full_rounds) => max_full_rounds,
+        None => return Err(RoundNumberError::NoSecureParameters { t }),
+    };
+
+    // With `R_F` fixed, the cost only grows with `R_P`, so only the fewest secure partial rounds are candidates.
+    for rf_secure in (inequalities.rf_stat..=max_full_rounds).step_by(2) {
+        if let Some(rp_secure) = inequalities.min_partial_rounds(rf_secure) {
+            let (rf, mut rp) = margin.apply(rf_secure, rp_secure);
+            if even_partial_rounds {
+                rp += rp % 2;
+            }
+            let tie = match tie_break {
+                TieBreak::MinFullRounds => rf,
+                TieBreak::MinPartialRounds => rp,
+                TieBreak::MinSboxes => n_sboxes(t, rf_secure, rp_secure),
+            };
+            let candidate = ((n_sboxes(t, rf, rp), tie, rf), rp);
+            if best.map_or(true, |best| candidate < best) {
+                best = Some(candidate);
+            }
+        }
+    }
+
+    best.map(|((_, _, rf), rp)| (rf, rp))
+        .ok_or(RoundNumberError::NoSecureParameters { t })
+}
+
+// Returns the largest `R_F` (before the margin) that the search must consider to find the minimum S-box cost, or
+// `None` if no round numbers are secure.
+//
+// The statistical bound `rf_stat` is the fewest full rounds that any number of partial rounds can make secure. A
+// candidate with `R_F` full rounds costs at least `t * R_F` (plus the margin), while `(rf_stat, rp_min)`, with the
+// fewest partial rounds secure with `rf_stat`, costs `t * rf_stat + R_P'`, where `R_P'` is `rp_min` with the margin.
+// So no candidate with more than `rf_stat + R_P' / t` full rounds can be cheaper.
+fn max_full_rounds(inequalities: &SecurityInequalities, margin: SecurityMargin) -> Option<usize> {
+    let rf_stat = inequalities.rf_stat;
+    let rp_min = inequalities.min_partial_rounds(rf_stat)?;
+
+    let (_, rp_with_margin) = margin.apply(rf_stat, rp_min);
+    let t = inequalities.t;
+    Some(rf_stat + (rp_with_margin + t - 1) / t.max(1))
+}
+
+// Returns the secure round numbers on the Pareto frontier of full and partial rounds, with `margin` applied, in
+// increasing `R_F` and decreasing `R_P`.
+//
+// Each even `R_F` from `rf_stat` is paired with the fewest partial rounds secure with it. Every bound on `R_P` is
+// non-increasing in `R_F`, so once the fewest partial rounds stop decreasing, the bound that holds does not depend on
+// `R_F`, and no more full rounds can save a partial round.
+fn round_number_frontier(
+    inequalities: &SecurityInequalities,
+    margin: SecurityMargin,
+) -> Vec<(usize, usize)> {
+    let mut frontier = Vec::new();
+    let mut rf_secure = inequalities.rf_stat;
+    let mut previous: Option<usize> = None;
+    while let Some(rp_secure) = inequalities.min_partial_rounds(rf_secure) {
+        if previous.map_or(false, |previous| rp_secure >= previous) {
+            break;
+        }
+        frontier.push(margin.apply(rf_secure, rp_secure));
+        if rp_secure == MIN_PARTIAL_ROUNDS {
+            break;
+        }
+        previous = Some(rp_secure);
+        rf_secure += 2;
+    }
+    frontier
+}
+
+/// Returns the round numbers `(R_F, R_P)` for a given width `t` minimizing `cost(R_F, R_P, t)` rather than the S-box
+/// cost, such as the constraints of a circuit, in which a partial round costs much less than a full round. See
+/// `calc_round_numbers`, which this matches with the cost `|rf, rp, t| n_sboxes(t, rf, rp)`.
+///
+/// The candidates are the round numbers of `round_number_candidates`: for each number of full rounds, the fewest
+/// secure partial rounds. Ties are broken in favor of fewer full rounds. The cost should not decrease with more full
+/// or partial rounds, as only these candidates are considered.
+///
+/// Returns `Error::InvalidSecurityLevel` unless `security_level` is within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`,
+/// and `Error::RoundNumberError` if no secure candidate is found.
+///
+/// # Example
+///
+/// ```
+/// use neptune::round_numbers::{
+///     calc_round_numbers_with_cost, DEFAULT_PRIME_BITLEN, DEFAULT_SECURITY_LEVEL,
+/// };
+/// use neptune::Sbox;
+///
+/// // Partial rounds cost a hundred times as much as full rounds.
+/// let (rf, rp) = calc_round_numbers_with_cost(
+///     3,
+///     DEFAULT_PRIME_BITLEN,
+///     DEFAULT_SECURITY_LEVEL,
+///     true,
+///     Sbox::Quintic,
+///     |rf, rp, _| rf + 100 * rp,
+/// )
+/// .unwrap();
+/// assert_eq!((rf, rp), (56, 5));
+/// ```
+pub fn calc_round_numbers_with_cost<F>(
+    t: usize,
+    prime_bitlen: usize,
+    security_level: usize,
+    security_margin: bool,
+    sbox: Sbox,
+    cost: F,
+) -> Result<(usize, usize), Error>
+where
+    F: Fn(usize, usize, usize) -> usize,
+{
+    validate_security_level(security_level)?;
+    let inequalities = SecurityInequalities::new(t, prime_bitlen, security_level, sbox.alpha());
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (rf, rp) in round_number_frontier(&inequalities, security_margin.into()) {
+        let candidate_cost = cost(rf, rp, t);
+        if best.map_or(true, |(best_cost, _, _)| candidate_cost < best_cost) {
+            best = Some((candidate_cost, rf, rp));
+        }
+    }
+
+    Ok(best
+        .map(|(_, rf, rp)| (rf, rp))
+        .ok_or(RoundNumberError::NoSecureParameters { t })?)
+}
+
+/// Returns every secure choice of round numbers for width `t` and `strength` that is not beaten on both full and
+/// partial rounds by another, in increasing `R_F`, with their costs. These are the candidates from which
+/// `RoundNumbers::new` chooses the lowest S-box cost, for an `x^5` S-box over a field of `DEFAULT_PRIME_BITLEN` bits at
+/// the default security level of 128 bits, with the security margin.
+///
+/// For `Strength::Strengthened`, the partial rounds of each candidate are increased by 25%. `Strength::Custom` yields
+/// its own round numbers if they are secure, and no candidates otherwise.
+pub fn round_number_candidates(t: usize, strength: Strength) -> Vec<RoundNumbers> {
+    let candidates = match strength {
+        Strength::Custom { full, partial } => {
+            if check_rounds_are_secure(full, partial, t, &strength).is_ok() && full % 2 == 0 {
+                vec![(full, partial)]
+            } else {
+                Vec::new()
+            }
+        }
+        _ => {
+            let inequalities = SecurityInequalities::new(
+                t,
+                DEFAULT_PRIME_BITLEN,
+                DEFAULT_SECURITY_LEVEL,
+                DEFAULT_ALPHA,
+            );
+            let frontier = round_number_frontier(&inequalities, DEFAULT_SECURITY_MARGIN);
+            if strength == Strength::Strengthened {
+                frontier
+                    .into_iter()
+                    .map(|(rf, rp)| (rf, strengthened_partial_rounds(rp)))
+                    .collect()
+            } else {
+                frontier
+            }
+        }
+    };
+
+    candidates
+        .into_iter()
+        .map(|(rf, rp)| RoundNumbers::from_rounds(t, rf, rp, DEFAULT_PRIME_BITLEN))
+        .collect()
+}
+
+/// Returns the round numbers `(R_F, R_P)` for width `t` that `calc_round_numbers` finds for an `x^5` S-box over a
+/// field of `DEFAULT_PRIME_BITLEN` bits at the default security level of 128 bits, with the security margin. See
+/// `const_calc_round_numbers`.
+///
+/// ```
+/// use neptune::const_round_numbers;
+///
+/// const RF_RP: (usize, usize) = const_round_numbers(3);
+/// assert_eq!(RF_RP, (8, 55));
+/// ```
+pub const fn const_round_numbers(t: usize) -> (usize, usize) {
+    const_calc_round_numbers(t, DEFAULT_PRIME_BITLEN, DEFAULT_SECURITY_LEVEL, true)
+}
+
+/// Returns the round numbers `(R_F, R_P)` that `calc_round_numbers` finds for width `t`, a field of `prime_bitlen`
+/// bits and a security level of `security_level` bits, with an `x^5` S-box.
+///
+/// As a `const fn`, this can fix round numbers at compile time. The search evaluates the security inequalities in
+/// integers, as `calc_round_numbers` does for `x^5`, and emulates the `f32` multiplication of the partial rounds by
+/// 1.075 for the security margin, so the two agree exactly.
+///
+/// ```
+/// use neptune::round_numbers::{const_calc_round_numbers, DEFAULT_SECURITY_LEVEL};
+///
+/// const RF_RP: (usize, usize) = const_calc_round_numbers(3, 255, DEFAULT_SECURITY_LEVEL, true);
+/// assert_eq!(RF_RP, (8, 55));
+/// ```
+///
+/// # Panics
+///
+/// Panics unless `security_level` is within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`, or fails to compile when
+/// evaluated at compile time.
+pub const fn const_calc_round_numbers(
+    t: usize,
+    prime_bitlen: usize,
+    security_level: usize,
+    security_margin: bool,
+) -> (usize, usize) {
+    // A `const fn` cannot panic with a message, so an invalid security level indexes out of bounds instead.
+    let level_is_valid =
+        security_level >= MIN_SECURITY_LEVEL && security_level <= MAX_SECURITY_LEVEL;
+    let _ = [()][!level_is_valid as usize];
+    const_search_round_numbers(t, prime_bitlen, security_level, security_margin)
+}
+
+// The search of `const_calc_round_numbers`, at any security level.
+const fn const_search_round_numbers(
+    t: usize,
+    prime_bitlen: usize,
+    security_level: usize,
+    security_margin: bool,
+) -> (usize, usize) {
+    let (n, m) = (prime_bitlen, security_level);
+    let log2_t_100 = ceil_log2_pow(t as u64, 100);
+    let rf_stat = statistical_full_rounds(t, n, m);
+
+    let (mut best_rf, mut best_rp) = const_margin(
+        rf_stat,
+        const_min_partial_rounds(t, rf_stat, n, m, log2_t_100),
+        security_margin,
+    );
+
+    // As in `max_full_rounds`, no candidate with more full rounds than this can be cheaper.
+    let width = if t > 1 { t } else { 1 };
+    let max_full_rounds = rf_stat + (best_rp + width - 1) / width;
+
+    // Candidates are visited in increasing `R_F`, so keeping the first of equal cost breaks ties as
+    // `search_round_numbers` does.
+    let mut rf_secure = rf_stat + 2;
+    while rf_secure <= max_full_rounds {
+        let (rf, rp) = const_margin(
+            rf_secure,
+            const_min_partial_rounds(t, rf_secure, n, m, log2_t_100),
+            security_margin,
+        );
+        if t * rf + rp < t * best_rf + best_rp {
+            best_rf = rf;
+            best_rp = rp;
+        }
+        rf_secure += 2;
+    }
+
+    (best_rf, best_rp)
+}
+
+// The fewest partial rounds, at least `MIN_PARTIAL_ROUNDS`, that are secure with `rf` full rounds for `x^5` with
+// prime bit length `n` and security level `m`. For `x^5` the bound is exact, see `SecurityInequalities`.
+const fn const_min_partial_rounds(
+    t: usize,
+    rf: usize,
+    n: usize,
+    m: usize,
+    log2_t_100: i64,
+) -> usize {
+    let rp = quintic_partial_rounds_bound(t, rf, n, m, log2_t_100);
+    if rp > MIN_PARTIAL_ROUNDS {
+        rp
+    } else {
+        MIN_PARTIAL_ROUNDS
+    }
+}
+
+// `DEFAULT_SECURITY_MARGIN` applied to `(rf, rp)` if `security_margin`, otherwise `(rf, rp)` unchanged.
+const fn const_margin(rf: usize, rp: usize, security_margin: bool) -> (usize, usize) {
+    if security_margin {
+        (
+            rf + DEFAULT_SECURITY_MARGIN.extra_full_rounds,
+            f32_margin_partial_rounds(rp),
+        )
+    } else {
+        (rf, rp)
+    }
+}
+
+// `ceil(1.075 * rp)` as `SecurityMargin::apply` evaluates it in `f32`. The `f32` nearest 1.075 is `9017754 / 2^23`,
+// and `rp` and the product are each rounded to the 24 significant bits of an `f32` before rounding up.
+const fn f32_margin_partial_rounds(rp: usize) -> usize {
+    let product = round_to_f32(rp as u128) * 9_017_754;
+    ((round_to_f32(product) + (1 << 23) - 1) >> 23) as usize
+}
+
+// Rounds `x` to the 24 significant bits of an `f32`, with ties to even, as converting or multiplying in `f32` does.
+const fn round_to_f32(x: u128) -> u128 {
+    let mut shift = 0;
+    while x >> shift >= 1 << 24 {
+        shift += 1;
+    }
+    if shift == 0 {
+        return x;
+    }
+
+    let (mut mantissa, rest, half) = (x >> shift, x & ((1 << shift) - 1), 1 << (shift - 1));
+    if rest > half || (rest == half && mantissa % 2 == 1) {
+        mantissa += 1;
+    }
+    mantissa << shift
+}
+
+// Round numbers for a given arity, calculated following the script:
+// https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/calc_round_numbers.py
+fn round_numbers_base(
+    arity: usize,
+    prime_bitlen: usize,
+    security_level: usize,
+    alpha: i64,
+    margin: SecurityMargin,
+) -> Result<(usize, usize), RoundNumberError> {
+    let t = arity + 1;
+    round_numbers_for_alpha(t, prime_bitlen, security_level, margin, alpha)
+}
+
+// In case of newly-discovered attacks, we may need stronger security.
+// This option exists so we can preemptively create circuits in order to switch
+// to them quickly if needed.
+//
+// "A realistic alternative is to increase the number of partial rounds by 25%.
+// Then it is unlikely that a new attack breaks through this number,
+// but even if this happens then the complexity is almost surely above 2^64, and you will be safe."
+// - D Khovratovich
+fn round_numbers_strengthened(
+    arity: usize,
+    prime_bitlen: usize,
+    security_level: usize,
+    alpha: i64,
+    margin: SecurityMargin,
+) -> Result<(usize, usize), RoundNumberError> {
+    let (full_round, partial_rounds) =
+        round_numbers_base(arity, prime_bitlen, security_level, alpha, margin)?;
+
+    Ok((full_round, strengthened_partial_rounds(partial_rounds)))
+}
+
+// Increases `partial_rounds` by 25%, rounding up.
+fn strengthened_partial_rounds(partial_rounds: usize) -> usize {
+    f64::ceil(partial_rounds as f64 * 1.25) as usize
+}
+
+/// Returns the round numbers `(R_F, R_P)` for `arity` and `strength`.
+pub fn round_numbers(arity: usize, strength: &Strength) -> (usize, usize) {
+    round_numbers_with_security(arity, strength, DEFAULT_SECURITY_LEVEL)
+}
+
+/// Returns the round numbers `(R_F, R_P)` for `arity` and `strength`, targeting a security level of
+/// `security_level` bits rather than the default of 128 bits.
+///
+/// # Panics
+///
+/// Panics if `security_level` is not within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`, or if no secure round numbers
+/// are found, see `calc_round_numbers`.
+pub fn round_numbers_with_security(
+    arity: usize,
+    strength: &Strength,
+    security_level: usize,
+) -> (usize, usize) {
+    round_numbers_for_prime(
+        arity,
+        strength,
+        DEFAULT_PRIME_BITLEN,
+        security_level,
+        DEFAULT_ALPHA,
+        DEFAULT_SECURITY_MARGIN,
+    )
+    .unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub(crate) fn round_numbers_for_prime(
+    arity: usize,
+    strength: &Strength,
+    prime_bitlen: usize,
+    security_level: usize,
+    alpha: i64,
+    margin: SecurityMargin,
+) -> Result<(usize, usize), Error> {
+    validate_security_level(security_level)?;
+    match strength {
+        Strength::Standard => Ok(round_numbers_base(
+            arity,
+            prime_bitlen,
+            security_level,
+            alpha,
+            margin,
+        )?),
+        Strength::Strengthened => Ok(round_numbers_strengthened(
+            arity,
+            prime_bitlen,
+            security_level,
+            alpha,
+            margin,
+        )?),
+        Strength::Custom { full, partial } => {
+            if full % 2 != 0 {
+                return Err(RoundNumberError::OddFullRounds { rf: *full }.into());
+            }
+            check_round_numbers(
+                arity + 1,
+                *full,
+                *partial,
+                strength,
+                prime_bitlen,
+                security_level,
+                alpha,
+            )?;
+            Ok((*full, *partial))
+        }
+    }
+}
+
+/// The round numbers of a Poseidon permutation, along with the costs minimized when deriving them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoundNumbers {
+    /// The number of full rounds, `R_F`, in which the S-box is applied to every element of the state.
+    pub full_rounds: usize,
+    /// The number of partial rounds, `R_P`, in which the S-box is applied only to the first element of the state.
+    pub partial_rounds: usize,
+    /// The total number of S-boxes applied, `t * R_F + R_P`. See `n_sboxes`.
+    pub sbox_cost: usize,
+    /// The S-box cost multiplied by the prime bit length, `n`.
+    pub size_cost: usize,
+}
+
+impl RoundNumbers {
+    /// Returns the round numbers used by `neptune` for a permutation of width `t` (`arity + 1`) and `strength`, over
+    /// a field of `DEFAULT_PRIME_BITLEN` bits.
+    pub fn new(t: usize, strength: Strength) -> Self {
+        assert!(t >= 2, "width must be at least 2");
+        let (full_rounds, partial_rounds) = round_numbers(t - 1, &strength);
+        Self::from_rounds(t, full_rounds, partial_rounds, DEFAULT_PRIME_BITLEN)
+    }
+
+    /// Returns the round numbers calculated by `calc_round_numbers` for a permutation of width `t`, at the default
+    /// prime bit length and security level, along with their costs.
+    pub fn compute(t: usize, security_margin: bool) -> Result<Self, RoundNumberError> {
+        let (full_rounds, partial_rounds) = round_numbers_for_alpha(
+            t,
+            DEFAULT_PRIME_BITLEN,
+            DEFAULT_SECURITY_LEVEL,
+            security_margin.into(),
+            DEFAULT_ALPHA,
+        )?;
+        Ok(Self::from_rounds(
+            t,
+            full_rounds,
+            partial_rounds,
+            DEFAULT_PRIME_BITLEN,
+        ))
+    }
+
+    /// Formats the round numbers for width `t` as a line of the five whitespace-separated columns `t R_F R_P
+    /// sbox_cost size_cost`, the format of `parameters/round_numbers.txt`.
+    pub fn to_line(&self, t: usize) -> String {
+        format!(
+            "{} {} {} {} {}",
+            t, self.full_rounds, self.partial_rounds, self.sbox_cost, self.size_cost
+        )
+    }
+
+    /// Parses a line formatted by `to_line`, returning the width `t` and its round numbers. Columns may be separated
+    /// by any whitespace.
+    ///
+    /// Returns `RoundNumberError::MalformedLine` unless the line has exactly five non-negative integer columns.
+    pub fn from_line(line: &str) -> Result<(usize, Self), RoundNumberError> {
+        let malformed = || RoundNumberError::MalformedLine {
+            line: line.to_string(),
+        };
+
+        let columns = line
+            .split_whitespace()
+            .map(|column| column.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| malformed())?;
+
+        match columns.as_slice() {
+            [t, full_rounds, partial_rounds, sbox_cost, size_cost] => Ok((
+                *t,
+                Self {
+                    full_rounds: *full_rounds,
+                    partial_rounds: *partial_rounds,
+                    sbox_cost: *sbox_cost,
+                    size_cost: *size_cost,
+                },
+            )),
+            _ => Err(malformed()),
+        }
+    }
+
+    pub(crate) fn from_rounds(
+        t: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+        prime_bitlen: usize,
+    ) -> Self {
+        let sbox_cost = n_sboxes(t, full_rounds, partial_rounds);
+        Self {
+            full_rounds,
+            partial_rounds,
+            sbox_cost,
+            size_cost: size_cost(t, full_rounds, partial_rounds, prime_bitlen),
+        }
+    }
+}
+
+/// Formats the round numbers as the reference script does in `parameters/round_numbers-*.txt`:
+/// `[R_F, R_P, S-box cost, Size cost]`.
+impl fmt::Display for RoundNumbers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "[{}, {}, {}, {}]",
+            self.full_rounds, self.partial_rounds, self.sbox_cost, self.size_cost
+        )
+    }
+}
+
+/// The evaluated security inequalities of the Poseidon paper for a candidate `(R_F, R_P)` at width `t`.
+///
+/// Each `rf_*` field is the minimum number of full rounds required by one attack, given the candidate's
+/// partial rounds. The candidate is secure when its `R_F` is at least the largest of these (`rf_max`).
+/// Following https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/calc_round_numbers.py
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoundNumberSecurityReport {
+    /// Bound from statistical (differential and linear) attacks: 6 if `M <= (n - 3) * (t + 1)`, otherwise 10. See
+    /// `StatisticalBound`.
+    pub rf_stat: f32,
+    /// Bound from interpolation attacks: `log_alpha(2) * M + log2(t) - R_P`, i.e. `0.43 * M + log2(t) - R_P` for
+    /// `x^5`.
+    pub rf_interp: f32,
+    /// First bound from Gröbner basis attacks: `log_alpha(2) / 2 * n - R_P`, i.e. `0.21 * n - R_P` for `x^5`.
+    pub rf_grob_1: f32,
+    /// Second bound from Gröbner basis attacks: `(log_alpha(2) / 3 * n - 1 - R_P) / (t - 1)`, i.e.
+    /// `(0.14 * n - 1 - R_P) / (t - 1)` for `x^5`.
+    pub rf_grob_2: f32,
+    /// The maximum of the ceilings of the bounds above, i.e. the minimum secure `R_F`. For `x^5`, this is evaluated
+    /// exactly, with the coefficients above as rationals, rather than from the rounded bounds.
+    pub rf_max: usize,
+    /// The inequality requiring the most full rounds, i.e. the one giving `rf_max`. When the candidate is not
+    /// secure, this is the inequality that fails by the widest margin. Ties go to the first in the order above.
+    pub binding_inequality: SecurityInequality,
+    /// `true` if the candidate `R_F` is at least `rf_max`.
+    pub is_secure: bool,
+}
+
+/// One of the four security inequalities of the Poseidon paper, each a lower bound on `R_F`. See
+/// `RoundNumberSecurityReport`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityInequality {
+    /// The bound from statistical (differential and linear) attacks, `rf_stat`.
+    Statistical,
+    /// The bound from interpolation attacks, `rf_interp`.
+    Interpolation,
+    /// The first bound from Gröbner basis attacks, `rf_grob_1`.
+    GroebnerBasis1,
+    /// The second bound from Gröbner basis attacks, `rf_grob_2`.
+    GroebnerBasis2,
+}
+
+impl fmt::Display for SecurityInequality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecurityInequality::Statistical => write!(f, "statistical"),
+            SecurityInequality::Interpolation => write!(f, "interpolation"),
+            SecurityInequality::GroebnerBasis1 => write!(f, "first Gröbner basis"),
+            SecurityInequality::GroebnerBasis2 => write!(f, "second Gröbner basis"),
+        }
+    }
+}
+
+/// Checks `rf` full rounds and `rp` partial rounds for width `t` and `strength` against the security inequalities
+/// of the Poseidon paper, for an `x^5` S-box over a field of `DEFAULT_PRIME_BITLEN` bits at the default security
+/// level of 128 bits.
+///
+/// Strengthened round numbers must have 25% more partial rounds than secure ones, as `Strength::Strengthened`
+/// adds, so `rp` is checked as if strengthened from `floor(rp / 1.25)`.
+///
+/// Returns `RoundNumberError::InsecureRoundNumbers` with the inequality requiring the most full rounds if any fails.
+pub fn check_rounds_are_secure(
+    rf: usize,
+    rp: usize,
+    t: usize,
+    strength: &Strength,
+) -> Result<(), RoundNumberError> {
+    check_round_numbers(
+        t,
+        rf,
+        rp,
+        strength,
+        DEFAULT_PRIME_BITLEN,
+        DEFAULT_SECURITY_LEVEL,
+        DEFAULT_ALPHA,
+    )
+}
+
+pub(crate) fn check_round_numbers(
+    t: usize,
+    rf: usize,
+    rp: usize,
+    strength: &Strength,
+    prime_bitlen: usize,
+    security_level: usize,
+    alpha: i64,
+) -> Result<(), RoundNumberError> {
+    let rp_secure = match strength {
+        Strength::Standard | Strength::Custom { .. } => rp,
+        Strength::Strengthened => rp * 4 / 5,
+    };
+
+    match failed_inequality(t, rf, rp_secure, prime_bitlen, security_level, alpha) {
+        Some(inequality) => Err(RoundNumberError::InsecureRoundNumbers {
+            t,
+            rf,
+            rp,
+            inequality,
+        }),
+        None => Ok(()),
+    }
+}
+
+// Returns the binding inequality of insecure round numbers, or `None` if they are secure. The inverse S-box has a
+// single Gröbner basis bound on `R_P`, reported as `SecurityInequality::GroebnerBasis1`.
+fn failed_inequality(
+    t: usize,
+    rf: usize,
+    rp: usize,
+    n: usize,
+    m: usize,
+    alpha: i64,
+) -> Option<SecurityInequality> {
+    if alpha == -1 {
+        let (rp_interp, rp_grob) = inverse_partial_rounds_bounds::<f32>(t, rf, n, m);
+        if rf < statistical_full_rounds(t, n, m) {
+            Some(SecurityInequality::Statistical)
+        } else if (rp as f32) < rp_interp {
+            Some(SecurityInequality::Interpolation)
+        } else if (rp as f32) < rp_grob {
+            Some(SecurityInequality::GroebnerBasis1)
+        } else {
+            None
+        }
+    } else {
+        let report = security_report(t, rf, rp, n, m, alpha, statistical_full_rounds(t, n, m));
+        if report.is_secure {
+            None
+        } else {
+            Some(report.binding_inequality)
+        }
+    }
+}
+
+/// Returns `true` if `rf` full rounds and `rp` partial rounds satisfy the security inequalities of the Poseidon paper
+/// for an `x^5` S-box at width `t`, a security level of `security_bits`, and a prime field of `prime_bitlen` bits.
+///
+/// This checks round numbers taken from elsewhere, such as a reference implementation, without computing the
+/// minimal ones. The security margin is not required: any round numbers satisfying the inequalities are secure. See
+/// `round_number_security_report` for which inequality fails.
+///
+/// # Example
+///
+/// ```
+/// use neptune::round_numbers::are_round_numbers_secure;
+///
+/// assert!(are_round_numbers_secure(3, 8, 57, 128, 255));
+/// assert!(!are_round_numbers_secure(3, 6, 50, 128, 255));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `security_bits` is not within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`.
+pub fn are_round_numbers_secure(
+    t: usize,
+    rf: usize,
+    rp: usize,
+    security_bits: usize,
+    prime_bitlen: usize,
+) -> bool {
+    validate_security_level(security_bits).unwrap_or_else(|e| panic!("{}", e));
+    round_numbers_are_secure(t, rf, rp, prime_bitlen, security_bits, DEFAULT_ALPHA)
+}
+
+/// Returns `true` if `rf` full rounds and `rp` partial rounds are secure for an `x^alpha` S-box, where an `alpha` of -1
+/// denotes the inverse S-box. See `are_round_numbers_secure`.
+///
+/// The interpolation and Gröbner basis bounds scale with `log_alpha(2)`, so round numbers secure for one exponent
+/// may not be for another. Returns `Error::InvalidAlpha` unless `alpha` is -1, or odd and at least 3, and
+/// `Error::InvalidSecurityLevel` unless `security_bits` is within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`.
+pub fn are_round_numbers_secure_with_alpha(
+    t: usize,
+    rf: usize,
+    rp: usize,
+    security_bits: usize,
+    prime_bitlen: usize,
+    alpha: i64,
+) -> Result<bool, Error> {
+    validate_alpha(alpha)?;
+    validate_security_level(security_bits)?;
+    Ok(round_numbers_are_secure(
+        t,
+        rf,
+        rp,
+        prime_bitlen,
+        security_bits,
+        alpha,
+    ))
+}
+
+/// Evaluates the security inequalities of the Poseidon paper for `rf` full rounds and `rp` partial rounds, as
+/// `are_round_numbers_secure` does, reporting each bound and the binding inequality.
+///
+/// # Panics
+///
+/// Panics if `security_bits` is not within `MIN_SECURITY_LEVEL..=MAX_SECURITY_LEVEL`.
+pub fn round_number_security_report(
+    t: usize,
+    rf: usize,
+    rp: usize,
+    security_bits: usize,
+    prime_bitlen: usize,
+) -> RoundNumberSecurityReport {
+    validate_security_level(security_bits).unwrap_or_else(|e| panic!("{}", e));
+    let rf_stat = statistical_full_rounds(t, prime_bitlen, security_bits);
+    security_report(
+        t,
+        rf,
+        rp,
+        prime_bitlen,
+        security_bits,
+        DEFAULT_ALPHA,
+        rf_stat,
+    )
+}
+
+/// Evaluates the security inequalities of the Poseidon paper for `rf` full rounds and `rp` partial rounds at
+/// width `t`, reporting the value of each bound so the binding constraint can be identified.
+pub fn check_round_number_security(rf: usize, rp: usize, t: usize) -> RoundNumberSecurityReport {
+    round_number_security_report(t, rf, rp, DEFAULT_SECURITY_LEVEL, DEFAULT_PRIME_BITLEN)
+}
+
+/// Returns the security inequality requiring the most full rounds with `rp` partial rounds at width `t`, as
+/// reported by `check_round_number_security`. Only whether the inequality holds depends on `rf`: the round numbers
+/// are secure when `rf` meets its bound.
+pub fn binding_constraint(rf: usize, rp: usize, t: usize) -> SecurityInequality {
+    check_round_number_security(rf, rp, t).binding_inequality
+}
+
+// The arithmetic of `f32` and `f64` with which the security inequalities are evaluated in either `Precision`.
+trait Float:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+{
+    fn from_usize(x: usize) -> Self;
+    fn log2(self) -> Self;
+    fn ceil(self) -> Self;
+    fn floor(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    // Converts to `usize` as `as` does, saturating, so that negative values are 0.
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! impl_float {
+    ($float:ty) => {
+        impl Float for $float {
+            fn from_usize(x: usize) -> Self {
+                x as $float
+            }
+            fn log2(self) -> Self {
+                <$float>::log2(self)
+            }
+            fn ceil(self) -> Self {
+                <$float>::ceil(self)
+            }
+            fn floor(self) -> Self {
+                <$float>::floor(self)
+            }
+            fn min(self, other: Self) -> Self {
+                <$float>::min(self, other)
+            }
+            fn max(self, other: Self) -> Self {
+                <$float>::max(self, other)
+            }
+            fn to_usize(self) -> usize {
+                self as usize
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
+
+// The coefficients of `M` in the interpolation bound and of `n` in the two Gröbner basis bounds: `log_alpha(2)`,
+// and its halves and thirds. For `x^5` these are the truncated values used by the reference script, so that the
+// round numbers match the published parameters. The hundredths are divided in `F`, giving the nearest `F` to each.
+fn alpha_coefficients<F: Float>(alpha: i64) -> (F, F, F) {
+    let hundredths = |x| F::from_usize(x) / F::from_usize(100);
+    if alpha == DEFAULT_ALPHA {
+        (hundredths(43), hundredths(21), hundredths(14))
+    } else {
+        let log_alpha_2 = F::from_usize(1) / F::from_usize(alpha as usize).log2();
+        (
+            log_alpha_2,
+            log_alpha_2 / F::from_usize(2),
+            log_alpha_2 / F::from_usize(3),
+        )
+    }
+}
+
+// The interpolation and two Gröbner basis bounds on `R_F` of `security_report` for `x^alpha`, evaluated in `F`.
+fn float_rf_bounds<F: Float>(t: usize, rp: usize, n: usize, m: usize, alpha: i64) -> (F, F, F) {
+    let (interp_coeff, grob_1_coeff, grob_2_coeff) = alpha_coefficients::<F>(alpha);
+    let one = F::from_usize(1);
+    let (rp, t, n, m) = (
+        F::from_usize(rp),
+        F::from_usize(t),
+        F::from_usize(n),
+        F::from_usize(m),
+    );
+
+    let rf_interp = interp_coeff * m + t.log2() - rp;
+    let rf_grob_1 = grob_1_coeff * n - rp;
+    let rf_grob_2 = (grob_2_coeff * n - one - rp) / (t - one);
+    (rf_interp, rf_grob_1, rf_grob_2)
+}
+
+// The minimum secure `R_F` for `x^alpha` with the bounds evaluated in `F`, as `security_report` does in `f32`.
+fn float_rf_max<F: Float>(
+    t: usize,
+    rp: usize,
+    n: usize,
+    m: usize,
+    alpha: i64,
+    rf_stat: usize,
+) -> usize {
+    let (rf_interp, rf_grob_1, rf_grob_2) = float_rf_bounds::<F>(t, rp, n, m, alpha);
+    rf_stat
+        .max(rf_interp.ceil().to_usize())
+        .max(rf_grob_1.ceil().to_usize())
+        .max(rf_grob_2.ceil().to_usize())
+}
+
+// `rf_stat` is the statistical bound, see `StatisticalBound`.
+fn security_report(
+    t: usize,
+    rf: usize,
+    rp: usize,
+    n: usize,
+    m: usize,
+    alpha: i64,
+    rf_stat: usize,
+) -> RoundNumberSecurityReport {
+    let exact_bounds = if alpha == DEFAULT_ALPHA {
+        Some(exact_rf_bounds(
+            t,
+            rp,
+            n,
+            m,
+            ceil_log2_pow(t as u64, 100),
+            rf_stat,
+        ))
+    } else {
+        None
+    };
+
+    let (rf_interp, rf_grob_1, rf_grob_2) = float_rf_bounds::<f32>(t, rp, n, m, alpha);
+    let rf_stat = rf_stat as f32;
+
+    let bounds = exact_bounds.unwrap_or_else(|| {
+        [
+            rf_stat.ceil() as usize,
+            rf_interp.ceil() as usize,
+            rf_grob_1.ceil() as usize,
+            rf_grob_2.ceil() as usize,
+        ]
+    });
+    let rf_max = *bounds.iter().max().unwrap();
+    let binding_inequality = [
+        SecurityInequality::Statistical,
+        SecurityInequality::Interpolation,
+        SecurityInequality::GroebnerBasis1,
+        SecurityInequality::GroebnerBasis2,
+    ][bounds.iter().position(|rf| *rf == rf_max).unwrap()];
+
+    RoundNumberSecurityReport {
+        rf_stat,
+        rf_interp,
+        rf_grob_1,
+        rf_grob_2,
+        rf_max,
+        binding_inequality,
+        is_secure: rf >= rf_max,
+    }
+}
+
+// The minimum secure `R_F` for `x^5`, evaluating the bounds of `security_report` in integers: the coefficients 0.43,
+// 0.21, and 0.14 are taken as hundredths, and `log2(t)` enters the interpolation bound as `ceil(log2(t^100))`.
+//
+// As `R_F` is an integer, `R_F >= 0.43 * M + log2(t) - R_P` exactly when `100 * (R_F + R_P) - 43 * M >=
+// log2(t^100)`, i.e. when `R_F >= ceil((43 * M + ceil(log2(t^100))) / 100) - R_P`.
+//
+// `log2_t_100` is `ceil(log2(t^100))`, see `ceil_log2_pow`, and `rf_stat` is the statistical bound.
+fn exact_rf_max(t: usize, rp: usize, n: usize, m: usize, log2_t_100: i64, rf_stat: usize) -> usize {
+    *exact_rf_bounds(t, rp, n, m, log2_t_100, rf_stat)
+        .iter()
+        .max()
+        .unwrap()
+}
+
+// The minimum `R_F` required by each of the statistical, interpolation, and two Gröbner basis bounds for `x^5`, as in
+// `exact_rf_max`.
+fn exact_rf_bounds(
+    t: usize,
+    rp: usize,
+    n: usize,
+    m: usize,
+    log2_t_100: i64,
+    rf_stat: usize,
+) -> [usize; 4] {
+    let rf_stat = rf_stat as i64;
+    let (t, rp, n, m) = (t as i64, rp as i64, n as i64, m as i64);
+
+    let rf_interp = div_ceil(43 * m + log2_t_100, 100) - rp;
+    let rf_grob_1 = div_ceil(21 * n, 100) - rp;
+    let rf_grob_2 = if t > 1 {
+        div_ceil(14 * n - 100 * (1 + rp), 100 * (t - 1))
+    } else if 14 * n - 100 * (1 + rp) <= 0 {
+        0
+    } else {
+        // No number of full rounds satisfies the bound.
+        i64::MAX
+    };
+
+    [
+        rf_stat.max(0) as usize,
+        rf_interp.max(0) as usize,
+        rf_grob_1.max(0) as usize,
+        rf_grob_2.max(0) as usize,
+    ]
+}
+
+// `ceil(a / b)` for a positive `b`. Integer division truncates towards zero, which rounds a negative quotient up.
+const fn div_ceil(a: i64, b: i64) -> i64 {
+    if a > 0 {
+        (a + b - 1) / b
+    } else {
+        a / b
+    }
+}
+
+// The number of 64-bit limbs of `ceil_log2_pow`, which holds `x^k` for any `x` when `k` is at most 100.
+const POW_LIMBS: usize = 101;
+
+// `ceil(log2(x^k))` for `x >= 1` and `k <= 100`, computed exactly by raising `x` to the `k`th power in 64-bit limbs.
+const fn ceil_log2_pow(x: u64, k: u32) -> i64 {
+    let mut limbs = [0u64; POW_LIMBS];
+    limbs[0] = 1;
+    let mut len = 1;
+
+    let mut i = 0;
+    while i < k {
+        let mut carry = 0u128;
+        let mut j = 0;
+        while j < len {
+            let product = limbs[j] as u128 * x as u128 + carry;
+            limbs[j] = product as u64;
+            carry = product >> 64;
+            j += 1;
+        }
+        if carry > 0 {
+            limbs[len] = carry as u64;
+            len += 1;
+        }
+        i += 1;
+    }
+
+    let top = limbs[len - 1];
+    let bits = 64 * len as i64 - top.leading_zeros() as i64;
+
+    // `x^k` is a power of two exactly when its top limb is one and the others are zero.
+    let mut rest_is_zero = true;
+    let mut j = 0;
+    while j < len - 1 {
+        rest_is_zero = rest_is_zero && limbs[j] == 0;
+        j += 1;
+    }
+    if top.is_power_of_two() && rest_is_zero {
+        bits - 1
+    } else {
+        bits
+    }
+}
+
+// Following the reference script for `x^-1`, whose interpolation and Gröbner basis bounds constrain `R_P` given
+// `R_F`, as the degree of the inverse grows by a factor of `t` with each full round.
+fn inverse_round_numbers_are_secure<F: Float>(
+    t: usize,
+    rf: usize,
+    rp: usize,
+    n: usize,
+    m: usize,
+    rf_stat: usize,
+) -> bool {
+    let (rp_interp, rp_grob) = inverse_partial_rounds_bounds::<F>(t, rf, n, m);
+    rf >= rf_stat && F::from_usize(rp) >= rp_interp.max(rp_grob)
+}
+
+// The interpolation and Gröbner basis bounds on `R_P` for `x^-1` with `rf` full rounds, evaluated in `F`.
+fn inverse_partial_rounds_bounds<F: Float>(t: usize, rf: usize, n: usize, m: usize) -> (F, F) {
+    let (one, two) = (F::from_usize(1), F::from_usize(2));
+    let (t, n, m) = (F::from_usize(t), F::from_usize(n), F::from_usize(m));
+
+    let rf_log_t = (F::from_usize(rf) * t.log2()).floor();
+    let rp_interp = one + (m.min(n) / two).ceil() + t.log2().ceil() - rf_log_t;
+    let rp_grob =
+        t - one + t.log2().ceil() + (m / (t + one)).ceil().min((n / two).ceil()) - rf_log_t;
+
+    (rp_interp, rp_grob)
+}
+
+/// Returns `true` if the provided round numbers satisfy the security inequalities specified in the
+/// Poseidon paper for an `x^alpha` S-box, where an `alpha` of -1 denotes the inverse S-box.
+fn round_numbers_are_secure(
+    t: usize,
+    rf: usize,
+    rp: usize,
+    n: usize,
+    m: usize,
+    alpha: i64,
+) -> bool {
+    SecurityInequalities::new(t, n, m, alpha).is_secure(rf, rp)
+}
+
+// The security inequalities for a given width, field, security level, and S-box, as a function of `(R_F, R_P)`.
+// `ceil(log2(t^100))` is computed once rather than for each candidate.
+struct SecurityInequalities {
+    t: usize,
+    n: usize,
+    m: usize,
+    alpha: i64,
+    log2_t_100: i64,
+    // The statistical bound, the fewest full rounds secure with any number of partial rounds.
+    rf_stat: usize,
+    precision: Precision,
+}
+
+impl SecurityInequalities {
+    fn new(t: usize, n: usize, m: usize, alpha: i64) -> Self {
+        let log2_t_100 = if alpha == DEFAULT_ALPHA {
+            ceil_log2_pow(t as u64, 100)
+        } else {
+            0
+        };
+
+        SecurityInequalities {
+            t,
+            n,
+            m,
+            alpha,
+            log2_t_100,
+            rf_stat: statistical_full_rounds(t, n, m),
+            precision: Precision::default(),
+        }
+    }
+
+    // The inequalities evaluated in `precision` for S-boxes other than `x^5`.
+    fn with_precision(self, precision: Precision) -> Self {
+        SecurityInequalities { precision, ..self }
+    }
+
+    // The inequalities with `statistical_bound` in place of `DEFAULT_STATISTICAL_BOUND`.
+    fn with_statistical_bound(self, statistical_bound: StatisticalBound) -> Self {
+        SecurityInequalities {
+            rf_stat: statistical_bound.full_rounds(self.t, self.n, self.m),
+            ..self
+        }
+    }
+
+    fn is_secure(&self, rf: usize, rp: usize) -> bool {
+        let (t, n, m, alpha, rf_stat) = (self.t, self.n, self.m, self.alpha, self.rf_stat);
+        match (alpha, self.precision) {
+            (DEFAULT_ALPHA, _) => rf >= exact_rf_max(t, rp, n, m, self.log2_t_100, rf_stat),
+            (-1, Precision::F32) => {
+                inverse_round_numbers_are_secure::<f32>(t, rf, rp, n, m, rf_stat)
+            }
+            (-1, Precision::F64) => {
+                inverse_round_numbers_are_secure::<f64>(t, rf, rp, n, m, rf_stat)
+            }
+            (_, Precision::F32) => rf >= float_rf_max::<f32>(t, rp, n, m, alpha, rf_stat),
+            (_, Precision::F64) => rf >= float_rf_max::<f64>(t, rp, n, m, alpha, rf_stat),
+        }
+    }
+
+    // Returns the fewest partial rounds, at least `MIN_PARTIAL_ROUNDS`, that are secure with `rf` full rounds, or `None`
+    // if `rf` is below the statistical bound.
+    //
+    // Every other bound decreases with `R_P`, so security only improves with more partial rounds. The bounds are
+    // rearranged for `R_P` to find the minimum directly, and for the rounded evaluation of S-boxes other than `x^5`,
+    // the estimate is corrected by stepping to the boundary.
+    fn min_partial_rounds(&self, rf: usize) -> Option<usize> {
+        if rf < self.rf_stat {
+            return None;
+        }
+
+        let mut rp = self.partial_rounds_bound(rf).max(MIN_PARTIAL_ROUNDS);
+        while rp > MIN_PARTIAL_ROUNDS && self.is_secure(rf, rp - 1) {
+            rp -= 1;
+        }
+        while !self.is_secure(rf, rp) {
+            rp += 1;
+        }
+        Some(rp)
+    }
+
+    // The bounds on `R_F` rearranged for `R_P` given `rf`. For `x^5`, `R_F >= ceil(a / b) - R_P` exactly when `R_P >=
+    // ceil(a / b) - R_F`, and for the second Gröbner basis bound `(t - 1) * R_F >= 0.14 * n - 1 - R_P` exactly when
+    // `R_P >= ceil((14 * n - 100 * (1 + (t - 1) * R_F)) / 100)`, which also holds at `t = 1`.
+    fn partial_rounds_bound(&self, rf: usize) -> usize {
+        if self.alpha == DEFAULT_ALPHA {
+            return quintic_partial_rounds_bound(self.t, rf, self.n, self.m, self.log2_t_100);
+        }
+
+        match self.precision {
+            Precision::F32 => {
+                float_partial_rounds_bound::<f32>(self.t, rf, self.n, self.m, self.alpha)
+            }
+            Precision::F64 => {
+                float_partial_rounds_bound::<f64>(self.t, rf, self.n, self.m, self.alpha)
+            }
+        }
+    }
+}
+
+// The bounds of `SecurityInequalities::partial_rounds_bound` for S-boxes other than `x^5`, evaluated in `F`.
+fn float_partial_rounds_bound<F: Float>(
+    t: usize,
+    rf: usize,
+    n: usize,
+    m: usize,
+    alpha: i64,
+) -> usize {
+    let rp_bounds = if alpha == -1 {
+        let (rp_interp, rp_grob) = inverse_partial_rounds_bounds::<F>(t, rf, n, m);
+        [rp_interp, rp_grob, F::from_usize(0)]
+    } else {
+        let (interp_coeff, grob_1_coeff, grob_2_coeff) = alpha_coefficients::<F>(alpha);
+        let one = F::from_usize(1);
+        let (t, rf, n, m) = (
+            F::from_usize(t),
+            F::from_usize(rf),
+            F::from_usize(n),
+            F::from_usize(m),
+        );
+
+        [
+            interp_coeff * m + t.log2() - rf,
+            grob_1_coeff * n - rf,
+            grob_2_coeff * n - one - (t - one) * rf,
+        ]
+    };
+
+    rp_bounds
+        .iter()
+        .map(|rp| rp.ceil().to_usize())
+        .max()
+        .unwrap()
+}
+
+// The fewest partial rounds secure with `rf` full rounds for `x^5`, see `SecurityInequalities::partial_rounds_bound`.
+const fn quintic_partial_rounds_bound(
+    t: usize,
+    rf: usize,
+    n: usize,
+    m: usize,
+    log2_t_100: i64,
+) -> usize {
+    let (t, rf, n, m) = (t as i64, rf as i64, n as i64, m as i64);
+    let rp_interp = div_ceil(43 * m + log2_t_100, 100) - rf;
+    let rp_grob_1 = div_ceil(21 * n, 100) - rf;
+    let rp_grob_2 = div_ceil(14 * n - 100 * (1 + (t - 1) * rf), 100);
+
+    let mut rp = 0;
+    if rp_interp > rp {
+        rp = rp_interp;
+    }
+    if rp_grob_1 > rp {
+        rp = rp_grob_1;
+    }
+    if rp_grob_2 > rp {
+        rp = rp_grob_2;
+    }
+    rp as usize
+}
+
+// The statistical bound of the Poseidon paper: 6 full rounds when `M <= (n - 3) * (t + 1)`, and 10 otherwise. See
+// `StatisticalBound`.
+const fn statistical_full_rounds(t: usize, n: usize, m: usize) -> usize {
+    DEFAULT_STATISTICAL_BOUND.full_rounds(t, n, m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The options of a prime of `n` bits, a security level of `m` bits, `margin`, and `alpha`, and the defaults
+    // otherwise.
+    fn options(n: usize, m: usize, margin: SecurityMargin, alpha: i64) -> RoundNumberOptions {
+        RoundNumberOptions {
+            prime_bitlen: n,
+            security_level: m,
+            margin,
+            alpha,
+            ..RoundNumberOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_against_known_values() {
+        // Each case contains a `t` (where `t = arity + 1`) and the `R_P` expected for that `t`.
+        let cases = [
+            (2, 55),
+            (3, 55),
+            (4, 56),
+            (5, 56),
+            (6, 56),
+            (7, 56),
+            (8, 57),
+            (9, 57),
+            (10, 57),
+            (11, 57),
+            (12, 57),
+            (13, 57),
+            (14, 57),
+            (15, 57),
+            (16, 59),
+            (17, 59),
+            (25, 59),
+            (37, 60),
+            (65, 61),
+        ];
+        for (t, rp_expected) in cases.iter() {
+            let (rf, rp) = calc_round_numbers(
+                *t,
+                DEFAULT_PRIME_BITLEN,
+                DEFAULT_SECURITY_LEVEL,
+                true,
+                Sbox::Quintic,
+            )
+            .unwrap();
+            assert_eq!(rf, 8);
+            assert_eq!(
+                rp, *rp_expected,
+                "wrong number of partial rounds for t = {}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn test_precomputed_round_numbers() {
+        for (i, (t, rf, rp)) in PRECOMPUTED_ROUND_NUMBERS.iter().enumerate() {
+            // One entry for each typenum arity, from 1 through 36, so that `t - 2` indexes the table.
+            assert_eq!(i + 2, *t);
+            for prime_bitlen in PRECOMPUTED_PRIME_BITLENS.iter() {
+                assert_eq!(
+                    (*rf, *rp),
+                    search_round_numbers(
+                        *t,
+                        *prime_bitlen,
+                        DEFAULT_SECURITY_LEVEL,
+                        DEFAULT_SECURITY_MARGIN,
+                        DEFAULT_ALPHA,
+                        TieBreak::default()
+                    )
+                    .unwrap(),
+                    "stale precomputed round numbers for t = {}, n = {}",
+                    t,
+                    prime_bitlen
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_precomputed_round_numbers_lookup() {
+        // The widths most used in Merkle trees (arities 2, 4, 8, and 11) are looked up rather than searched for, and
+        // widths outside of the table fall back to the search.
+        for t in [2, 3, 5, 9, 12, 25, 37, 38, 65].iter() {
+            assert_eq!(
+                search_round_numbers(
+                    *t,
+                    DEFAULT_PRIME_BITLEN,
+                    DEFAULT_SECURITY_LEVEL,
+                    DEFAULT_SECURITY_MARGIN,
+                    DEFAULT_ALPHA,
+                    TieBreak::default()
+                ),
+                calc_round_numbers(
+                    *t,
+                    DEFAULT_PRIME_BITLEN,
+                    DEFAULT_SECURITY_LEVEL,
+                    true,
+                    Sbox::Quintic
+                ),
+                "wrong round numbers for t = {}",
+                t
+            );
+        }
+    }
+
+    // The output of https://extgit.iaik.tugraz.at/krypto/hadeshash/blob/master/code/scripts/calc_round_numbers.py for
+    // each file in `parameters` named `round_numbers-<N>-<t>-<M>-<field>-<sbox>.txt`, embedded so that checking
+    // against it needs neither the script nor the working directory.
+    macro_rules! script_outputs {
+        ($($name:literal),*) => {
+            [$(($name, include_str!(concat!("../parameters/", $name)))),*]
+        };
+    }
+
+    const SCRIPT_OUTPUTS: [(&str, &str); 13] = script_outputs!(
+        "round_numbers-512-2-128-1-1.txt",
+        "round_numbers-768-3-128-1-1.txt",
+        "round_numbers-1024-4-128-1-1.txt",
+        "round_numbers-1280-5-128-1-1.txt",
+        "round_numbers-1536-6-128-1-1.txt",
+        "round_numbers-1792-7-128-1-1.txt",
+        "round_numbers-2048-8-128-1-1.txt",
+        "round_numbers-2304-9-128-1-1.txt",
+        "round_numbers-3072-12-128-1-1.txt",
+        "round_numbers-3840-15-128-1-1.txt",
+        "round_numbers-4096-16-128-1-1.txt",
+        "round_numbers-4352-17-128-1-1.txt",
+        "round_numbers-16640-65-128-1-1.txt"
+    );
+
+    #[test]
+    fn test_round_numbers_against_python_script() {
+        for (name, output) in SCRIPT_OUTPUTS.iter() {
+            let params: Vec<usize> = name
+                .trim_end_matches(".txt")
+                .split('-')
+                .skip(1)
+                .map(|s| s.parse().unwrap())
+                .collect();
+            // The script's `N` is the total state size in bits, `n * t`.
+            let (big_n, t, m) = (params[0], params[1], params[2]);
+            let prime_bitlen = big_n / t;
+
+            let value = |key: &str| -> usize {
+                output
+                    .lines()
+                    .find(|line| line.starts_with(key))
+                    .map(|line| line[key.len()..].trim().parse().unwrap())
+                    .unwrap()
+            };
+            let (rf, rp) = (value("R_F = "), value("R_P = "));
+            let (sbox_cost, size_cost) = (value("S-box cost = "), value("Size cost = "));
+
+            let (rf_calc, rp_calc) =
+                calc_round_numbers(t, prime_bitlen, m, true, Sbox::Quintic).unwrap();
+            // On a mismatch, report the bound each set of round numbers is limited by.
+            let binding = |rf, rp| {
+                round_number_security_report(t, rf, rp, m, prime_bitlen).binding_inequality
+            };
+            assert_eq!(
+                (rf, rp),
+                (rf_calc, rp_calc),
+                "wrong round numbers for t = {}, n = {}, M = {}: the script's are bound by the {} inequality, and \
+                 the calculated ones by the {} inequality",
+                t,
+                prime_bitlen,
+                m,
+                binding(rf, rp),
+                binding(rf_calc, rp_calc)
+            );
+            assert_eq!(sbox_cost, n_sboxes(t, rf_calc, rp_calc));
+            assert_eq!(
+                (rf, rp),
+                const_calc_round_numbers(t, prime_bitlen, m, true),
+                "wrong const round numbers for t = {}, n = {}, M = {}",
+                t,
+                prime_bitlen,
+                m
+            );
+            assert_eq!(
+                size_cost,
+                super::size_cost(t, rf_calc, rp_calc, prime_bitlen)
+            );
+
+            // The script also prints the round numbers and costs as a list.
+            if prime_bitlen == DEFAULT_PRIME_BITLEN && m == DEFAULT_SECURITY_LEVEL {
+                let summary = RoundNumbers::compute(t, true).unwrap().to_string();
+                assert!(
+                    output.lines().any(|line| line == summary),
+                    "missing {} for t = {}",
+                    summary,
+                    t
+                );
+            }
+        }
+    }
+
+    // The search of the reference script for `x^5` with the security margin: every even `R_F` from 4 and `R_P` from
+    // 1, with the inequalities evaluated in floating point as the script does, independently of `SecurityInequalities`.
+    fn script_round_numbers(t: usize, n: usize, m: usize) -> (usize, usize) {
+        let (t_f, n_f, m_f) = (t as f64, n as f64, m as f64);
+        let is_secure = |rf: usize, rp: usize| {
+            let rp = rp as f64;
+            let rf_stat = if m_f <= (n_f - 3.0) * (t_f + 1.0) {
+                6.0
+            } else {
+                10.0
+            };
+            let rf_interp = 0.43 * m_f + t_f.log2() - rp;
+            let rf_grob_1 = 0.21 * n_f - rp;
+            let rf_grob_2 = (0.14 * n_f - 1.0 - rp) / (t_f - 1.0);
+            let rf_max = [rf_stat, rf_interp, rf_grob_1, rf_grob_2]
+                .iter()
+                .map(|bound| bound.ceil())
+                .fold(f64::MIN, f64::max);
+            rf as f64 >= rf_max
+        };
+
+        let mut best = (usize::MAX, 0, 0);
+        for rp_secure in 1..500 {
+            for rf_secure in (4..100).step_by(2) {
+                if is_secure(rf_secure, rp_secure) {
+                    let rf = rf_secure + 2;
+                    let rp = (rp_secure as f64 * 1.075).ceil() as usize;
+                    let cost = t * rf + rp;
+                    if cost < best.0 || (cost == best.0 && rf < best.1) {
+                        best = (cost, rf, rp);
+                    }
+                }
+            }
+        }
+        (best.1, best.2)
+    }
+
+    #[test]
+    fn test_round_numbers_file() {
+        // `parameters/round_numbers.txt` is written by `cargo run --bin gen_round_numbers`. Each line must still be
+        // calculated, agree with the script's search, and be precomputed.
+        let mut widths = Vec::new();
+        for line in include_str!("../parameters/round_numbers.txt")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+        {
+            let (t, round_numbers) = RoundNumbers::from_line(line).unwrap();
+            assert_eq!(
+                (t, round_numbers.full_rounds, round_numbers.partial_rounds),
+                PRECOMPUTED_ROUND_NUMBERS[t - 2],
+                "precomputed round numbers for t = {} differ from the file",
+                t
+            );
+            assert_eq!(
+                RoundNumbers::compute(t, true).unwrap(),
+                round_numbers,
+                "round numbers for t = {} have changed",
+                t
+            );
+            assert_eq!(
+                script_round_numbers(t, DEFAULT_PRIME_BITLEN, DEFAULT_SECURITY_LEVEL),
+                (round_numbers.full_rounds, round_numbers.partial_rounds),
+                "round numbers for t = {} differ from the script's",
+                t
+            );
+            widths.push(t);
+        }
+        assert_eq!((2..=37).collect::<Vec<_>>(), widths);
+    }
+
+    #[test]
+    fn test_round_numbers_line() {
+        let round_numbers = RoundNumbers::from_rounds(3, 8, 55, 256);
+        assert_eq!("3 8 55 79 20224", round_numbers.to_line(3));
+        assert_eq!(
+            Ok((3, round_numbers)),
+            RoundNumbers::from_line(&round_numbers.to_line(3))
+        );
+        assert_eq!(
+            Ok((3, round_numbers)),
+            RoundNumbers::from_line("  3\t8 55  79 20224\n")
+        );
+
+        for line in [
+            "",
+            "3 8 55 79",
+            "3 8 55 79 20224 1",
+            "3 8 -55 79 20224",
+            "[8, 55, 79, 20224]",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Err(RoundNumberError::MalformedLine {
+                    line: line.to_string()
+                }),
+                RoundNumbers::from_line(line)
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_round_numbers_serde() {
+        let round_numbers = RoundNumbers::new(9, Strength::Standard);
+        let json = serde_json::to_string(&round_numbers).unwrap();
+        assert_eq!(round_numbers, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn test_round_numbers_with_security() {
+        // Each case contains a security level `M`, a `t`, and the `(R_F, R_P)` expected with and without the
+        // security margin, as produced by the reference script for `n = 256`.
+        let cases = [
+            (80, 3, (8, 52), (6, 48)),
+            (80, 9, (8, 52), (6, 48)),
+            (80, 12, (8, 52), (6, 48)),
+            (256, 3, (8, 114), (6, 106)),
+            (256, 5, (8, 116), (6, 107)),
+            (256, 9, (8, 117), (6, 108)),
+            (256, 12, (8, 117), (6, 108)),
+        ];
+        for (m, t, with_margin, without_margin) in cases.iter() {
+            assert_eq!(
+                *with_margin,
+                calc_round_numbers_with_options(*t, &options(256, *m, DEFAULT_SECURITY_MARGIN, 5))
+                    .unwrap(),
+                "wrong round numbers for M = {}, t = {}",
+                m,
+                t
+            );
+            assert_eq!(
+                *without_margin,
+                calc_round_numbers_with_options(*t, &options(256, *m, NO_SECURITY_MARGIN, 5))
+                    .unwrap(),
+                "wrong round numbers for M = {}, t = {} without security margin",
+                m,
+                t
+            );
+        }
+
+        // The default security level is 128 bits.
+        for t in 2..=17 {
+            assert_eq!(
+                calc_round_numbers(
+                    t,
+                    DEFAULT_PRIME_BITLEN,
+                    DEFAULT_SECURITY_LEVEL,
+                    true,
+                    Sbox::Quintic
+                )
+                .unwrap(),
+                calc_round_numbers_with_options(t, &RoundNumberOptions::default()).unwrap()
+            );
+        }
+
+        // Security levels outside of the supported range are rejected.
+        for m in [0, 64, 79, 257, 512].iter() {
+            match calc_round_numbers_with_options(3, &options(256, *m, DEFAULT_SECURITY_MARGIN, 5))
+            {
+                Err(Error::InvalidSecurityLevel(bits)) => assert_eq!(*m, bits),
+                res => panic!("expected an invalid security level error, got {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_with_prime_bitlen() {
+        // Each case contains a prime bit length `n`, a `t`, and the `(R_F, R_P)` expected with and without the
+        // security margin at 128-bit security.
+        let cases = [
+            // BN254
+            (254, 3, (8, 55), (6, 51)),
+            // Pallas and Vesta
+            (255, 9, (8, 57), (6, 53)),
+            // The statistical bound requires more full rounds for small fields.
+            (32, 3, (12, 51), (10, 47)),
+            // The first Gröbner basis bound requires more partial rounds for large fields.
+            (381, 3, (8, 81), (6, 75)),
+            (512, 5, (8, 110), (6, 102)),
+        ];
+        for (n, t, with_margin, without_margin) in cases.iter() {
+            assert_eq!(
+                *with_margin,
+                calc_round_numbers(*t, *n, 128, true, Sbox::Quintic).unwrap()
+            );
+            assert_eq!(
+                *without_margin,
+                calc_round_numbers(*t, *n, 128, false, Sbox::Quintic).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_for_prime() {
+        let for_prime = |t, n| {
+            calc_round_numbers_with_options(t, &options(n, 128, DEFAULT_SECURITY_MARGIN, 5))
+                .unwrap()
+        };
+        assert_eq!((8, 55), for_prime(3, DEFAULT_PRIME_BITLEN));
+        assert_eq!((8, 55), for_prime(2, 130));
+
+        // Below the threshold, the statistical bound depends on the width.
+        assert_eq!((12, 51), for_prime(2, 45));
+        assert_eq!((8, 55), for_prime(2, 46));
+        assert_eq!((12, 51), for_prime(3, 32));
+        assert_eq!((8, 56), for_prime(4, 32));
+    }
+
+    #[test]
+    fn test_round_numbers_with_margin() {
+        // The boolean margin selects the default margin, or none.
+        for t in 2..=40 {
+            for (margin, security_margin) in
+                [(DEFAULT_SECURITY_MARGIN, true), (NO_SECURITY_MARGIN, false)].iter()
+            {
+                assert_eq!(
+                    calc_round_numbers(t, 255, 128, *security_margin, Sbox::Quintic).unwrap(),
+                    calc_round_numbers_with_options(t, &options(255, 128, *margin, 5)).unwrap()
+                );
+            }
+        }
+
+        // Each case contains a margin, a `t`, and the `(R_F, R_P)` expected at 128-bit security.
+        let cases = [
+            ((2, 1.25), 3, (8, 64)),
+            ((2, 1.25), 9, (8, 67)),
+            ((4, 1.25), 3, (10, 64)),
+            ((0, 1.25), 9, (6, 67)),
+        ];
+        for ((extra_full_rounds, partial_multiplier), t, expected) in cases.iter() {
+            let margin = SecurityMargin {
+                extra_full_rounds: *extra_full_rounds,
+                partial_multiplier: *partial_multiplier,
+            };
+            assert_eq!(
+                *expected,
+                calc_round_numbers_with_options(*t, &options(256, 128, margin, 5)).unwrap(),
+                "wrong round numbers for {:?}, t = {}",
+                margin,
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn test_apply_security_margin() {
+        // Each case contains `R_P` and `ceil(1.075 * R_P)` with `1.075` as an `f32`.
+        let cases = [
+            (0, 0),
+            (1, 2),
+            (39, 42),
+            // 1.075 * 40 = 43 exactly.
+            (40, 43),
+            (41, 45),
+            (51, 55),
+            (80, 86),
+            (120, 129),
+            // The `f32` product exceeds the integer 215, so rounds up to 216.
+            (200, 216),
+            (360, 388),
+        ];
+        for (rp, expected) in cases.iter() {
+            assert_eq!(
+                (8, *expected),
+                apply_security_margin(6, *rp),
+                "R_P = {}",
+                rp
+            );
+        }
+
+        // The default margin is what `calc_round_numbers` adds to the minimal secure round numbers.
+        for t in 2..=37 {
+            let (rf, rp) = calc_round_numbers(t, 255, 128, false, Sbox::Quintic).unwrap();
+            assert_eq!(
+                calc_round_numbers(t, 255, 128, true, Sbox::Quintic).unwrap(),
+                apply_security_margin(rf, rp),
+                "t = {}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_with_tie_break() {
+        let tie_breaks = [
+            TieBreak::MinFullRounds,
+            TieBreak::MinPartialRounds,
+            TieBreak::MinSboxes,
+        ];
+
+        // No two candidates cost the same at the default parameters, so every tie-break agrees with the precomputed
+        // round numbers.
+        for t in 2..=37 {
+            for tie_break in tie_breaks.iter() {
+                assert_eq!(
+                    calc_round_numbers(t, 255, 128, true, Sbox::Quintic).ok(),
+                    calc_round_numbers_with_options(
+                        t,
+                        &RoundNumberOptions {
+                            tie_break: *tie_break,
+                            ..options(255, 128, DEFAULT_SECURITY_MARGIN, 5)
+                        }
+                    )
+                    .ok(),
+                    "t = {}, {:?}",
+                    t,
+                    tie_break
+                );
+            }
+        }
+
+        // Each case contains `t`, `n`, `M`, a margin, an S-box, and the `(R_F, R_P)` expected for each tie-break.
+        let cases = [
+            (
+                1,
+                64,
+                80,
+                DEFAULT_SECURITY_MARGIN,
+                Sbox::Quintic,
+                [(24, 14), (28, 10), (24, 14)],
+            ),
+            (
+                3,
+                64,
+                128,
+                SecurityMargin {
+                    extra_full_rounds: 0,
+                    partial_multiplier: 2.0,
+                },
+                Sbox::Inverse,
+                [(12, 34), (20, 10), (12, 34)],
+            ),
+        ];
+        for (t, n, m, margin, sbox, expected) in cases.iter() {
+            for (tie_break, (rf, rp)) in tie_breaks.iter().zip(expected.iter()) {
+                assert_eq!(
+                    (*rf, *rp),
+                    calc_round_numbers_with_options(
+                        *t,
+                        &RoundNumberOptions {
+                            tie_break: *tie_break,
+                            ..options(*n, *m, *margin, sbox.alpha())
+                        }
+                    )
+                    .unwrap(),
+                    "t = {}, {:?}",
+                    t,
+                    tie_break
+                );
+            }
+            // The tie-breaks choose among candidates of equal cost.
+            let costs: Vec<_> = expected
+                .iter()
+                .map(|(rf, rp)| n_sboxes(*t, *rf, *rp))
+                .collect();
+            assert!(costs.iter().all(|cost| *cost == costs[0]));
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_with_cost() {
+        // The S-box cost reproduces the default search.
+        for t in 2..=37 {
+            for security_margin in [true, false].iter() {
+                assert_eq!(
+                    calc_round_numbers(t, 255, 128, *security_margin, Sbox::Quintic).ok(),
+                    calc_round_numbers_with_cost(
+                        t,
+                        255,
+                        128,
+                        *security_margin,
+                        Sbox::Quintic,
+                        |rf, rp, t| n_sboxes(t, rf, rp)
+                    )
+                    .ok(),
+                    "t = {}, security margin: {}",
+                    t,
+                    security_margin
+                );
+            }
+        }
+
+        // Costing full rounds alone takes the fewest, and partial rounds alone the fewest of those.
+        assert_eq!(
+            (8, 55),
+            calc_round_numbers_with_cost(3, 255, 128, true, Sbox::Quintic, |rf, _, _| rf).unwrap()
+        );
+        assert_eq!(
+            (56, 5),
+            calc_round_numbers_with_cost(3, 255, 128, true, Sbox::Quintic, |rf, rp, _| rf
+                + 100 * rp)
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_number_candidates() {
+        for t in 2..=37 {
+            for strength in [Strength::Standard, Strength::Strengthened].iter() {
+                let candidates = round_number_candidates(t, *strength);
+
+                // Neptune's round numbers are the cheapest candidate.
+                let chosen = RoundNumbers::new(t, *strength);
+                assert!(candidates.contains(&chosen), "t = {}, {:?}", t, strength);
+                let min_cost = candidates.iter().map(|c| c.sbox_cost).min().unwrap();
+                assert_eq!(chosen.sbox_cost, min_cost, "t = {}, {:?}", t, strength);
+
+                for pair in candidates.windows(2) {
+                    assert!(pair[0].full_rounds < pair[1].full_rounds);
+                    assert!(pair[0].partial_rounds > pair[1].partial_rounds);
+                }
+                for candidate in candidates.iter() {
+                    assert_eq!(
+                        Ok(()),
+                        check_rounds_are_secure(
+                            candidate.full_rounds,
+                            candidate.partial_rounds,
+                            t,
+                            strength
+                        )
+                    );
+                }
+            }
+        }
+
+        let custom = Strength::Custom {
+            full: 8,
+            partial: 60,
+        };
+        assert_eq!(
+            vec![RoundNumbers::from_rounds(3, 8, 60, DEFAULT_PRIME_BITLEN)],
+            round_number_candidates(3, custom)
+        );
+        let insecure = Strength::Custom {
+            full: 8,
+            partial: 40,
+        };
+        assert!(round_number_candidates(3, insecure).is_empty());
+    }
+
+    #[test]
+    fn test_round_numbers_with_statistical_bound() {
+        // The default bound reproduces the default search.
+        for t in 2..=37 {
+            for (n, m) in [(255, 128), (64, 80), (256, 256)].iter() {
+                for sbox in [Sbox::Quintic, Sbox::Inverse].iter() {
+                    let options = options(*n, *m, DEFAULT_SECURITY_MARGIN, sbox.alpha());
+                    assert_eq!(
+                        calc_round_numbers(t, *n, *m, true, *sbox).ok(),
+                        search_inequalities(
+                            &SecurityInequalities::new(t, *n, *m, sbox.alpha())
+                                .with_statistical_bound(StatisticalBound::default()),
+                            options.margin,
+                            TieBreak::default(),
+                            false
+                        )
+                        .ok(),
+                        "t = {}, n = {}, M = {}, {:?}",
+                        t,
+                        n,
+                        m,
+                        sbox
+                    );
+                }
+            }
+        }
+
+        // `M = 1024` is above the threshold at `t = 3`, `(256 - 3) * 4 = 1012`, so the bound requires 10 full rounds,
+        // or 6 if the threshold is raised.
+        assert_eq!(10, DEFAULT_STATISTICAL_BOUND.full_rounds(3, 256, 1024));
+        assert_eq!(6, DEFAULT_STATISTICAL_BOUND.full_rounds(3, 256, 1012));
+        let raised = StatisticalBound {
+            threshold_offset: 0,
+            ..DEFAULT_STATISTICAL_BOUND
+        };
+        assert_eq!(6, raised.full_rounds(3, 256, 1024));
+
+        // Each case contains a `t`, a statistical bound, and the `(R_F, R_P)` expected without the margin. At `t = 3`,
+        // the statistical bound limits `R_F`, and each pair of full rounds trades for two partial rounds.
+        let with_full_rounds = |full_rounds_below| StatisticalBound {
+            full_rounds_below,
+            ..DEFAULT_STATISTICAL_BOUND
+        };
+        let cases = [
+            (3, DEFAULT_STATISTICAL_BOUND, (6, 51)),
+            (3, with_full_rounds(4), (4, 53)),
+            (3, with_full_rounds(8), (8, 49)),
+        ];
+        for (t, statistical_bound, expected) in cases.iter() {
+            let (rf, rp) = calc_round_numbers_with_options(
+                *t,
+                &RoundNumberOptions {
+                    statistical_bound: *statistical_bound,
+                    ..options(256, 128, NO_SECURITY_MARGIN, 5)
+                },
+            )
+            .unwrap();
+            assert_eq!(*expected, (rf, rp), "{:?}", statistical_bound);
+            assert!(rf >= statistical_bound.full_rounds(*t, 256, 128));
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_with_even_partial_rounds() {
+        for t in 2..=37 {
+            for (n, m, sbox) in [
+                (255, 128, Sbox::Quintic),
+                (256, 256, Sbox::Quintic),
+                (64, 80, Sbox::Quintic),
+                (255, 128, Sbox::Inverse),
+            ]
+            .iter()
+            {
+                let (rf, rp) = calc_round_numbers_with_options(
+                    t,
+                    &RoundNumberOptions {
+                        even_partial_rounds: true,
+                        ..options(*n, *m, DEFAULT_SECURITY_MARGIN, sbox.alpha())
+                    },
+                )
+                .unwrap();
+                assert_eq!(0, rp % 2, "t = {}, n = {}, M = {}, {:?}", t, n, m, sbox);
+
+                // The round numbers are the cheapest once every candidate is rounded up, not only the cheapest
+                // candidate rounded up.
+                let even_cost = |rf, rp, t| n_sboxes(t, rf, rp + rp % 2);
+                let (rf_cheapest, rp_cheapest) =
+                    calc_round_numbers_with_cost(t, *n, *m, true, *sbox, even_cost).unwrap();
+                assert_eq!(
+                    (rf_cheapest, rp_cheapest + rp_cheapest % 2),
+                    (rf, rp),
+                    "t = {}, n = {}, M = {}, {:?}",
+                    t,
+                    n,
+                    m,
+                    sbox
+                );
+                assert!(
+                    are_round_numbers_secure_with_alpha(t, rf, rp, *m, *n, sbox.alpha()).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_with_precision() {
+        assert_eq!((0.43, 0.21, 0.14), alpha_coefficients::<f32>(DEFAULT_ALPHA));
+
+        for t in 2..=50 {
+            for alpha in [5, 3, 7, 11, -1].iter() {
+                for (n, m) in [(255, 128), (256, 128), (64, 80), (255, 256)].iter() {
+                    for margin in [DEFAULT_SECURITY_MARGIN, NO_SECURITY_MARGIN].iter() {
+                        let rounds = |precision| {
+                            calc_round_numbers_with_options(
+                                t,
+                                &RoundNumberOptions {
+                                    precision,
+                                    ..options(*n, *m, *margin, *alpha)
+                                },
+                            )
+                            .ok()
+                        };
+                        assert_eq!(
+                            rounds(Precision::F32),
+                            rounds(Precision::F64),
+                            "t = {}, alpha = {}, n = {}, M = {}, margin = {:?}",
+                            t,
+                            alpha,
+                            n,
+                            m,
+                            margin
+                        );
+                    }
+                }
+
+                assert_eq!(
+                    search_round_numbers(
+                        t,
+                        255,
+                        128,
+                        DEFAULT_SECURITY_MARGIN,
+                        *alpha,
+                        TieBreak::default()
+                    )
+                    .ok(),
+                    calc_round_numbers_with_options(
+                        t,
+                        &RoundNumberOptions {
+                            precision: Precision::default(),
+                            ..options(255, 128, DEFAULT_SECURITY_MARGIN, *alpha)
+                        }
+                    )
+                    .ok()
+                );
+            }
+        }
+
+        // The interpolation bound `log_3(2) * 1721 + log2(9)` exceeds 1089 by less than the precision of an `f32`,
+        // which rounds it to 1089, allowing one fewer partial round.
+        let (rf_interp, _, _) = float_rf_bounds::<f32>(9, 0, 255, 1721, 3);
+        assert_eq!(1089.0, rf_interp);
+        let (rf_interp, _, _) = float_rf_bounds::<f64>(9, 0, 255, 1721, 3);
+        assert!(rf_interp > 1089.0);
+
+        // The security level is above `MAX_SECURITY_LEVEL`, so the inequalities are searched directly.
+        let rounds = |precision| {
+            let inequalities = SecurityInequalities::new(9, 255, 1721, 3).with_precision(precision);
+            search_inequalities(
+                &inequalities,
+                NO_SECURITY_MARGIN,
+                TieBreak::default(),
+                false,
+            )
+            .unwrap()
+        };
+        assert_eq!((6, 1083), rounds(Precision::F32));
+        assert_eq!((6, 1084), rounds(Precision::F64));
+    }
+
+    #[test]
+    #[should_panic(expected = "extra full rounds must be even: 3")]
+    fn test_round_numbers_with_odd_margin() {
+        let margin = SecurityMargin {
+            extra_full_rounds: 3,
+            partial_multiplier: 1.0,
+        };
+        calc_round_numbers_with_options(3, &options(256, 128, margin, 5)).unwrap();
+    }
+
+    #[test]
+    fn test_round_numbers_at_high_security_levels() {
+        // Each case contains a `t`, a prime bit length `n`, a security level `M`, an S-box, and the `(R_F, R_P)`
+        // expected with and without the security margin. These require well over 200 partial rounds. The levels are
+        // above `MAX_SECURITY_LEVEL`, which the public functions reject, so the searches are called directly.
+        let cases = [
+            (200, 256, 3000, Sbox::Quintic, (8, 1389), (6, 1292)),
+            (9, 256, 5000, Sbox::Quintic, (12, 2305), (10, 2144)),
+            (
+                3,
+                256,
+                5_000_000,
+                Sbox::Quintic,
+                (12, 2311242),
+                (10, 2149992),
+            ),
+            (9, 256, 5_000_000, Sbox::Cubic, (12, 3391241), (10, 3154642)),
+            (1, 512, 512, Sbox::Inverse, (8, 277), (6, 257)),
+        ];
+        for (t, n, m, sbox, with_margin, without_margin) in cases.iter() {
+            for (security_margin, expected) in [(true, with_margin), (false, without_margin)].iter()
+            {
+                let margin = SecurityMargin::from(*security_margin);
+                let (rf, rp) =
+                    search_round_numbers(*t, *n, *m, margin, sbox.alpha(), TieBreak::default())
+                        .unwrap();
+                assert_eq!(
+                    **expected,
+                    (rf, rp),
+                    "wrong round numbers for {:?}, t = {}, n = {}, M = {}",
+                    sbox,
+                    t,
+                    n,
+                    m
+                );
+
+                // The minimum was not clamped by the search bound.
+                let inequalities = SecurityInequalities::new(*t, *n, *m, sbox.alpha());
+                assert!(
+                    rf - margin.extra_full_rounds < max_full_rounds(&inequalities, margin).unwrap()
+                );
+
+                if *sbox == Sbox::Quintic {
+                    assert_eq!(
+                        (rf, rp),
+                        const_search_round_numbers(*t, *n, *m, *security_margin)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_exact_rf_max() {
+        // The exact minimum secure `R_F` is never below that given by the rounded bounds, so no candidate insecure
+        // under the float evaluation is reported as secure.
+        for n in [255, 256].iter() {
+            for m in [80, 128, 256].iter() {
+                for t in 2..=128 {
+                    for rp in MIN_PARTIAL_ROUNDS..200 {
+                        let rf_stat = statistical_full_rounds(t, *n, *m);
+                        let report = security_report(t, 0, rp, *n, *m, DEFAULT_ALPHA, rf_stat);
+                        let float_rf_max = [
+                            report.rf_stat,
+                            report.rf_interp,
+                            report.rf_grob_1,
+                            report.rf_grob_2,
+                        ]
+                        .iter()
+                        .map(|rf| rf.ceil() as usize)
+                        .max()
+                        .unwrap();
+                        assert!(
+                            report.rf_max >= float_rf_max,
+                            "exact R_F bound {} is below {} for t = {}, R_P = {}, n = {}, M = {}",
+                            report.rf_max,
+                            float_rf_max,
+                            t,
+                            rp,
+                            n,
+                            m
+                        );
+                    }
+                }
+            }
+        }
+
+        // `ceil(log2(x^k))`, exactly at powers of two.
+        assert_eq!(0, ceil_log2_pow(1, 100));
+        assert_eq!(100, ceil_log2_pow(2, 100));
+        assert_eq!(2, ceil_log2_pow(3, 1));
+        assert_eq!(159, ceil_log2_pow(3, 100));
+        assert_eq!(6, ceil_log2_pow(4, 3));
+        assert_eq!(128, ceil_log2_pow(u64::MAX, 2));
+        assert_eq!(128, ceil_log2_pow(1 << 32, 4));
+        assert_eq!(6400, ceil_log2_pow(u64::MAX, 100));
+        assert_eq!(-3, div_ceil(-7, 2));
+        assert_eq!(4, div_ceil(7, 2));
+    }
+
+    #[test]
+    fn test_const_round_numbers() {
+        const RF_RP: (usize, usize) = const_round_numbers(3);
+        assert_eq!((8, 55), RF_RP);
+
+        for t in 2..=37 {
+            let expected = search_round_numbers(
+                t,
+                DEFAULT_PRIME_BITLEN,
+                DEFAULT_SECURITY_LEVEL,
+                DEFAULT_SECURITY_MARGIN,
+                DEFAULT_ALPHA,
+                TieBreak::default(),
+            )
+            .unwrap();
+            assert_eq!(expected, const_round_numbers(t), "t = {}", t);
+        }
+
+        // Each case contains a prime bit length `n` and a security level `M`.
+        let cases = [(255, 128), (64, 80), (381, 128), (256, 256), (1024, 100)];
+        for (n, m) in cases.iter() {
+            for t in 2..=37 {
+                for security_margin in [true, false].iter() {
+                    assert_eq!(
+                        calc_round_numbers(t, *n, *m, *security_margin, Sbox::Quintic).unwrap(),
+                        const_calc_round_numbers(t, *n, *m, *security_margin),
+                        "t = {}, n = {}, M = {}, security margin: {}",
+                        t,
+                        n,
+                        m,
+                        security_margin
+                    );
+                }
+            }
+        }
+
+        // The margin agrees with multiplying by the `f32` 1.075, including where `ceil(43 * rp / 40)` does not.
+        for rp in (0..100_000).chain((1 << 24) - 1000..(1 << 24) + 1000) {
+            assert_eq!(
+                DEFAULT_SECURITY_MARGIN.apply(8, rp),
+                const_margin(8, rp, true),
+                "rp = {}",
+                rp
+            );
+        }
+        assert_eq!((8, 216), const_margin(6, 200, true));
+        assert_eq!((6, 200), const_margin(6, 200, false));
+    }
+
+    // The round numbers found by trying every even `R_F` up to 100 and every `R_P` below 200, keeping the first of
+    // the candidates of lowest cost, i.e. the one with the fewest full rounds.
+    fn brute_force_round_numbers(
+        t: usize,
+        n: usize,
+        m: usize,
+        margin: SecurityMargin,
+        alpha: i64,
+    ) -> (usize, usize) {
+        let inequalities = SecurityInequalities::new(t, n, m, alpha);
+        let mut best_cost = usize::MAX;
+        let mut best = (0, 0);
+        for rf_secure in (2..=100).step_by(2) {
+            for rp_secure in MIN_PARTIAL_ROUNDS..200 {
+                if inequalities.is_secure(rf_secure, rp_secure) {
+                    let (rf, rp) = margin.apply(rf_secure, rp_secure);
+                    let cost = n_sboxes(t, rf, rp);
+                    if cost < best_cost || (cost == best_cost && rf < best.0) {
+                        best_cost = cost;
+                        best = (rf, rp);
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn test_search_matches_brute_force() {
+        for sbox in [Sbox::Quintic, Sbox::Cubic, Sbox::Inverse].iter() {
+            for margin in [DEFAULT_SECURITY_MARGIN, NO_SECURITY_MARGIN].iter() {
+                for t in 2..=128 {
+                    assert_eq!(
+                        brute_force_round_numbers(t, 255, 128, *margin, sbox.alpha()),
+                        search_round_numbers(
+                            t,
+                            255,
+                            128,
+                            *margin,
+                            sbox.alpha(),
+                            TieBreak::default()
+                        )
+                        .unwrap(),
+                        "wrong round numbers for {:?}, t = {}, margin = {:?}",
+                        sbox,
+                        t,
+                        margin
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_for_512_bit_prime() {
+        // Each case contains a security level `M`, a `t`, and the `(R_F, R_P)` expected with and without the security
+        // margin for `n = 512`.
+        let cases = [
+            (128, 3, (8, 110), (6, 102)),
+            (256, 9, (8, 117), (6, 108)),
+            (512, 3, (8, 233), (6, 216)),
+            (512, 17, (8, 236), (6, 219)),
+        ];
+        for (m, t, with_margin, without_margin) in cases.iter() {
+            for (margin, expected) in [
+                (DEFAULT_SECURITY_MARGIN, with_margin),
+                (NO_SECURITY_MARGIN, without_margin),
+            ]
+            .iter()
+            {
+                let (rf, rp) =
+                    search_round_numbers(*t, 512, *m, *margin, DEFAULT_ALPHA, TieBreak::default())
+                        .unwrap();
+                assert_eq!(
+                    **expected,
+                    (rf, rp),
+                    "wrong round numbers for t = {}, M = {}",
+                    t,
+                    m
+                );
+
+                // At a security level of 512 bits, the optimum needs more than 200 partial rounds: a search capped
+                // below that settles on its boundary, with more full rounds and a higher cost.
+                let (rf_capped, rp_capped) =
+                    brute_force_round_numbers(*t, 512, *m, *margin, DEFAULT_ALPHA);
+                if *m == 512 {
+                    assert!(n_sboxes(*t, rf, rp) < n_sboxes(*t, rf_capped, rp_capped));
+                    assert!(rf < rf_capped);
+                } else {
+                    assert_eq!((rf, rp), (rf_capped, rp_capped));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_min_partial_rounds() {
+        // The fewest secure partial rounds for each `R_F` match those found by trying each `R_P` in turn, and there
+        // are none below the statistical bound.
+        for alpha in [-1, 3, 5, 7].iter() {
+            for t in [1, 2, 3, 5, 9, 17, 65].iter() {
+                for n in [64, 255].iter() {
+                    for m in [80, 128, 256].iter() {
+                        let inequalities = SecurityInequalities::new(*t, *n, *m, *alpha);
+                        for rf in 0..=40 {
+                            let expected = (MIN_PARTIAL_ROUNDS..10_000)
+                                .find(|rp| inequalities.is_secure(rf, *rp));
+                            assert_eq!(
+                                expected,
+                                inequalities.min_partial_rounds(rf),
+                                "wrong minimum R_P for alpha = {}, t = {}, n = {}, M = {}, R_F = {}",
+                                alpha,
+                                t,
+                                n,
+                                m,
+                                rf
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_with_alpha() {
+        // Each case contains an S-box exponent `alpha`, a `t`, and the `(R_F, R_P)` expected with and without the
+        // security margin at 128-bit security and `n = 256`.
+        let cases = [
+            (3, 3, (8, 83), (6, 77)),
+            (3, 9, (8, 84), (6, 78)),
+            (5, 3, (8, 55), (6, 51)),
+            (5, 9, (8, 57), (6, 53)),
+            (7, 3, (8, 46), (6, 42)),
+            (11, 9, (8, 38), (6, 35)),
+        ];
+        for (alpha, t, with_margin, without_margin) in cases.iter() {
+            assert_eq!(
+                *with_margin,
+                calc_round_numbers_with_options(
+                    *t,
+                    &options(256, 128, DEFAULT_SECURITY_MARGIN, *alpha)
+                )
+                .unwrap(),
+                "wrong round numbers for alpha = {}, t = {}",
+                alpha,
+                t
+            );
+            assert_eq!(
+                *without_margin,
+                calc_round_numbers_with_options(*t, &options(256, 128, NO_SECURITY_MARGIN, *alpha))
+                    .unwrap(),
+                "wrong round numbers for alpha = {}, t = {} without security margin",
+                alpha,
+                t
+            );
+        }
+
+        // The default exponent falls through to the same calculation as `calc_round_numbers`.
+        for t in [2, 5, 12, 40].iter() {
+            assert_eq!(
+                calc_round_numbers(*t, 255, 128, true, Sbox::Quintic).unwrap(),
+                calc_round_numbers_with_options(*t, &options(255, 128, DEFAULT_SECURITY_MARGIN, 5))
+                    .unwrap()
+            );
+        }
+
+        // Even exponents never give a permutation, and 0 and 1 are not useful S-boxes.
+        for alpha in [-5, -2, 0, 1, 2, 4, 6].iter() {
+            match calc_round_numbers_with_options(
+                3,
+                &options(256, 128, DEFAULT_SECURITY_MARGIN, *alpha),
+            ) {
+                Err(Error::InvalidAlpha(a)) => assert_eq!(*alpha, a),
+                res => panic!("expected an invalid alpha error, got {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_numbers_with_sbox() {
+        // Each case contains an S-box, a `t`, and the `(R_F, R_P)` expected with and without the security margin at
+        // 128-bit security and `n = 256`.
+        let cases = [
+            (Sbox::Quintic, 3, (8, 55), (6, 51)),
+            (Sbox::Quintic, 9, (8, 57), (6, 53)),
+            (Sbox::Cubic, 3, (8, 83), (6, 77)),
+            (Sbox::Cubic, 5, (8, 84), (6, 78)),
+            (Sbox::Inverse, 2, (8, 65), (6, 60)),
+            (Sbox::Inverse, 3, (8, 63), (6, 58)),
+            (Sbox::Inverse, 5, (8, 60), (6, 55)),
+            (Sbox::Inverse, 9, (8, 54), (6, 50)),
+            (Sbox::Inverse, 17, (8, 50), (6, 46)),
+        ];
+        for (sbox, t, with_margin, without_margin) in cases.iter() {
+            assert_eq!(
+                *with_margin,
+                calc_round_numbers(*t, 256, 128, true, *sbox).unwrap(),
+                "wrong round numbers for {:?}, t = {}",
+                sbox,
+                t
+            );
+            assert_eq!(
+                *without_margin,
+                calc_round_numbers(*t, 256, 128, false, *sbox).unwrap(),
+                "wrong round numbers for {:?}, t = {} without security margin",
+                sbox,
+                t
+            );
+            assert_eq!(
+                *with_margin,
+                calc_round_numbers_with_options(
+                    *t,
+                    &options(256, 128, DEFAULT_SECURITY_MARGIN, sbox.alpha())
+                )
+                .unwrap()
+            );
+        }
+
+        // With the inverse S-box, more full rounds reduce the partial rounds required.
+        assert!(round_numbers_are_secure(3, 6, 58, 256, 128, -1));
+        assert!(!round_numbers_are_secure(3, 6, 57, 256, 128, -1));
+        assert!(round_numbers_are_secure(3, 8, 55, 256, 128, -1));
+        assert!(!round_numbers_are_secure(3, 4, 80, 256, 128, -1));
+    }
+
+    #[test]
+    fn test_custom_round_numbers() {
+        let custom = |full, partial| Strength::Custom { full, partial };
+        assert_eq!((8, 57), round_numbers(2, &custom(8, 57)));
+        assert_eq!(
+            RoundNumbers::from_rounds(3, 8, 57, DEFAULT_PRIME_BITLEN),
+            RoundNumbers::new(3, custom(8, 57))
+        );
+
+        let for_prime = |strength| {
+            round_numbers_for_prime(
+                2,
+                &strength,
+                DEFAULT_PRIME_BITLEN,
+                DEFAULT_SECURITY_LEVEL,
+                DEFAULT_ALPHA,
+                DEFAULT_SECURITY_MARGIN,
+            )
+        };
+        assert_eq!(
+            Err(RoundNumberError::OddFullRounds { rf: 9 }),
+            for_prime(custom(9, 57))
+        );
+        assert_eq!(
+            Err(RoundNumberError::InsecureRoundNumbers {
+                t: 3,
+                rf: 8,
+                rp: 40,
+                inequality: SecurityInequality::Interpolation,
+            }),
+            for_prime(custom(8, 40))
+        );
+        // Unlike `Strength::Strengthened`, custom partial rounds are checked as given.
+        assert!(check_rounds_are_secure(8, 55, 3, &custom(8, 55)).is_ok());
+        assert!(check_rounds_are_secure(8, 55, 3, &Strength::Strengthened).is_err());
+    }
+
+    #[test]
+    fn test_strengthened_round_constants() {
+        let cases = [
+            (1, 69),
+            (2, 69),
+            (3, 70),
+            (4, 70),
+            (5, 70),
+            (6, 70),
+            (7, 72),
+            (8, 72),
+            (9, 72),
+            (10, 72),
+            (11, 72),
+            (16, 74),
+            (24, 74),
+            (36, 75),
+            (64, 77),
+        ];
+
+        cases.iter().for_each(|(arity, expected_rounds)| {
+            let (full_rounds, actual_rounds) = round_numbers_strengthened(
+                *arity,
+                DEFAULT_PRIME_BITLEN,
+                DEFAULT_SECURITY_LEVEL,
+                DEFAULT_ALPHA,
+                DEFAULT_SECURITY_MARGIN,
+            )
+            .unwrap();
+            assert_eq!(8, full_rounds);
+            assert_eq!(
+                *expected_rounds, actual_rounds,
+                "wrong number of partial rounds for arity {}",
+                *arity
+            );
+        })
+    }
+
+    #[test]
+    fn test_round_numbers_struct() {
+        let standard = RoundNumbers::new(9, Strength::Standard);
+        assert_eq!(
+            RoundNumbers {
+                full_rounds: 8,
+                partial_rounds: 57,
+                sbox_cost: 129,
+                size_cost: 129 * 256,
+            },
+            standard
+        );
+
+        let strengthened = RoundNumbers::new(9, Strength::Strengthened);
+        assert_eq!(
+            (8, 72),
+            (strengthened.full_rounds, strengthened.partial_rounds)
+        );
+        assert_eq!(n_sboxes(9, 8, 72), strengthened.sbox_cost);
+        assert_eq!(size_cost(9, 8, 72, prime_bitlen()), strengthened.size_cost);
+        assert_eq!(129 * 256, size_cost(9, 8, 57, 256));
+
+        assert_eq!(standard, RoundNumbers::compute(9, true).unwrap());
+        let without_margin = RoundNumbers::compute(9, false).unwrap();
+        assert_eq!(
+            (6, 53),
+            (without_margin.full_rounds, without_margin.partial_rounds)
+        );
+        assert_eq!("[8, 57, 129, 33024]", standard.to_string());
+    }
+
+    #[test]
+    fn test_poseidon_cost() {
+        assert_eq!(
+            PoseidonCost {
+                n_sboxes: 129,
+                n_full_round_sboxes: 72,
+                n_partial_round_sboxes: 57,
+                circuit_constraints_estimate: 387,
+                size_cost_bits: 129 * 255,
+            },
+            poseidon_cost(9, 8, 57, 255)
+        );
+
+        let standard = RoundNumbers::new(3, Strength::Standard);
+        let cost = poseidon_cost(3, standard.full_rounds, standard.partial_rounds, 256);
+        assert_eq!(standard.sbox_cost, cost.n_sboxes);
+        assert_eq!(standard.size_cost, cost.size_cost_bits);
+        assert_eq!(
+            cost.n_sboxes,
+            cost.n_full_round_sboxes + cost.n_partial_round_sboxes
+        );
+    }
+
+    #[test]
+    fn test_check_round_number_security() {
+        // Minimal secure round numbers for t = 3, before any security margin is applied.
+        let report = check_round_number_security(6, 51, 3);
+        assert!(report.is_secure);
+        assert_eq!(report.rf_stat, 6.0);
+        assert_eq!(report.rf_max, 6);
+
+        // One fewer partial round makes the interpolation bound binding.
+        let report = check_round_number_security(6, 50, 3);
+        assert!(!report.is_secure);
+        assert_eq!(report.rf_max, 7);
+        assert_eq!(report.rf_interp.ceil() as usize, report.rf_max);
+        assert_eq!(SecurityInequality::Interpolation, report.binding_inequality);
+
+        // No number of partial rounds compensates for too few full rounds.
+        let report = check_round_number_security(4, 80, 3);
+        assert!(!report.is_secure);
+        assert_eq!(report.rf_max, 6);
+
+        assert_eq!(SecurityInequality::Statistical, report.binding_inequality);
+        assert_eq!(
+            SecurityInequality::Statistical,
+            binding_constraint(4, 80, 3)
+        );
+        assert_eq!(
+            SecurityInequality::Interpolation,
+            binding_constraint(6, 50, 3)
+        );
+
+        // The round numbers actually used for t = 3.
+        assert!(round_numbers_are_secure(
+            3,
+            8,
+            55,
+            DEFAULT_PRIME_BITLEN,
+            DEFAULT_SECURITY_LEVEL,
+            DEFAULT_ALPHA
+        ));
+        assert!(!round_numbers_are_secure(
+            9,
+            6,
+            52,
+            DEFAULT_PRIME_BITLEN,
+            DEFAULT_SECURITY_LEVEL,
+            DEFAULT_ALPHA
+        ));
+    }
+
+    #[test]
+    fn test_check_rounds_are_secure() {
+        use SecurityInequality::*;
+
+        // Each case contains a `t`, a prime bit length `n`, a security level `M`, and round numbers at, one below, and
+        // well above the bound of the inequality, which fails one below.
+        let cases = [
+            (3, 256, 128, (6, 51), (5, 80), (20, 51), Statistical),
+            (3, 256, 128, (6, 51), (6, 50), (6, 150), Interpolation),
+            (9, 255, 80, (6, 48), (6, 47), (6, 100), GroebnerBasis1),
+            (1, 255, 40, (20, 35), (20, 34), (20, 100), GroebnerBasis2),
+        ];
+        for (t, n, m, at, below, above, inequality) in cases.iter() {
+            let check = |(rf, rp): (usize, usize)| {
+                check_round_numbers(*t, rf, rp, &Strength::Standard, *n, *m, DEFAULT_ALPHA)
+            };
+            assert_eq!(Ok(()), check(*at), "{:?} at the {} bound", at, inequality);
+            assert_eq!(
+                Ok(()),
+                check(*above),
+                "{:?} above the {} bound",
+                above,
+                inequality
+            );
+            assert_eq!(
+                Err(RoundNumberError::InsecureRoundNumbers {
+                    t: *t,
+                    rf: below.0,
+                    rp: below.1,
+                    inequality: *inequality,
+                }),
+                check(*below)
+            );
+        }
+
+        // The published round numbers, with and without strengthening.
+        for (t, rf, rp) in PRECOMPUTED_ROUND_NUMBERS.iter() {
+            assert_eq!(
+                Ok(()),
+                check_rounds_are_secure(*rf, *rp, *t, &Strength::Standard)
+            );
+            let (rf, rp) = round_numbers(t - 1, &Strength::Strengthened);
+            assert_eq!(
+                Ok(()),
+                check_rounds_are_secure(rf, rp, *t, &Strength::Strengthened)
+            );
+        }
+
+        // Strengthened from the minimal `(6, 51)` for `t = 3`, with one partial round fewer.
+        assert_eq!(
+            Ok(()),
+            check_rounds_are_secure(6, 64, 3, &Strength::Strengthened)
+        );
+        assert_eq!(
+            "Round numbers R_F = 6, R_P = 63 for width 3 fail the interpolation bound.",
+            check_rounds_are_secure(6, 63, 3, &Strength::Strengthened)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_are_round_numbers_secure() {
+        // The published round numbers for each width are secure at 128 bits, and no longer are with fewer rounds.
+        for (t, rf, rp) in PRECOMPUTED_ROUND_NUMBERS.iter() {
+            for prime_bitlen in PRECOMPUTED_PRIME_BITLENS.iter() {
+                assert!(are_round_numbers_secure(*t, *rf, *rp, 128, *prime_bitlen));
+                assert!(!are_round_numbers_secure(*t, 4, *rp, 128, *prime_bitlen));
+                assert!(!are_round_numbers_secure(*t, *rf, 10, 128, *prime_bitlen));
+            }
+        }
+
+        // The minimal round numbers for other exponents, from `test_round_numbers_with_alpha`.
+        for (alpha, t, rf, rp) in
+            [(3, 3, 6, 77), (7, 3, 6, 42), (11, 9, 6, 35), (-1, 3, 6, 58)].iter()
+        {
+            assert!(are_round_numbers_secure_with_alpha(*t, *rf, *rp, 128, 256, *alpha).unwrap());
+            assert!(
+                !are_round_numbers_secure_with_alpha(*t, *rf, *rp - 1, 128, 256, *alpha).unwrap()
+            );
+        }
+        assert!(are_round_numbers_secure_with_alpha(3, 8, 55, 128, 256, 5).unwrap());
+        assert!(!are_round_numbers_secure_with_alpha(3, 8, 55, 128, 256, 3).unwrap());
+        match are_round_numbers_secure_with_alpha(3, 8, 55, 128, 256, 4) {
+            Err(Error::InvalidAlpha(4)) => (),
+            res => panic!("expected an invalid alpha error, got {:?}", res),
+        }
+        match are_round_numbers_secure_with_alpha(3, 8, 55, 300, 256, 5) {
+            Err(Error::InvalidSecurityLevel(300)) => (),
+            res => panic!("expected an invalid security level error, got {:?}", res),
+        }
+
+        // Each of the four inequalities can be the one to fail, though the second Gröbner basis bound only exceeds
+        // the first at width 1.
+        let cases = [
+            (3, 4, 80, 128, 255, SecurityInequality::Statistical),
+            (3, 6, 50, 128, 255, SecurityInequality::Interpolation),
+            (9, 6, 40, 80, 255, SecurityInequality::GroebnerBasis1),
+            (1, 6, 20, 128, 255, SecurityInequality::GroebnerBasis2),
+        ];
+        for (t, rf, rp, security_bits, prime_bitlen, inequality) in cases.iter() {
+            let report = round_number_security_report(*t, *rf, *rp, *security_bits, *prime_bitlen);
+            assert!(!report.is_secure);
+            assert!(!are_round_numbers_secure(
+                *t,
+                *rf,
+                *rp,
+                *security_bits,
+                *prime_bitlen
+            ));
+            assert_eq!(
+                *inequality, report.binding_inequality,
+                "wrong binding inequality for t = {}, R_F = {}, R_P = {}",
+                t, rf, rp
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_security_levels() {
+        for m in [0, 79, 257, 3000].iter() {
+            match calc_round_numbers(3, 255, *m, true, Sbox::Quintic) {
+                Err(Error::InvalidSecurityLevel(bits)) => assert_eq!(*m, bits),
+                res => panic!("expected an invalid security level error, got {:?}", res),
+            }
+            match calc_round_numbers_with_cost(3, 255, *m, true, Sbox::Quintic, |rf, _, _| rf) {
+                Err(Error::InvalidSecurityLevel(bits)) => assert_eq!(*m, bits),
+                res => panic!("expected an invalid security level error, got {:?}", res),
+            }
+            match round_numbers_for_prime(
+                2,
+                &Strength::Standard,
+                255,
+                *m,
+                DEFAULT_ALPHA,
+                DEFAULT_SECURITY_MARGIN,
+            ) {
+                Err(Error::InvalidSecurityLevel(bits)) => assert_eq!(*m, bits),
+                res => panic!("expected an invalid security level error, got {:?}", res),
+            }
+        }
+
+        // The bounds are inclusive.
+        for m in [MIN_SECURITY_LEVEL, MAX_SECURITY_LEVEL].iter() {
+            assert_eq!(
+                calc_round_numbers(3, 255, *m, true, Sbox::Quintic).unwrap(),
+                const_calc_round_numbers(3, 255, *m, true)
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn test_const_round_numbers_invalid_security_level() {
+        const_calc_round_numbers(3, 255, 3000, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Security level of 3000 bits is outside of the supported range.")]
+    fn test_round_numbers_with_security_invalid_security_level() {
+        round_numbers_with_security(2, &Strength::Standard, 3000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Security level of 5000 bits is outside of the supported range.")]
+    fn test_are_round_numbers_secure_invalid_security_level() {
+        are_round_numbers_secure(3, 8, 55, 5000, 255);
+    }
+}
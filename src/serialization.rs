@@ -0,0 +1,464 @@
+use crate::hash_type::HashType;
+use crate::matrix::Matrix;
+use crate::mds::check_loaded_mds_matrix;
+use crate::poseidon::{Arity, PoseidonConstants};
+use crate::round_numbers::{check_round_numbers, validate_security_level, SecurityMargin};
+use crate::{alpha_is_coprime, Error, Sbox, Strength, DEFAULT_SECURITY_LEVEL};
+use ff::{PrimeField, PrimeFieldRepr, ScalarEngine};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "json")]
+use std::io::{Read, Write};
+
+/// The hash types supported by `PoseidonConstants`, as serialized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum SerializedHashType {
+    MerkleTree,
+    ConstantLength(usize),
+    Encryption,
+}
+
+/// The serialized form of `PoseidonConstants`: everything they are derived from, with field elements as big-endian
+/// hexadecimal strings. The optimized constants are derived again when loading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SerializedConstants {
+    arity: usize,
+    strength: Strength,
+    sbox: Sbox,
+    hash_type: SerializedHashType,
+    domain_tag: String,
+    full_rounds: usize,
+    partial_rounds: usize,
+    round_constants: Vec<String>,
+    mds_matrix: Vec<Vec<String>>,
+    /// The seed of `PoseidonConstants::new_seeded` in hexadecimal, omitted for constants without a seed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<String>,
+    /// The security level in bits of the round numbers, the default if omitted, as by earlier versions.
+    #[serde(default = "default_security_level")]
+    security_level: usize,
+    /// The security margin of the round numbers, the default if omitted, as by earlier versions.
+    #[serde(default)]
+    security_margin: SecurityMargin,
+}
+
+fn default_security_level() -> usize {
+    DEFAULT_SECURITY_LEVEL
+}
+
+impl<E, A> Serialize for PoseidonConstants<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let hash_type = match self.hash_type {
+            HashType::MerkleTree => SerializedHashType::MerkleTree,
+            HashType::ConstantLength(length) => SerializedHashType::ConstantLength(length),
+            HashType::Encryption => SerializedHashType::Encryption,
+            ref hash_type => {
+                return Err(ser::Error::custom(format!(
+                    "unsupported hash type: {:?}",
+                    hash_type
+                )))
+            }
+        };
+
+        SerializedConstants {
+            arity: A::to_usize(),
+            strength: self.strength,
+            sbox: self.sbox,
+            hash_type,
+            domain_tag: fr_to_hex::<E>(&self.domain_tag),
+            full_rounds: self.full_rounds,
+            partial_rounds: self.partial_rounds,
+            round_constants: self.round_constants.iter().map(fr_to_hex::<E>).collect(),
+            mds_matrix: self
+                .mds_matrices
+                .m
+                .iter()
+                .map(|row| row.iter().map(fr_to_hex::<E>).collect())
+                .collect(),
+            seed: self.seed.as_ref().map(|seed| bytes_to_hex(seed)),
+            security_level: self.security_level,
+            security_margin: self.security_margin,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializes constants, accepting them only if they are consistent and secure. See `load_from_json`.
+impl<'de, E, A> Deserialize<'de> for PoseidonConstants<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let serialized = SerializedConstants::deserialize(deserializer)?;
+        Self::from_serialized(serialized).map_err(de::Error::custom)
+    }
+}
+
+impl<E, A> PoseidonConstants<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Writes the constants to `writer` as JSON, from which they can be loaded with `load_from_json` rather than
+    /// generated again.
+    #[cfg(feature = "json")]
+    pub fn save_to_json<W: Write>(&self, writer: W) -> Result<(), Error> {
+        serde_json::to_writer(writer, self).map_err(|e| Error::Other(e.to_string()))
+    }
+
+    /// Reads constants written by `save_to_json` from `reader`.
+    ///
+    /// The round numbers must satisfy the security inequalities for the width, strength, S-box, and field, at the
+    /// security level they were saved with, or `Error::RoundNumberError` is returned. A security level out of range
+    /// returns `Error::InvalidSecurityLevel`. Constants of another arity, with the wrong
+    /// number of round constants, or with a matrix other than the generated one failing `check_mds_matrix` return
+    /// `Error::InvalidConstants`, and field elements that cannot be decoded return `Error::DecodingError`.
+    #[cfg(feature = "json")]
+    pub fn load_from_json<R: Read>(reader: R) -> Result<Self, Error> {
+        let serialized: SerializedConstants =
+            serde_json::from_reader(reader).map_err(|e| Error::Other(e.to_string()))?;
+        Self::from_serialized(serialized)
+    }
+
+    fn from_serialized(serialized: SerializedConstants) -> Result<Self, Error> {
+        let invalid = |message: String| Err(Error::InvalidConstants(message));
+
+        let arity = A::to_usize();
+        let width = arity + 1;
+        if serialized.arity != arity {
+            return invalid(format!(
+                "arity {} does not match {}",
+                serialized.arity, arity
+            ));
+        }
+
+        let (full_rounds, partial_rounds) = (serialized.full_rounds, serialized.partial_rounds);
+        if full_rounds % 2 != 0 {
+            return invalid(format!("full rounds must be even: {}", full_rounds));
+        }
+        if let Strength::Custom { full, partial } = serialized.strength {
+            if (full, partial) != (full_rounds, partial_rounds) {
+                return invalid(format!(
+                    "round numbers ({}, {}) do not match custom strength ({}, {})",
+                    full_rounds, partial_rounds, full, partial
+                ));
+            }
+        }
+        let alpha = serialized.sbox.alpha();
+        if alpha > 0 && !alpha_is_coprime::<E::Fr>(alpha as u64) {
+            return Err(Error::InvalidAlpha(alpha));
+        }
+        let security_level = serialized.security_level;
+        validate_security_level(security_level)?;
+        serialized.security_margin.check()?;
+        check_round_numbers(
+            width,
+            full_rounds,
+            partial_rounds,
+            &serialized.strength,
+            E::Fr::NUM_BITS as usize,
+            security_level,
+            alpha,
+        )?;
+
+        let hash_type = match serialized.hash_type {
+            SerializedHashType::MerkleTree => HashType::MerkleTree,
+            SerializedHashType::ConstantLength(length) if length > 0 && length <= arity => {
+                HashType::ConstantLength(length)
+            }
+            SerializedHashType::ConstantLength(length) => {
+                return invalid(format!(
+                    "constant length {} is not between 1 and the arity",
+                    length
+                ))
+            }
+            SerializedHashType::Encryption => HashType::Encryption,
+        };
+
+        let round_constants = serialized
+            .round_constants
+            .iter()
+            .map(|hex| hex_to_fr::<E>(hex))
+            .collect::<Result<Vec<_>, _>>()?;
+        if round_constants.len() != width * (full_rounds + partial_rounds) {
+            return invalid(format!(
+                "expected {} round constants, found {}",
+                width * (full_rounds + partial_rounds),
+                round_constants.len()
+            ));
+        }
+
+        let mds_matrix = serialized
+            .mds_matrix
+            .iter()
+            .map(|row| row.iter().map(|hex| hex_to_fr::<E>(hex)).collect())
+            .collect::<Result<Matrix<_>, _>>()?;
+        check_loaded_mds_matrix::<E>(&mds_matrix, width)?;
+
+        let domain_tag = hex_to_fr::<E>(&serialized.domain_tag)?;
+        let seed = match serialized.seed {
+            Some(ref hex) => Some(hex_to_bytes(hex)?),
+            None => None,
+        };
+
+        let mut constants = Self::from_parts(
+            serialized.strength,
+            hash_type,
+            serialized.sbox,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds_matrix,
+            seed,
+        );
+        constants.domain_tag = domain_tag;
+        constants.security_level = security_level;
+        constants.security_margin = serialized.security_margin;
+        Ok(constants)
+    }
+}
+
+fn fr_to_hex<E: ScalarEngine>(fr: &E::Fr) -> String {
+    let mut bytes = Vec::new();
+    fr.into_repr()
+        .write_be(&mut bytes)
+        .expect("writing to a Vec cannot fail");
+    bytes_to_hex(&bytes)
+}
+
+fn hex_to_fr<E: ScalarEngine>(hex: &str) -> Result<E::Fr, Error> {
+    let bytes = hex_to_bytes(hex)?;
+
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    if bytes.len() != repr.as_ref().len() * 8 {
+        return Err(Error::DecodingError);
+    }
+    repr.read_be(&bytes[..]).map_err(|_| Error::DecodingError)?;
+    E::Fr::from_repr(repr).map_err(|_| Error::DecodingError)
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, Error> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return Err(Error::DecodingError);
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Error::DecodingError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::Poseidon;
+    use crate::round_numbers::NO_SECURITY_MARGIN;
+    use crate::scalar_from_u64;
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U4, U8};
+    use serde_json::Value;
+
+    fn round_trip<A: Arity<Fr>>(constants: &PoseidonConstants<Bls12, A>) {
+        let json = serde_json::to_string(constants).unwrap();
+        let loaded: PoseidonConstants<Bls12, A> = serde_json::from_str(&json).unwrap();
+        assert_eq!(*constants, loaded);
+
+        let preimage = vec![scalar_from_u64::<Fr>(7); constants.arity()];
+        assert_eq!(
+            Poseidon::new_with_preimage(&preimage, constants).hash(),
+            Poseidon::new_with_preimage(&preimage, &loaded).hash()
+        );
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        round_trip(&PoseidonConstants::<Bls12, U2>::new());
+        round_trip(&PoseidonConstants::<Bls12, U8>::new_with_strength(
+            Strength::Strengthened,
+        ));
+        round_trip(&PoseidonConstants::<Bls12, U4>::new_constant_length(3));
+        round_trip(&PoseidonConstants::<Bls12, U4>::new_with_sbox(
+            Sbox::Inverse,
+        ));
+        round_trip(&PoseidonConstants::<Bls12, U2>::new().with_domain_tag(scalar_from_u64(9)));
+        round_trip(&PoseidonConstants::<Bls12, U4>::new_seeded(
+            b"my-protocol-v1",
+        ));
+        round_trip(&PoseidonConstants::<Bls12, U2>::new_with_strength(
+            Strength::Custom {
+                full: 8,
+                partial: 57,
+            },
+        ));
+    }
+
+    #[test]
+    fn serialize_round_trip_security_level() {
+        let constants = PoseidonConstants::<Bls12, U2>::new_with_security_level(80);
+        round_trip(&constants);
+        round_trip(&PoseidonConstants::<Bls12, U2>::new_with_security_margin(
+            NO_SECURITY_MARGIN,
+        ));
+
+        // The round numbers of 80 bits are checked at 80 bits, and are not secure at the default of 128.
+        let mut value = serde_json::to_value(&constants).unwrap();
+        assert_eq!(80, value["security_level"]);
+        value["security_level"] = Value::from(128);
+        match load::<U2>(&value) {
+            Err(Error::RoundNumberError(_)) => (),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        value["security_level"] = Value::from(5000);
+        match load::<U2>(&value) {
+            Err(Error::InvalidSecurityLevel(5000)) => (),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+
+        // Constants saved without a security level and margin were generated with the defaults.
+        let mut value = serde_json::to_value(PoseidonConstants::<Bls12, U2>::new()).unwrap();
+        let object = value.as_object_mut().unwrap();
+        object.remove("security_level");
+        object.remove("security_margin");
+        assert_eq!(PoseidonConstants::new(), load::<U2>(&value).unwrap());
+    }
+
+    #[test]
+    fn serialize_round_trip_custom_mds() {
+        // Not symmetric, so it is stored transposed.
+        let mds: Matrix<Fr> = [[1, 1, 1], [1, 2, 3], [1, 4, 2]]
+            .iter()
+            .map(|row| row.iter().map(|x| scalar_from_u64::<Fr>(*x)).collect())
+            .collect();
+        round_trip(&PoseidonConstants::<Bls12, U2>::new_with_mds(mds).unwrap());
+    }
+
+    fn load<A: Arity<Fr>>(value: &Value) -> Result<PoseidonConstants<Bls12, A>, Error> {
+        let serialized: SerializedConstants = serde_json::from_value(value.clone()).unwrap();
+        PoseidonConstants::from_serialized(serialized)
+    }
+
+    #[test]
+    fn deserialize_rejects_invalid_constants() {
+        let value = serde_json::to_value(PoseidonConstants::<Bls12, U2>::new()).unwrap();
+        assert!(load::<U2>(&value).is_ok());
+
+        let with = |field: &str, replacement: Value| {
+            let mut value = value.clone();
+            value[field] = replacement;
+            value
+        };
+
+        match load::<U4>(&value) {
+            Err(Error::InvalidConstants(message)) => {
+                assert_eq!("arity 2 does not match 4", message)
+            }
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        match load::<U2>(&with("partial_rounds", Value::from(40))) {
+            Err(Error::RoundNumberError(_)) => (),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        match load::<U2>(&with("sbox", Value::from("Cubic"))) {
+            Err(Error::InvalidAlpha(3)) => (),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+
+        let mut round_constants = value["round_constants"].as_array().unwrap().clone();
+        round_constants.pop();
+        match load::<U2>(&with(
+            "round_constants",
+            Value::from(round_constants.clone()),
+        )) {
+            Err(Error::InvalidConstants(_)) => (),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+
+        // The modulus is not a field element.
+        round_constants.push(Value::from(
+            "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
+        ));
+        match load::<U2>(&with("round_constants", Value::from(round_constants))) {
+            Err(Error::DecodingError) => (),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+
+        let mut mds_matrix = value["mds_matrix"].clone();
+        let zero = Value::from("0".repeat(64));
+        mds_matrix[0] = Value::from(vec![zero.clone(), zero.clone(), zero]);
+        match load::<U2>(&with("mds_matrix", mds_matrix)) {
+            Err(Error::InvalidConstants(message)) => {
+                assert_eq!("MDS matrix is not invertible", message)
+            }
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn serialize_seed() {
+        let value = serde_json::to_value(PoseidonConstants::<Bls12, U2>::new()).unwrap();
+        assert!(value.get("seed").is_none());
+
+        let seeded =
+            serde_json::to_value(PoseidonConstants::<Bls12, U2>::new_seeded(b"abc")).unwrap();
+        assert_eq!("616263", seeded["seed"]);
+
+        // Constants loaded with another seed are not equal to those generated with it.
+        let mut reseeded = seeded.clone();
+        reseeded["seed"] = Value::from("616264");
+        assert_ne!(
+            PoseidonConstants::<Bls12, U2>::new_seeded(b"abc"),
+            load::<U2>(&reseeded).unwrap()
+        );
+        reseeded["seed"] = Value::from("6162x");
+        match load::<U2>(&reseeded) {
+            Err(Error::DecodingError) => (),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn deserialize_rejects_tampered_width() {
+        let mut value = serde_json::to_value(PoseidonConstants::<Bls12, U2>::new()).unwrap();
+
+        // The matrix of width 5 is rejected by its dimensions before it is inverted or factored.
+        let wide = serde_json::to_value(PoseidonConstants::<Bls12, U4>::new()).unwrap();
+        value["mds_matrix"] = wide["mds_matrix"].clone();
+        let error = serde_json::from_value::<PoseidonConstants<Bls12, U2>>(value.clone())
+            .err()
+            .unwrap();
+        assert_eq!(
+            "Invalid Poseidon constants: MDS matrix must be 3 by 3",
+            error.to_string()
+        );
+
+        // The arity is checked against `A` when deserializing through serde.
+        value["arity"] = Value::from(4);
+        let error = serde_json::from_value::<PoseidonConstants<Bls12, U2>>(value)
+            .err()
+            .unwrap();
+        assert_eq!(
+            "Invalid Poseidon constants: arity 4 does not match 2",
+            error.to_string()
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip() {
+        let constants = PoseidonConstants::<Bls12, U8>::new();
+        let mut json = Vec::new();
+        constants.save_to_json(&mut json).unwrap();
+        assert_eq!(
+            constants,
+            PoseidonConstants::<Bls12, U8>::load_from_json(&json[..]).unwrap()
+        );
+    }
+}
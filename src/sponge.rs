@@ -0,0 +1,320 @@
+use crate::hash_type::HashType;
+use crate::poseidon::{Arity, Poseidon, PoseidonConstants};
+use ff::{Field, ScalarEngine};
+use generic_array::typenum::marker_traits::Unsigned;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SpongeMode {
+    Absorbing,
+    Squeezing,
+}
+
+/// A sponge construction over the Poseidon permutation, for hashing sequences of field elements of any length.
+///
+/// The permutation's state of `A + 1` elements is split into `capacity` elements, which are never absorbed into or
+/// squeezed from, and `rate = A + 1 - capacity` elements. Elements are absorbed `rate` at a time, by adding them to
+/// the rate portion of the state and applying the permutation between blocks.
+///
+/// The first capacity element holds the `HashType::VariableLength` domain tag, unless another is given to
+/// `new_with_domain_tag`, and absorbed elements are padded with a single one (followed by zeros) when squeezing, so
+/// that preimages of different lengths are distinguished.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoseidonSponge<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    state: Poseidon<'a, E, A>,
+    capacity: usize,
+    rate: usize,
+    mode: SpongeMode,
+    /// Position within the rate of the next element to absorb or squeeze.
+    pos: usize,
+}
+
+impl<'a, E, A> PoseidonSponge<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Creates a sponge with a capacity of one element, and so a rate equal to the arity.
+    pub fn new(constants: &'a PoseidonConstants<E, A>) -> Self {
+        Self::new_with_capacity(constants, 1)
+    }
+
+    /// # Panics
+    ///
+    /// Panics unless `capacity` is at least 1 and less than the width, leaving a rate of at least 1.
+    pub fn new_with_capacity(constants: &'a PoseidonConstants<E, A>, capacity: usize) -> Self {
+        let width = A::ConstantsSize::to_usize();
+        assert!(
+            capacity >= 1 && capacity < width,
+            "capacity must be between 1 and {}",
+            width - 1
+        );
+
+        let domain_tag = HashType::<E::Fr, A>::VariableLength.domain_tag(&constants.strength);
+        Self::with_initial_capacity(constants, capacity, domain_tag)
+    }
+
+    /// Creates a sponge with a capacity of one element, initialized to `domain_tag` rather than the
+    /// `HashType::VariableLength` domain tag, separating the outputs of
+    /// sponges used for different purposes.
+    pub fn new_with_domain_tag(constants: &'a PoseidonConstants<E, A>, domain_tag: E::Fr) -> Self {
+        Self::with_initial_capacity(constants, 1, domain_tag)
+    }
+
+    fn with_initial_capacity(
+        constants: &'a PoseidonConstants<E, A>,
+        capacity: usize,
+        domain_tag: E::Fr,
+    ) -> Self {
+        let width = A::ConstantsSize::to_usize();
+        let mut state = Poseidon::new(constants);
+        state.elements[0] = domain_tag;
+
+        PoseidonSponge {
+            state,
+            capacity,
+            rate: width - capacity,
+            mode: SpongeMode::Absorbing,
+            pos: 0,
+        }
+    }
+
+    /// Returns the capacity, in field elements.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the rate, in field elements.
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Absorbs a single element. Absorbing after squeezing continues from the current state, as in a duplex.
+    pub fn absorb(&mut self, element: &E::Fr) {
+        match self.mode {
+            SpongeMode::Absorbing => {
+                if self.pos == self.rate {
+                    self.state.permute();
+                    self.pos = 0;
+                }
+            }
+            SpongeMode::Squeezing => {
+                self.mode = SpongeMode::Absorbing;
+                self.pos = 0;
+            }
+        }
+
+        self.state.elements[self.capacity + self.pos].add_assign(element);
+        self.pos += 1;
+    }
+
+    /// Absorbs each of `elements` in turn.
+    pub fn absorb_elements(&mut self, elements: &[E::Fr]) {
+        for element in elements {
+            self.absorb(element);
+        }
+    }
+
+    /// Squeezes a single element, padding and permuting the absorbed elements first if needed.
+    pub fn squeeze(&mut self) -> E::Fr {
+        match self.mode {
+            SpongeMode::Absorbing => {
+                self.pad();
+                self.state.permute();
+                self.mode = SpongeMode::Squeezing;
+                self.pos = 0;
+            }
+            SpongeMode::Squeezing => {
+                if self.pos == self.rate {
+                    self.state.permute();
+                    self.pos = 0;
+                }
+            }
+        }
+
+        let output = self.state.elements[self.capacity + self.pos];
+        self.pos += 1;
+        output
+    }
+
+    /// Squeezes `n` elements.
+    pub fn squeeze_elements(&mut self, n: usize) -> Vec<E::Fr> {
+        (0..n).map(|_| self.squeeze()).collect()
+    }
+
+    /// Hashes `preimage`, of any length, to a single element.
+    pub fn hash(preimage: &[E::Fr], constants: &'a PoseidonConstants<E, A>) -> E::Fr {
+        let mut sponge = Self::new(constants);
+        sponge.absorb_elements(preimage);
+        sponge.squeeze()
+    }
+
+    // Adds a one after the absorbed elements, permuting first if the rate is full.
+    fn pad(&mut self) {
+        if self.pos == self.rate {
+            self.state.permute();
+            self.pos = 0;
+        }
+        self.state.elements[self.capacity + self.pos].add_assign(&E::Fr::one());
+    }
+}
+
+/// With the `zeroize` feature, `zeroize` overwrites the state, including the domain tag and any absorbed elements,
+/// with zeros, leaving the sponge absorbing from an all-zero state. The state is a `Poseidon`, which is zeroized when
+/// dropped, and so is the sponge.
+#[cfg(feature = "zeroize")]
+impl<'a, E, A> Zeroize for PoseidonSponge<'a, E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn zeroize(&mut self) {
+        self.state.zeroize();
+        self.mode = SpongeMode::Absorbing;
+        self.pos = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::HashMode;
+    use crate::{scalar_from_u64, Strength};
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U4};
+
+    fn scalars(xs: &[u64]) -> Vec<Fr> {
+        xs.iter().map(|x| scalar_from_u64::<Fr>(*x)).collect()
+    }
+
+    #[test]
+    fn sponge_matches_permutation() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let preimage = scalars(&[1, 2, 3]);
+
+        // Rebuild the sponge with the reference permutation: absorb [1, 2], permute, absorb [3] and the padding.
+        let mut p = Poseidon::<Bls12, U2>::new(&constants);
+        p.elements[0] = HashType::<Fr, U2>::VariableLength.domain_tag(&Strength::Standard);
+        p.elements[1] = preimage[0];
+        p.elements[2] = preimage[1];
+        p.hash_in_mode(HashMode::Correct);
+
+        let mut q = Poseidon::<Bls12, U2>::new(&constants);
+        q.elements = p.elements.clone();
+        q.elements[1].add_assign(&preimage[2]);
+        q.elements[2].add_assign(&Fr::one());
+        let expected = q.hash_in_mode(HashMode::Correct);
+
+        let mut sponge = PoseidonSponge::new(&constants);
+        assert_eq!(2, sponge.rate());
+        sponge.absorb_elements(&preimage);
+        assert_eq!(expected, sponge.squeeze());
+        // The second squeezed element is the rest of the rate.
+        assert_eq!(q.elements[2], sponge.squeeze());
+
+        assert_eq!(expected, PoseidonSponge::hash(&preimage, &constants));
+    }
+
+    #[test]
+    fn sponge_padding() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+
+        let digests = vec![
+            PoseidonSponge::hash(&[], &constants),
+            PoseidonSponge::hash(&scalars(&[0]), &constants),
+            PoseidonSponge::hash(&scalars(&[0, 0]), &constants),
+            PoseidonSponge::hash(&scalars(&[0, 0, 0]), &constants),
+            PoseidonSponge::hash(&scalars(&[1]), &constants),
+        ];
+        assert_distinct(&digests);
+    }
+
+    fn assert_distinct(xs: &[Fr]) {
+        for (i, x) in xs.iter().enumerate() {
+            for y in xs[i + 1..].iter() {
+                assert_ne!(x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn sponge_squeeze_elements() {
+        let constants = PoseidonConstants::<Bls12, U4>::new();
+        let mut sponge = PoseidonSponge::new_with_capacity(&constants, 2);
+        assert_eq!(2, sponge.capacity());
+        assert_eq!(3, sponge.rate());
+
+        sponge.absorb_elements(&scalars(&[1, 2, 3, 4, 5, 6, 7]));
+        let mut sponge2 = sponge.clone();
+
+        let outputs = sponge.squeeze_elements(7);
+        assert_eq!(outputs[0], sponge2.squeeze());
+        assert_eq!(&outputs[1..], &sponge2.squeeze_elements(6)[..]);
+
+        // Squeezing across blocks applies the permutation, so outputs do not repeat.
+        assert_distinct(&outputs);
+    }
+
+    #[test]
+    fn sponge_long_message() {
+        let constants = PoseidonConstants::<Bls12, U4>::new();
+        let message = scalars(&(0..37).collect::<Vec<_>>());
+
+        // 37 elements fill 9 blocks of the rate of 4, and the padding follows the last element in the 10th.
+        let mut p = Poseidon::<Bls12, U4>::new(&constants);
+        p.elements[0] = HashType::<Fr, U4>::VariableLength.domain_tag(&Strength::Standard);
+        for (i, element) in message.iter().enumerate() {
+            if i > 0 && i % 4 == 0 {
+                // The round state is not reset by hashing, so the next block needs a fresh `Poseidon`.
+                p.hash_in_mode(HashMode::Correct);
+                let elements = p.elements.clone();
+                p = Poseidon::new(&constants);
+                p.elements = elements;
+            }
+            p.elements[1 + i % 4].add_assign(element);
+        }
+        p.elements[2].add_assign(&Fr::one());
+        let expected = p.hash_in_mode(HashMode::Correct);
+
+        assert_eq!(expected, PoseidonSponge::hash(&message, &constants));
+    }
+
+    #[test]
+    fn sponge_domain_tag() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let preimage = scalars(&[1, 2, 3]);
+        let squeeze = |mut sponge: PoseidonSponge<Bls12, U2>| {
+            sponge.absorb_elements(&preimage);
+            sponge.squeeze()
+        };
+
+        let tag = HashType::<Fr, U2>::VariableLength.domain_tag(&Strength::Standard);
+        assert_eq!(
+            PoseidonSponge::hash(&preimage, &constants),
+            squeeze(PoseidonSponge::new_with_domain_tag(&constants, tag))
+        );
+        assert_distinct(&[
+            squeeze(PoseidonSponge::new(&constants)),
+            squeeze(PoseidonSponge::new_with_domain_tag(
+                &constants,
+                scalar_from_u64(1),
+            )),
+            squeeze(PoseidonSponge::new_with_domain_tag(
+                &constants,
+                scalar_from_u64(2),
+            )),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be between 1 and 2")]
+    fn sponge_full_capacity() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        PoseidonSponge::new_with_capacity(&constants, 3);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::hash_type::HashType;
+use crate::poseidon::{Arity, PoseidonConstants};
+use crate::{Sbox, Strength};
+use ff::{PrimeField, PrimeFieldRepr, ScalarEngine};
+use std::fmt;
+
+/// The length in bytes of `PoseidonConstants::fingerprint`.
+pub const FINGERPRINT_BYTES: usize = 8;
+
+/// What identifies an instance of `PoseidonConstants`, without its round constants and matrices: enough to tell
+/// whether two implementations hash with the same parameters, and by the fingerprint, with the same constants.
+///
+/// `Display` formats it on a single line, as `Display` and `Debug` of `PoseidonConstants` do.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstantsSummary<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// The modulus of the field.
+    pub modulus: <E::Fr as PrimeField>::Repr,
+    pub width: usize,
+    pub arity: usize,
+    pub strength: Strength,
+    pub sbox: Sbox,
+    pub hash_type: HashType<E::Fr, A>,
+    pub domain_tag: E::Fr,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    /// See `PoseidonConstants::fingerprint`.
+    pub fingerprint: [u8; FINGERPRINT_BYTES],
+}
+
+impl<E, A> PoseidonConstants<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    /// Returns the parameters identifying the constants, and their fingerprint.
+    pub fn summary(&self) -> ConstantsSummary<E, A> {
+        ConstantsSummary {
+            modulus: E::Fr::char(),
+            width: self.width(),
+            arity: self.arity(),
+            strength: self.strength,
+            sbox: self.sbox,
+            hash_type: self.hash_type.clone(),
+            domain_tag: self.domain_tag,
+            full_rounds: self.full_rounds,
+            partial_rounds: self.partial_rounds,
+            fingerprint: self.fingerprint(),
+        }
+    }
+
+    /// Returns a short fingerprint of the round constants and MDS matrix, so that two parties can check that their
+    /// constants agree by comparing 16 hexadecimal digits.
+    ///
+    /// The fingerprint is the first 8 bytes of the BLAKE2s-256 hash of the round constants, as generated (not
+    /// compressed), followed by the MDS matrix `m`, row by row, each element in its 32-byte little-endian canonical
+    /// representation. It does not change between releases unless the constants do: `fingerprint_golden` pins it.
+    pub fn fingerprint(&self) -> [u8; FINGERPRINT_BYTES] {
+        let mut state = blake2s_simd::State::new();
+        let mut bytes = Vec::new();
+        for element in self
+            .round_constants
+            .iter()
+            .chain(self.mds_matrices.m.iter().flatten())
+        {
+            bytes.clear();
+            element
+                .into_repr()
+                .write_le(&mut bytes)
+                .expect("writing to a Vec cannot fail");
+            state.update(&bytes);
+        }
+
+        let mut fingerprint = [0u8; FINGERPRINT_BYTES];
+        fingerprint.copy_from_slice(&state.finalize().as_bytes()[..FINGERPRINT_BYTES]);
+        fingerprint
+    }
+}
+
+/// Formats the summary as, for example,
+/// `Poseidon(p = 0x73ed...0001, t = 3, arity = 2, Standard, x^5, MerkleTree, domain tag = Fr(0x...03), R_F = 8,
+/// R_P = 55, fingerprint = f926cdbbdaeec122)`, with the modulus in full.
+impl<E, A> fmt::Display for ConstantsSummary<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "Poseidon(p = {}, t = {}, arity = {}, {:?}, x^{}, {:?}, ",
+            self.modulus,
+            self.width,
+            self.arity,
+            self.strength,
+            self.sbox.alpha(),
+            self.hash_type
+        )?;
+        write!(
+            f,
+            "domain tag = {}, R_F = {}, R_P = {}, fingerprint = ",
+            self.domain_tag, self.full_rounds, self.partial_rounds
+        )?;
+        for byte in self.fingerprint.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Formats the summary of the constants, see `ConstantsSummary`.
+impl<E, A> fmt::Display for PoseidonConstants<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.summary())
+    }
+}
+
+/// Formats the summary of the constants rather than every round constant and matrix element, which run to megabytes
+/// for the widest hashers.
+impl<E, A> fmt::Debug for PoseidonConstants<E, A>
+where
+    E: ScalarEngine,
+    A: Arity<E::Fr>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellperson::bls::{Bls12, Fr};
+    use generic_array::typenum::{U2, U8};
+
+    fn hex(fingerprint: [u8; FINGERPRINT_BYTES]) -> String {
+        fingerprint.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn summary() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let summary = constants.summary();
+        assert_eq!(Fr::char(), summary.modulus);
+        assert_eq!((3, 2), (summary.width, summary.arity));
+        assert_eq!(Strength::Standard, summary.strength);
+        assert_eq!(Sbox::Quintic, summary.sbox);
+        assert_eq!(HashType::MerkleTree, summary.hash_type);
+        assert_eq!(constants.domain_tag, summary.domain_tag);
+        assert_eq!((8, 55), (summary.full_rounds, summary.partial_rounds));
+
+        let display = constants.to_string();
+        assert!(display.starts_with("Poseidon(p = 0x73eda753"));
+        assert!(display.ends_with(&format!(
+            "R_F = 8, R_P = 55, fingerprint = {})",
+            hex(constants.fingerprint())
+        )));
+        assert_eq!(display, format!("{:?}", constants));
+        assert!(display.len() < 400);
+    }
+
+    #[test]
+    fn fingerprint_golden() {
+        // Computed independently by hashing the round constants and MDS matrix of `scripts/generate_test_vectors.py`.
+        // If these change, so do the constants, and with them every digest.
+        if cfg!(feature = "mds-security") {
+            // The subspace trail checks may select another Cauchy matrix.
+            return;
+        }
+        assert_eq!(
+            "f926cdbbdaeec122",
+            hex(PoseidonConstants::<Bls12, U2>::new().fingerprint())
+        );
+        assert_eq!(
+            "5f6a517b4d5dfc40",
+            hex(PoseidonConstants::<Bls12, U8>::new().fingerprint())
+        );
+    }
+
+    #[test]
+    fn fingerprint_distinguishes_constants() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        // The domain tag is summarized separately, and does not change the round constants.
+        let tagged = constants.with_domain_tag(crate::scalar_from_u64(42));
+        assert_eq!(constants.fingerprint(), tagged.fingerprint());
+
+        let strengthened =
+            PoseidonConstants::<Bls12, U2>::new_with_strength(Strength::Strengthened);
+        assert_ne!(constants.fingerprint(), strengthened.fingerprint());
+        assert_ne!(constants.summary(), strengthened.summary());
+    }
+}
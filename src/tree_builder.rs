@@ -247,7 +247,6 @@ where
     }
 }
 
-#[cfg(all(any(feature = "gpu", feature = "opencl"), not(target_os = "macos")))]
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -412,6 +412,9 @@ fn init_hash2(ctx: &mut FutharkContext, strength: Strength) -> Result<BatcherSta
                 .map_err(|e| Error::GPUError(format!("{:?}", e)))?;
             Ok(BatcherState::Arity2s(state))
         }
+        Strength::Custom { .. } => Err(Error::GPUError(
+            "custom round numbers are not supported by the GPU kernels".to_string(),
+        )),
     }
 }
 
@@ -444,6 +447,9 @@ fn init_hash8(ctx: &mut FutharkContext, strength: Strength) -> Result<BatcherSta
 
             Ok(BatcherState::Arity8s(state))
         }
+        Strength::Custom { .. } => Err(Error::GPUError(
+            "custom round numbers are not supported by the GPU kernels".to_string(),
+        )),
     }
 }
 
@@ -477,6 +483,9 @@ fn init_hash11(ctx: &mut FutharkContext, strength: Strength) -> Result<BatcherSt
 
             Ok(BatcherState::Arity11s(state))
         }
+        Strength::Custom { .. } => Err(Error::GPUError(
+            "custom round numbers are not supported by the GPU kernels".to_string(),
+        )),
     }
 }
 
@@ -0,0 +1,183 @@
+//! JavaScript bindings for the Poseidon hash, for computing in the browser the digests computed by the same code on a
+//! server, and those of circomlib's Poseidon, which Circom circuits and `circomlibjs` compute.
+//!
+//! With the `wasm` feature, which enables `bn254`, `wasm-pack` builds an ES module into `pkg/` from the `neptune-wasm`
+//! crate of the workspace, a `cdylib` re-exporting this module, as the crate itself is built as an `rlib` only:
+//!
+//! ```sh
+//! wasm-pack build wasm --target web --out-dir ../pkg --out-name neptune
+//! ```
+//!
+//! The module exports:
+//!
+//! ```js
+//! poseidonHash(inputs: Uint8Array): Uint8Array
+//! circomPoseidonHash(inputs: Uint8Array): Uint8Array
+//! ```
+//!
+//! `poseidonHash` hashes 32-byte little-endian BLS12-381 scalars, as `ffi` does, and `circomPoseidonHash` 32-byte
+//! little-endian BN254 scalars with `bn254::circom_hash`. Both hash with constants created once per process, on the
+//! first hash of each arity. `tests/wasm/poseidon.test.mjs` checks the module against the test vectors of
+//! `tests/compatibility.rs` and the digests of `circomlibjs`.
+use crate::bn254::{
+    circom_hash, shared_circom_constants, Bn254, Bn254Field, CIRCOM_PARTIAL_ROUNDS,
+};
+use crate::poseidon::{batch_hash, visit_arity, Arity, ArityVisitor, MAX_WIDTH};
+use crate::{scalar_from_le_bytes, Scalar};
+use bellperson::bls::Bls12;
+use ff::{PrimeField, PrimeFieldRepr};
+use wasm_bindgen::prelude::*;
+
+/// The number of bytes of a field element.
+const ELEMENT_BYTES: usize = 32;
+
+/// Hashes `inputs`, the concatenated encodings of 1 through 127 field elements, with the Poseidon constants of arity
+/// their number, as `poseidon::batch_hash` does, and returns the 32-byte digest.
+///
+/// Throws if `inputs` is not a supported number of elements, or if an input is not the canonical encoding of a field
+/// element.
+#[wasm_bindgen(js_name = poseidonHash)]
+pub fn poseidon_hash(inputs: &[u8]) -> Result<Vec<u8>, JsValue> {
+    hash(inputs).map_err(|e| JsValue::from_str(&e))
+}
+
+/// Hashes `inputs`, the concatenated encodings of 1 through 16 elements of the scalar field of BN254, as circomlib's
+/// `poseidon` does, with the constants of `bn254::circom_constants` for their number, and returns the 32-byte digest.
+///
+/// Throws if `inputs` is not a supported number of elements, or if an input is not the canonical encoding of a field
+/// element.
+#[wasm_bindgen(js_name = circomPoseidonHash)]
+pub fn circom_poseidon_hash(inputs: &[u8]) -> Result<Vec<u8>, JsValue> {
+    circom(inputs).map_err(|e| JsValue::from_str(&e))
+}
+
+// `poseidon_hash`, with errors as strings, as a `JsValue` cannot be created outside of WebAssembly.
+fn hash(inputs: &[u8]) -> Result<Vec<u8>, String> {
+    let preimage = decode::<Scalar>(inputs, MAX_WIDTH - 1)?;
+    Ok(encode(batch_hash::<Bls12>(&[preimage])[0]))
+}
+
+// `circom_poseidon_hash`, with errors as strings.
+fn circom(inputs: &[u8]) -> Result<Vec<u8>, String> {
+    struct CircomHash(Vec<Bn254Field>);
+
+    impl ArityVisitor<Bn254> for CircomHash {
+        type Output = Bn254Field;
+
+        fn visit<A: Arity<Bn254Field> + Send + Sync + 'static>(self) -> Bn254Field {
+            circom_hash(&self.0, &shared_circom_constants::<A>())
+        }
+    }
+
+    let preimage = decode::<Bn254Field>(inputs, CIRCOM_PARTIAL_ROUNDS.len())?;
+    Ok(encode(visit_arity::<Bn254, _>(
+        preimage.len(),
+        CircomHash(preimage),
+    )))
+}
+
+// Decodes the concatenated encodings of 1 through `max_elements` field elements.
+fn decode<F: PrimeField>(inputs: &[u8], max_elements: usize) -> Result<Vec<F>, String> {
+    let n_inputs = inputs.len() / ELEMENT_BYTES;
+    if inputs.len() % ELEMENT_BYTES != 0 || n_inputs == 0 || n_inputs > max_elements {
+        return Err(format!(
+            "expected 1 to {} field elements of {} bytes, found {} bytes",
+            max_elements,
+            ELEMENT_BYTES,
+            inputs.len()
+        ));
+    }
+
+    inputs
+        .chunks(ELEMENT_BYTES)
+        .map(scalar_from_le_bytes)
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| "an input is not the canonical encoding of a field element".to_string())
+}
+
+fn encode<F: PrimeField>(digest: F) -> Vec<u8> {
+    let mut output = Vec::with_capacity(ELEMENT_BYTES);
+    digest
+        .into_repr()
+        .write_le(&mut output)
+        .expect("writing to a Vec cannot fail");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::{Poseidon, PoseidonConstants};
+    use crate::scalar_from_u64;
+    use generic_array::typenum::{U2, U8};
+
+    fn to_bytes<F: PrimeField>(elements: &[F]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for element in elements {
+            element.into_repr().write_le(&mut bytes).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn hash_matches_poseidon() {
+        let preimage: Vec<Scalar> = (0..8).map(scalar_from_u64).collect();
+
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let expected = Poseidon::new_with_preimage(&preimage[..2], &constants).hash();
+        assert_eq!(Ok(to_bytes(&[expected])), hash(&to_bytes(&preimage[..2])));
+
+        let constants = PoseidonConstants::<Bls12, U8>::new();
+        let expected = Poseidon::new_with_preimage(&preimage, &constants).hash();
+        assert_eq!(Ok(to_bytes(&[expected])), hash(&to_bytes(&preimage)));
+    }
+
+    #[test]
+    fn hash_rejects_invalid_inputs() {
+        let inputs = to_bytes(&[scalar_from_u64(1), scalar_from_u64(2)]);
+        assert!(hash(&[]).is_err());
+        assert!(hash(&inputs[..ELEMENT_BYTES + 1]).is_err());
+        assert!(hash(&vec![0; 128 * ELEMENT_BYTES]).is_err());
+
+        // The modulus is not a canonical encoding.
+        let mut inputs = inputs;
+        Scalar::char()
+            .write_le(&mut inputs[ELEMENT_BYTES..])
+            .unwrap();
+        assert_eq!(
+            Err("an input is not the canonical encoding of a field element".to_string()),
+            hash(&inputs)
+        );
+    }
+
+    #[test]
+    fn circom_matches_circomlibjs() {
+        // The digests of circomlibjs's `poseidon` for the preimages `[1]`, `[1, 2]`, and `[1, 2, 3, 4]`, big-endian.
+        let expected = [
+            "29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133",
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+            "299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465",
+        ];
+        for (n, expected) in [1, 2, 4].iter().zip(expected.iter()) {
+            let preimage: Vec<Bn254Field> = (1..=*n).map(scalar_from_u64).collect();
+            let digest = circom(&to_bytes(&preimage)).unwrap();
+            let hex: String = digest.iter().rev().map(|b| format!("{:02x}", b)).collect();
+            assert_eq!(*expected, hex);
+        }
+    }
+
+    #[test]
+    fn circom_rejects_invalid_inputs() {
+        assert!(circom(&[]).is_err());
+        assert!(circom(&vec![0; 17 * ELEMENT_BYTES]).is_err());
+        assert!(circom(&vec![0; 16 * ELEMENT_BYTES]).is_ok());
+
+        // The BLS12-381 modulus is canonical there, but exceeds the modulus of BN254.
+        let mut inputs = Vec::new();
+        Scalar::char().write_le(&mut inputs).unwrap();
+        assert_eq!(
+            Err("an input is not the canonical encoding of a field element".to_string()),
+            circom(&inputs)
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+/// Overwrites each element of `elements` with `zero`, by volatile writes which the compiler cannot remove as dead
+/// stores, even when `elements` is about to be dropped.
+pub(crate) fn wipe<T: Copy>(elements: &mut [T], zero: T) {
+    for element in elements.iter_mut() {
+        // `element` is a valid reference, and `T` is `Copy`, so overwriting it drops nothing.
+        unsafe { ptr::write_volatile(element, zero) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Empties `vec` and overwrites its whole capacity with `zero`, including elements removed before, which `Vec` leaves
+/// in its buffer.
+pub(crate) fn wipe_vec<T: Copy>(vec: &mut Vec<T>, zero: T) {
+    let capacity = vec.capacity();
+    vec.clear();
+    let start = vec.as_mut_ptr();
+    for i in 0..capacity {
+        // The buffer holds `capacity` elements, and writing does not read the possibly uninitialized ones.
+        unsafe { ptr::write_volatile(start.add(i), zero) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::{Poseidon, PoseidonConstants};
+    use crate::scalar_from_u64;
+    use bellperson::bls::{Bls12, Fr};
+    use ff::Field;
+    use generic_array::typenum::U2;
+    #[cfg(feature = "zeroize")]
+    use std::mem::ManuallyDrop;
+
+    // A recognizable secret, which should not be found in a buffer once it is wiped.
+    const CANARY: u64 = 0x5ec2_e75e_c2e7_5ec2;
+
+    #[test]
+    fn wipe_slice_and_vec() {
+        let mut elements = [1u8, 2, 3];
+        wipe(&mut elements, 0);
+        assert_eq!([0, 0, 0], elements);
+
+        let mut vec = vec![1u8, 2, 3, 4];
+        vec.truncate(1);
+        wipe_vec(&mut vec, 0);
+        assert!(vec.is_empty());
+        // Every element of the buffer was written, so all of them can be read back.
+        unsafe { vec.set_len(vec.capacity()) };
+        assert!(vec.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn poseidon_wipe() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let canary: Fr = scalar_from_u64(CANARY);
+        let mut poseidon = Poseidon::new_with_preimage(&[canary, canary], &constants);
+        let digest = poseidon.hash();
+
+        poseidon.wipe();
+        assert!(!poseidon.elements.contains(&canary));
+        assert!(!poseidon.elements.contains(&digest));
+        assert_eq!(Poseidon::new(&constants), poseidon);
+
+        // The hasher can be reused.
+        poseidon.set_preimage(&[canary, canary]);
+        assert_eq!(digest, poseidon.hash());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn poseidon_state_is_zeroized_on_drop() {
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let canary: Fr = scalar_from_u64(CANARY);
+        let mut poseidon =
+            ManuallyDrop::new(Poseidon::new_with_preimage(&[canary, canary], &constants));
+        assert!(poseidon.elements.contains(&canary));
+
+        // The elements are `Copy` and stay in place, so they can be checked after the drop.
+        unsafe { ManuallyDrop::drop(&mut poseidon) };
+        assert!(poseidon.elements.iter().all(|x| x.is_zero()));
+
+        let mut poseidon =
+            ManuallyDrop::new(Poseidon::new_with_preimage(&[canary, canary], &constants));
+        poseidon.hash();
+        assert!(poseidon.elements.iter().any(|x| !x.is_zero()));
+        unsafe { ManuallyDrop::drop(&mut poseidon) };
+        assert!(poseidon.elements.iter().all(|x| x.is_zero()));
+        assert!(poseidon.domain_tag.is_zero());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn sponge_state_is_zeroized() {
+        use crate::sponge::PoseidonSponge;
+        use zeroize::Zeroize;
+
+        let constants = PoseidonConstants::<Bls12, U2>::new();
+        let canary: Fr = scalar_from_u64(CANARY);
+        let mut sponge = PoseidonSponge::new(&constants);
+        sponge.absorb(&canary);
+        sponge.zeroize();
+
+        // Absorbing from the zeroized state hashes as a sponge with a zero domain tag.
+        let mut expected = PoseidonSponge::new_with_domain_tag(&constants, Fr::zero());
+        expected.absorb(&canary);
+        sponge.absorb(&canary);
+        assert_eq!(expected.squeeze(), sponge.squeeze());
+    }
+}
@@ -0,0 +1,157 @@
+use bellperson::bls::{Bls12, Fr};
+use ff::{PrimeField, PrimeFieldRepr};
+use generic_array::typenum::{U11, U16, U2, U23, U24, U32, U36, U4, U8};
+use neptune::filecoin::{filecoin_poseidon_12, filecoin_poseidon_24};
+use neptune::poseidon::PoseidonConstants;
+use neptune::{Arity, Poseidon};
+use serde_json::Value;
+use std::fs::File;
+
+// The test vectors shared with other implementations, generated by `scripts/generate_test_vectors.py`.
+const VECTORS: &str = "tests/vectors/poseidon_bls12_381.json";
+
+// The test vectors of circomlib's Poseidon over BN254, generated by `scripts/generate_bn254_vectors.py`.
+#[cfg(feature = "bn254")]
+const BN254_VECTORS: &str = "tests/vectors/poseidon_bn254.json";
+
+// Parses a big-endian hex string of 64 digits, prefixed by `0x`.
+fn repr_from_hex<F: PrimeField>(value: &Value) -> F::Repr {
+    let hex = value.as_str().expect("field elements are strings");
+    let digits = hex.trim_start_matches("0x");
+    assert_eq!(
+        64,
+        digits.len(),
+        "field elements are 64 hex digits: {}",
+        hex
+    );
+    let bytes: Vec<u8> = (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+        .collect();
+
+    let mut repr = F::Repr::default();
+    repr.read_be(&bytes[..]).unwrap();
+    repr
+}
+
+fn element_from_hex<F: PrimeField>(value: &Value) -> F {
+    F::from_repr(repr_from_hex::<F>(value)).unwrap()
+}
+
+fn hash<A: Arity<Fr>>(inputs: &[Fr]) -> Fr {
+    let constants = PoseidonConstants::<Bls12, A>::new();
+    Poseidon::new_with_preimage(inputs, &constants).hash()
+}
+
+fn read_vectors_of<F: PrimeField>(path: &str, field: &str) -> Vec<Value> {
+    let file: Value = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+    assert_eq!(field, file["field"]);
+    assert_eq!(F::char(), repr_from_hex::<F>(&file["modulus"]));
+    file["vectors"].as_array().unwrap().clone()
+}
+
+fn read_vectors() -> Vec<Value> {
+    read_vectors_of::<Fr>(VECTORS, "bls12-381")
+}
+
+fn inputs<F: PrimeField>(vector: &Value) -> Vec<F> {
+    vector["inputs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(element_from_hex::<F>)
+        .collect()
+}
+
+#[test]
+fn test_against_vectors() {
+    let vectors = read_vectors();
+    assert!(vectors.len() >= 50);
+    for vector in &vectors {
+        let inputs = inputs::<Fr>(vector);
+        let arity = vector["arity"].as_u64().unwrap() as usize;
+        assert_eq!(arity, inputs.len());
+
+        let output = match arity {
+            2 => hash::<U2>(&inputs),
+            4 => hash::<U4>(&inputs),
+            8 => hash::<U8>(&inputs),
+            11 => hash::<U11>(&inputs),
+            16 => hash::<U16>(&inputs),
+            23 => hash::<U23>(&inputs),
+            24 => hash::<U24>(&inputs),
+            32 => hash::<U32>(&inputs),
+            36 => hash::<U36>(&inputs),
+            _ => panic!("no test vectors expected for arity {}", arity),
+        };
+        assert_eq!(
+            element_from_hex::<Fr>(&vector["expected_output"]),
+            output,
+            "inputs: {:?}",
+            vector["inputs"]
+        );
+    }
+}
+
+// The Filecoin configurations of widths 12 and 24 hash the vectors of arities 11 and 23.
+#[test]
+fn test_filecoin_against_vectors() {
+    let vectors = read_vectors();
+    let mut checked = 0;
+    for vector in &vectors {
+        let inputs = inputs::<Fr>(vector);
+        let output = match vector["arity"].as_u64().unwrap() {
+            11 => Poseidon::new_with_preimage(&inputs, &filecoin_poseidon_12()).hash(),
+            23 => Poseidon::new_with_preimage(&inputs, &filecoin_poseidon_24()).hash(),
+            _ => continue,
+        };
+        assert_eq!(element_from_hex::<Fr>(&vector["expected_output"]), output);
+        checked += 1;
+    }
+    assert_eq!(18, checked);
+}
+
+#[cfg(feature = "bn254")]
+#[test]
+fn test_circom_against_vectors() {
+    use generic_array::typenum::{U1, U10, U12, U13, U14, U15, U3, U5, U6, U7, U9};
+    use neptune::bn254::{circom_constants, circom_hash, Bn254Field};
+
+    fn hash<A: Arity<Bn254Field>>(inputs: &[Bn254Field]) -> Bn254Field {
+        circom_hash(inputs, &circom_constants::<A>())
+    }
+
+    let vectors = read_vectors_of::<Bn254Field>(BN254_VECTORS, "bn254");
+    assert!(vectors.len() >= 50);
+    for vector in &vectors {
+        let inputs = inputs::<Bn254Field>(vector);
+        let arity = vector["arity"].as_u64().unwrap() as usize;
+        assert_eq!(arity, inputs.len());
+
+        let output = match arity {
+            1 => hash::<U1>(&inputs),
+            2 => hash::<U2>(&inputs),
+            3 => hash::<U3>(&inputs),
+            4 => hash::<U4>(&inputs),
+            5 => hash::<U5>(&inputs),
+            6 => hash::<U6>(&inputs),
+            7 => hash::<U7>(&inputs),
+            8 => hash::<U8>(&inputs),
+            9 => hash::<U9>(&inputs),
+            10 => hash::<U10>(&inputs),
+            11 => hash::<U11>(&inputs),
+            12 => hash::<U12>(&inputs),
+            13 => hash::<U13>(&inputs),
+            14 => hash::<U14>(&inputs),
+            15 => hash::<U15>(&inputs),
+            16 => hash::<U16>(&inputs),
+            _ => panic!("no test vectors expected for arity {}", arity),
+        };
+        assert_eq!(
+            element_from_hex::<Bn254Field>(&vector["expected_output"]),
+            output,
+            "inputs: {:?}",
+            vector["inputs"]
+        );
+    }
+}
@@ -0,0 +1,68 @@
+// Checks the WebAssembly package against the test vectors of tests/compatibility.rs, and circomPoseidonHash also
+// against the digests of circomlibjs. From the root of the repository:
+//
+//     wasm-pack build wasm --target web --out-dir ../pkg --out-name neptune
+//     node tests/wasm/poseidon.test.mjs
+
+import assert from "assert";
+import { readFileSync } from "fs";
+import init, { circomPoseidonHash, poseidonHash } from "../../pkg/neptune.js";
+
+const ELEMENT_BYTES = 32;
+
+// The 32-byte little-endian encoding of a big-endian hex string.
+function toBytes(hex) {
+  const digits = hex.slice(2).padStart(2 * ELEMENT_BYTES, "0");
+  const bytes = new Uint8Array(ELEMENT_BYTES);
+  for (let i = 0; i < ELEMENT_BYTES; i++) {
+    const end = digits.length - 2 * i;
+    bytes[i] = parseInt(digits.slice(end - 2, end), 16);
+  }
+  return bytes;
+}
+
+function toHex(bytes) {
+  return "0x" + Array.from(bytes).reverse().map((b) => b.toString(16).padStart(2, "0")).join("");
+}
+
+await init(readFileSync(new URL("../../pkg/neptune_bg.wasm", import.meta.url)));
+
+const { vectors } = JSON.parse(readFileSync(new URL("../vectors/poseidon_bls12_381.json", import.meta.url)));
+for (const { arity, inputs, expected_output } of vectors) {
+  const preimage = new Uint8Array(arity * ELEMENT_BYTES);
+  inputs.forEach((input, i) => preimage.set(toBytes(input), i * ELEMENT_BYTES));
+  assert.strictEqual(toHex(poseidonHash(preimage)), expected_output.toLowerCase(), `arity ${arity}`);
+}
+
+// The digests of circomlibjs's `poseidon([1, 2, ..., n])`, by n.
+const circomDigests = {
+  1: "0x29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133",
+  2: "0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+  4: "0x299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465",
+  5: "0x0dab9449e4a1398a15224c0b15a49d598b2174d305a316c918125f8feeb123c0",
+  6: "0x2d1a03850084442813c8ebf094dea47538490a68b05f2239134a4cca2f6302e1",
+};
+for (const [n, expected] of Object.entries(circomDigests)) {
+  const preimage = new Uint8Array(n * ELEMENT_BYTES);
+  for (let i = 0; i < n; i++) {
+    preimage.set(toBytes("0x" + (i + 1).toString(16)), i * ELEMENT_BYTES);
+  }
+  assert.strictEqual(toHex(circomPoseidonHash(preimage)), expected, `circomlib, ${n} inputs`);
+}
+
+const circom = JSON.parse(readFileSync(new URL("../vectors/poseidon_bn254.json", import.meta.url))).vectors;
+for (const { arity, inputs, expected_output } of circom) {
+  const preimage = new Uint8Array(arity * ELEMENT_BYTES);
+  inputs.forEach((input, i) => preimage.set(toBytes(input), i * ELEMENT_BYTES));
+  assert.strictEqual(toHex(circomPoseidonHash(preimage)), expected_output, `circomlib, arity ${arity}`);
+}
+
+assert.throws(() => poseidonHash(new Uint8Array(0)), /field elements/);
+assert.throws(() => poseidonHash(new Uint8Array(ELEMENT_BYTES + 1)), /field elements/);
+assert.throws(() => poseidonHash(new Uint8Array(2 * ELEMENT_BYTES).fill(0xff)), /canonical encoding/);
+
+assert.throws(() => circomPoseidonHash(new Uint8Array(17 * ELEMENT_BYTES)), /field elements/);
+assert.throws(() => circomPoseidonHash(new Uint8Array(ELEMENT_BYTES).fill(0xff)), /canonical encoding/);
+
+const passed = vectors.length + circom.length;
+console.log(`${passed} vectors and ${Object.keys(circomDigests).length} circomlib digests passed`);
@@ -0,0 +1,6 @@
+//! The `cdylib` from which `wasm-pack` builds the ES module of `neptune::wasm`, as neptune is built as an `rlib` only:
+//!
+//! ```sh
+//! wasm-pack build wasm --target web --out-dir ../pkg --out-name neptune
+//! ```
+pub use neptune::wasm::*;